@@ -0,0 +1,138 @@
+use std::collections::VecDeque;
+
+// Energy-equivalent value of the resources collected from explorer discoveries
+pub const MINERAL_ENERGY_VALUE: i64 = 4;
+pub const SCIENCE_ENERGY_VALUE: i64 = 50;
+
+// Ticks the rolling balance covers, and how many ticks in a row it must stay negative (with a
+// full window) before the fleet is advised it has more explorers than it can use
+pub const AUDIT_WINDOW: usize = 500;
+pub const ADVISORY_TICKS: u64 = 200;
+
+// What explorers cost and what their discoveries were worth during one tick
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AuditSample {
+    pub explorer_energy: u32,   // Energy explorers burned
+    pub discovery_value: i64,   // Energy-equivalent value collected from cells explorers reported first
+}
+
+impl AuditSample {
+    // Value of a harvest in energy terms
+    pub fn harvest_value(energy: u32, minerals: u32, science: u32) -> i64 {
+        energy as i64 + minerals as i64 * MINERAL_ENERGY_VALUE + science as i64 * SCIENCE_ENERGY_VALUE
+    }
+}
+
+// Live energy audit of exploration: the explorers' energy spending against the value their
+// discoveries enabled, over the last AUDIT_WINDOW ticks
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExplorationAudit {
+    window: VecDeque<AuditSample>,
+    window_energy: i64,
+    window_value: i64,
+    pub total_energy: u64,   // Over the whole run
+    pub total_value: i64,
+    negative_streak: u64,    // Ticks in a row the full window has been in deficit
+    pub advisories: u32,     // Advisories raised so far
+}
+
+impl ExplorationAudit {
+    // Record a tick; returns the window balance when this tick makes the deficit persistent.
+    // An advisory is raised once per stretch of deficit: the balance has to recover first
+    pub fn record(&mut self, sample: AuditSample) -> Option<i64> {
+        self.window.push_back(sample);
+        self.window_energy += sample.explorer_energy as i64;
+        self.window_value += sample.discovery_value;
+        if self.window.len() > AUDIT_WINDOW {
+            let old = self.window.pop_front().unwrap();
+            self.window_energy -= old.explorer_energy as i64;
+            self.window_value -= old.discovery_value;
+        }
+        self.total_energy += sample.explorer_energy as u64;
+        self.total_value += sample.discovery_value;
+
+        if self.window.len() < AUDIT_WINDOW || self.balance() >= 0 {
+            self.negative_streak = 0;
+            return None;
+        }
+        self.negative_streak += 1;
+        if self.negative_streak != ADVISORY_TICKS {
+            return None;
+        }
+        self.advisories += 1;
+        Some(self.balance())
+    }
+
+    // Value minus energy spent over the window
+    pub fn balance(&self) -> i64 {
+        self.window_value - self.window_energy
+    }
+
+    // Value returned per unit of explorer energy over the window, if any was spent
+    pub fn ratio(&self) -> Option<f64> {
+        (self.window_energy > 0).then(|| self.window_value as f64 / self.window_energy as f64)
+    }
+
+    // Value returned per unit of explorer energy over the whole run
+    pub fn total_ratio(&self) -> Option<f64> {
+        (self.total_energy > 0).then(|| self.total_value as f64 / self.total_energy as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(explorer_energy: u32, discovery_value: i64) -> AuditSample {
+        AuditSample { explorer_energy, discovery_value }
+    }
+
+    #[test]
+    fn test_rolling_ratio_and_balance() {
+        let mut audit = ExplorationAudit::default();
+        assert_eq!(audit.ratio(), None);
+        for _ in 0..AUDIT_WINDOW {
+            audit.record(sample(2, 0));
+        }
+        audit.record(sample(0, 3000));
+        // The window dropped its oldest tick: 499 ticks of 2 energy against 3000 value
+        assert_eq!(audit.balance(), 3000 - 998);
+        assert_eq!(audit.ratio(), Some(3000.0 / 998.0));
+        assert_eq!(audit.total_ratio(), Some(3000.0 / 1000.0));
+        assert_eq!(AuditSample::harvest_value(10, 2, 1), 10 + 2 * MINERAL_ENERGY_VALUE + SCIENCE_ENERGY_VALUE);
+    }
+
+    #[test]
+    fn test_advisory_needs_a_full_window_in_deficit_for_long_enough() {
+        let mut audit = ExplorationAudit::default();
+        let mut raised = Vec::new();
+        for tick in 0..AUDIT_WINDOW as u64 + 2 * ADVISORY_TICKS {
+            if audit.record(sample(1, 0)).is_some() {
+                raised.push(tick);
+            }
+        }
+        // The window fills on tick AUDIT_WINDOW - 1, which starts the streak
+        assert_eq!(raised, vec![AUDIT_WINDOW as u64 - 2 + ADVISORY_TICKS]);
+        assert_eq!(audit.advisories, 1);
+
+        // Recovering rearms it
+        audit.record(sample(0, 10_000));
+        assert!(audit.balance() >= 0);
+        let mut again = 0;
+        for _ in 0..AUDIT_WINDOW as u64 + ADVISORY_TICKS {
+            again += audit.record(sample(1, 0)).is_some() as u32;
+        }
+        assert_eq!((again, audit.advisories), (1, 2));
+    }
+
+    #[test]
+    fn test_break_even_exploration_is_not_flagged() {
+        let mut audit = ExplorationAudit::default();
+        for tick in 0..3 * AUDIT_WINDOW {
+            // Spending every tick, paid back in bursts
+            let value = if tick % 50 == 49 { 50 } else { 0 };
+            assert_eq!(audit.record(sample(1, value)), None);
+        }
+        assert_eq!(audit.advisories, 0);
+    }
+}
@@ -0,0 +1,294 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::events::ActivityTally;
+use crate::map::{Map, MapConfig, Terrain};
+use crate::simulation::Simulation;
+
+// Size of the world generated for each run of a batch
+const BATCH_MAP_WIDTH: usize = 100;
+const BATCH_MAP_HEIGHT: usize = 50;
+
+// Ticks each run lasts when --ticks is not given
+pub const DEFAULT_BATCH_TICKS: u64 = 10000;
+
+// What to run: `runs` worlds on seeds counting up from `base_seed`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchPlan {
+    pub runs: usize,
+    pub ticks: u64,
+    pub base_seed: u32,
+    pub wrap: bool,
+    pub terrain: Terrain,
+    pub threads: usize, // Runs executed at once; 1 runs them one after another
+}
+
+impl BatchPlan {
+    // Seed of the run at `index`, so any run of a batch can be replayed on its own
+    pub fn seed(&self, index: usize) -> u32 {
+        self.base_seed.wrapping_add(index as u32)
+    }
+}
+
+// How one run ended
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunOutcome {
+    pub seed: u32,
+    pub explored_percent: f64,
+    pub energy: u32,
+    pub minerals: u32, // Raw and refined
+    pub science: u32,
+    pub robots: usize,
+    pub deaths: u32, // Robots that ran down and were recovered as wrecks
+    pub score: u64,
+}
+
+// One row of the batch results
+#[derive(Debug, Clone)]
+pub struct RunResult {
+    pub outcome: RunOutcome,
+    pub wall_time: Duration,
+}
+
+impl RunResult {
+    // One CSV row: seed,explored_percent,energy,minerals,science,robots,deaths,score,wall_time_ms
+    fn csv_line(&self) -> String {
+        let outcome = &self.outcome;
+        format!(
+            "{},{:.2},{},{},{},{},{},{},{}",
+            outcome.seed,
+            outcome.explored_percent,
+            outcome.energy,
+            outcome.minerals,
+            outcome.science,
+            outcome.robots,
+            outcome.deaths,
+            outcome.score,
+            self.wall_time.as_millis()
+        )
+    }
+
+    // Every summarized metric, in report order
+    fn metrics(&self) -> [(&'static str, f64); 8] {
+        let outcome = &self.outcome;
+        [
+            ("explored %", outcome.explored_percent),
+            ("energy", outcome.energy as f64),
+            ("minerals", outcome.minerals as f64),
+            ("science", outcome.science as f64),
+            ("robots", outcome.robots as f64),
+            ("deaths", outcome.deaths as f64),
+            ("score", outcome.score as f64),
+            ("wall time ms", self.wall_time.as_secs_f64() * 1000.0),
+        ]
+    }
+}
+
+// The world a batch run plays out on, counting wrecks as deaths
+fn batch_world(seed: u32, wrap: bool, terrain: Terrain) -> Simulation {
+    let map = Map::with_config(BATCH_MAP_WIDTH, BATCH_MAP_HEIGHT, seed, &MapConfig { terrain, ..MapConfig::default() });
+    let mut simulation = Simulation::setup(map, None);
+    simulation.map.wrap = wrap;
+    simulation.events.subscribe(Box::new(ActivityTally::default()));
+    simulation
+}
+
+// Run one headless world for the given number of ticks
+pub fn run_one(seed: u32, ticks: u64, wrap: bool, terrain: Terrain) -> RunResult {
+    let start = Instant::now();
+    let mut simulation = batch_world(seed, wrap, terrain);
+    for _ in 0..ticks {
+        simulation.tick();
+    }
+    let station = &simulation.station();
+    let outcome = RunOutcome {
+        seed,
+        explored_percent: simulation.map.exploration_stats().percentage,
+        energy: station.energy,
+        minerals: station.raw_minerals + station.refined_minerals,
+        science: station.science_points,
+        robots: station.robots.len(),
+        deaths: simulation.events.subscriber::<ActivityTally>().map_or(0, |tally| tally.wrecks),
+        score: simulation.score(),
+    };
+    RunResult { outcome, wall_time: start.elapsed() }
+}
+
+// Run every world of the plan, spreading them over `plan.threads` workers (each run builds its
+// own Simulation, so they share nothing). `progress` is called on this thread as runs finish,
+// with the number done so far; the results come back in seed order
+pub fn run_batch<F>(plan: &BatchPlan, mut progress: F) -> Vec<RunResult>
+where
+    F: FnMut(usize, &RunResult),
+{
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let mut results: Vec<(usize, RunResult)> = Vec::with_capacity(plan.runs);
+    thread::scope(|scope| {
+        for _ in 0..plan.threads.clamp(1, plan.runs.max(1)) {
+            let (next, sender) = (&next, sender.clone());
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= plan.runs {
+                    break;
+                }
+                let result = run_one(plan.seed(index), plan.ticks, plan.wrap, plan.terrain);
+                if sender.send((index, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+        for (index, result) in receiver {
+            progress(results.len() + 1, &result);
+            results.push((index, result));
+        }
+    });
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+// Write the per-run rows to a CSV file, replacing any previous one
+pub fn write_csv(path: &Path, results: &[RunResult]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    writeln!(file, "seed,explored_percent,energy,minerals,science,robots,deaths,score,wall_time_ms")?;
+    for result in results {
+        writeln!(file, "{}", result.csv_line())?;
+    }
+    Ok(())
+}
+
+// Spread of one metric over the runs of a batch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricSummary {
+    pub mean: f64,
+    pub median: f64,
+    pub stddev: f64, // Population standard deviation
+    pub min: f64,
+    pub max: f64,
+}
+
+impl MetricSummary {
+    pub fn of(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let count = sorted.len() as f64;
+        let mean = sorted.iter().sum::<f64>() / count;
+        let middle = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) { (sorted[middle - 1] + sorted[middle]) / 2.0 } else { sorted[middle] };
+        let variance = sorted.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / count;
+        Some(Self { mean, median, stddev: variance.sqrt(), min: sorted[0], max: sorted[sorted.len() - 1] })
+    }
+}
+
+// Per-metric summary of a whole batch
+pub struct BatchSummary {
+    pub runs: usize,
+    pub metrics: Vec<(&'static str, MetricSummary)>,
+}
+
+impl BatchSummary {
+    pub fn of(results: &[RunResult]) -> Self {
+        let metrics = match results.first() {
+            Some(first) => (0..first.metrics().len())
+                .filter_map(|column| {
+                    let values: Vec<f64> = results.iter().map(|result| result.metrics()[column].1).collect();
+                    MetricSummary::of(&values).map(|summary| (first.metrics()[column].0, summary))
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        Self { runs: results.len(), metrics }
+    }
+}
+
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Batch summary over {} runs:", self.runs)?;
+        writeln!(f, "{:<14}{:>12}{:>12}{:>12}{:>12}{:>12}", "metric", "mean", "median", "stddev", "min", "max")?;
+        for (name, summary) in &self.metrics {
+            writeln!(
+                f,
+                "{:<14}{:>12.2}{:>12.2}{:>12.2}{:>12.2}{:>12.2}",
+                name, summary.mean, summary.median, summary.stddev, summary.min, summary.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robot::RobotState;
+
+    fn tiny_plan(threads: usize) -> BatchPlan {
+        BatchPlan { runs: 3, ticks: 200, base_seed: 40, wrap: false, terrain: Terrain::Open, threads }
+    }
+
+    fn outcomes(results: &[RunResult]) -> Vec<RunOutcome> {
+        results.iter().map(|result| result.outcome.clone()).collect()
+    }
+
+    #[test]
+    fn test_tiny_batch_rows_and_summary() {
+        let mut reported = Vec::new();
+        let results = run_batch(&tiny_plan(1), |done, result| reported.push((done, result.outcome.seed)));
+        assert_eq!(results.len(), 3);
+        assert_eq!(reported.iter().map(|(done, _)| *done).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(outcomes(&results).iter().map(|outcome| outcome.seed).collect::<Vec<_>>(), vec![40, 41, 42]);
+
+        let summary = BatchSummary::of(&results);
+        assert_eq!(summary.runs, 3);
+        let (name, score) = summary.metrics.iter().find(|(name, _)| *name == "score").unwrap();
+        assert_eq!(*name, "score");
+        let scores: Vec<f64> = results.iter().map(|result| result.outcome.score as f64).collect();
+        assert!((score.mean - scores.iter().sum::<f64>() / 3.0).abs() < 1e-9);
+        assert!(score.min <= score.median && score.median <= score.max);
+
+        let path = std::env::temp_dir().join(format!("rusty_games_batch_{}.csv", std::process::id()));
+        write_csv(&path, &results).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(text.lines().count(), 4); // Header and one row per run
+        assert!(text.lines().nth(1).unwrap().starts_with("40,"));
+    }
+
+    #[test]
+    fn test_deaths_are_only_robots_respawned_or_still_down() {
+        let mut simulation = batch_world(5, false, Terrain::Open);
+        simulation.run_ticks(1000);
+        let deaths = simulation.events.subscriber::<ActivityTally>().unwrap().wrecks;
+        let robots: Vec<_> = simulation.station().robots.iter().filter(|robot| robot.state != RobotState::Decommissioned).collect();
+        let respawns: u32 = robots.iter().map(|robot| robot.lifetime.respawns).sum();
+        let down = robots.iter().filter(|robot| robot.energy == 0).count() as u32;
+        assert!(deaths <= respawns + down, "{} deaths for {} respawns and {} robots down", deaths, respawns, down);
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let serial = run_batch(&tiny_plan(1), |_, _| {});
+        let parallel = run_batch(&tiny_plan(3), |_, _| {});
+        assert_eq!(outcomes(&serial), outcomes(&parallel));
+    }
+
+    #[test]
+    fn test_metric_summary_math() {
+        let summary = MetricSummary::of(&[4.0, 1.0, 3.0, 2.0]).unwrap();
+        assert_eq!(summary.mean, 2.5);
+        assert_eq!(summary.median, 2.5);
+        assert!((summary.stddev - 1.25f64.sqrt()).abs() < 1e-9);
+        assert_eq!((summary.min, summary.max), (1.0, 4.0));
+        assert_eq!(MetricSummary::of(&[7.0, 1.0, 3.0]).unwrap().median, 3.0);
+        assert_eq!(MetricSummary::of(&[]), None);
+    }
+}
@@ -0,0 +1,505 @@
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::map::{Map, Region};
+use crate::robot::{Direction, Robot, RobotType, RobotView};
+use crate::station::EXPLORER_REGION_GRID;
+
+// What a behavior has its robot do this tick
+#[derive(Debug, Clone, Copy)]
+pub enum Action {
+    Move(Direction), // Step to the next cell that way
+    Collect,         // Stay on the deposit underfoot, harvesting it again next tick
+    Return,          // Head back to the station
+    Wait,            // Stay put
+}
+
+// What a behavior sees of the world besides its robot and the map
+pub struct Context<'a> {
+    pub other_robots: &'a [RobotView],
+    pub station: (usize, usize),
+    pub tick: u64,
+    pub harvested: bool, // The robot took something from its cell this tick
+}
+
+// How a robot picks its next move once the shared rules (heading home, station tasks, getting
+// unstuck) have had their say. The robot is lent mutably: built-in behaviors draw on its
+// randomness and claim the deposits they head for
+pub trait Behavior {
+    fn decide(&mut self, robot: &mut Robot, map: &Map, ctx: &Context) -> Action;
+    fn clone_box(&self) -> Box<dyn Behavior>;
+}
+
+impl Clone for Box<dyn Behavior> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+// Stay on a deposit that still holds more, otherwise step where `choose` points, or any open way
+fn collect_or_step(robot: &mut Robot, map: &Map, ctx: &Context, choose: impl FnOnce(&mut Robot) -> Option<Direction>) -> Action {
+    if ctx.harvested && robot.found_something_at_current_position(map) {
+        return Action::Collect;
+    }
+    match choose(robot).or_else(|| robot.random_open_direction(map, ctx.other_robots)) {
+        Some(direction) => Action::Move(direction),
+        None => Action::Wait,
+    }
+}
+
+// Explorers: toward the most unexplored ground
+#[derive(Clone, Copy)]
+pub struct Explore;
+
+impl Behavior for Explore {
+    fn decide(&mut self, robot: &mut Robot, map: &Map, ctx: &Context) -> Action {
+        collect_or_step(robot, map, ctx, |robot| robot.choose_explorer_direction(map, ctx.other_robots, ctx.tick))
+    }
+
+    fn clone_box(&self) -> Box<dyn Behavior> {
+        Box::new(*self)
+    }
+}
+
+// Energy collectors, mineral collectors and scientists: toward the deposits they harvest
+#[derive(Clone, Copy)]
+pub struct Harvest;
+
+impl Behavior for Harvest {
+    fn decide(&mut self, robot: &mut Robot, map: &Map, ctx: &Context) -> Action {
+        collect_or_step(robot, map, ctx, |robot| match robot.robot_type {
+            RobotType::EnergyCollector => robot.choose_energy_collector_direction(map, ctx.other_robots),
+            RobotType::Scientist => robot.choose_scientist_direction(map, ctx.other_robots),
+            _ => robot.choose_mineral_collector_direction(map, ctx.other_robots),
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn Behavior> {
+        Box::new(*self)
+    }
+}
+
+// Miners: toward fenced deposits to drill open
+#[derive(Clone, Copy)]
+pub struct Mine;
+
+impl Behavior for Mine {
+    fn decide(&mut self, robot: &mut Robot, map: &Map, ctx: &Context) -> Action {
+        collect_or_step(robot, map, ctx, |robot| robot.choose_miner_direction(map, ctx.other_robots))
+    }
+
+    fn clone_box(&self) -> Box<dyn Behavior> {
+        Box::new(*self)
+    }
+}
+
+// Carriers and repairers, which ferry and repair instead of wandering
+#[derive(Clone, Copy)]
+pub struct Idle;
+
+impl Behavior for Idle {
+    fn decide(&mut self, _robot: &mut Robot, _map: &Map, _ctx: &Context) -> Action {
+        Action::Wait
+    }
+
+    fn clone_box(&self) -> Box<dyn Behavior> {
+        Box::new(*self)
+    }
+}
+
+// The behavior a robot type is built with
+pub fn builtin(robot_type: RobotType) -> Box<dyn Behavior> {
+    match robot_type {
+        RobotType::Explorer => Box::new(Explore),
+        RobotType::EnergyCollector | RobotType::MineralCollector | RobotType::Scientist => Box::new(Harvest),
+        RobotType::Miner => Box::new(Mine),
+        RobotType::Carrier | RobotType::Repairer => Box::new(Idle),
+    }
+}
+
+// Decisions a spiral sweep spends closing in on a corner before giving up on it
+const SPIRAL_PATIENCE: u32 = 12;
+
+// Spiral sweep: square spiral out from the station, the arms growing by one sensor sweep every
+// two turns so each pass borders the last. Corners off the map are clipped to its edge, and a
+// corner rock keeps the robot from is given up on. Once the arms outgrow the map the robot
+// heads home and the next sweep starts over
+#[derive(Clone, Copy)]
+pub struct SpiralSweep {
+    legs: u32,                  // Arms walked so far
+    corner: Option<(i64, i64)>, // End of the current arm, possibly off the map
+    closest: u32,               // Nearest the robot got to that corner
+    stalled: u32,               // Decisions since it last got nearer
+}
+
+impl SpiralSweep {
+    pub fn new() -> Self {
+        Self { legs: 0, corner: None, closest: u32::MAX, stalled: 0 }
+    }
+
+    fn arm_length(&self, spacing: i64) -> i64 {
+        spacing * (self.legs / 2 + 1) as i64
+    }
+
+    // Start the next arm: east, south, west, north, and round again
+    fn next_corner(&mut self, from: (i64, i64), spacing: i64) -> (i64, i64) {
+        let (dx, dy) = [(1, 0), (0, 1), (-1, 0), (0, -1)][self.legs as usize % 4];
+        let length = self.arm_length(spacing);
+        self.legs += 1;
+        self.closest = u32::MAX;
+        self.stalled = 0;
+        (from.0 + dx * length, from.1 + dy * length)
+    }
+}
+
+impl Behavior for SpiralSweep {
+    fn decide(&mut self, robot: &mut Robot, map: &Map, ctx: &Context) -> Action {
+        if ctx.harvested && robot.found_something_at_current_position(map) {
+            return Action::Collect;
+        }
+        let spacing = 2 * robot.sensor_radius() as i64 + 1;
+        let station = (ctx.station.0 as i64, ctx.station.1 as i64);
+        let mut corner = *self.corner.get_or_insert(station);
+        let clip = |(x, y): (i64, i64)| (x.clamp(0, map.width as i64 - 1) as usize, y.clamp(0, map.height as i64 - 1) as usize);
+        loop {
+            let (x, y) = clip(corner);
+            let distance = map.distance(robot.x, robot.y, x, y);
+            if distance < self.closest {
+                self.closest = distance;
+                self.stalled = 0;
+            }
+            if distance > robot.sensor_radius() as u32 && self.stalled < SPIRAL_PATIENCE {
+                break;
+            }
+            // Swept: every arm now reaches past the map from the station
+            if self.arm_length(spacing) > 2 * map.width.max(map.height) as i64 {
+                *self = Self::new();
+                return Action::Return;
+            }
+            corner = self.next_corner(corner, spacing);
+            self.corner = Some(corner);
+        }
+        self.stalled += 1;
+
+        let (x, y) = clip(corner);
+        let step = Direction::ALL
+            .into_iter()
+            .filter(|&direction| !robot.is_jammed(direction))
+            .filter_map(|direction| robot.get_next_position(direction, map).map(|next| (direction, next)))
+            .filter(|&(_, (next_x, next_y))| robot.is_valid_move(next_x, next_y, map, ctx.other_robots))
+            .min_by_key(|&(_, (next_x, next_y))| map.distance(next_x, next_y, x, y));
+        match step {
+            Some((direction, _)) => Action::Move(direction),
+            None => Action::Wait,
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Behavior> {
+        Box::new(*self)
+    }
+}
+
+// Lawn-mower sweep of the region the station assigned: the region is cut into lanes one sensor
+// sweep tall, walked along their middle row west to east, then east to west on the next lane
+// down. Each decision heads, on an A* path, for the spot that brings the first cell of that
+// order still unexplored into view while reaching farthest along the lane; spots no path leads
+// to are passed over. With nothing left the region counts as swept and the robot heads home
+#[derive(Clone, Default)]
+pub struct RowSweep {
+    region: Option<Region>,               // Bounds being swept, looked up when the assignment changes
+    unreachable: HashSet<(usize, usize)>, // Spots no path led to
+    sweeping: bool,                       // Stepped in the region since it was assigned
+}
+
+impl RowSweep {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Where to stand next: the first unexplored passable cell in sweep order, and the spot in
+    // sensor range of it nearest the lane's middle row and farthest along the lane
+    fn next_spot(&self, region: &Region, map: &Map, radius: usize) -> Option<(usize, usize)> {
+        let lane_height = 2 * radius + 1;
+        let open = |x: usize, y: usize| map.get_cell(x, y).is_some_and(|cell| !cell.cell_type.blocks_movement());
+        for (lane, lane_start) in (region.y_start..region.y_end).step_by(lane_height).enumerate() {
+            let lane_end = (lane_start + lane_height).min(region.y_end);
+            let middle = (lane_start + radius).min(lane_end - 1);
+            let eastward = lane % 2 == 0;
+            let unexplored = (lane_start..lane_end)
+                .flat_map(|y| (region.x_start..region.x_end).map(move |x| (x, y)))
+                .filter(|&(x, y)| open(x, y) && !map.get_cell(x, y).is_some_and(|cell| cell.explored) && !self.unreachable.contains(&(x, y)))
+                .min_by_key(|&(x, y)| (if eastward { x } else { usize::MAX - x }, y.abs_diff(middle)));
+            let Some((ux, uy)) = unexplored else {
+                continue;
+            };
+            let near = |from: usize, start: usize, end: usize| from.saturating_sub(radius).max(start)..(from + radius + 1).min(end);
+            return near(uy, region.y_start, region.y_end)
+                .flat_map(|y| near(ux, region.x_start, region.x_end).map(move |x| (x, y)))
+                .filter(|&(x, y)| open(x, y) && !self.unreachable.contains(&(x, y)))
+                .min_by_key(|&(x, y)| (y.abs_diff(middle), if eastward { usize::MAX - x } else { x }));
+        }
+        None
+    }
+}
+
+impl Behavior for RowSweep {
+    fn decide(&mut self, robot: &mut Robot, map: &Map, ctx: &Context) -> Action {
+        if ctx.harvested && robot.found_something_at_current_position(map) {
+            return Action::Collect;
+        }
+        let (grid_w, grid_h) = EXPLORER_REGION_GRID;
+        let (x, y) = robot.target_x.zip(robot.target_y).unwrap_or((robot.x, robot.y));
+        let index = map.region_of(x, y, grid_w, grid_h);
+        if self.region.is_none_or(|region| region.index != index) {
+            *self = Self { region: map.regions(grid_w, grid_h).get(index).copied(), ..Self::new() };
+        }
+        let Some(region) = self.region else {
+            return Action::Wait;
+        };
+        while let Some(spot) = self.next_spot(&region, map, robot.sensor_radius()) {
+            let step = match spot != (robot.x, robot.y) {
+                true => robot.next_cell_towards(spot.0, spot.1, map).and_then(|(x, y)| robot.get_direction_to_position(x, y, map)),
+                false => None,
+            };
+            match step {
+                Some(direction) => {
+                    self.sweeping = true;
+                    // On its way, however long since the last find: the unstuck rule is not for it
+                    robot.steps_since_last_find = 0;
+                    return Action::Move(direction);
+                }
+                None => {
+                    self.unreachable.insert(spot);
+                }
+            }
+        }
+        if std::mem::take(&mut self.sweeping) {
+            robot.regions_swept += 1;
+        }
+        Action::Return
+    }
+
+    fn clone_box(&self) -> Box<dyn Behavior> {
+        Box::new(self.clone())
+    }
+}
+
+// Behaviors selectable for explorers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BehaviorKind {
+    #[default]
+    Builtin, // Each robot type's own
+    Spiral,  // Explorers sweep a square spiral out from the station
+    Sweep,   // Explorers sweep their assigned region row by row
+}
+
+impl BehaviorKind {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "builtin" => Ok(BehaviorKind::Builtin),
+            "spiral" => Ok(BehaviorKind::Spiral),
+            "sweep" => Ok(BehaviorKind::Sweep),
+            other => Err(format!("unknown behavior '{}', expected builtin, spiral or sweep", other)),
+        }
+    }
+
+    // The behavior a robot of this type gets; only explorers have a choice
+    pub fn build(self, robot_type: RobotType) -> Box<dyn Behavior> {
+        match (self, robot_type) {
+            (BehaviorKind::Spiral, RobotType::Explorer) => Box::new(SpiralSweep::new()),
+            (BehaviorKind::Sweep, RobotType::Explorer) => Box::new(RowSweep::new()),
+            _ => builtin(robot_type),
+        }
+    }
+}
+
+impl fmt::Display for BehaviorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BehaviorKind::Builtin => "builtin",
+            BehaviorKind::Spiral => "spiral",
+            BehaviorKind::Sweep => "sweep",
+        })
+    }
+}
+
+// The thresholds and scoring weights robots decide by, tunable from a TOML file for balancing
+// runs without touching the logic. Keys left out of the file keep their defaults
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BehaviorConfig {
+    pub explorer_report_updates: usize, // Unreported cells an explorer holds before heading home to report (a few dozen steps of fresh ground)
+    pub return_budget_percent: u32,     // Slack on the estimated trip home a robot turns back at
+    pub unstuck_after_steps: u32,       // Steps without a find before a robot sets off for open ground
+    pub unstuck_radius: i32,            // Farthest ring searched for that open ground
+    pub unexplored_bonus: i32,          // Explorer score of a step onto an unexplored cell
+    pub explored_penalty: i32,          // Taken off an explorer's step onto an explored cell
+    pub unexplored_neighbor_bonus: i32, // Per unexplored cell around an explorer's step
+    pub edge_bonus: i32,                // Explorer steps onto the map edge
+    pub corner_bonus: i32,              // On top of the edge bonus in a corner
+    pub region_pull_bonus: i32,         // Explorer steps toward the region the station assigned
+    pub target_bonus: i32,              // Collector steps onto an unclaimed deposit they harvest
+    pub crowding_penalty: i32,          // Per robot close by, see robot::crowding_penalty
+    pub trail_penalty: i32,             // Explorer steps back onto the breadcrumb trail
+    pub collector_trail_penalty: i32,   // Collector steps back onto it; their scores run much smaller
+    pub formation_bonus: i32,           // Trailing explorer steps that keep it in band with its leader
+    pub formation_penalty: i32,         // Times the square of the cells a trailing explorer's step ends up outside that band
+    pub wear_after_steps: u32,          // Lifetime steps before a robot is worn, see robot::WORN_REFUEL_SURCHARGE_PERCENT
+    pub wear_after_collections: u32,    // Lifetime harvests before it is worn
+    pub levy_flight_odds: u32,          // 1 in n random moves start a straight flight, see robot::LEVY_FLIGHT_STEPS; 0 for single steps
+    pub path_node_limit: usize,         // Nodes a path search expands before a walk settles for the way nearest its goal
+}
+
+impl Default for BehaviorConfig {
+    fn default() -> Self {
+        Self {
+            explorer_report_updates: 150,
+            return_budget_percent: 130,
+            unstuck_after_steps: 5,
+            unstuck_radius: 25,
+            unexplored_bonus: 150,
+            explored_penalty: 30,
+            unexplored_neighbor_bonus: 15,
+            edge_bonus: 40,
+            corner_bonus: 20,
+            region_pull_bonus: 60,
+            target_bonus: 25,
+            crowding_penalty: 20,
+            trail_penalty: 40,
+            collector_trail_penalty: 5,
+            formation_bonus: 40,
+            formation_penalty: 100,
+            wear_after_steps: 5000,
+            wear_after_collections: 500,
+            levy_flight_odds: 0,
+            path_node_limit: 10_000,
+        }
+    }
+}
+
+impl BehaviorConfig {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|err| format!("could not parse behavior config: {}", err))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("could not read behavior config '{}': {}", path, err))?;
+        Self::parse(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spiral_sweep_walks_out_from_the_station_and_comes_home() {
+        let (map, _) = Map::from_ascii(&vec![".".repeat(21); 21].join("\n")).unwrap();
+        let mut robot = Robot::new_with_type(10, 10, RobotType::Explorer);
+        let mut spiral = SpiralSweep::new();
+        let ctx = Context { other_robots: &[], station: (10, 10), tick: 0, harvested: false };
+        let mut visited = vec![(robot.x, robot.y)];
+        let mut returned = false;
+        for _ in 0..500 {
+            match spiral.decide(&mut robot, &map, &ctx) {
+                Action::Move(direction) => {
+                    robot.energy = robot.spec.max_energy; // Only the route matters here
+                    assert!(robot.move_in_direction(direction, &map, &[]));
+                    visited.push((robot.x, robot.y));
+                }
+                Action::Return => {
+                    returned = true;
+                    break;
+                }
+                action => panic!("unexpected {:?}", action),
+            }
+        }
+        assert!(returned);
+        // The first arm heads east, the second south
+        assert_eq!(visited[1], (11, 10));
+        assert!(visited.contains(&(15, 15)));
+        // Every corner of the map came within sensor range on the way
+        for (x, y) in [(0, 0), (20, 0), (0, 20), (20, 20)] {
+            assert!(visited.iter().any(|&(vx, vy)| map.distance(vx, vy, x, y) <= 2 * robot.spec.sensor_radius as u32));
+        }
+    }
+
+    // Drive a sweeping explorer through its region, exploring as it goes; the moves made and
+    // whether it headed home
+    fn sweep_region(map: &mut Map, robot: &mut Robot) -> (usize, bool) {
+        let mut sweep = RowSweep::new();
+        let ctx = Context { other_robots: &[], station: (0, 0), tick: 0, harvested: false };
+        for moves in 0..500 {
+            robot.explore(map, 0);
+            match sweep.decide(robot, map, &ctx) {
+                Action::Move(direction) => {
+                    robot.energy = robot.spec.max_energy;
+                    assert!(robot.move_in_direction(direction, map, &[]));
+                }
+                Action::Return => return (moves, true),
+                action => panic!("unexpected {:?}", action),
+            }
+        }
+        (500, false)
+    }
+
+    #[test]
+    fn test_row_sweep_covers_an_open_region_in_near_optimal_steps() {
+        let (mut map, _) = Map::from_ascii(&vec![".".repeat(30); 30].join("\n")).unwrap();
+        let mut robot = Robot::new_with_type(0, 0, RobotType::Explorer);
+        (robot.target_x, robot.target_y) = (Some(5), Some(5)); // The top-left region, 10x10
+        let (moves, returned) = sweep_region(&mut map, &mut robot);
+        assert!(returned);
+        let region = map.regions(3, 3)[0];
+        assert_eq!(region.stats.percentage, 100.0);
+        // A 5x5 sensor sees at most 5 new cells a move: 91 cells past the first view need 19
+        assert!(moves <= 20, "{} moves", moves);
+        assert_eq!(robot.regions_swept, 1);
+        // The neighbouring region was barely touched
+        assert!(map.regions(3, 3)[1].stats.percentage < 25.0);
+    }
+
+    #[test]
+    fn test_row_sweep_walks_around_rock_and_passes_over_sealed_pockets() {
+        // A wall across the region with a gap at its east end, and a pocket of rock-ringed cells
+        // too deep inside for the sensor to reach
+        let mut rows = vec![".".repeat(60); 60];
+        rows[4] = format!("{}.{}", "#".repeat(19), ".".repeat(40));
+        for (y, row) in ["..#######", "..#.....#", "..#.....#", "..#.....#", "..#.....#", "..#.....#", "..#######"].iter().enumerate() {
+            rows[y + 9].replace_range(..9, row);
+        }
+        let (mut map, _) = Map::from_ascii(&rows.join("\n")).unwrap();
+        let mut robot = Robot::new_with_type(0, 0, RobotType::Explorer);
+        (robot.target_x, robot.target_y) = (Some(10), Some(10)); // The top-left region, 20x20
+        let (_, returned) = sweep_region(&mut map, &mut robot);
+        assert!(returned);
+        let explored = |x, y| map.get_cell(x, y).unwrap().explored;
+        assert!((0..20).all(|x| explored(x, 0) && explored(x, 19)));
+        assert!(!explored(5, 12));
+        assert_eq!(robot.regions_swept, 1);
+    }
+
+    #[test]
+    fn test_behavior_config_keeps_defaults_for_keys_left_out() {
+        let config = BehaviorConfig::parse("explorer_report_updates = 40\ntrail_penalty = 0\n").unwrap();
+        assert_eq!(config, BehaviorConfig { explorer_report_updates: 40, trail_penalty: 0, ..BehaviorConfig::default() });
+        assert_eq!(BehaviorConfig::parse("").unwrap(), BehaviorConfig::default());
+        assert!(BehaviorConfig::parse("report_updates = 40").is_err());
+    }
+
+    #[test]
+    fn test_behavior_kinds_parse_and_only_change_explorers() {
+        assert_eq!(BehaviorKind::parse("spiral"), Ok(BehaviorKind::Spiral));
+        assert_eq!(BehaviorKind::parse(&BehaviorKind::Sweep.to_string()), Ok(BehaviorKind::Sweep));
+        assert_eq!(BehaviorKind::parse(&BehaviorKind::Builtin.to_string()), Ok(BehaviorKind::Builtin));
+        assert!(BehaviorKind::parse("zigzag").is_err());
+
+        // A collector standing on a deposit it just harvested stays whichever kind is picked
+        let (map, _) = Map::from_ascii(".....\n.....\n..m..\n").unwrap();
+        let mut collector = Robot::new_with_type(2, 2, RobotType::MineralCollector);
+        let ctx = Context { other_robots: &[], station: (0, 0), tick: 0, harvested: true };
+        let mut behavior = BehaviorKind::Spiral.build(RobotType::MineralCollector);
+        assert!(matches!(behavior.decide(&mut collector, &map, &ctx), Action::Collect));
+    }
+}
@@ -0,0 +1,251 @@
+use serde::Deserialize;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::map::Map;
+use crate::simulation::Simulation;
+
+// Manifest read from the working directory when present, otherwise the bundled copy is used
+pub const CHALLENGES_PATH: &str = "challenges.toml";
+const BUNDLED_CHALLENGES: &str = include_str!("../challenges.toml");
+
+// Every challenge attempt is appended here
+pub const HISTORY_PATH: &str = "challenge_history.csv";
+
+// Smallest world a challenge may ask for
+const MIN_CHALLENGE_SIZE: usize = 10;
+
+// A named, fixed world with a score to beat within a tick limit
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Challenge {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub seed: u32,
+    pub width: usize,
+    pub height: usize,
+    pub tick_limit: u64,
+    pub target_score: u64,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    #[serde(default, rename = "challenge")]
+    challenges: Vec<Challenge>,
+}
+
+// Errors raised while loading or starting a challenge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChallengeError {
+    Parse(String),
+    Invalid { name: String, reason: String },
+    DuplicateName(String),
+    NotFound { name: String, available: Vec<String> },
+}
+
+impl fmt::Display for ChallengeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChallengeError::Parse(message) => write!(f, "could not parse challenge manifest: {}", message),
+            ChallengeError::Invalid { name, reason } => write!(f, "challenge '{}' is invalid: {}", name, reason),
+            ChallengeError::DuplicateName(name) => write!(f, "challenge '{}' is defined more than once", name),
+            ChallengeError::NotFound { name, available } => write!(
+                f,
+                "no challenge named '{}' (available: {})",
+                name,
+                available.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ChallengeError {}
+
+// Outcome of a challenge attempt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChallengeResult {
+    pub name: String,
+    pub ticks: u64,
+    pub score: u64,
+    pub target_score: u64,
+    pub passed: bool,
+}
+
+impl ChallengeResult {
+    pub fn summary(&self) -> String {
+        let verdict = if self.passed { "PASSED" } else { "FAILED" };
+        format!(
+            "Challenge '{}' {}: score {} vs target {} after {} ticks",
+            self.name, verdict, self.score, self.target_score, self.ticks
+        )
+    }
+
+    // One CSV row: name,timestamp,ticks,score,target,passed
+    fn history_line(&self, timestamp: u64) -> String {
+        format!(
+            "\"{}\",{},{},{},{},{}",
+            self.name.replace('"', "\"\""), timestamp, self.ticks, self.score, self.target_score, self.passed
+        )
+    }
+}
+
+impl Challenge {
+    fn validate(&self) -> Result<(), ChallengeError> {
+        let invalid = |reason: &str| ChallengeError::Invalid { name: self.name.clone(), reason: reason.to_string() };
+        if self.name.trim().is_empty() {
+            return Err(invalid("name must not be empty"));
+        }
+        if self.width < MIN_CHALLENGE_SIZE || self.height < MIN_CHALLENGE_SIZE {
+            return Err(invalid(&format!("world must be at least {}x{}", MIN_CHALLENGE_SIZE, MIN_CHALLENGE_SIZE)));
+        }
+        if self.tick_limit == 0 {
+            return Err(invalid("tick_limit must be positive"));
+        }
+        Ok(())
+    }
+
+    // The challenge's fixed starting world
+    pub fn build_simulation(&self) -> Simulation {
+        Simulation::setup(Map::new(self.width, self.height, self.seed), None)
+    }
+
+    // Compare a finished (or abandoned) run against the target
+    pub fn evaluate(&self, simulation: &Simulation) -> ChallengeResult {
+        let score = simulation.score();
+        ChallengeResult {
+            name: self.name.clone(),
+            ticks: simulation.clock.now(),
+            score,
+            target_score: self.target_score,
+            passed: score >= self.target_score,
+        }
+    }
+}
+
+// Parse and validate a challenge manifest
+pub fn parse_manifest(text: &str) -> Result<Vec<Challenge>, ChallengeError> {
+    let manifest: Manifest = toml::from_str(text).map_err(|err| ChallengeError::Parse(err.to_string()))?;
+    for (i, challenge) in manifest.challenges.iter().enumerate() {
+        challenge.validate()?;
+        if manifest.challenges[..i].iter().any(|other| other.name.eq_ignore_ascii_case(&challenge.name)) {
+            return Err(ChallengeError::DuplicateName(challenge.name.clone()));
+        }
+    }
+    Ok(manifest.challenges)
+}
+
+// Load the manifest from the working directory, falling back to the bundled challenges
+pub fn load_challenges() -> Result<Vec<Challenge>, ChallengeError> {
+    match std::fs::read_to_string(CHALLENGES_PATH) {
+        Ok(text) => parse_manifest(&text),
+        Err(_) => parse_manifest(BUNDLED_CHALLENGES),
+    }
+}
+
+// Look a challenge up by name (case-insensitive)
+pub fn find_challenge(challenges: &[Challenge], name: &str) -> Result<Challenge, ChallengeError> {
+    challenges
+        .iter()
+        .find(|challenge| challenge.name.eq_ignore_ascii_case(name))
+        .cloned()
+        .ok_or_else(|| ChallengeError::NotFound {
+            name: name.to_string(),
+            available: challenges.iter().map(|challenge| challenge.name.clone()).collect(),
+        })
+}
+
+// Append an attempt to the history file, writing the header for a new file
+pub fn append_history(path: &Path, result: &ChallengeResult, timestamp: u64) -> io::Result<()> {
+    let is_new = !path.exists();
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if is_new {
+        writeln!(file, "challenge,timestamp,ticks,score,target,passed")?;
+    }
+    writeln!(file, "{}", result.history_line(timestamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Run a whole challenge without a UI
+    fn run_headless(challenge: &Challenge) -> ChallengeResult {
+        let mut simulation = challenge.build_simulation();
+        while simulation.clock.now() < challenge.tick_limit {
+            simulation.tick();
+        }
+        challenge.evaluate(&simulation)
+    }
+
+    #[test]
+    fn test_bundled_manifest_is_valid() {
+        let challenges = parse_manifest(BUNDLED_CHALLENGES).unwrap();
+        assert!(challenges.len() >= 3);
+        assert!(find_challenge(&challenges, "the labyrinth").is_ok());
+        assert!(find_challenge(&challenges, "Energy Desert").is_ok());
+    }
+
+    #[test]
+    fn test_manifest_validation() {
+        let too_small = "[[challenge]]\nname = \"Tiny\"\nseed = 1\nwidth = 5\nheight = 20\ntick_limit = 10\ntarget_score = 1\n";
+        assert!(matches!(parse_manifest(too_small), Err(ChallengeError::Invalid { .. })));
+
+        let no_ticks = "[[challenge]]\nname = \"Zero\"\nseed = 1\nwidth = 20\nheight = 20\ntick_limit = 0\ntarget_score = 1\n";
+        assert!(matches!(parse_manifest(no_ticks), Err(ChallengeError::Invalid { .. })));
+
+        let entry = "[[challenge]]\nname = \"Twice\"\nseed = 1\nwidth = 20\nheight = 20\ntick_limit = 10\ntarget_score = 1\n";
+        let duplicated = format!("{}{}", entry, entry.replace("Twice", "twice"));
+        assert_eq!(parse_manifest(&duplicated), Err(ChallengeError::DuplicateName("twice".to_string())));
+
+        assert!(matches!(parse_manifest("[[challenge]]\nname = 3\n"), Err(ChallengeError::Parse(_))));
+    }
+
+    #[test]
+    fn test_unknown_challenge_lists_available() {
+        let challenges = parse_manifest(BUNDLED_CHALLENGES).unwrap();
+        match find_challenge(&challenges, "Nope") {
+            Err(ChallengeError::NotFound { available, .. }) => assert_eq!(available.len(), challenges.len()),
+            other => panic!("expected NotFound, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_tiny_challenge_headless_evaluation() {
+        let challenges = parse_manifest(BUNDLED_CHALLENGES).unwrap();
+        let challenge = find_challenge(&challenges, "First Steps").unwrap();
+
+        let result = run_headless(&challenge);
+        assert_eq!(result.ticks, challenge.tick_limit);
+        assert_eq!(result.passed, result.score >= challenge.target_score);
+        assert_eq!(result, run_headless(&challenge)); // Fixed worlds replay identically
+
+        // Same run judged against an easy and an impossible target
+        let easy = Challenge { target_score: 0, ..challenge.clone() };
+        assert!(run_headless(&easy).passed);
+        let impossible = Challenge { target_score: u64::MAX, ..challenge };
+        assert!(!run_headless(&impossible).passed);
+    }
+
+    #[test]
+    fn test_history_append() {
+        let path = std::env::temp_dir().join(format!("rusty_games_history_{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let result = ChallengeResult {
+            name: "First Steps".to_string(),
+            ticks: 200,
+            score: 1800,
+            target_score: 1500,
+            passed: true,
+        };
+        append_history(&path, &result, 1_700_000_000).unwrap();
+        append_history(&path, &result, 1_700_000_100).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 3); // Header written once
+        assert_eq!(lines[1], "\"First Steps\",1700000000,200,1800,1500,true");
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,643 @@
+use std::fmt::Write;
+
+use crate::behavior::BehaviorKind;
+use crate::config::CliOverrides;
+use crate::hash;
+use crate::map::Terrain;
+use crate::preview::SeedCursor;
+use crate::query::Query;
+use crate::ui;
+
+// Name the binary is invoked by in usage and error messages
+pub const PROGRAM: &str = "rusty-games";
+
+// Settings of the interactive subcommands, resolved through the config layers at startup
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SessionArgs {
+    pub overrides: CliOverrides, // --seed, --map, --ticks-per-frame, --wrap, --terrain and --underground
+    pub print_effective_config: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RunArgs {
+    pub session: SessionArgs,
+    pub dump_robots: Option<Query>, // Print the robots matching this query headless instead of starting the UI
+    pub ticks: Option<u64>,         // Ticks run before the dump
+    pub scenario: Option<String>,   // Scenario file setting up the world, station and roster
+    pub print_hash: bool,           // Print the starting world's content hash instead of starting the UI
+    pub snapshot_ticks: Vec<u64>,   // Ticks that get a map thumbnail in the report, besides the exploration milestones
+    pub explain_station: bool,      // Log why the station decides what it does
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviewArgs {
+    pub seeds: SeedCursor,
+    pub session: SessionArgs,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChallengeArgs {
+    pub name: String,
+    pub session: SessionArgs,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchArgs {
+    pub runs: usize,
+    pub ticks: Option<u64>,      // Ticks per run
+    pub out: Option<String>,     // CSV file for the per-run rows
+    pub threads: Option<usize>,  // Runs executed at once
+    pub overrides: CliOverrides, // --seed (the base seed), --wrap and --terrain
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffRunsArgs {
+    pub left: String, // Each a seed or a map file
+    pub right: String,
+}
+
+// What the binary was asked to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Run(RunArgs),
+    Preview(PreviewArgs),
+    Challenge(ChallengeArgs),
+    Tutorial(SessionArgs),
+    Batch(BatchArgs),
+    DiffRuns(DiffRunsArgs),
+    Help(Option<&'static str>), // Overall usage, or that of one subcommand
+}
+
+// One subcommand: its operands as shown in usage, the options it accepts, a one-line summary
+// and why the other options do not apply to it
+struct Subcommand {
+    name: &'static str,
+    operands: &'static str,
+    options: &'static [&'static str],
+    about: &'static str,
+    restriction: &'static str,
+}
+
+const SUBCOMMANDS: [Subcommand; 6] = [
+    Subcommand {
+        name: "run",
+        operands: "",
+        options: &[
+            "--seed", "--map", "--scenario", "--wrap", "--terrain", "--underground", "--explorer-behavior", "--behavior-config", "--pair-explorers",
+            "--malfunction-odds", "--stations", "--ticks-per-frame",
+            "--print-effective-config", "--print-hash", "--dump-robots", "--ticks", "--snapshot-ticks", "--explain-station",
+        ],
+        about: "Explore a generated world or a hand-crafted map (the default when no subcommand is given)",
+        restriction: "it plays a single interactive world",
+    },
+    Subcommand {
+        name: "preview",
+        operands: "<seed|first..last>",
+        options: &["--wrap", "--terrain", "--ticks-per-frame", "--print-effective-config"],
+        about: "Page through generated worlds, then explore the one you pick",
+        restriction: "previews generate their own worlds",
+    },
+    Subcommand {
+        name: "challenge",
+        operands: "<name>",
+        options: &["--ticks-per-frame", "--print-effective-config"],
+        about: "Take on a community challenge from challenges.toml",
+        restriction: "challenge worlds are locked",
+    },
+    Subcommand {
+        name: "tutorial",
+        operands: "",
+        options: &["--ticks-per-frame", "--print-effective-config"],
+        about: "Play the guided tutorial scenario",
+        restriction: "the tutorial plays a fixed scenario",
+    },
+    Subcommand {
+        name: "batch",
+        operands: "<runs>",
+        options: &["--seed", "--wrap", "--terrain", "--ticks", "--out", "--threads"],
+        about: "Run many headless generated worlds and summarize how they ended",
+        restriction: "batch runs are headless generated worlds",
+    },
+    Subcommand {
+        name: "diff-runs",
+        operands: "<left> <right>",
+        options: &[],
+        about: "Run two worlds (seeds or map files) in lockstep and report where they first differ",
+        restriction: "both worlds are given as operands",
+    },
+];
+
+// Every option: its name, value placeholder (empty for switches) and help text
+const OPTIONS: [(&str, &str, &str); 20] = [
+    ("--seed", "<n|word>", "World seed, or any text to derive one from (the base seed of a batch)"),
+    ("--map", "<file>", "Load a hand-crafted map instead of generating one"),
+    ("--scenario", "<file>", "Start from a scenario: map, station and robot roster"),
+    ("--wrap", "", "Turn the map into a torus"),
+    ("--terrain", "<open|caves>", "Kind of generated terrain"),
+    ("--underground", "", "Generate mineral-rich tunnels under the surface, mined through shafts"),
+    ("--explorer-behavior", "<builtin|spiral|sweep>", "How explorers pick their moves"),
+    ("--behavior-config", "<file>", "Tune robot thresholds and scoring weights from a TOML file"),
+    ("--pair-explorers", "", "Send explorers out in pairs that keep in formation and report each other's loss"),
+    ("--malfunction-odds", "<n>", "A robot in the field malfunctions with a chance of 1 in n a tick (0 for never)"),
+    ("--stations", "<n>", "Number of bases, each with a fleet of its own"),
+    ("--ticks-per-frame", "<n>", "Starting simulation speed"),
+    ("--print-effective-config", "", "Print every resolved setting with its source and exit"),
+    ("--print-hash", "", "Print the seed, size and content hash of the starting world and exit"),
+    ("--ticks", "<n>", "Ticks each batch run lasts, or run before --dump-robots"),
+    ("--dump-robots", "<query>", "Run headless and print the robots matching a fleet query"),
+    ("--snapshot-ticks", "<n,n,...>", "Ticks that get a map thumbnail in the end report"),
+    ("--explain-station", "", "Log why the station builds, refines and sends explorers where it does"),
+    ("--out", "<file>", "Write one CSV row per batch run"),
+    ("--threads", "<n>", "Batch runs executed at once (all cores by default)"),
+];
+
+// Flags that selected a mode before subcommands existed
+const FORMER_MODE_FLAGS: [&str; 5] = ["--preview", "--challenge", "--tutorial", "--batch", "--diff-runs"];
+
+// Parse `[<subcommand>] [operands] [options]`; without a subcommand the arguments belong to `run`.
+// Errors name the subcommand and point at its help
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let (subcommand, rest) = match args.first().map(String::as_str) {
+        Some("help") => return help_topic(args.get(1)),
+        Some(name) if !name.starts_with('-') => match find_subcommand(name) {
+            Some(subcommand) => (subcommand, &args[1..]),
+            None => return Err(format!("unknown subcommand '{}'\nRun `{} help` for usage.", name, PROGRAM)),
+        },
+        _ => (&SUBCOMMANDS[0], args),
+    };
+    if rest.iter().any(|arg| arg == "--help" || arg == "-h") {
+        return Ok(Command::Help(Some(subcommand.name)));
+    }
+    parse_subcommand(subcommand, rest)
+        .map_err(|err| format!("{}: {}\nRun `{} help {}` for usage.", subcommand.name, err, PROGRAM, subcommand.name))
+}
+
+fn find_subcommand(name: &str) -> Option<&'static Subcommand> {
+    SUBCOMMANDS.iter().find(|subcommand| subcommand.name == name)
+}
+
+fn help_topic(topic: Option<&String>) -> Result<Command, String> {
+    match topic {
+        None => Ok(Command::Help(None)),
+        Some(name) => match find_subcommand(name) {
+            Some(subcommand) => Ok(Command::Help(Some(subcommand.name))),
+            None => Err(format!("no help for unknown subcommand '{}'\nRun `{} help` for usage.", name, PROGRAM)),
+        },
+    }
+}
+
+// Operands and option values found after the subcommand name
+#[derive(Default)]
+struct Arguments {
+    operands: Vec<String>,
+    session: SessionArgs,
+    dump_robots: Option<Query>,
+    ticks: Option<u64>,
+    scenario: Option<String>,
+    print_hash: bool,
+    snapshot_ticks: Vec<u64>,
+    explain_station: bool,
+    out: Option<String>,
+    threads: Option<usize>,
+}
+
+fn parse_subcommand(subcommand: &Subcommand, args: &[String]) -> Result<Command, String> {
+    let arguments = parse_arguments(subcommand, args)?;
+    let overrides = &arguments.session.overrides;
+    let command = match subcommand.name {
+        "run" => {
+            expect_operands(subcommand, &arguments.operands, 0)?;
+            if overrides.map_file.is_some() && overrides.seed.is_some() {
+                return Err("--seed has no effect on a map loaded with --map".to_string());
+            }
+            for (flag, given) in [("--terrain", overrides.terrain.is_some()), ("--underground", overrides.underground.is_some())] {
+                if overrides.map_file.is_some() && given {
+                    return Err(format!("{} has no effect on a map loaded with --map", flag));
+                }
+            }
+            if arguments.scenario.is_some() {
+                for (flag, given) in [
+                    ("--map", overrides.map_file.is_some()),
+                    ("--seed", overrides.seed.is_some()),
+                    ("--terrain", overrides.terrain.is_some()),
+                    ("--underground", overrides.underground.is_some()),
+                ] {
+                    if given {
+                        return Err(format!("{} has no effect on a scenario, which sets up its own world", flag));
+                    }
+                }
+            }
+            if arguments.dump_robots.is_none() && arguments.ticks.is_some() {
+                return Err("--ticks only applies with --dump-robots".to_string());
+            }
+            if arguments.dump_robots.is_some() && arguments.session.print_effective_config {
+                return Err("--dump-robots cannot be combined with --print-effective-config".to_string());
+            }
+            for (flag, given) in [("--dump-robots", arguments.dump_robots.is_some()), ("--print-effective-config", arguments.session.print_effective_config)] {
+                if arguments.print_hash && given {
+                    return Err(format!("--print-hash cannot be combined with {}", flag));
+                }
+            }
+            for (flag, given) in [("--snapshot-ticks", !arguments.snapshot_ticks.is_empty()), ("--explain-station", arguments.explain_station)] {
+                if given && (arguments.dump_robots.is_some() || arguments.print_hash) {
+                    return Err(format!("{} only applies to an interactive run", flag));
+                }
+            }
+            Command::Run(RunArgs {
+                session: arguments.session,
+                dump_robots: arguments.dump_robots,
+                ticks: arguments.ticks,
+                scenario: arguments.scenario,
+                print_hash: arguments.print_hash,
+                snapshot_ticks: arguments.snapshot_ticks,
+                explain_station: arguments.explain_station,
+            })
+        }
+        "preview" => {
+            expect_operands(subcommand, &arguments.operands, 1)?;
+            Command::Preview(PreviewArgs { seeds: SeedCursor::parse(&arguments.operands[0])?, session: arguments.session })
+        }
+        "challenge" => {
+            // An unquoted name spanning several words is taken as a whole
+            if arguments.operands.is_empty() {
+                return Err(format!("expected {}", subcommand.operands));
+            }
+            Command::Challenge(ChallengeArgs { name: arguments.operands.join(" "), session: arguments.session })
+        }
+        "tutorial" => {
+            expect_operands(subcommand, &arguments.operands, 0)?;
+            Command::Tutorial(arguments.session)
+        }
+        "batch" => {
+            expect_operands(subcommand, &arguments.operands, 1)?;
+            let runs = match arguments.operands[0].parse::<usize>() {
+                Ok(runs) if runs > 0 => runs,
+                _ => return Err(format!("'{}' is not a number of runs", arguments.operands[0])),
+            };
+            Command::Batch(BatchArgs {
+                runs,
+                ticks: arguments.ticks,
+                out: arguments.out,
+                threads: arguments.threads,
+                overrides: arguments.session.overrides,
+            })
+        }
+        _ => {
+            expect_operands(subcommand, &arguments.operands, 2)?;
+            let mut operands = arguments.operands.into_iter();
+            let (left, right) = (operands.next().unwrap_or_default(), operands.next().unwrap_or_default());
+            Command::DiffRuns(DiffRunsArgs { left, right })
+        }
+    };
+    Ok(command)
+}
+
+fn expect_operands(subcommand: &Subcommand, operands: &[String], count: usize) -> Result<(), String> {
+    if operands.len() == count {
+        return Ok(());
+    }
+    match count {
+        0 => Err(format!("unexpected argument '{}'", operands[0])),
+        _ => Err(format!("expected {}", subcommand.operands)),
+    }
+}
+
+// Collect operands and options, rejecting options the subcommand does not take
+fn parse_arguments(subcommand: &Subcommand, args: &[String]) -> Result<Arguments, String> {
+    let mut arguments = Arguments::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let flag = arg.as_str();
+        if !flag.starts_with("--") {
+            arguments.operands.push(arg.clone());
+            continue;
+        }
+        if FORMER_MODE_FLAGS.contains(&flag) {
+            return Err(format!("{} is now a subcommand: use `{} {} ...`", flag, PROGRAM, &flag[2..]));
+        }
+        if !OPTIONS.iter().any(|(name, _, _)| *name == flag) {
+            return Err(format!("unknown option '{}'", flag));
+        }
+        if !subcommand.options.contains(&flag) {
+            return Err(format!("{} does not apply here: {}", flag, subcommand.restriction));
+        }
+        let overrides = &mut arguments.session.overrides;
+        match flag {
+            "--map" => match iter.next() {
+                Some(path) => overrides.map_file = Some(path.clone()),
+                None => return Err("--map requires a file path".to_string()),
+            },
+            "--seed" => match iter.next().filter(|seed| !seed.trim().is_empty()) {
+                Some(seed) => overrides.seed = Some(hash::seed_from_text(seed)),
+                None => return Err("--seed requires a number or a word".to_string()),
+            },
+            "--ticks-per-frame" => match iter.next().map(|ticks| ticks.parse::<u32>()) {
+                Some(Ok(ticks)) if (1..=ui::MAX_TICKS_PER_FRAME).contains(&ticks) => overrides.ticks_per_frame = Some(ticks),
+                _ => return Err(format!("--ticks-per-frame requires a number from 1 to {}", ui::MAX_TICKS_PER_FRAME)),
+            },
+            "--wrap" => overrides.wrap = Some(true),
+            "--underground" => overrides.underground = Some(true),
+            "--pair-explorers" => overrides.pair_explorers = Some(true),
+            "--terrain" => match iter.next() {
+                Some(name) => overrides.terrain = Some(Terrain::parse(name)?),
+                None => return Err("--terrain requires open or caves".to_string()),
+            },
+            "--explorer-behavior" => match iter.next() {
+                Some(name) => overrides.explorer_behavior = Some(BehaviorKind::parse(name)?),
+                None => return Err("--explorer-behavior requires builtin, spiral or sweep".to_string()),
+            },
+            "--malfunction-odds" => match iter.next().map(|odds| odds.parse::<u32>()) {
+                Some(Ok(odds)) => overrides.malfunction_odds = Some(odds),
+                _ => return Err("--malfunction-odds requires a number (0 for no malfunctions)".to_string()),
+            },
+            "--stations" => match iter.next().map(|count| count.parse::<usize>()) {
+                Some(Ok(count)) if count > 0 => overrides.stations = Some(count),
+                _ => return Err("--stations requires a number of at least 1".to_string()),
+            },
+            "--behavior-config" => match iter.next() {
+                Some(path) => overrides.behavior_config = Some(path.clone()),
+                None => return Err("--behavior-config requires a file path".to_string()),
+            },
+            "--print-effective-config" => arguments.session.print_effective_config = true,
+            "--print-hash" => arguments.print_hash = true,
+            "--explain-station" => arguments.explain_station = true,
+            "--snapshot-ticks" => {
+                let ticks = iter.next().map(|list| list.split(',').map(|tick| tick.trim().parse::<u64>()).collect::<Result<Vec<_>, _>>());
+                match ticks {
+                    Some(Ok(ticks)) if !ticks.contains(&0) => arguments.snapshot_ticks = ticks,
+                    _ => return Err("--snapshot-ticks requires tick numbers like 500,1000".to_string()),
+                }
+            }
+            "--ticks" => match iter.next().map(|ticks| ticks.parse::<u64>()) {
+                Some(Ok(ticks)) if ticks > 0 => arguments.ticks = Some(ticks),
+                _ => return Err("--ticks requires a number of ticks".to_string()),
+            },
+            "--dump-robots" => match iter.next() {
+                Some(query) => arguments.dump_robots = Some(Query::parse(query).map_err(|err| format!("bad --dump-robots query: {}", err))?),
+                None => return Err("--dump-robots requires a query like \"energy<20\"".to_string()),
+            },
+            "--scenario" => match iter.next() {
+                Some(path) => arguments.scenario = Some(path.clone()),
+                None => return Err("--scenario requires a file path".to_string()),
+            },
+            "--out" => match iter.next() {
+                Some(path) => arguments.out = Some(path.clone()),
+                None => return Err("--out requires a file path".to_string()),
+            },
+            _ => match iter.next().map(|threads| threads.parse::<usize>()) {
+                Some(Ok(threads)) if threads > 0 => arguments.threads = Some(threads),
+                _ => return Err("--threads requires a number of threads".to_string()),
+            },
+        }
+    }
+    Ok(arguments)
+}
+
+// Usage text: every subcommand, or one subcommand with its options
+pub fn help(topic: Option<&str>) -> String {
+    let mut text = String::new();
+    match topic.and_then(find_subcommand) {
+        None => {
+            let _ = writeln!(text, "Usage: {} [<subcommand>] [options]\n\nSubcommands:", PROGRAM);
+            for subcommand in &SUBCOMMANDS {
+                let _ = writeln!(text, "  {:<32} {}", format!("{} {}", subcommand.name, subcommand.operands), subcommand.about);
+            }
+            let _ = writeln!(text, "\nRun `{} help <subcommand>` for its options.", PROGRAM);
+        }
+        Some(subcommand) => {
+            let _ = writeln!(text, "Usage: {} {} {}[options]", PROGRAM, subcommand.name, format_operands(subcommand));
+            let _ = writeln!(text, "{}", subcommand.about);
+            if !subcommand.options.is_empty() {
+                let _ = writeln!(text, "\nOptions:");
+            }
+            for (name, value, about) in OPTIONS.iter().filter(|(name, _, _)| subcommand.options.contains(name)) {
+                let _ = writeln!(text, "  {:<32} {}", format!("{} {}", name, value), about);
+            }
+        }
+    }
+    text
+}
+
+fn format_operands(subcommand: &Subcommand) -> String {
+    match subcommand.operands {
+        "" => String::new(),
+        operands => format!("{} ", operands),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn parse_str(values: &[&str]) -> Result<Command, String> {
+        parse(&args(values))
+    }
+
+    fn run(values: &[&str]) -> RunArgs {
+        match parse_str(values) {
+            Ok(Command::Run(run)) => run,
+            other => panic!("expected run, got {:?}", other),
+        }
+    }
+
+    fn session(values: &[&str]) -> SessionArgs {
+        run(values).session
+    }
+
+    #[test]
+    fn test_run_is_the_default() {
+        assert_eq!(parse_str(&[]), Ok(Command::Run(RunArgs::default())));
+        assert_eq!(parse_str(&["--seed", "12"]), parse_str(&["run", "--seed", "12"]));
+        assert_eq!(session(&["--map", "maze.txt"]).overrides.map_file, Some("maze.txt".to_string()));
+        assert_eq!(session(&["--seed", "12"]).overrides.seed, Some(12));
+        assert_eq!(session(&["--ticks-per-frame", "4"]).overrides.ticks_per_frame, Some(4));
+        assert_eq!(session(&["--wrap"]).overrides.wrap, Some(true));
+        assert_eq!(session(&["--terrain", "caves"]).overrides.terrain, Some(Terrain::Caves));
+        assert_eq!(session(&["--underground"]).overrides.underground, Some(true));
+        assert_eq!(session(&["--explorer-behavior", "spiral"]).overrides.explorer_behavior, Some(BehaviorKind::Spiral));
+        assert!(session(&["--print-effective-config"]).print_effective_config);
+    }
+
+    #[test]
+    fn test_run_validation() {
+        assert_eq!(session(&["--seed", "lava lake"]).overrides.seed, Some(hash::seed_from_text("lava lake")));
+        assert!(parse_str(&["--seed", " "]).is_err());
+        assert!(parse_str(&["--ticks-per-frame", "0"]).is_err());
+        assert!(parse_str(&["--map"]).is_err());
+        assert!(parse_str(&["--terrain", "lava"]).is_err());
+        assert!(parse_str(&["--explorer-behavior", "zigzag"]).is_err());
+        assert_eq!(session(&["--behavior-config", "tuning.toml"]).overrides.behavior_config, Some("tuning.toml".to_string()));
+        assert!(parse_str(&["--behavior-config"]).is_err());
+        assert_eq!(session(&["--pair-explorers"]).overrides.pair_explorers, Some(true));
+        assert_eq!(session(&["--malfunction-odds", "0"]).overrides.malfunction_odds, Some(0));
+        assert!(parse_str(&["--malfunction-odds", "rare"]).is_err());
+        assert_eq!(session(&["--stations", "2"]).overrides.stations, Some(2));
+        assert!(parse_str(&["--stations", "0"]).is_err());
+        assert!(parse_str(&["--bogus"]).unwrap_err().contains("unknown option '--bogus'"));
+        assert!(parse_str(&["run", "extra"]).unwrap_err().contains("unexpected argument 'extra'"));
+        assert!(parse_str(&["--map", "maze.txt", "--seed", "3"]).is_err());
+        assert!(parse_str(&["--terrain", "caves", "--map", "maze.txt"]).is_err());
+        assert!(parse_str(&["--underground", "--map", "maze.txt"]).unwrap_err().contains("--underground has no effect"));
+        assert!(parse_str(&["--scenario", "a.toml", "--underground"]).is_err());
+        assert!(parse_str(&["preview", "5", "--underground"]).unwrap_err().contains("does not apply here"));
+        assert!(parse_str(&["run", "--out", "x.csv"]).unwrap_err().contains("--out does not apply here"));
+        assert!(parse_str(&["explore"]).unwrap_err().contains("unknown subcommand 'explore'"));
+    }
+
+    #[test]
+    fn test_parse_dump_robots() {
+        let dump = run(&["--seed", "3", "--ticks", "500", "--dump-robots", "type:explorer energy<20"]);
+        assert_eq!(dump.dump_robots, Some(Query::parse("type:explorer energy<20").unwrap()));
+        assert_eq!((dump.ticks, dump.session.overrides.seed), (Some(500), Some(3)));
+        assert!(parse_str(&["--dump-robots", "wear<3"]).unwrap_err().contains("bad --dump-robots query: unknown field 'wear'"));
+        assert!(parse_str(&["--dump-robots"]).is_err());
+        assert!(parse_str(&["--ticks", "500"]).unwrap_err().contains("only applies with --dump-robots"));
+        assert!(parse_str(&["--dump-robots", "", "--print-effective-config"]).is_err());
+        assert!(parse_str(&["tutorial", "--dump-robots", "energy<20"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_print_hash() {
+        let hash = run(&["--seed", "3", "--terrain", "caves", "--print-hash"]);
+        assert!(hash.print_hash);
+        assert_eq!(hash.session.overrides.seed, Some(3));
+        assert!(run(&["--scenario", "a.toml", "--print-hash"]).print_hash);
+        assert!(parse_str(&["--print-hash", "--dump-robots", "energy<20"]).unwrap_err().contains("cannot be combined with --dump-robots"));
+        assert!(parse_str(&["--print-hash", "--print-effective-config"]).is_err());
+        assert!(parse_str(&["preview", "5", "--print-hash"]).unwrap_err().contains("does not apply here"));
+    }
+
+    #[test]
+    fn test_parse_snapshot_ticks() {
+        assert_eq!(run(&["--snapshot-ticks", "500, 1000,200"]).snapshot_ticks, vec![500, 1000, 200]);
+        assert!(run(&[]).snapshot_ticks.is_empty());
+        for bad in ["", "0", "10,x", "-5"] {
+            assert!(parse_str(&["--snapshot-ticks", bad]).is_err(), "{:?}", bad);
+        }
+        assert!(parse_str(&["--snapshot-ticks"]).is_err());
+        assert!(parse_str(&["--snapshot-ticks", "10", "--print-hash"]).unwrap_err().contains("interactive run"));
+        assert!(parse_str(&["tutorial", "--snapshot-ticks", "10"]).unwrap_err().contains("does not apply here"));
+        assert!(run(&["--explain-station"]).explain_station);
+        assert!(parse_str(&["--explain-station", "--print-hash"]).unwrap_err().contains("--explain-station only applies"));
+    }
+
+    #[test]
+    fn test_parse_scenario() {
+        let scenario = run(&["--scenario", "scenarios/crossroads.toml", "--wrap"]);
+        assert_eq!(scenario.scenario, Some("scenarios/crossroads.toml".to_string()));
+        assert_eq!(scenario.session.overrides.wrap, Some(true));
+        assert!(parse_str(&["--scenario"]).is_err());
+        let err = parse_str(&["--scenario", "a.toml", "--seed", "3"]).unwrap_err();
+        assert!(err.contains("--seed has no effect on a scenario"), "{}", err);
+        assert!(parse_str(&["--map", "maze.txt", "--scenario", "a.toml"]).is_err());
+        assert!(parse_str(&["preview", "3", "--scenario", "a.toml"]).is_err());
+    }
+
+    #[test]
+    fn test_former_mode_flags_point_at_their_subcommand() {
+        for flag in FORMER_MODE_FLAGS {
+            let err = parse_str(&[flag, "5"]).unwrap_err();
+            assert!(err.contains(&format!("`{} {} ...`", PROGRAM, &flag[2..])), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_parse_preview() {
+        let seeds = SeedCursor { first: 5, last: 9, current: 5 };
+        assert_eq!(
+            parse_str(&["preview", "5..9", "--terrain", "caves"]),
+            Ok(Command::Preview(PreviewArgs {
+                seeds,
+                session: SessionArgs {
+                    overrides: CliOverrides { terrain: Some(Terrain::Caves), ..CliOverrides::default() },
+                    print_effective_config: false,
+                },
+            }))
+        );
+        assert!(parse_str(&["preview"]).unwrap_err().contains("expected <seed|first..last>"));
+        assert!(parse_str(&["preview", "5", "6"]).is_err());
+        assert!(parse_str(&["preview", "5", "--map", "maze.txt"]).unwrap_err().contains("generate their own worlds"));
+        assert!(parse_str(&["preview", "5", "--seed", "3"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_challenge() {
+        let challenge = |values: &[&str]| match parse_str(values) {
+            Ok(Command::Challenge(challenge)) => challenge,
+            other => panic!("expected challenge, got {:?}", other),
+        };
+        assert_eq!(challenge(&["challenge", "First Steps"]).name, "First Steps");
+        assert_eq!(challenge(&["challenge", "The", "Labyrinth"]).name, "The Labyrinth");
+        // Speed does not affect the outcome, so it stays adjustable
+        assert_eq!(challenge(&["challenge", "First Steps", "--ticks-per-frame", "20"]).session.overrides.ticks_per_frame, Some(20));
+        assert!(parse_str(&["challenge"]).is_err());
+        for locked in [&["--map", "maze.txt"][..], &["--seed", "3"], &["--wrap"], &["--terrain", "caves"]] {
+            let mut combined = args(&["challenge", "First Steps"]);
+            combined.extend(args(locked));
+            assert!(parse(&combined).unwrap_err().contains("locked"), "challenge accepted {:?}", locked);
+        }
+    }
+
+    #[test]
+    fn test_parse_tutorial() {
+        assert_eq!(parse_str(&["tutorial"]), Ok(Command::Tutorial(SessionArgs::default())));
+        assert!(parse_str(&["tutorial", "--ticks-per-frame", "3"]).is_ok());
+        for other in [&["--map", "maze.txt"][..], &["--seed", "3"], &["--wrap"], &["--terrain", "caves"], &["extra"]] {
+            let mut combined = args(&["tutorial"]);
+            combined.extend(args(other));
+            assert!(parse(&combined).is_err(), "tutorial accepted {:?}", other);
+        }
+    }
+
+    #[test]
+    fn test_parse_batch() {
+        let batch = match parse_str(&["batch", "50", "--ticks", "10000", "--out", "results.csv", "--seed", "7"]) {
+            Ok(Command::Batch(batch)) => batch,
+            other => panic!("expected batch, got {:?}", other),
+        };
+        assert_eq!((batch.runs, batch.ticks, batch.out.as_deref()), (50, Some(10000), Some("results.csv")));
+        assert_eq!(batch.overrides.seed, Some(7));
+        assert!(parse_str(&["batch", "3", "--threads", "2", "--wrap", "--terrain", "caves"]).is_ok());
+        assert!(parse_str(&["batch", "0"]).unwrap_err().contains("not a number of runs"));
+        assert!(parse_str(&["batch"]).is_err());
+        assert!(parse_str(&["batch", "3", "--threads", "0"]).is_err());
+        let err = parse_str(&["batch", "3", "--map", "maze.txt"]).unwrap_err();
+        assert!(err.starts_with("batch: --map does not apply here"), "{}", err);
+        assert!(err.ends_with(&format!("`{} help batch` for usage.", PROGRAM)));
+        assert!(parse_str(&["batch", "3", "--ticks-per-frame", "2"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_diff_runs() {
+        assert_eq!(
+            parse_str(&["diff-runs", "7", "maps/example.txt"]),
+            Ok(Command::DiffRuns(DiffRunsArgs { left: "7".to_string(), right: "maps/example.txt".to_string() }))
+        );
+        assert!(parse_str(&["diff-runs", "7"]).unwrap_err().contains("expected <left> <right>"));
+        assert!(parse_str(&["diff-runs", "7", "8", "9"]).is_err());
+        assert!(parse_str(&["diff-runs", "7", "8", "--seed", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_help() {
+        assert_eq!(parse_str(&["help"]), Ok(Command::Help(None)));
+        assert_eq!(parse_str(&["help", "batch"]), Ok(Command::Help(Some("batch"))));
+        assert_eq!(parse_str(&["batch", "--help"]), Ok(Command::Help(Some("batch"))));
+        assert_eq!(parse_str(&["-h"]), Ok(Command::Help(Some("run"))));
+        assert!(parse_str(&["help", "bogus"]).is_err());
+
+        let overview = help(None);
+        assert!(SUBCOMMANDS.iter().all(|subcommand| overview.contains(subcommand.about)));
+        let batch = help(Some("batch"));
+        assert!(batch.starts_with(&format!("Usage: {} batch <runs> [options]", PROGRAM)));
+        assert!(batch.contains("--threads <n>"));
+        assert!(!batch.contains("--map"));
+        // An option too wide for its column is still set apart from its help text
+        assert!(help(Some("run")).contains("--explorer-behavior <builtin|spiral|sweep> How explorers pick their moves"));
+        assert!(help(Some("run")).contains("--underground"));
+    }
+}
@@ -0,0 +1,100 @@
+// Simulation time: the current tick, periodic checks against it and work deferred to a later
+// tick. Time-based systems ask the clock instead of doing tick arithmetic themselves, so a test
+// can fast-forward it and have scheduled work come due without running every tick in between
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimClock<W> {
+    now: u64,
+    deferred: Vec<(u64, W)>, // Sorted by due tick, in registration order within a tick
+}
+
+impl<W> Default for SimClock<W> {
+    fn default() -> Self {
+        Self { now: 0, deferred: Vec::new() }
+    }
+}
+
+impl<W> SimClock<W> {
+    // Ticks run so far
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    // One tick further
+    pub fn advance(&mut self) {
+        self.now += 1;
+    }
+
+    // Skip `ticks` ticks at once. Nothing runs for the ticks skipped; deferred work they passed
+    // is due on the next take_due
+    #[cfg(test)]
+    pub fn fast_forward(&mut self, ticks: u64) {
+        self.now += ticks;
+    }
+
+    // Whether this tick is one of every `interval` ticks, not counting the very first
+    pub fn every(&self, interval: u64) -> bool {
+        self.now > 0 && interval > 0 && self.now.is_multiple_of(interval)
+    }
+
+    // Whether `tick` has been reached
+    pub fn reached(&self, tick: u64) -> bool {
+        self.now >= tick
+    }
+
+    // Defer work to `tick`; work due in the past comes due right away
+    pub fn at(&mut self, tick: u64, work: W) {
+        let index = self.deferred.partition_point(|&(due, _)| due <= tick);
+        self.deferred.insert(index, (tick, work));
+    }
+
+    // Deferred work still waiting
+    #[cfg(test)]
+    pub fn pending(&self) -> usize {
+        self.deferred.len()
+    }
+
+    // Remove and return the deferred work that has come due, as (due tick, work) in due order
+    pub fn take_due(&mut self) -> Vec<(u64, W)> {
+        let due = self.deferred.partition_point(|&(due, _)| due <= self.now);
+        self.deferred.drain(..due).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_periodic_checks() {
+        let mut clock: SimClock<()> = SimClock::default();
+        assert!(!clock.every(5)); // Not on the very first tick
+        let mut fired = Vec::new();
+        for _ in 0..20 {
+            clock.advance();
+            if clock.every(5) {
+                fired.push(clock.now());
+            }
+        }
+        assert_eq!(fired, vec![5, 10, 15, 20]);
+        assert!(!clock.every(0));
+        assert!(clock.reached(20) && !clock.reached(21));
+    }
+
+    #[test]
+    fn test_deferred_work_comes_due_in_order() {
+        let mut clock = SimClock::default();
+        clock.at(10, "b");
+        clock.at(3, "a");
+        clock.at(10, "c"); // Same tick, registered later
+        assert_eq!(clock.pending(), 3);
+        assert!(clock.take_due().is_empty());
+
+        clock.fast_forward(9);
+        assert_eq!(clock.take_due(), vec![(3, "a")]);
+        clock.advance();
+        assert_eq!(clock.take_due(), vec![(10, "b"), (10, "c")]);
+        clock.at(2, "late"); // Already past: due at once
+        assert_eq!(clock.take_due(), vec![(2, "late")]);
+        assert_eq!(clock.pending(), 0);
+    }
+}
@@ -0,0 +1,281 @@
+use std::fmt;
+
+use crate::behavior::BehaviorKind;
+use crate::challenge::Challenge;
+use crate::map::Terrain;
+use crate::simulation::DEFAULT_MALFUNCTION_ODDS;
+
+// Ticks batched per frame and frame duration when nothing else is requested
+pub const DEFAULT_TICKS_PER_FRAME: u32 = 1;
+pub const DEFAULT_FRAME_TIME_MS: u64 = 100; // Even faster updates for more aggressive exploration
+
+// Where the value of a setting came from, lowest to highest precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    Cli,
+    Preview,   // Seed accepted in the preview browser
+    Startup,   // Seed typed on the startup screen
+    Challenge, // Locked by the selected challenge
+}
+
+impl ConfigSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            ConfigSource::Default => "default",
+            ConfigSource::Cli => "cli",
+            ConfigSource::Preview => "preview",
+            ConfigSource::Startup => "startup",
+            ConfigSource::Challenge => "challenge",
+        }
+    }
+}
+
+// A resolved value together with the layer that set it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: ConfigSource,
+}
+
+impl<T> Setting<T> {
+    fn default(value: T) -> Self {
+        Self { value, source: ConfigSource::Default }
+    }
+
+    // Replace the value if a layer provides one
+    fn layer(&mut self, value: Option<T>, source: ConfigSource) {
+        if let Some(value) = value {
+            *self = Self { value, source };
+        }
+    }
+}
+
+// Settings requested on the command line (None when the flag was not given)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CliOverrides {
+    pub seed: Option<u32>,
+    pub map_file: Option<String>,
+    pub ticks_per_frame: Option<u32>,
+    pub wrap: Option<bool>,
+    pub terrain: Option<Terrain>,
+    pub underground: Option<bool>,
+    pub explorer_behavior: Option<BehaviorKind>,
+    pub behavior_config: Option<String>,
+    pub malfunction_odds: Option<u32>,
+    pub pair_explorers: Option<bool>,
+    pub stations: Option<usize>,
+}
+
+// Fully resolved run settings. `None` values are filled in at startup
+// (a random seed, a map sized to the terminal, no tick limit)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimConfig {
+    pub seed: Setting<Option<u32>>,
+    pub map_width: Setting<Option<usize>>,
+    pub map_height: Setting<Option<usize>>,
+    pub map_file: Setting<Option<String>>,
+    pub tick_limit: Setting<Option<u64>>,
+    pub wrap: Setting<bool>, // Torus map: robots leaving one edge enter at the opposite one
+    pub terrain: Setting<Terrain>, // How generated maps shape their rock
+    pub underground: Setting<bool>, // Generated maps get a tunnel layer reached through shafts
+    pub explorer_behavior: Setting<BehaviorKind>, // How explorers pick their moves
+    pub behavior_config: Setting<Option<String>>, // TOML file tuning robot thresholds and weights
+    pub pair_explorers: Setting<bool>, // Buddy system: explorers go out in pairs
+    pub malfunction_odds: Setting<u32>, // A robot in the field malfunctions with a chance of 1 in this many a tick; 0 never
+    pub stations: Setting<usize>, // Bases, each with a fleet of its own
+    pub ticks_per_frame: Setting<u32>,
+    pub frame_time_ms: Setting<u64>,
+}
+
+impl SimConfig {
+    // Layer defaults, then command-line flags, then the challenge's locked world
+    pub fn resolve(cli: &CliOverrides, challenge: Option<&Challenge>) -> Self {
+        let mut config = Self {
+            seed: Setting::default(None),
+            map_width: Setting::default(None),
+            map_height: Setting::default(None),
+            map_file: Setting::default(None),
+            tick_limit: Setting::default(None),
+            wrap: Setting::default(false),
+            terrain: Setting::default(Terrain::Open),
+            underground: Setting::default(false),
+            explorer_behavior: Setting::default(BehaviorKind::Builtin),
+            behavior_config: Setting::default(None),
+            pair_explorers: Setting::default(false),
+            malfunction_odds: Setting::default(DEFAULT_MALFUNCTION_ODDS),
+            stations: Setting::default(1),
+            ticks_per_frame: Setting::default(DEFAULT_TICKS_PER_FRAME),
+            frame_time_ms: Setting::default(DEFAULT_FRAME_TIME_MS),
+        };
+
+        config.seed.layer(cli.seed.map(Some), ConfigSource::Cli);
+        config.map_file.layer(cli.map_file.clone().map(Some), ConfigSource::Cli);
+        config.ticks_per_frame.layer(cli.ticks_per_frame, ConfigSource::Cli);
+        config.wrap.layer(cli.wrap, ConfigSource::Cli);
+        config.terrain.layer(cli.terrain, ConfigSource::Cli);
+        config.underground.layer(cli.underground, ConfigSource::Cli);
+        config.explorer_behavior.layer(cli.explorer_behavior, ConfigSource::Cli);
+        config.behavior_config.layer(cli.behavior_config.clone().map(Some), ConfigSource::Cli);
+        config.pair_explorers.layer(cli.pair_explorers, ConfigSource::Cli);
+        config.malfunction_odds.layer(cli.malfunction_odds, ConfigSource::Cli);
+        config.stations.layer(cli.stations, ConfigSource::Cli);
+
+        if let Some(challenge) = challenge {
+            config.seed.layer(Some(Some(challenge.seed)), ConfigSource::Challenge);
+            config.map_width.layer(Some(Some(challenge.width)), ConfigSource::Challenge);
+            config.map_height.layer(Some(Some(challenge.height)), ConfigSource::Challenge);
+            config.tick_limit.layer(Some(Some(challenge.tick_limit)), ConfigSource::Challenge);
+        }
+        config
+    }
+
+    // Record the seed picked in the preview browser
+    pub fn set_preview_seed(&mut self, seed: u32) {
+        self.seed.layer(Some(Some(seed)), ConfigSource::Preview);
+    }
+
+    // Record the seed typed on the startup screen
+    pub fn set_startup_seed(&mut self, seed: u32) {
+        self.seed.layer(Some(Some(seed)), ConfigSource::Startup);
+    }
+
+    // (name, value, source) for every setting, in display order
+    pub fn rows(&self) -> Vec<(&'static str, String, ConfigSource)> {
+        fn or_auto<T: fmt::Display>(value: &Option<T>, auto: &str) -> String {
+            value.as_ref().map_or_else(|| auto.to_string(), |value| value.to_string())
+        }
+        vec![
+            ("seed", or_auto(&self.seed.value, "random"), self.seed.source),
+            ("map_width", or_auto(&self.map_width.value, "terminal"), self.map_width.source),
+            ("map_height", or_auto(&self.map_height.value, "terminal"), self.map_height.source),
+            (
+                "map_file",
+                self.map_file.value.as_ref().map_or_else(|| "none".to_string(), |path| format!("{:?}", path)),
+                self.map_file.source,
+            ),
+            ("tick_limit", or_auto(&self.tick_limit.value, "none"), self.tick_limit.source),
+            ("wrap", self.wrap.value.to_string(), self.wrap.source),
+            ("terrain", self.terrain.value.to_string(), self.terrain.source),
+            ("underground", self.underground.value.to_string(), self.underground.source),
+            ("explorer_behavior", self.explorer_behavior.value.to_string(), self.explorer_behavior.source),
+            (
+                "behavior_config",
+                self.behavior_config.value.as_ref().map_or_else(|| "defaults".to_string(), |path| format!("{:?}", path)),
+                self.behavior_config.source,
+            ),
+            ("pair_explorers", self.pair_explorers.value.to_string(), self.pair_explorers.source),
+            ("malfunction_odds", self.malfunction_odds.value.to_string(), self.malfunction_odds.source),
+            ("stations", self.stations.value.to_string(), self.stations.source),
+            ("ticks_per_frame", self.ticks_per_frame.value.to_string(), self.ticks_per_frame.source),
+            ("frame_time_ms", self.frame_time_ms.value.to_string(), self.frame_time_ms.source),
+        ]
+    }
+
+    // (name, value) of the settings that decide how the world plays out, leaving out the
+    // display speed: runs sharing these can be compared with each other
+    pub fn determinism_settings(&self) -> Vec<(&'static str, String)> {
+        self.rows()
+            .into_iter()
+            .filter(|(name, _, _)| !matches!(*name, "ticks_per_frame" | "frame_time_ms"))
+            .map(|(name, value, _)| (name, value))
+            .collect()
+    }
+}
+
+// One `name = value  # source` line per setting
+impl fmt::Display for SimConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value, source) in self.rows() {
+            writeln!(f, "{:<17} = {:<12} # {}", name, value, source.label())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge() -> Challenge {
+        Challenge {
+            name: "Test".to_string(),
+            description: String::new(),
+            seed: 42,
+            width: 50,
+            height: 20,
+            tick_limit: 500,
+            target_score: 100,
+        }
+    }
+
+    fn source_of(config: &SimConfig, name: &str) -> ConfigSource {
+        config.rows().into_iter().find(|(row, _, _)| *row == name).unwrap().2
+    }
+
+    #[test]
+    fn test_defaults_only() {
+        let config = SimConfig::resolve(&CliOverrides::default(), None);
+        assert!(config.rows().iter().all(|(_, _, source)| *source == ConfigSource::Default));
+        assert_eq!(config.ticks_per_frame.value, DEFAULT_TICKS_PER_FRAME);
+        assert_eq!(config.seed.value, None);
+    }
+
+    #[test]
+    fn test_cli_overrides_defaults() {
+        let cli = CliOverrides {
+            seed: Some(7),
+            ticks_per_frame: Some(5),
+            terrain: Some(Terrain::Caves),
+            explorer_behavior: Some(BehaviorKind::Spiral),
+            ..CliOverrides::default()
+        };
+        let config = SimConfig::resolve(&cli, None);
+        assert_eq!(config.explorer_behavior, Setting { value: BehaviorKind::Spiral, source: ConfigSource::Cli });
+        assert_eq!(config.seed, Setting { value: Some(7), source: ConfigSource::Cli });
+        assert_eq!(config.terrain, Setting { value: Terrain::Caves, source: ConfigSource::Cli });
+        assert_eq!(config.ticks_per_frame, Setting { value: 5, source: ConfigSource::Cli });
+        assert_eq!(source_of(&config, "map_width"), ConfigSource::Default);
+    }
+
+    #[test]
+    fn test_challenge_locks_world_but_not_speed() {
+        let cli = CliOverrides { ticks_per_frame: Some(10), ..CliOverrides::default() };
+        let config = SimConfig::resolve(&cli, Some(&challenge()));
+        assert_eq!(config.seed, Setting { value: Some(42), source: ConfigSource::Challenge });
+        assert_eq!(source_of(&config, "map_width"), ConfigSource::Challenge);
+        assert_eq!(source_of(&config, "map_height"), ConfigSource::Challenge);
+        assert_eq!(config.tick_limit.value, Some(500));
+        assert_eq!(config.ticks_per_frame, Setting { value: 10, source: ConfigSource::Cli });
+        assert_eq!(source_of(&config, "frame_time_ms"), ConfigSource::Default);
+    }
+
+    #[test]
+    fn test_map_file_and_preview_sources() {
+        let cli = CliOverrides { map_file: Some("maze.txt".to_string()), ..CliOverrides::default() };
+        let config = SimConfig::resolve(&cli, None);
+        assert_eq!(source_of(&config, "map_file"), ConfigSource::Cli);
+
+        let mut config = SimConfig::resolve(&CliOverrides::default(), None);
+        config.set_preview_seed(9);
+        assert_eq!(config.seed, Setting { value: Some(9), source: ConfigSource::Preview });
+    }
+
+    #[test]
+    fn test_dump_annotates_sources() {
+        let cli = CliOverrides { seed: Some(3), ..CliOverrides::default() };
+        let dump = SimConfig::resolve(&cli, None).to_string();
+        assert!(dump.lines().any(|line| line.starts_with("seed") && line.contains("= 3") && line.ends_with("# cli")));
+        assert!(dump.lines().any(|line| line.starts_with("map_width") && line.contains("terminal") && line.ends_with("# default")));
+        assert!(dump.lines().any(|line| line.starts_with("terrain") && line.contains("open") && line.ends_with("# default")));
+        assert_eq!(dump.lines().count(), 15);
+    }
+
+    #[test]
+    fn test_speed_is_not_a_determinism_setting() {
+        let slow = SimConfig::resolve(&CliOverrides { seed: Some(3), ..CliOverrides::default() }, None);
+        let fast = SimConfig::resolve(&CliOverrides { seed: Some(3), ticks_per_frame: Some(50), ..CliOverrides::default() }, None);
+        assert_eq!(slow.determinism_settings(), fast.determinism_settings());
+        assert!(slow.determinism_settings().contains(&("seed", "3".to_string())));
+    }
+}
@@ -0,0 +1,246 @@
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+
+use crate::events::{SimEvent, Subscriber};
+use crate::robot::RobotType;
+use crate::station::Station;
+
+// File the decisions of a run started with --explain-station are written to, one per line
+pub const DECISION_LOG_PATH: &str = "station_decisions.log";
+
+// Tag starting every decision line, so the log can be filtered down to them
+pub const DECISION_TAG: &str = "[decision]";
+
+// Why the station did or did not build a robot, checked in this order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildVerdict {
+    Recalled, // The fleet is called home
+    FleetFull { robots: usize, max: usize },
+    MineralBuffer { have: u32, need: u32 },
+    EnergyBuffer { have: u32, need: u32 },
+    StorageFull, // Spend instead of hoarding, whatever targets are known
+    TooFewTargets { known: usize, need: usize },
+    Targets { known: usize, need: usize },
+}
+
+impl BuildVerdict {
+    pub fn approves(self) -> bool {
+        matches!(self, BuildVerdict::StorageFull | BuildVerdict::Targets { .. })
+    }
+
+    pub fn reason(self) -> String {
+        match self {
+            BuildVerdict::Recalled => "fleet recalled: no robots built until the recall is lifted".to_string(),
+            BuildVerdict::FleetFull { robots, max } => format!("fleet full: {} >= {} robots", robots, max),
+            BuildVerdict::MineralBuffer { have, need } => format!("insufficient mineral buffer: {} < {}", have, need),
+            BuildVerdict::EnergyBuffer { have, need } => format!("insufficient energy buffer: {} < {}", have, need),
+            BuildVerdict::StorageFull => "storage full: spending instead of hoarding".to_string(),
+            BuildVerdict::TooFewTargets { known, need } => format!("too few known targets: {} < {}", known, need),
+            BuildVerdict::Targets { known, need } => format!("enough known targets: {} >= {}", known, need),
+        }
+    }
+}
+
+// Which rule picked the type of a new robot, first match wins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeReason {
+    NoExplorer,
+    FewExplorers { explorers: usize, open_cells: usize },
+    LowEnergy { energy: u32, below: u32, deposits: usize, collectors: usize },
+    BrokenRobots { robots: usize },
+    FencedDeposits { deposits: usize },
+    CollectorsInField { collectors: usize },
+    MineralsAbundant { minerals: usize, energy: usize },
+    ScienceAvailable { sites: usize },
+    ExplorersOnHold,
+    MapExplored { explored: u32 }, // Percent of the map explored, past RETROFIT_EXPLORED_PERCENT
+    Default,
+}
+
+impl TypeReason {
+    pub fn reason(self) -> String {
+        match self {
+            TypeReason::NoExplorer => "no explorer in the fleet".to_string(),
+            TypeReason::FewExplorers { explorers, open_cells } => {
+                format!("only {} explorer(s) with {} known open cells", explorers, open_cells)
+            }
+            TypeReason::LowEnergy { energy, below, deposits, collectors } => format!(
+                "station energy low: {} < {} with {} known energy deposit(s) and {} collector(s)",
+                energy, below, deposits, collectors
+            ),
+            TypeReason::BrokenRobots { robots } => format!("{} robot(s) broken down and no repairer", robots),
+            TypeReason::FencedDeposits { deposits } => format!("{} deposits fenced in by rock and no miner", deposits),
+            TypeReason::CollectorsInField { collectors } => format!("{} collectors in the field and no carrier", collectors),
+            TypeReason::MineralsAbundant { minerals, energy } => {
+                format!("more mineral deposits than energy ones: {} > {}", minerals, energy)
+            }
+            TypeReason::ScienceAvailable { sites } => format!("{} known science site(s) and no scientist", sites),
+            TypeReason::ExplorersOnHold => "explorers on hold after an exploration advisory".to_string(),
+            TypeReason::MapExplored { explored } => format!("map {}% explored: collectors over explorers", explored),
+            TypeReason::Default => "nothing more pressing: keep exploring".to_string(),
+        }
+    }
+}
+
+// How an explorer's target was picked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TargetReason {
+    FreeRegion { explored: f64 },   // Least-explored region nobody else is heading for
+    SharedRegion { explored: f64 }, // Every region with unexplored cells is taken
+    StaleCell { idle_ticks: u64 },  // Whole map explored: re-scout the stalest cell
+    Buddy { leader: usize },        // A trailing explorer shares its leader's region
+}
+
+// An explorer target and why it was picked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TargetChoice {
+    pub region: usize,
+    pub target: (usize, usize),
+    pub reason: TargetReason,
+}
+
+impl TargetChoice {
+    pub fn reason(&self) -> String {
+        match self.reason {
+            TargetReason::FreeRegion { explored } => format!("least-explored free region: {:.1}% explored", explored),
+            TargetReason::SharedRegion { explored } => {
+                format!("all unexplored regions taken, sharing the least explored: {:.1}% explored", explored)
+            }
+            TargetReason::StaleCell { idle_ticks } => format!("map explored, re-scouting a cell unvisited for {} ticks", idle_ticks),
+            TargetReason::Buddy { leader } => format!("trailing robot #{} in its region", leader + 1),
+        }
+    }
+}
+
+// Whether the refinery runs this tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefineVerdict {
+    Refining { raw: u32 },
+    NothingToRefine,
+    EnergyReserve { energy: u32, need: u32 },
+}
+
+impl RefineVerdict {
+    pub fn reason(self) -> String {
+        match self {
+            RefineVerdict::Refining { raw } => format!("{} raw minerals waiting", raw),
+            RefineVerdict::NothingToRefine => "no raw minerals".to_string(),
+            RefineVerdict::EnergyReserve { energy, need } => format!("energy below refinery reserve: {} < {}", energy, need),
+        }
+    }
+}
+
+// A station decision as recorded in dry-run explanation mode: what was decided, the outcome, why,
+// and the inputs it was decided on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub tick: u64,
+    pub decision: &'static str, // build, robot type, retrofit, explorer target or refinery
+    pub outcome: String,
+    pub reason: String,
+    pub inputs: Vec<(&'static str, String)>,
+}
+
+impl Explanation {
+    // A build decision
+    pub fn build(tick: u64, verdict: BuildVerdict, inputs: Vec<(&'static str, String)>) -> Self {
+        let outcome = if verdict.approves() { "build" } else { "declined" };
+        Self { tick, decision: "build", outcome: outcome.to_string(), reason: verdict.reason(), inputs }
+    }
+
+    // The type a surplus explorer was refitted as
+    pub fn retrofit(tick: u64, robot: usize, robot_type: RobotType, reason: TypeReason, inputs: Vec<(&'static str, String)>) -> Self {
+        let outcome = format!("robot #{} to {:?}", robot + 1, robot_type);
+        Self { tick, decision: "retrofit", outcome, reason: reason.reason(), inputs }
+    }
+
+    // The type picked for a robot just built
+    pub fn robot_type(tick: u64, robot_type: RobotType, reason: TypeReason, inputs: Vec<(&'static str, String)>) -> Self {
+        Self { tick, decision: "robot type", outcome: format!("{:?}", robot_type), reason: reason.reason(), inputs }
+    }
+
+    // The target given to the explorer `robot`
+    pub fn explorer_target(tick: u64, robot: usize, choice: &TargetChoice) -> Self {
+        let (x, y) = choice.target;
+        Self {
+            tick,
+            decision: "explorer target",
+            outcome: format!("robot #{} to ({},{}) in region {}", robot + 1, x, y, choice.region),
+            reason: choice.reason(),
+            inputs: Vec::new(),
+        }
+    }
+
+    // The refinery starting, stopping or pausing
+    pub fn refinery(tick: u64, verdict: RefineVerdict, inputs: Vec<(&'static str, String)>) -> Self {
+        let outcome = if matches!(verdict, RefineVerdict::Refining { .. }) { "running" } else { "idle" };
+        Self { tick, decision: "refinery", outcome: outcome.to_string(), reason: verdict.reason(), inputs }
+    }
+
+    // One log line: `[decision] tick 120 build: declined (insufficient mineral buffer: 140 < 150) | robots=3 ...`
+    pub fn line(&self) -> String {
+        let mut line = format!("{} tick {} {}: {} ({})", DECISION_TAG, self.tick, self.decision, self.outcome, self.reason);
+        if !self.inputs.is_empty() {
+            let inputs: Vec<String> = self.inputs.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+            line.push_str(" | ");
+            line.push_str(&inputs.join(" "));
+        }
+        line
+    }
+}
+
+// The station state behind its build and type decisions
+pub fn station_inputs(station: &Station) -> Vec<(&'static str, String)> {
+    vec![
+        ("robots", station.robots.len().to_string()),
+        ("refined_minerals", station.refined_minerals.to_string()),
+        ("parts", station.parts.to_string()),
+        ("energy", station.energy.to_string()),
+        ("storage_full", station.storage_full.to_string()),
+    ]
+}
+
+// Writes every explained decision to a log file as it is published
+pub struct DecisionLog {
+    writer: LineWriter<File>,
+    pub written: usize,
+}
+
+impl DecisionLog {
+    pub fn create(path: &str) -> io::Result<Self> {
+        Ok(Self { writer: LineWriter::new(File::create(path)?), written: 0 })
+    }
+}
+
+impl Subscriber for DecisionLog {
+    fn on_event(&mut self, event: &SimEvent) {
+        if let SimEvent::StationDecision(explanation) = event {
+            // A full disk loses explanations, not the run
+            if writeln!(self.writer, "{}", explanation.line()).is_ok() {
+                self.written += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explanation_lines() {
+        let declined = Explanation::build(
+            120,
+            BuildVerdict::MineralBuffer { have: 140, need: 150 },
+            vec![("robots", "3".to_string()), ("energy", "900".to_string())],
+        );
+        assert_eq!(declined.line(), "[decision] tick 120 build: declined (insufficient mineral buffer: 140 < 150) | robots=3 energy=900");
+        assert!(BuildVerdict::StorageFull.approves() && !BuildVerdict::TooFewTargets { known: 1, need: 2 }.approves());
+
+        let choice = TargetChoice { region: 4, target: (10, 6), reason: TargetReason::FreeRegion { explored: 12.5 } };
+        assert_eq!(
+            Explanation::explorer_target(7, 0, &choice).line(),
+            "[decision] tick 7 explorer target: robot #1 to (10,6) in region 4 (least-explored free region: 12.5% explored)"
+        );
+    }
+}
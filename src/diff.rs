@@ -0,0 +1,263 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::map::Map;
+use crate::robot::Robot;
+use crate::simulation::Simulation;
+
+// Size of the world generated for a seed given to diff-runs
+const DIFF_MAP_WIDTH: usize = 100;
+const DIFF_MAP_HEIGHT: usize = 50;
+
+// Ticks run in lockstep before two runs are declared identical
+pub const DIFF_RUNS_MAX_TICKS: u64 = 2000;
+
+// The observable state of one robot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RobotSnapshot {
+    pub x: usize,
+    pub y: usize,
+    pub energy: u32,
+    pub energy_cargo: u32,
+    pub minerals: u32,
+    pub science_points: u32,
+    pub robot_type: String,
+    pub state: String,
+}
+
+impl RobotSnapshot {
+    pub fn capture(robot: &Robot) -> Self {
+        Self {
+            x: robot.x,
+            y: robot.y,
+            energy: robot.energy,
+            energy_cargo: robot.energy_cargo,
+            minerals: robot.minerals,
+            science_points: robot.science_points,
+            robot_type: format!("{:?}", robot.robot_type),
+            state: format!("{:?}", robot.state),
+        }
+    }
+
+    // Energy, minerals and science being carried
+    pub fn cargo(&self) -> u32 {
+        self.energy_cargo + self.minerals + self.science_points
+    }
+
+    fn fields(&self) -> [(&'static str, String); 8] {
+        [
+            ("x", self.x.to_string()),
+            ("y", self.y.to_string()),
+            ("energy", self.energy.to_string()),
+            ("energy_cargo", self.energy_cargo.to_string()),
+            ("minerals", self.minerals.to_string()),
+            ("science_points", self.science_points.to_string()),
+            ("type", self.robot_type.clone()),
+            ("state", self.state.clone()),
+        ]
+    }
+}
+
+// The observable state of a whole simulation at the end of a tick
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub station_energy: u32,
+    pub raw_minerals: u32,
+    pub refined_minerals: u32,
+    pub science_points: u32,
+    pub known_cells: usize,
+    pub debris: Vec<(usize, usize)>,
+    pub robots: Vec<RobotSnapshot>,
+}
+
+impl Snapshot {
+    pub fn capture(simulation: &Simulation) -> Self {
+        let station = &simulation.station();
+        Self {
+            tick: simulation.clock.now(),
+            station_energy: station.energy,
+            raw_minerals: station.raw_minerals,
+            refined_minerals: station.refined_minerals,
+            science_points: station.science_points,
+            known_cells: station.known_map.len(),
+            debris: simulation.map.debris.clone(),
+            robots: station.robots.iter().map(RobotSnapshot::capture).collect(),
+        }
+    }
+
+    // Station-level fields; robots are compared one by one
+    fn station_fields(&self) -> [(&'static str, String); 6] {
+        [
+            ("station energy", self.station_energy.to_string()),
+            ("raw minerals", self.raw_minerals.to_string()),
+            ("refined minerals", self.refined_minerals.to_string()),
+            ("science points", self.science_points.to_string()),
+            ("known cells", self.known_cells.to_string()),
+            ("debris", format!("{:?}", self.debris)),
+        ]
+    }
+
+    // Hash of the state, for a cheap equality check per tick (only stable within one build)
+    pub fn digest(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for (name, value) in self.station_fields() {
+            (name, value).hash(&mut hasher);
+        }
+        for robot in &self.robots {
+            robot.fields().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+// One field that differs between two snapshots; `robot` is None for station-level fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    pub robot: Option<usize>,
+    pub field: &'static str,
+    pub left: String,
+    pub right: String,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.robot {
+            Some(robot) => write!(f, "robot {} {}: {} vs {}", robot, self.field, self.left, self.right),
+            None => write!(f, "{}: {} vs {}", self.field, self.left, self.right),
+        }
+    }
+}
+
+// Every field that differs, station fields first and then robots in index order
+pub fn diff_snapshots(left: &Snapshot, right: &Snapshot) -> Vec<FieldDiff> {
+    let mut diffs = Vec::new();
+    for ((field, left_value), (_, right_value)) in left.station_fields().into_iter().zip(right.station_fields()) {
+        if left_value != right_value {
+            diffs.push(FieldDiff { robot: None, field, left: left_value, right: right_value });
+        }
+    }
+    for (index, (left_robot, right_robot)) in left.robots.iter().zip(&right.robots).enumerate() {
+        for ((field, left_value), (_, right_value)) in left_robot.fields().into_iter().zip(right_robot.fields()) {
+            if left_value != right_value {
+                diffs.push(FieldDiff { robot: Some(index), field, left: left_value, right: right_value });
+            }
+        }
+    }
+    if left.robots.len() != right.robots.len() {
+        diffs.push(FieldDiff {
+            robot: None,
+            field: "robot count",
+            left: left.robots.len().to_string(),
+            right: right.robots.len().to_string(),
+        });
+    }
+    diffs
+}
+
+// Where two runs first disagree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub tick: u64, // Ticks completed when the states first differed
+    pub diffs: Vec<FieldDiff>,
+    pub previous: Option<(Snapshot, Snapshot)>, // Both states one tick earlier, when they still agreed
+}
+
+impl Divergence {
+    // The first robot involved in the divergence, if any
+    pub fn robot(&self) -> Option<usize> {
+        self.diffs.iter().find_map(|diff| diff.robot)
+    }
+
+    // Human-readable report: the differing fields, then the first differing robot a tick earlier
+    pub fn report(&self) -> String {
+        let mut lines = vec![format!("Runs diverge after tick {}:", self.tick)];
+        lines.extend(self.diffs.iter().map(|diff| format!("  {}", diff)));
+        if let (Some(robot), Some((left, right))) = (self.robot(), &self.previous) {
+            lines.push(format!("Robot {} on the previous tick:", robot));
+            for (side, snapshot) in [("left", left), ("right", right)] {
+                if let Some(state) = snapshot.robots.get(robot) {
+                    lines.push(format!("  {}: {:?}", side, state));
+                }
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+// Run two simulations in lockstep for up to `max_ticks` ticks and return the first tick where
+// their state digests differ. `before_tick` may alter the right run before each of its ticks
+pub fn find_divergence<F>(left: &mut Simulation, right: &mut Simulation, max_ticks: u64, mut before_tick: F) -> Option<Divergence>
+where
+    F: FnMut(&mut Simulation),
+{
+    let mut previous = None;
+    for step in 0..=max_ticks {
+        if step > 0 {
+            left.tick();
+            before_tick(right);
+            right.tick();
+        }
+        let (left_state, right_state) = (Snapshot::capture(left), Snapshot::capture(right));
+        if left_state.digest() != right_state.digest() {
+            return Some(Divergence {
+                tick: left_state.tick,
+                diffs: diff_snapshots(&left_state, &right_state),
+                previous,
+            });
+        }
+        previous = Some((left_state, right_state));
+    }
+    None
+}
+
+// Build a run from a seed (a generated world) or the path of an ASCII map
+pub fn load_run(spec: &str) -> Result<Simulation, String> {
+    if let Ok(seed) = spec.parse::<u32>() {
+        return Ok(Simulation::setup(Map::new(DIFF_MAP_WIDTH, DIFF_MAP_HEIGHT, seed), None));
+    }
+    let text = std::fs::read_to_string(spec).map_err(|err| format!("could not read map file '{}': {}", spec, err))?;
+    let (map, station) = Map::from_ascii(&text).map_err(|err| format!("invalid map file '{}': {}", spec, err))?;
+    Ok(Simulation::setup(map, station))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_runs_never_diverge() {
+        let (mut left, mut right) = (load_run("5").unwrap(), load_run("5").unwrap());
+        assert_eq!(find_divergence(&mut left, &mut right, 150, |_| {}), None);
+        assert_eq!(left.clock.now(), 150);
+    }
+
+    #[test]
+    fn test_injected_change_is_localized() {
+        // Drain one unit of robot 0's battery before tick 40 of the right run only
+        let (mut left, mut right) = (load_run("5").unwrap(), load_run("5").unwrap());
+        let divergence = find_divergence(&mut left, &mut right, 150, |simulation| {
+            if simulation.clock.now() == 40 {
+                simulation.stations[0].robots[0].energy -= 1;
+            }
+        })
+        .unwrap();
+
+        assert_eq!(divergence.tick, 41);
+        assert_eq!(divergence.robot(), Some(0));
+        assert!(divergence.diffs.iter().any(|diff| diff.robot == Some(0) && diff.field == "energy"));
+        let (before_left, before_right) = divergence.previous.as_ref().unwrap();
+        assert_eq!(before_left.tick, 40);
+        assert_eq!(before_left, before_right);
+        assert!(divergence.report().contains("Robot 0 on the previous tick"));
+    }
+
+    #[test]
+    fn test_different_worlds_diverge_at_setup() {
+        let (mut left, mut right) = (load_run("5").unwrap(), load_run("6").unwrap());
+        let divergence = find_divergence(&mut left, &mut right, 10, |_| {}).unwrap();
+        assert_eq!(divergence.tick, 0);
+        assert!(divergence.previous.is_none());
+    }
+}
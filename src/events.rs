@@ -0,0 +1,280 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::decisions::Explanation;
+use crate::facilities::FacilityKind;
+use crate::robot::{Malfunction, RobotType};
+use crate::tasks::Task;
+use crate::upgrades::Upgrade;
+
+// Things that happen during a tick that other parts of the game may react to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimEvent {
+    ResourceCollected { robot: usize, x: usize, y: usize },
+    Unloaded { robot: usize, x: usize, y: usize, energy: u32, minerals: u32, science: u32 },
+    DataShared { robot: usize, cells: usize },
+    Refueled { robot: usize, amount: u32 },
+    RobotRespawned { robot: usize },
+    RobotCreated { robot: usize, x: usize, y: usize },
+    DistressSignal { robot: usize, x: usize, y: usize },
+    RescueDispatched { robot: usize, rescuer: usize },
+    RobotRescued { robot: usize, rescuer: usize, amount: u32 },
+    RobotWrecked { robot: usize },
+    RobotPoweredDown { robot: usize, x: usize, y: usize }, // Nobody came while it waited in low power: a wreck to rebuild
+    StorageFull { robot: usize, energy: u32, minerals: u32, science: u32 }, // Overflow left on the robot
+    CacheDropped { robot: usize, x: usize, y: usize },
+    PickupDispatched { robot: usize, x: usize, y: usize },
+    CacheRecovered { robot: usize, x: usize, y: usize, minerals: u32, science: u32 },
+    CacheLost { x: usize, y: usize, minerals: u32, science: u32 }, // Expired before anyone fetched it
+    ExpeditionPlanned { robot: usize, refuel_stops: usize },
+    ExplorerSurplus { explorers: usize, balance: i64 }, // Exploration has cost more energy than it returned for a while
+    CargoHandedOver { robot: usize, carrier: usize, energy: u32, minerals: u32, science: u32 },
+    UpgradePurchased { upgrade: Upgrade, level: u32 },
+    FacilityBuilt { facility: FacilityKind, level: u32, minerals: u32 }, // Bought with refined minerals
+    TaskAssigned { robot: usize, task: Task },
+    RobotRepaired { robot: usize, repairer: usize, integrity: u32 },
+    RobotsSwapped { robot: usize, other: usize }, // Two robots blocking each other traded places
+    RobotMalfunctioned { robot: usize, malfunction: Malfunction },
+    MalfunctionsRepaired { robot: usize, malfunctions: usize, minerals: u32 },
+    ExplorersPaired { leader: usize, trailer: usize },
+    BuddyLost { robot: usize, x: usize, y: usize }, // Reported at docking; an explorer is sent back there
+    EnergyShared { robot: usize, donor: usize, amount: u32 }, // A robot running dry was given power by its neighbour
+    RobotDecommissioned { robot: usize, refund: u32 }, // A worn robot scrapped at the station for refined minerals
+    RobotRetrofitted { robot: usize, from: RobotType, to: RobotType, minerals: u32 },
+    OutpostFounded { robot: usize, station: usize, x: usize, y: usize }, // The robot sent there with BuildOutpostAt arrived
+    StationDecision(Explanation), // Only published in explanation mode
+}
+
+// A reaction to simulation events. Subscribers only see events, never the world itself,
+// so adding one cannot change how a tick plays out
+pub trait Subscriber: Any {
+    fn on_event(&mut self, event: &SimEvent);
+}
+
+// Per-tick event queue. Events are delivered when the queue is drained: in the order they were
+// published, each one to every subscriber in subscription order before the next event
+pub struct EventBus {
+    queue: VecDeque<SimEvent>,
+    subscribers: Vec<Box<dyn Subscriber>>,
+    paranoid: bool,              // Contain panicking subscribers instead of propagating the panic
+    pub failures: Vec<String>,   // Subscriber panics caught in paranoid mode
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            subscribers: Vec::new(),
+            paranoid: false,
+            failures: Vec::new(),
+        }
+    }
+
+    pub fn set_paranoid(&mut self, paranoid: bool) {
+        self.paranoid = paranoid;
+    }
+
+    pub fn subscribe(&mut self, subscriber: Box<dyn Subscriber>) {
+        self.subscribers.push(subscriber);
+    }
+
+    // The first subscriber of a given type, for reading back what it collected
+    pub fn subscriber<T: Subscriber>(&self) -> Option<&T> {
+        self.subscribers
+            .iter()
+            .find_map(|subscriber| (subscriber.as_ref() as &dyn Any).downcast_ref::<T>())
+    }
+
+    pub fn publish(&mut self, event: SimEvent) {
+        self.queue.push_back(event);
+    }
+
+    // Deliver every queued event and return them in delivery order
+    pub fn drain(&mut self) -> Vec<SimEvent> {
+        let events: Vec<SimEvent> = self.queue.drain(..).collect();
+        for event in &events {
+            for subscriber in &mut self.subscribers {
+                if self.paranoid {
+                    let outcome = panic::catch_unwind(AssertUnwindSafe(|| subscriber.on_event(event)));
+                    if let Err(payload) = outcome {
+                        let message = payload
+                            .downcast_ref::<&str>()
+                            .map(|text| text.to_string())
+                            .or_else(|| payload.downcast_ref::<String>().cloned())
+                            .unwrap_or_else(|| "unknown panic".to_string());
+                        self.failures.push(format!("subscriber panicked on {:?}: {}", event, message));
+                    }
+                } else {
+                    subscriber.on_event(event);
+                }
+            }
+        }
+        events
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Running totals of swarm activity, reported when the simulation ends
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ActivityTally {
+    pub resources_collected: u32,
+    pub deliveries: u32,
+    pub cells_shared: usize,
+    pub energy_refueled: u32,
+    pub respawns: u32,
+    pub robots_created: u32,
+    pub rescues: u32,
+    pub wrecks: u32,
+    pub power_downs: u32,
+    pub caches_dropped: u32,
+    pub caches_recovered: u32,
+    pub caches_lost: u32,
+    pub explorer_advisories: u32,
+    pub cargo_handovers: u32,
+    pub upgrades_purchased: u32,
+    pub facilities_built: u32,
+    pub tasks_assigned: u32,
+    pub repairs: u32,
+    pub swaps: u32,
+    pub malfunctions: u32,
+    pub malfunctions_repaired: usize,
+    pub buddies_lost: u32,
+    pub energy_shared: u32,
+    pub decommissioned: u32,
+    pub retrofits: u32,
+}
+
+impl Subscriber for ActivityTally {
+    fn on_event(&mut self, event: &SimEvent) {
+        match *event {
+            SimEvent::ResourceCollected { .. } => self.resources_collected += 1,
+            SimEvent::Unloaded { .. } => self.deliveries += 1,
+            SimEvent::DataShared { cells, .. } => self.cells_shared += cells,
+            SimEvent::Refueled { amount, .. } => self.energy_refueled += amount,
+            SimEvent::RobotRespawned { .. } => self.respawns += 1,
+            SimEvent::RobotCreated { .. } => self.robots_created += 1,
+            SimEvent::RobotRescued { .. } => self.rescues += 1,
+            SimEvent::RobotWrecked { .. } => self.wrecks += 1,
+            SimEvent::RobotPoweredDown { .. } => self.power_downs += 1,
+            SimEvent::CacheDropped { .. } => self.caches_dropped += 1,
+            SimEvent::CacheRecovered { .. } => self.caches_recovered += 1,
+            SimEvent::CacheLost { .. } => self.caches_lost += 1,
+            SimEvent::ExplorerSurplus { .. } => self.explorer_advisories += 1,
+            SimEvent::CargoHandedOver { .. } => self.cargo_handovers += 1,
+            SimEvent::UpgradePurchased { .. } => self.upgrades_purchased += 1,
+            SimEvent::FacilityBuilt { .. } => self.facilities_built += 1,
+            SimEvent::TaskAssigned { .. } => self.tasks_assigned += 1,
+            SimEvent::RobotRepaired { .. } => self.repairs += 1,
+            SimEvent::RobotsSwapped { .. } => self.swaps += 1,
+            SimEvent::RobotMalfunctioned { .. } => self.malfunctions += 1,
+            SimEvent::MalfunctionsRepaired { malfunctions, .. } => self.malfunctions_repaired += malfunctions,
+            SimEvent::BuddyLost { .. } => self.buddies_lost += 1,
+            SimEvent::EnergyShared { amount, .. } => self.energy_shared += amount,
+            SimEvent::RobotDecommissioned { .. } => self.decommissioned += 1,
+            SimEvent::RobotRetrofitted { .. } => self.retrofits += 1,
+            SimEvent::DistressSignal { .. }
+            | SimEvent::RescueDispatched { .. }
+            | SimEvent::StorageFull { .. }
+            | SimEvent::PickupDispatched { .. }
+            | SimEvent::ExpeditionPlanned { .. }
+            | SimEvent::ExplorersPaired { .. }
+            | SimEvent::OutpostFounded { .. }
+            | SimEvent::StationDecision(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // Records (subscriber name, event) pairs into a shared log
+    struct Recorder {
+        name: &'static str,
+        log: Rc<RefCell<Vec<(&'static str, SimEvent)>>>,
+    }
+
+    impl Subscriber for Recorder {
+        fn on_event(&mut self, event: &SimEvent) {
+            self.log.borrow_mut().push((self.name, event.clone()));
+        }
+    }
+
+    struct Faulty;
+
+    impl Subscriber for Faulty {
+        fn on_event(&mut self, _event: &SimEvent) {
+            panic!("faulty subscriber");
+        }
+    }
+
+    #[test]
+    fn test_drain_order() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = EventBus::new();
+        bus.subscribe(Box::new(Recorder { name: "first", log: log.clone() }));
+        bus.subscribe(Box::new(Recorder { name: "second", log: log.clone() }));
+
+        let unloaded = SimEvent::Unloaded { robot: 0, x: 0, y: 0, energy: 5, minerals: 0, science: 0 };
+        let refueled = SimEvent::Refueled { robot: 0, amount: 10 };
+        bus.publish(unloaded.clone());
+        bus.publish(refueled.clone());
+        assert!(log.borrow().is_empty()); // Nothing is delivered before the drain
+
+        assert_eq!(bus.drain(), vec![unloaded.clone(), refueled.clone()]);
+        assert_eq!(
+            *log.borrow(),
+            vec![("first", unloaded.clone()), ("second", unloaded), ("first", refueled.clone()), ("second", refueled)]
+        );
+        assert!(bus.drain().is_empty());
+    }
+
+    #[test]
+    fn test_paranoid_mode_isolates_panicking_subscriber() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = EventBus::new();
+        bus.set_paranoid(true);
+        bus.subscribe(Box::new(Faulty));
+        bus.subscribe(Box::new(Recorder { name: "after", log: log.clone() }));
+
+        bus.publish(SimEvent::RobotRespawned { robot: 3 });
+        bus.publish(SimEvent::RobotRespawned { robot: 4 });
+        bus.drain();
+
+        assert_eq!(log.borrow().len(), 2); // Later subscribers still saw every event
+        assert_eq!(bus.failures.len(), 2);
+        assert!(bus.failures[0].contains("faulty subscriber"));
+    }
+
+    #[test]
+    #[should_panic(expected = "faulty subscriber")]
+    fn test_panics_propagate_outside_paranoid_mode() {
+        let mut bus = EventBus::new();
+        bus.subscribe(Box::new(Faulty));
+        bus.publish(SimEvent::RobotRespawned { robot: 0 });
+        bus.drain();
+    }
+
+    #[test]
+    fn test_tally_subscriber() {
+        let mut bus = EventBus::new();
+        bus.subscribe(Box::new(ActivityTally::default()));
+        bus.publish(SimEvent::DataShared { robot: 1, cells: 12 });
+        bus.publish(SimEvent::Refueled { robot: 1, amount: 30 });
+        bus.publish(SimEvent::RobotCreated { robot: 9, x: 2, y: 2 });
+        bus.drain();
+
+        let tally = bus.subscriber::<ActivityTally>().unwrap();
+        assert_eq!(tally.cells_shared, 12);
+        assert_eq!(tally.energy_refueled, 30);
+        assert_eq!(tally.robots_created, 1);
+    }
+}
@@ -0,0 +1,232 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::map::{CellType, Map};
+use crate::robot::{ENERGY_COSTS, INITIAL_ROBOT_ENERGY};
+use crate::station::KnownMap;
+
+// Known energy deposits considered as refuel stops for one plan, those nearest the route first
+pub const MAX_REFUEL_POINTS: usize = 8;
+
+// Deposits holding less than this are not worth planning a stop around
+pub const MIN_REFUEL_RESERVE: u32 = 20;
+
+// Most energy a plan counts on carrying after a refuel stop
+pub const MAX_PLANNED_CHARGE: u32 = 2 * INITIAL_ROBOT_ENERGY;
+
+// A known energy deposit a robot can top up its battery from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefuelPoint {
+    pub x: usize,
+    pub y: usize,
+    pub reserve: u32, // Energy the station was last told the deposit holds
+}
+
+// One stop of an expedition: a refuel point, or the target itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Leg {
+    pub x: usize,
+    pub y: usize,
+    pub refuel: bool,
+}
+
+// The refuel points worth considering for a trip from `start` to `target` and back to
+// `station`, built from the station's knowledge: deposits with a useful reserve, those with
+// the smallest detour off either half of the trip first
+pub fn refuel_points(
+    known_map: &KnownMap,
+    map: &Map,
+    start: (usize, usize),
+    target: (usize, usize),
+    station: (usize, usize),
+) -> Vec<RefuelPoint> {
+    let distance = |(x1, y1): (usize, usize), (x2, y2): (usize, usize)| map.distance(x1, y1, x2, y2);
+    let mut points: Vec<RefuelPoint> = known_map
+        .iter()
+        .filter_map(|(&(x, y), known)| match known.cell_type {
+            CellType::Energy(reserve) if reserve >= MIN_REFUEL_RESERVE => Some(RefuelPoint { x, y, reserve }),
+            _ => None,
+        })
+        .collect();
+    let detour = |point: &RefuelPoint| {
+        let position = (point.x, point.y);
+        let outbound = distance(start, position) + distance(position, target);
+        let homebound = distance(target, position) + distance(position, station);
+        (outbound.min(homebound), point.x, point.y) // Positions break ties: the known map is unordered
+    };
+    points.sort_by_key(detour);
+    points.truncate(MAX_REFUEL_POINTS);
+    points
+}
+
+// Search state: where the robot is, the energy it has there (after any refuel), which refuel
+// points it has used and whether it has been to the target
+type State = (usize, u32, u16, bool);
+
+// Node indices of the refuel graph; refuel point `i` is node FIRST_POINT + i
+const START: usize = 0;
+const TARGET: usize = 1;
+const STATION: usize = 2;
+const FIRST_POINT: usize = 3;
+
+// Cheapest plan for a robot at `start` with `energy` on board to reach `target` and then
+// `station`, refuelling at `points` on the way (each used at most once, refilling up to
+// MAX_PLANNED_CHARGE). `leg_cost` gives the energy an unloaded robot spends walking between
+// two positions and the steps it takes, None when there is no path; the battery charged above
+// the starting level is load on top of that, counted at its weight when the leg starts. Every
+// leg must leave `margin` to spare. Among equally cheap plans the one with the fewest stops
+// wins. Returns the stops in order, the walk home to the station being implied, or None when no
+// chain of affordable legs gets there and back
+pub fn plan<F>(
+    start: (usize, usize),
+    energy: u32,
+    target: (usize, usize),
+    station: (usize, usize),
+    points: &[RefuelPoint],
+    margin: u32,
+    mut leg_cost: F,
+) -> Option<Vec<Leg>>
+where
+    F: FnMut((usize, usize), (usize, usize)) -> Option<(u32, u32)>,
+{
+    let points = &points[..points.len().min(MAX_REFUEL_POINTS)];
+    let mut positions = vec![start, target, station];
+    positions.extend(points.iter().map(|point| (point.x, point.y)));
+    let mut costs: HashMap<(usize, usize), Option<(u32, u32)>> = HashMap::new();
+
+    let initial: State = (START, energy, 0, false);
+    let mut best: HashMap<State, (u32, u32)> = HashMap::from([(initial, (0, 0))]);
+    let mut previous: HashMap<State, State> = HashMap::new();
+    let mut queue = BinaryHeap::from([Reverse((0u32, 0u32, initial))]);
+    while let Some(Reverse((cost, stops, state))) = queue.pop() {
+        if best.get(&state).is_some_and(|&known| known < (cost, stops)) {
+            continue; // Reached more cheaply since this entry was queued
+        }
+        let (node, charge, used, reached_target) = state;
+        if node == STATION && reached_target {
+            return Some(legs(&previous, state, &positions));
+        }
+        for next in TARGET..positions.len() {
+            let point = next.checked_sub(FIRST_POINT);
+            let allowed = match next {
+                TARGET => !reached_target,
+                STATION => reached_target,
+                _ => next != node && point.is_some_and(|point| used & (1 << point) == 0),
+            };
+            if !allowed {
+                continue;
+            }
+            let Some((energy, steps)) = *costs.entry((node, next)).or_insert_with(|| leg_cost(positions[node], positions[next])) else {
+                continue;
+            };
+            let leg = energy + steps * (charge.saturating_sub(INITIAL_ROBOT_ENERGY) / ENERGY_COSTS.load_per_extra_move);
+            if leg + margin > charge {
+                continue;
+            }
+            let arrival = charge - leg;
+            let (charge, used) = match point {
+                Some(point) => ((arrival + points[point].reserve).min(MAX_PLANNED_CHARGE).max(arrival), used | (1 << point)),
+                None => (arrival, used),
+            };
+            let next_state = (next, charge, used, reached_target || next == TARGET);
+            let next_cost = (cost + leg, stops + 1);
+            if best.get(&next_state).is_none_or(|&known| next_cost < known) {
+                best.insert(next_state, next_cost);
+                previous.insert(next_state, state);
+                queue.push(Reverse((next_cost.0, next_cost.1, next_state)));
+            }
+        }
+    }
+    None
+}
+
+// The stops leading to a final state, start and station excluded
+fn legs(previous: &HashMap<State, State>, last: State, positions: &[(usize, usize)]) -> Vec<Leg> {
+    let mut legs = Vec::new();
+    let mut state = last;
+    while let Some(&before) = previous.get(&state) {
+        state = before;
+        let node = state.0;
+        if node != START {
+            let (x, y) = positions[node];
+            legs.push(Leg { x, y, refuel: node >= FIRST_POINT });
+        }
+    }
+    legs.reverse();
+    legs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::station::KnownCell;
+
+    type Position = (usize, usize);
+
+    // Leg costs from a symmetric table of named positions, every step on plains
+    fn table_cost(table: &[(Position, Position, u32)]) -> impl FnMut(Position, Position) -> Option<(u32, u32)> + '_ {
+        move |from, to| {
+            table
+                .iter()
+                .find(|&&(a, b, _)| (a, b) == (from, to) || (a, b) == (to, from))
+                .map(|&(_, _, cost)| (cost, cost))
+        }
+    }
+
+    #[test]
+    fn test_plan_finds_the_only_working_chain() {
+        // A corridor from station S to target T 120 away, A on it 50 out, B just off it on the way
+        // home and C a small deposit next to the station that only makes the trip longer.
+        // Refuelling at A and B both on the way out would leave the robot too heavy to get back
+        let (s, t, a, b, c) = ((0, 0), (120, 0), (50, 0), (70, 1), (0, 1));
+        let table = [
+            (s, a, 50), (a, t, 70), (t, b, 51), (b, s, 71), (s, t, 120), (a, b, 21),
+            (s, c, 1), (c, t, 121), (c, a, 51), (c, b, 72),
+        ];
+        let points = [
+            RefuelPoint { x: c.0, y: c.1, reserve: 30 },
+            RefuelPoint { x: a.0, y: a.1, reserve: 80 },
+            RefuelPoint { x: b.0, y: b.1, reserve: 80 },
+        ];
+        let legs = plan(s, 100, t, s, &points, 5, table_cost(&table)).unwrap();
+        let stops: Vec<((usize, usize), bool)> = legs.iter().map(|leg| ((leg.x, leg.y), leg.refuel)).collect();
+        assert_eq!(stops, vec![(a, true), (t, false), (b, true)]);
+
+        // Without the outbound stop, or the homebound one, there is no way there and back
+        assert_eq!(plan(s, 100, t, s, &[points[0], points[2]], 5, table_cost(&table)), None);
+        assert_eq!(plan(s, 100, t, s, &[points[0], points[1]], 5, table_cost(&table)), None);
+    }
+
+    #[test]
+    fn test_plan_without_stops_when_in_range() {
+        let (s, t, a) = ((0, 0), (5, 0), (2, 0));
+        let table = [(s, t, 40), (s, a, 20), (a, t, 20)];
+        let points = [RefuelPoint { x: a.0, y: a.1, reserve: 50 }];
+        // The round trip fits a single charge, and a stop on the way costs no less
+        assert_eq!(plan(s, 100, t, s, &points, 5, table_cost(&table)), Some(vec![Leg { x: 5, y: 0, refuel: false }]));
+        // The margin counts: 2 * 40 + 5 does not fit 84
+        let legs = plan(s, 84, t, s, &points, 5, table_cost(&table)).unwrap();
+        assert!(legs.iter().any(|leg| leg.refuel));
+    }
+
+    #[test]
+    fn test_unreachable_target_has_no_plan() {
+        let (s, t, a) = ((0, 0), (5, 0), (2, 0));
+        let table = [(s, a, 20)]; // No path to the target at all
+        let points = [RefuelPoint { x: a.0, y: a.1, reserve: 50 }];
+        assert_eq!(plan(s, 100, t, s, &points, 5, table_cost(&table)), None);
+    }
+
+    #[test]
+    fn test_refuel_points_from_station_knowledge() {
+        let map = Map::new(40, 10, 3);
+        let known = HashMap::from([
+            ((5, 5), KnownCell::new(CellType::Energy(100), 0)),
+            ((35, 5), KnownCell::new(CellType::Energy(100), 0)),
+            ((20, 5), KnownCell::new(CellType::Energy(MIN_REFUEL_RESERVE - 1), 0)), // Too little to plan around
+            ((21, 5), KnownCell::new(CellType::Mineral(100), 0)),
+        ]);
+        let points = refuel_points(&known, &map, (0, 5), (10, 5), (0, 5));
+        assert_eq!(points, vec![RefuelPoint { x: 5, y: 5, reserve: 100 }, RefuelPoint { x: 35, y: 5, reserve: 100 }]);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::audit::MINERAL_ENERGY_VALUE;
+
+// Buildings the station buys with refined minerals, each with a few levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FacilityKind {
+    Refinery,      // +10% minerals credited per unload and the base refining rate again per level
+    ChargingArray, // Refuels cost the station 25% less energy per level
+    Workshop,      // Robots cost 10 fewer refined minerals per level
+    Warehouse,     // Storage caps rise by half the default per level
+}
+
+pub const FACILITY_KINDS: [FacilityKind; 4] =
+    [FacilityKind::Refinery, FacilityKind::ChargingArray, FacilityKind::Workshop, FacilityKind::Warehouse];
+
+const REFINERY_YIELD_PERCENT_PER_LEVEL: u32 = 10;
+const CHARGING_DISCOUNT_PERCENT_PER_LEVEL: u32 = 25;
+const WORKSHOP_DISCOUNT_PER_LEVEL: u32 = 10; // Refined minerals, like the construction upgrade
+const WAREHOUSE_CAPACITY_PERCENT_PER_LEVEL: u32 = 50; // Of the default storage capacity
+
+// A facility is only bought once the station has run this many ticks, so the rates it is judged
+// by mean something, and only if it pays for itself within FACILITY_PAYBACK_TICKS at those rates
+pub const FACILITY_WARMUP_TICKS: u64 = 300;
+pub const FACILITY_PAYBACK_TICKS: u64 = 3000;
+
+impl FacilityKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            FacilityKind::Refinery => "refinery",
+            FacilityKind::ChargingArray => "charging array",
+            FacilityKind::Workshop => "workshop",
+            FacilityKind::Warehouse => "warehouse",
+        }
+    }
+
+    // Levels on offer
+    pub fn max_level(self) -> u32 {
+        match self {
+            FacilityKind::ChargingArray => 2,
+            _ => 3,
+        }
+    }
+
+    // Refined minerals the level after `level` costs
+    pub fn cost(self, level: u32) -> u32 {
+        let base = match self {
+            FacilityKind::Refinery => 100,
+            FacilityKind::ChargingArray => 80,
+            FacilityKind::Workshop => 120,
+            FacilityKind::Warehouse => 150,
+        };
+        base * (level + 1)
+    }
+
+    // What each level adds to (refinery, warehouse) or takes off (the others) what it applies to:
+    // a percent, but refined minerals off each robot for the workshop
+    pub fn bonus_per_level(self) -> u32 {
+        match self {
+            FacilityKind::Refinery => REFINERY_YIELD_PERCENT_PER_LEVEL,
+            FacilityKind::ChargingArray => CHARGING_DISCOUNT_PERCENT_PER_LEVEL,
+            FacilityKind::Workshop => WORKSHOP_DISCOUNT_PER_LEVEL,
+            FacilityKind::Warehouse => WAREHOUSE_CAPACITY_PERCENT_PER_LEVEL,
+        }
+    }
+}
+
+// A facility the station owns and the level it is built up to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Facility {
+    pub kind: FacilityKind,
+    pub level: u32,
+}
+
+// Level of `kind` among the facilities owned, 0 when it was never built
+pub fn level(facilities: &[Facility], kind: FacilityKind) -> u32 {
+    facilities.iter().find(|facility| facility.kind == kind).map_or(0, |facility| facility.level)
+}
+
+// What the facilities owned add to (refinery, warehouse) or take off (the others) what `kind` applies to, in
+// the units of FacilityKind::bonus_per_level
+pub fn bonus(facilities: &[Facility], kind: FacilityKind) -> u32 {
+    level(facilities, kind) * kind.bonus_per_level()
+}
+
+// The facilities owned, like "refinery 2, workshop 1", or "none"
+pub fn summary(facilities: &[Facility]) -> String {
+    if facilities.is_empty() {
+        return "none".to_string();
+    }
+    facilities.iter().map(|facility| format!("{} {}", facility.kind.name(), facility.level)).collect::<Vec<_>>().join(", ")
+}
+
+// What the station has done that facilities would make cheaper or richer, since it was built
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FacilityUsage {
+    pub ticks: u64,
+    pub minerals_unloaded: u32,     // Minerals robots delivered, before any refinery bonus
+    pub refuel_energy: u32,         // Energy refuels were worth, before any charging discount
+    pub robots_built: u32,          // Robots the station paid minerals for
+    pub turned_away: u32,           // Minerals full storage turned away, and energy at its audit value in minerals
+}
+
+impl FacilityUsage {
+    // Ticks the next level of `kind` would take to earn its `cost` back at the rates so far,
+    // energy counted at its audit value in minerals. None when it would earn nothing
+    pub fn payback_ticks(&self, kind: FacilityKind, cost: u32) -> Option<u64> {
+        let (usage, per_mineral, per_use) = match kind {
+            FacilityKind::Refinery => (self.minerals_unloaded, 1, 1),
+            FacilityKind::ChargingArray => (self.refuel_energy, MINERAL_ENERGY_VALUE as u64, 1),
+            FacilityKind::Workshop => (self.robots_built, 1, 100), // A flat saving on each robot
+            // Counted as if each level made room for its share of what was turned away
+            FacilityKind::Warehouse => (self.turned_away, 1, 1),
+        };
+        let gain = usage as u64 * per_use * kind.bonus_per_level() as u64; // Over `ticks`, in hundredths
+        (gain > 0).then(|| cost as u64 * 100 * per_mineral * self.ticks / gain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payback_follows_the_usage_rates() {
+        let usage = FacilityUsage { ticks: 1000, minerals_unloaded: 500, refuel_energy: 2000, robots_built: 0, turned_away: 0 };
+        // 10% of 500 minerals in 1000 ticks is 0.05 a tick: 100 minerals take 2000 ticks
+        assert_eq!(usage.payback_ticks(FacilityKind::Refinery, 100), Some(2000));
+        // 25% of 2000 energy is 500 energy or 125 minerals in 1000 ticks: 80 minerals take 640
+        assert_eq!(usage.payback_ticks(FacilityKind::ChargingArray, 80), Some(640));
+        assert_eq!(usage.payback_ticks(FacilityKind::Workshop, 120), None);
+        // 10 minerals off each of 6 robots in 1000 ticks: 120 minerals take 2000 ticks
+        assert_eq!(FacilityUsage { robots_built: 6, ..usage }.payback_ticks(FacilityKind::Workshop, 120), Some(2000));
+        // Half of 300 turned away in 1000 ticks is 0.15 a tick: 150 minerals take 1000 ticks
+        assert_eq!(usage.payback_ticks(FacilityKind::Warehouse, 150), None);
+        assert_eq!(FacilityUsage { turned_away: 300, ..usage }.payback_ticks(FacilityKind::Warehouse, 150), Some(1000));
+
+        let facilities = [Facility { kind: FacilityKind::Workshop, level: 2 }];
+        assert_eq!(bonus(&facilities, FacilityKind::Workshop), 20);
+        assert_eq!(level(&facilities, FacilityKind::Refinery), 0);
+        assert_eq!(summary(&facilities), "workshop 2");
+        assert_eq!(summary(&[]), "none");
+    }
+}
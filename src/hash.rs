@@ -0,0 +1,71 @@
+// FNV-1a over explicitly sized little-endian fields: unlike std's DefaultHasher the result is
+// the same across builds and platforms, so hashes can be pinned in tests and quoted in bug reports
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher(u64);
+
+impl StableHasher {
+    pub fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.write(&[value]);
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    // Also used for usize values, so 32- and 64-bit builds agree
+    pub fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    pub fn finish(self) -> u64 {
+        self.0
+    }
+}
+
+// The world seed a --seed value or startup answer stands for: a number is used as is, any
+// other text is hashed, so friends can share a world as a word. Surrounding spaces are ignored
+pub fn seed_from_text(text: &str) -> u32 {
+    let text = text.trim();
+    text.parse().unwrap_or_else(|_| {
+        let mut hasher = StableHasher::new();
+        hasher.write(text.as_bytes());
+        let hash = hasher.finish();
+        (hash ^ (hash >> 32)) as u32
+    })
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Map;
+
+    #[test]
+    fn test_text_seeds_are_stable() {
+        assert_eq!(seed_from_text("1234"), 1234);
+        assert_eq!(seed_from_text(" 7 "), 7);
+        // Pinned: a word must name the same world in every build
+        assert_eq!(seed_from_text("volcano"), 0x060c_3637);
+        assert_eq!(seed_from_text("volcano"), seed_from_text(" volcano"));
+        assert_ne!(seed_from_text("volcano"), seed_from_text("Volcano"));
+        assert_eq!(seed_from_text("99999999999"), seed_from_text("99999999999")); // Too big for a u32: hashed
+        // So `--seed volcano` and `--seed 101463607` start on the same world
+        assert_eq!(Map::new(40, 20, seed_from_text("volcano")).content_hash(), Map::new(40, 20, 101_463_607).content_hash());
+    }
+}
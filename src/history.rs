@@ -0,0 +1,219 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::events::ActivityTally;
+use crate::hash::StableHasher;
+use crate::simulation::Simulation;
+
+// Every interactive run is appended here, keyed by the hash of its config
+pub const RUN_HISTORY_PATH: &str = "run_history.csv";
+
+// Hash of the settings that decide how a run plays out, so runs can be compared with earlier
+// ones of the same world. FNV-1a over the `name=value` lines sorted by name: stable across
+// builds and platforms, and independent of the order the settings are listed in
+pub fn config_hash(settings: &[(&str, String)]) -> u64 {
+    let mut sorted: Vec<&(&str, String)> = settings.iter().collect();
+    sorted.sort();
+    let mut hasher = StableHasher::new();
+    for (name, value) in sorted {
+        hasher.write(format!("{}={}\n", name, value).as_bytes());
+    }
+    hasher.finish()
+}
+
+// How one run ended, as recorded in the history file
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    pub config_hash: u64,
+    pub timestamp: u64,
+    pub ticks: u64,
+    pub explored_percent: f64,
+    pub score: u64,
+    pub deaths: u32,
+    pub ticks_to_half_explored: Option<u64>, // None when the run never got there
+}
+
+impl RunRecord {
+    pub fn of(simulation: &Simulation, config_hash: u64, timestamp: u64) -> Self {
+        Self {
+            config_hash,
+            timestamp,
+            ticks: simulation.clock.now(),
+            explored_percent: simulation.map.exploration_stats().percentage,
+            score: simulation.score(),
+            deaths: simulation.events.subscriber::<ActivityTally>().map_or(0, |tally| tally.wrecks),
+            ticks_to_half_explored: simulation.half_explored_at,
+        }
+    }
+
+    // One CSV row: config_hash,timestamp,ticks,explored_percent,score,deaths,ticks_to_half_explored
+    fn history_line(&self) -> String {
+        format!(
+            "{:016x},{},{},{:.2},{},{},{}",
+            self.config_hash,
+            self.timestamp,
+            self.ticks,
+            self.explored_percent,
+            self.score,
+            self.deaths,
+            self.ticks_to_half_explored.map_or_else(String::new, |ticks| ticks.to_string())
+        )
+    }
+
+    fn parse_line(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split(',').collect();
+        let [hash, timestamp, ticks, explored, score, deaths, half] = fields[..] else {
+            return None;
+        };
+        Some(Self {
+            config_hash: u64::from_str_radix(hash, 16).ok()?,
+            timestamp: timestamp.parse().ok()?,
+            ticks: ticks.parse().ok()?,
+            explored_percent: explored.parse().ok()?,
+            score: score.parse().ok()?,
+            deaths: deaths.parse().ok()?,
+            ticks_to_half_explored: if half.is_empty() { None } else { Some(half.parse().ok()?) },
+        })
+    }
+}
+
+// Append a run to the history file, writing the header for a new file
+pub fn append_run(path: &Path, record: &RunRecord) -> io::Result<()> {
+    let is_new = !path.exists();
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if is_new {
+        writeln!(file, "config_hash,timestamp,ticks,explored_percent,score,deaths,ticks_to_half_explored")?;
+    }
+    writeln!(file, "{}", record.history_line())
+}
+
+// The latest run recorded with this config hash; a missing file or unreadable rows count as no run
+pub fn previous_run(path: &Path, config_hash: u64) -> Option<RunRecord> {
+    let text = std::fs::read_to_string(path).ok()?;
+    text.lines().skip(1).filter_map(RunRecord::parse_line).filter(|record| record.config_hash == config_hash).last()
+}
+
+// A whole number with thousands separators
+fn thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut text = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            text.push(',');
+        }
+        text.push(digit);
+    }
+    text
+}
+
+// A signed change, with a real minus sign and ±0 for no change
+fn signed(delta: i64) -> String {
+    match delta {
+        0 => "±0".to_string(),
+        delta if delta > 0 => format!("+{}", thousands(delta as u64)),
+        delta => format!("−{}", thousands(delta.unsigned_abs())),
+    }
+}
+
+// One metric against its baseline: "14,320 (−480)", or the bare value without a baseline
+fn compared(current: u64, previous: Option<u64>, unit: &str) -> String {
+    match previous {
+        Some(previous) => format!("{}{} ({})", thousands(current), unit, signed(current as i64 - previous as i64)),
+        None => format!("{}{}", thousands(current), unit),
+    }
+}
+
+// The end-of-run summary lines, with the change from `baseline` (the previous run with the same
+// config) after each metric. Percentages are compared in whole points
+pub fn summary_lines(current: &RunRecord, baseline: Option<&RunRecord>) -> Vec<String> {
+    let explored = |record: &RunRecord| record.explored_percent.round() as u64;
+    let half = match (current.ticks_to_half_explored, baseline.map(|baseline| baseline.ticks_to_half_explored)) {
+        (Some(ticks), Some(Some(previous))) => compared(ticks, Some(previous), ""),
+        (Some(ticks), Some(None)) => format!("{} (not reached before)", thousands(ticks)),
+        (Some(ticks), None) => thousands(ticks),
+        (None, Some(Some(previous))) => format!("not reached (was {})", thousands(previous)),
+        (None, _) => "not reached".to_string(),
+    };
+    vec![
+        match baseline {
+            Some(baseline) => format!("Compared with the previous run of this config ({} ticks)", thousands(baseline.ticks)),
+            None => "First run of this config: no previous run to compare with".to_string(),
+        },
+        format!("Exploration {}", compared(explored(current), baseline.map(explored), "%")),
+        format!("Score {}", compared(current.score, baseline.map(|baseline| baseline.score), "")),
+        format!("Robot deaths {}", compared(current.deaths as u64, baseline.map(|baseline| baseline.deaths as u64), "")),
+        format!("Ticks to 50% explored {}", half),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(explored_percent: f64, score: u64, deaths: u32, ticks_to_half_explored: Option<u64>) -> RunRecord {
+        RunRecord { config_hash: 7, timestamp: 1_700_000_000, ticks: 5000, explored_percent, score, deaths, ticks_to_half_explored }
+    }
+
+    #[test]
+    fn test_config_hash_ignores_setting_order() {
+        let settings = [("seed", "42".to_string()), ("terrain", "caves".to_string()), ("wrap", "false".to_string())];
+        let shuffled = [settings[2].clone(), settings[0].clone(), settings[1].clone()];
+        assert_eq!(config_hash(&settings), config_hash(&shuffled));
+        // Pinned so the hashes in existing history files keep matching
+        assert_eq!(config_hash(&settings), 0x4a80_31de_1500_503b);
+        let other_seed = [("seed", "43".to_string()), settings[1].clone(), settings[2].clone()];
+        assert_ne!(config_hash(&settings), config_hash(&other_seed));
+    }
+
+    #[test]
+    fn test_first_run_has_no_deltas() {
+        let lines = summary_lines(&record(82.4, 14_320, 3, Some(4_100)), None);
+        assert_eq!(
+            lines,
+            vec![
+                "First run of this config: no previous run to compare with",
+                "Exploration 82%",
+                "Score 14,320",
+                "Robot deaths 3",
+                "Ticks to 50% explored 4,100",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deltas_against_the_previous_run() {
+        let previous = record(74.6, 14_800, 5, Some(4_700));
+        let lines = summary_lines(&record(82.4, 14_320, 3, Some(4_100)), Some(&previous));
+        assert_eq!(
+            lines[1..],
+            ["Exploration 82% (+7)", "Score 14,320 (−480)", "Robot deaths 3 (−2)", "Ticks to 50% explored 4,100 (−600)"]
+        );
+
+        let same = summary_lines(&previous, Some(&previous));
+        assert_eq!(same[2], "Score 14,800 (±0)");
+        assert_eq!(signed(1_234_567), "+1,234,567");
+
+        // Half the map explored in only one of the two runs
+        assert_eq!(summary_lines(&record(40.0, 0, 0, None), Some(&previous))[4], "Ticks to 50% explored not reached (was 4,700)");
+        let slow = record(40.0, 0, 0, None);
+        assert_eq!(summary_lines(&previous, Some(&slow))[4], "Ticks to 50% explored 4,700 (not reached before)");
+    }
+
+    #[test]
+    fn test_history_lookup_finds_the_latest_matching_run() {
+        let path = std::env::temp_dir().join(format!("rusty_games_run_history_{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(previous_run(&path, 7), None);
+
+        let first = record(50.0, 100, 1, None);
+        let second = RunRecord { timestamp: 1_700_000_100, ..record(60.25, 200, 0, Some(900)) };
+        let other = RunRecord { config_hash: 8, ..record(90.0, 999, 0, Some(10)) };
+        for run in [&first, &second, &other] {
+            append_run(&path, run).unwrap();
+        }
+        assert_eq!(previous_run(&path, 7), Some(second));
+        assert_eq!(previous_run(&path, 9), None);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,139 @@
+// Rows of a bordered one-line panel
+pub const PANEL_ROWS: u16 = 3;
+
+// The map keeps at least this many rows (borders included) before any panel is shown under it
+pub const MIN_MAP_ROWS: u16 = 12;
+
+// Optional panels are only added while the map still keeps this many rows
+const ROOMY_MAP_ROWS: u16 = 40;
+
+// The panels that can be shown under the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Panel {
+    StationStats,
+    SwarmStats,
+    Exploration,
+    Activity, // Swarm activity tally; optional
+    Info,
+}
+
+impl Panel {
+    // Which panels keep their rows first when the terminal is short (lowest first)
+    fn priority(self) -> u8 {
+        match self {
+            Panel::StationStats => 0,
+            Panel::Info => 1,
+            Panel::SwarmStats => 2,
+            Panel::Exploration => 3,
+            Panel::Activity => 4,
+        }
+    }
+
+    // Optional panels are left out rather than condensed when they do not fit
+    fn optional(self) -> bool {
+        self == Panel::Activity
+    }
+}
+
+// Every panel, in the order they are stacked under the map
+pub const ALL_PANELS: [Panel; 5] = [Panel::StationStats, Panel::SwarmStats, Panel::Exploration, Panel::Activity, Panel::Info];
+
+// How the rows of the terminal are shared out: the map on top, the panels that fit under it
+// and, when some required panel does not fit, a one-line condensed status at the bottom
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutPlan {
+    pub map_rows: u16,       // Borders included
+    pub panels: Vec<Panel>,  // PANEL_ROWS each, in display order
+    pub condensed: Vec<Panel>, // Required panels folded into the status line, in display order
+}
+
+impl LayoutPlan {
+    // Rows of the condensed status line: one when it has anything to show
+    pub fn status_rows(&self) -> u16 {
+        if self.condensed.is_empty() { 0 } else { 1 }
+    }
+}
+
+// Share `height` rows between the map and the `enabled` panels (listed in display order):
+// panels are given rows by priority while the map keeps MIN_MAP_ROWS (ROOMY_MAP_ROWS for
+// optional ones), and required panels left without rows are condensed into the status line
+pub fn plan(height: u16, enabled: &[Panel]) -> LayoutPlan {
+    let mut by_priority = enabled.to_vec();
+    by_priority.sort_by_key(|panel| panel.priority());
+    let mut shown = Vec::new();
+    let mut condensed = Vec::new();
+    for panel in by_priority {
+        let map_floor = if panel.optional() { ROOMY_MAP_ROWS } else { MIN_MAP_ROWS };
+        if height >= map_floor + PANEL_ROWS * (shown.len() as u16 + 1) {
+            shown.push(panel);
+        } else if !panel.optional() {
+            condensed.push(panel);
+        }
+    }
+    // The status line takes its row from the least important panel when the map has none to spare
+    if !condensed.is_empty() && height < MIN_MAP_ROWS + PANEL_ROWS * shown.len() as u16 + 1 {
+        if let Some(panel) = shown.pop() {
+            condensed.push(panel);
+        }
+    }
+    if height <= PANEL_ROWS * shown.len() as u16 {
+        condensed.clear(); // Not even one row left for it
+    }
+
+    let in_display_order = |panels: &mut Vec<Panel>| panels.sort_by_key(|panel| enabled.iter().position(|enabled| enabled == panel));
+    in_display_order(&mut shown);
+    in_display_order(&mut condensed);
+    let mut plan = LayoutPlan { map_rows: 0, panels: shown, condensed };
+    plan.map_rows = height - PANEL_ROWS * plan.panels.len() as u16 - plan.status_rows();
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_across_terminal_heights() {
+        for height in 0..120 {
+            let plan = plan(height, &ALL_PANELS);
+            assert_eq!(plan.map_rows + PANEL_ROWS * plan.panels.len() as u16 + plan.status_rows(), height, "height {}", height);
+            if !plan.panels.is_empty() {
+                assert!(plan.map_rows >= MIN_MAP_ROWS, "height {}", height);
+            }
+            // Shown panels are the most important ones, in display order
+            let required = ALL_PANELS.iter().filter(|panel| !panel.optional()).count();
+            let mut kept: Vec<u8> = plan.panels.iter().map(|panel| panel.priority()).collect();
+            kept.sort();
+            assert_eq!(kept, (0..kept.len() as u8).collect::<Vec<_>>());
+            assert!(plan.panels.windows(2).all(|pair| ALL_PANELS.iter().position(|p| *p == pair[0]) < ALL_PANELS.iter().position(|p| *p == pair[1])));
+            // Every required panel is either shown or condensed, optional ones only when roomy
+            let required_shown = plan.panels.iter().filter(|panel| !panel.optional()).count();
+            if height > 0 {
+                assert_eq!(required_shown + plan.condensed.len(), required, "height {}", height);
+            }
+            if plan.panels.contains(&Panel::Activity) {
+                assert!(plan.map_rows >= ROOMY_MAP_ROWS);
+            }
+        }
+    }
+
+    #[test]
+    fn test_plan_for_common_terminal_sizes() {
+        use Panel::*;
+        // Tight: the most important panels keep their rows, the others go on the status line
+        let tight = plan(18, &ALL_PANELS);
+        assert_eq!((tight.map_rows, tight.panels.clone(), tight.condensed.clone()), (14, vec![StationStats], vec![SwarmStats, Exploration, Info]));
+        let cramped = plan(20, &ALL_PANELS);
+        assert_eq!((cramped.map_rows, cramped.panels.clone(), cramped.condensed.clone()), (13, vec![StationStats, Info], vec![SwarmStats, Exploration]));
+        // Standard: every required panel, no status line
+        let standard = plan(24, &ALL_PANELS);
+        assert_eq!((standard.map_rows, standard.panels.clone(), standard.status_rows()), (12, vec![StationStats, SwarmStats, Exploration, Info], 0));
+        assert_eq!(plan(30, &ALL_PANELS).map_rows, 18);
+        // Generous: the optional activity panel joins in
+        let tall = plan(60, &ALL_PANELS);
+        assert_eq!((tall.map_rows, tall.panels), (45, ALL_PANELS.to_vec()));
+        // Disabled panels are neither shown nor condensed
+        let few = plan(20, &[StationStats, Info]);
+        assert_eq!((few.map_rows, few.panels, few.condensed), (14, vec![StationStats, Info], vec![]));
+    }
+}
@@ -3,23 +3,128 @@ mod robot;
 mod ui;
 mod station; // Add station module
 mod startup; // Add startup module
+mod simulation;
+mod viewport;
+mod challenge;
+mod preview;
+mod events;
+mod config;
+mod diff;
+mod tutorial;
+mod batch;
+mod cli;
+mod query;
+mod expedition;
+mod scenario;
+mod history;
+mod layout;
+mod hash;
+mod motion;
+mod audit;
+mod snapshots;
+mod position;
+mod radio;
+mod decisions;
+mod clock;
+mod upgrades;
+mod facilities;
+mod station_log;
+mod tasks;
+mod behavior;
+mod pathfinding;
 
 use rand::Rng;
 use std::thread;
 use std::time::{Duration, Instant};
 
-use map::Map;
-use robot::{Robot, RobotType}; // Add RobotType import
-use ui::UI;
-use crate::station::Station; // Add import for Station
+use map::{Map, MapConfig};
+use ui::{FrameSettings, Frontend, InputAction, UI};
 use crate::startup::StartupScreen; // Add import for StartupScreen
+use crate::simulation::Simulation;
+use crate::events::ActivityTally;
+use crate::decisions::DecisionLog;
+use crate::config::SimConfig;
+use crate::behavior::BehaviorConfig;
+use crate::cli::{BatchArgs, Command, RunArgs};
+use crate::query::Query;
+use crate::scenario::Scenario;
+use crate::tutorial::Tutorial;
+use crate::robot::LifetimeStats;
+
+// Robots listed in the end-of-run discovery leaderboard
+const LEADERBOARD_SIZE: usize = 5;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Show startup screen and wait for Enter
-    if !StartupScreen::show() {
-        println!("Failed to start game.");
+    // Route the subcommand to its driver, reporting bad arguments before the TUI starts
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match cli::parse(&args) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(2);
+        }
+    };
+    let (session, preview, challenge_name, tutorial, scenario_path, snapshot_ticks, explain_station) = match command {
+        Command::Help(topic) => {
+            print!("{}", cli::help(topic));
+            return Ok(());
+        }
+        Command::DiffRuns(runs) => return diff_runs(&runs.left, &runs.right),
+        Command::Batch(batch) => return run_batch(&batch),
+        Command::Run(RunArgs { session, dump_robots: Some(query), ticks, scenario, .. }) => {
+            let config = SimConfig::resolve(&session.overrides, None);
+            return dump_robots(&config, scenario.as_deref(), &query, ticks.unwrap_or(DEFAULT_DUMP_TICKS));
+        }
+        Command::Run(RunArgs { session, print_hash: true, scenario, .. }) => {
+            let config = SimConfig::resolve(&session.overrides, None);
+            return print_hash(&config, scenario.as_deref());
+        }
+        Command::Run(run) => (run.session, None, None, false, run.scenario, run.snapshot_ticks, run.explain_station),
+        Command::Preview(preview) => (preview.session, Some(preview.seeds), None, false, None, Vec::new(), false),
+        Command::Challenge(challenge) => (challenge.session, None, Some(challenge.name), false, None, Vec::new(), false),
+        Command::Tutorial(session) => (session, None, None, true, None, Vec::new(), false),
+    };
+    let challenge = match &challenge_name {
+        Some(name) => Some(challenge::find_challenge(&challenge::load_challenges()?, name)?),
+        None => None,
+    };
+    let mut config = SimConfig::resolve(&session.overrides, challenge.as_ref());
+    if session.print_effective_config {
+        print!("{}", config);
         return Ok(());
     }
+    // Load a hand-crafted map or a scenario if one was given
+    let ascii_map = match &config.map_file.value {
+        Some(path) => Some(load_ascii_map(path)?),
+        None => None,
+    };
+    let scenario = scenario_path.as_deref().map(load_scenario);
+    let behavior_config = load_behavior_config(&config)?;
+
+    // Either page through previewed worlds and start on the accepted seed,
+    // or show the startup screen and wait for Enter
+    let previewed = match preview {
+        Some(seeds) => match preview::run(seeds, config.terrain.value)? {
+            Some(seed) => {
+                config.set_preview_seed(seed);
+                true
+            }
+            None => return Ok(()),
+        },
+        None => false,
+    };
+    if !previewed {
+        // Only a generated world can take a seed typed here
+        let ask_seed = config.seed.value.is_none() && ascii_map.is_none() && scenario.is_none() && challenge.is_none() && !tutorial;
+        match StartupScreen::show(ask_seed) {
+            Some(Some(seed)) => config.set_startup_seed(seed),
+            Some(None) => {}
+            None => {
+                println!("Failed to start game.");
+                return Ok(());
+            }
+        }
+    }
 
     // Clear screen before starting game
     print!("\x1B[2J\x1B[1;1H");
@@ -28,334 +133,517 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize user interface
     let mut ui = UI::new()?;
 
-    // Generate random seed for the map
-    let seed = rand::thread_rng().gen();
+    // The tutorial, challenges and scenarios bring their own fixed world; otherwise use the loaded map or generate one sized to the terminal
+    let mut simulation = match (&challenge, ascii_map, scenario) {
+        _ if tutorial => tutorial::build_simulation(),
+        (_, _, Some(simulation)) => simulation,
+        (Some(challenge), _, None) => challenge.build_simulation(),
+        (None, Some((map, suggested_station)), None) => Simulation::setup(map, suggested_station),
+        (None, None, None) => {
+            // Use the configured seed, or generate a random one for the map
+            let seed = *config.seed.value.get_or_insert_with(|| rand::thread_rng().gen());
+
+            // Size the map to the terminal, leaving room for the layout in ui.rs
+            let (map_width, map_height) = ui::generated_map_size(ui.get_terminal_size()?);
+            let map_width = *config.map_width.value.get_or_insert(map_width);
+            let map_height = *config.map_height.value.get_or_insert(map_height);
+            let map_config = MapConfig { terrain: config.terrain.value, underground: config.underground.value, ..MapConfig::default() };
+            Simulation::setup(Map::with_config(map_width, map_height, seed, &map_config), None)
+        }
+    };
+    simulation.map.wrap = config.wrap.value;
+    configure_stations(&mut simulation, &config, behavior_config)?;
+    simulation.malfunction_odds = config.malfunction_odds.value;
+    simulation.record_snapshots(&snapshot_ticks);
+    let generated = simulation.map.summary();
+    let world_hash = simulation.map.content_hash();
+
+    // Keep a tally of swarm activity; a misbehaving reaction must not take the run down
+    simulation.events.set_paranoid(true);
+    simulation.events.subscribe(Box::new(ActivityTally::default()));
+    if explain_station {
+        simulation.explain_station = true;
+        simulation.events.subscribe(Box::new(DecisionLog::create(decisions::DECISION_LOG_PATH)?));
+    }
+    let frame_time = Duration::from_millis(config.frame_time_ms.value);
+    let settings = FrameSettings { ticks_per_frame: config.ticks_per_frame.value, paused: false };
+    let tick_limit = config.tick_limit.value;
+    // Runs are compared with the previous one of the same world and settings
+    let mut determinism_settings = config.determinism_settings();
+    let world = match &scenario_path {
+        _ if tutorial => "tutorial".to_string(),
+        Some(path) => format!("scenario {}", path),
+        None => "config".to_string(),
+    };
+    determinism_settings.push(("world", world));
+    let config_hash = history::config_hash(&determinism_settings);
+    let mut tutorial = tutorial.then(|| Tutorial::new(tutorial::bundled_steps()));
+    let seed = config.seed.value;
+    ui.set_config(config);
+    run_frames(&mut ui, &mut simulation, settings, frame_time, tick_limit, tutorial.as_mut())?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let history_path = std::path::Path::new(history::RUN_HISTORY_PATH);
+    let record = history::RunRecord::of(&simulation, config_hash, timestamp);
+    let comparison = history::summary_lines(&record, history::previous_run(history_path, config_hash).as_ref());
+    ui.show_summary(&comparison)?;
 
-    // Get terminal size from UI
-    let terminal_size = ui.get_terminal_size()?;
-    let map_width = terminal_size.width as usize;
-    // Adjust map_height to accommodate the new layout in ui.rs (map + 3 lines for stats/controls)
-    let map_height = terminal_size.height.saturating_sub(10) as usize; // Adjusted for 9 lines panel + 1 map border
-    let mut map = Map::new(map_width, map_height, seed);
+    // Clean up and restore the terminal
+    ui.cleanup()?;
 
-    // --- Station Placement Logic ---
-    let mut station_x = map_width / 2;
-    let mut station_y = map_height / 2;
-    let mut station_placement_attempts = 0;
-    loop {
-        if let Some(cell) = map.get_cell(station_x, station_y) {
-            if cell.cell_type != map::CellType::Obstacle {
-                break; // Found a non-obstacle spot for the station
-            }
+    println!("Autonomous exploration simulation ended.");
+    println!("Final station statistics:");
+    println!("Ticks Simulated: {}", simulation.clock.now());
+    if let Some(seed) = seed {
+        println!("Seed: {} (pass --seed {} to play this world again)", seed, seed);
+    }
+    let explored = simulation.map.exploration_stats();
+    let known = simulation.station().known_coverage(&simulation.map);
+    println!("Map Explored: {:.1}% ({}/{} passable cells)", explored.percentage, explored.explored_cells, explored.passable_cells);
+    println!("Reported to Station: {:.1}%", known.percentage);
+    let regions_swept: u32 = simulation.station().robots.iter().map(|robot| robot.regions_swept).sum();
+    println!(
+        "Explorer Coverage: {:.1}% in {} ticks with the {} behavior, {} regions swept",
+        explored.percentage,
+        simulation.clock.now(),
+        simulation.station().explorer_behavior,
+        regions_swept
+    );
+    println!("Station Energy: {}", simulation.station().energy);
+    println!("Station Minerals: {} refined, {} raw awaiting refinement", simulation.station().refined_minerals, simulation.station().raw_minerals);
+    println!("Station Science Points: {} ({} science points analyzed on site)", simulation.station().science_points, simulation.station().analyzed_cells.len());
+    println!(
+        "Upgrades: {} ({} science points spent)",
+        simulation.station().upgrades.summary(),
+        simulation.station().upgrades.science_spent()
+    );
+    println!("Facilities: {}", facilities::summary(&simulation.station().facilities));
+    println!(
+        "Data Analysis: {} science from archived observations, {} data units left unanalyzed",
+        simulation.station().science_from_analysis, simulation.station().unanalyzed_data
+    );
+    println!(
+        "Salvaged Parts: {} in stock, {} minerals saved on construction",
+        simulation.station().parts, simulation.station().parts_savings
+    );
+    println!("Total Robots Created: {}", simulation.station().robots.len());
+    println!("Station Log: {}", simulation.station().events.summary());
+    if simulation.stations.len() > 1 {
+        println!("Stations (the figures above are the main one's):");
+        for station in &simulation.stations {
+            println!(
+                "  #{} at ({}, {}): {} energy, {} minerals, {} science points, {} robots",
+                station.id + 1,
+                station.x,
+                station.y,
+                station.energy,
+                station.raw_minerals + station.refined_minerals,
+                station.science_points,
+                station.robots.len()
+            );
         }
-        // Try a new spot if current is an obstacle or out of bounds (though get_cell handles out of bounds)
-        station_x = (map_width / 2 + station_placement_attempts) % map_width;
-        station_y = (map_height / 2 + station_placement_attempts / map_width) % map_height;
-        station_placement_attempts += 1;
-        if station_placement_attempts > map_width * map_height { // Safety break
-            eprintln!("Could not find a non-obstacle position for the station. Placing at (0,0) as fallback.");
-            station_x = 0;
-            station_y = 0;
-            // Ensure (0,0) is not an obstacle, or have a more robust fallback.
-            // For simplicity, we might just overwrite it or require map generation to leave (0,0) clear.
-            if let Some(cell_mut) = map.get_cell_mut(0,0) { // Example: Force (0,0) to be Empty
-                cell_mut.cell_type = map::CellType::Empty;
-            }
-            break;
+    }
+    let path_searches: u64 = simulation.station().robots.iter().map(|robot| robot.path_searches()).sum();
+    let fallback_steps: u32 = simulation.station().robots.iter().map(|robot| robot.fallback_steps).sum();
+    println!("Path Searches: {} A* searches by the fleet, {} walks fell back on greedy steps", path_searches, fallback_steps);
+    println!("Robot Lifetimes:");
+    println!("  {:>4} {:<17} {}", "id", "type", LifetimeStats::header());
+    for robot in &simulation.station().robots {
+        println!("  {:>4} {:<17} {}", format!("#{}", robot.id + 1), format!("{:?}", robot.robot_type), robot.lifetime_stats().row());
+    }
+    println!("World at Start: {}", generated);
+    println!("World Hash: {:016x} (final state {:016x})", world_hash, simulation.state_hash());
+    println!("Left in the World: {}", simulation.map.summary());
+    if let Some(tally) = simulation.events.subscriber::<ActivityTally>() {
+        println!(
+            "Swarm Activity: {} resources collected, {} deliveries, {} cells reported, {} rescues, {} wrecks, {} respawns",
+            tally.resources_collected, tally.deliveries, tally.cells_shared, tally.rescues, tally.wrecks, tally.respawns
+        );
+        println!(
+            "Field Caches: {} dropped, {} recovered, {} lost",
+            tally.caches_dropped, tally.caches_recovered, tally.caches_lost
+        );
+        println!(
+            "Breakdowns: {} malfunctions, {} repaired at the station, {} worn robots decommissioned",
+            tally.malfunctions, tally.malfunctions_repaired, tally.decommissioned
+        );
+        println!("Retrofits: {} surplus explorers refitted as another type", tally.retrofits);
+        println!("Low power: {} robots powered down for good waiting for a rescue", tally.power_downs);
+    }
+    let audit = &simulation.audit;
+    println!(
+        "Exploration Audit: explorers spent {} energy, their discoveries yielded {} ({}), {} advisories",
+        audit.total_energy,
+        audit.total_value,
+        audit.total_ratio().map_or("no spending".to_string(), |ratio| format!("{:.2} per energy", ratio)),
+        audit.advisories
+    );
+    let leaderboard = simulation.station().discovery_leaderboard();
+    if !leaderboard.is_empty() {
+        println!("Discovery Leaderboard:");
+        for (rank, (robot, cells)) in leaderboard.iter().take(LEADERBOARD_SIZE).enumerate() {
+            println!("  {}. Robot #{} ({:?}): {} cells", rank + 1, robot + 1, simulation.station().robots[*robot].robot_type, cells);
         }
     }
-    let mut station = Station::new(station_x, station_y); // Create station at the validated non-obstacle position
-
-    // Create the first robot - an Explorer
-    let mut first_robot_x = station.x;
-    let mut first_robot_y = station.y.saturating_sub(1);
-    if first_robot_y == station.y {
-        first_robot_y = station.y.saturating_add(1);
-        if first_robot_y >= map_height {
-            first_robot_x = station.x.saturating_sub(1);
-            first_robot_y = station.y;
-            if first_robot_x == station.x { 
-                first_robot_x = station.x.saturating_add(1);
-            }
+    if !simulation.snapshots.snapshots.is_empty() {
+        println!("Map Snapshots:");
+        for line in simulation.snapshots.report_lines() {
+            println!("{}", line);
         }
     }
-    
-    if first_robot_x >= map_width { 
-        first_robot_x = map_width.saturating_sub(1);
+    for line in &comparison {
+        println!("{}", line);
+    }
+    if let Err(err) = history::append_run(history_path, &record) {
+        eprintln!("Could not record run history: {}", err);
     }
-    if first_robot_y >= map_height { 
-        first_robot_y = map_height.saturating_sub(1);
+    if let Some(log) = simulation.events.subscriber::<DecisionLog>() {
+        println!("Station Decisions: {} explained in {}", log.written, decisions::DECISION_LOG_PATH);
+    }
+    for failure in &simulation.events.failures {
+        eprintln!("Event subscriber failure: {}", failure);
     }
 
-    let mut robot_placement_attempts = 0;
-    loop {
-        let is_on_station = first_robot_x == station.x && first_robot_y == station.y;
-        let mut is_on_obstacle = false;
-        if let Some(cell) = map.get_cell(first_robot_x, first_robot_y) {
-            if cell.cell_type == map::CellType::Obstacle {
-                is_on_obstacle = true;
-            }
-        } else {
-            is_on_obstacle = true;
+    if let Some(challenge) = challenge {
+        let result = challenge.evaluate(&simulation);
+        println!("{}", result.summary());
+        if let Err(err) = challenge::append_history(std::path::Path::new(challenge::HISTORY_PATH), &result, timestamp) {
+            eprintln!("Could not record challenge history: {}", err);
         }
+    }
+
+    Ok(())
+}
+
+// Ticks a --dump-robots run lasts when --ticks is not given
+const DEFAULT_DUMP_TICKS: u64 = 1000;
+
+// Size of the world generated for a --dump-robots run, which has no terminal to fill
+// (and for --print-hash without a terminal)
+const DUMP_MAP_WIDTH: usize = 100;
+const DUMP_MAP_HEIGHT: usize = 50;
+
+fn load_ascii_map(path: &str) -> Result<(Map, Option<(usize, usize)>), String> {
+    let text = std::fs::read_to_string(path).map_err(|err| format!("could not read map file '{}': {}", path, err))?;
+    Map::from_ascii(&text).map_err(|err| format!("invalid map file '{}': {}", path, err))
+}
+
+// The robot tuning from the configured file, or the defaults without one
+fn load_behavior_config(config: &SimConfig) -> Result<BehaviorConfig, String> {
+    config.behavior_config.value.as_deref().map_or(Ok(BehaviorConfig::default()), BehaviorConfig::load)
+}
 
-        if !is_on_station && !is_on_obstacle {
-            break;
+// Found the extra bases asked for, then give every station the run's fleet policy
+fn configure_stations(simulation: &mut Simulation, config: &SimConfig, behavior_config: BehaviorConfig) -> Result<(), String> {
+    for _ in 1..config.stations.value {
+        if simulation.add_outpost().is_none() {
+            let separation = simulation::MIN_STATION_SEPARATION;
+            return Err(format!("the map has no room for {} stations {} cells apart", config.stations.value, separation));
         }
+    }
+    for station in &mut simulation.stations {
+        station.set_explorer_behavior(config.explorer_behavior.value);
+        station.set_behavior_config(behavior_config);
+        station.pair_explorers = config.pair_explorers.value;
+    }
+    Ok(())
+}
 
-        first_robot_x = (station.x + robot_placement_attempts) % map_width;
-        first_robot_y = (station.y + robot_placement_attempts / map_width) % map_height;
-        robot_placement_attempts += 1;
+// Build a scenario's world, reporting every problem with the file before the TUI starts
+fn load_scenario(path: &str) -> Simulation {
+    match Scenario::load(path).and_then(|scenario| scenario.build_simulation()) {
+        Ok(simulation) => simulation,
+        Err(err) => {
+            eprintln!("error: {}: {}", path, err);
+            std::process::exit(2);
+        }
+    }
+}
 
-        if robot_placement_attempts > map_width * map_height {
-            let (fallback_x, fallback_y) = find_clear_spot_for_robot(&map, station.x, station.y);
-            first_robot_x = fallback_x;
-            first_robot_y = fallback_y;
-            eprintln!("Could not find ideal spot for first robot, using fallback: ({}, {}).", first_robot_x, first_robot_y);
-            break;
+// Run a world headless for some ticks and print the robots matching a fleet query
+fn dump_robots(config: &SimConfig, scenario: Option<&str>, query: &Query, ticks: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let mut simulation = match (scenario, &config.map_file.value) {
+        (Some(path), _) => load_scenario(path),
+        (None, Some(path)) => {
+            let (map, suggested_station) = load_ascii_map(path)?;
+            Simulation::setup(map, suggested_station)
+        }
+        (None, None) => {
+            let seed = config.seed.value.unwrap_or_else(|| rand::thread_rng().gen());
+            println!("Seed: {}", seed);
+            let map_config = MapConfig { terrain: config.terrain.value, underground: config.underground.value, ..MapConfig::default() };
+            Simulation::setup(Map::with_config(DUMP_MAP_WIDTH, DUMP_MAP_HEIGHT, seed, &map_config), None)
         }
+    };
+    simulation.map.wrap = config.wrap.value;
+    configure_stations(&mut simulation, config, load_behavior_config(config)?)?;
+    simulation.malfunction_odds = config.malfunction_odds.value;
+    for _ in 0..ticks {
+        simulation.tick();
     }
-    
-    // Create initial robots - prioritize explorers for better coverage
-    let robot_types = [
-        RobotType::Explorer,
-        RobotType::Explorer,     
-        RobotType::Explorer,     
-        RobotType::Explorer,     // Additional explorer
-        RobotType::Explorer,     // Additional explorer
-        RobotType::Explorer,     // Additional explorer
-        RobotType::EnergyCollector, 
-        RobotType::MineralCollector,
-        RobotType::Scientist,
-    ];
-
-    // Define starting directions to spread robots out - more directions for more robots
-    let start_directions = [
-        (0, -8),    // North (further)
-        (8, 0),     // East (further)
-        (0, 8),     // South (further)
-        (-8, 0),    // West (further)
-        (6, -6),    // Northeast (further)
-        (-6, 6),    // Southwest (further)
-        (6, 6),     // Southeast (further)
-        (-6, -6),   // Northwest (further)
-        (0, -12),   // Far North
-    ];
-
-    for (i, robot_type) in robot_types.iter().enumerate() {
-        let (robot_x, robot_y) = if i == 0 {
-            // Use the calculated position for the first robot
-            (first_robot_x, first_robot_y)
-        } else {
-            // Try to place robots in different directions from station
-            let (dx, dy) = start_directions[i % start_directions.len()];
-            let target_x = (station.x as i32 + dx).max(0).min(map_width as i32 - 1) as usize;
-            let target_y = (station.y as i32 + dy).max(0).min(map_height as i32 - 1) as usize;
-            
-            // Find nearest clear spot to the target direction
-            find_clear_spot_near_target(&map, target_x, target_y, &station.robots)
-        };
-        
-        // Create robot directly and add to station (bypass resource cost for initial robots)
-        let robot = Robot::new_with_type(robot_x, robot_y, *robot_type);
-        station.robots.push(robot);
-    }
-
-    // Main loop
-    let mut running = true;
-    let frame_time = Duration::from_millis(100); // Even faster updates for more aggressive exploration
-
-    while running {
-        let frame_start = Instant::now();
+    let fleet = query::fleet(&simulation);
+    let matching: Vec<_> = fleet.iter().filter(|entry| query.matches(entry)).collect();
+    println!("Tick {}: {} of {} robots match '{}'", simulation.clock.now(), matching.len(), fleet.len(), query);
+    println!("{}", query::FleetEntry::header());
+    for entry in matching {
+        println!("{}", entry.row());
+    }
+    Ok(())
+}
 
-        // Handle user input (only quit in autonomous mode)
-        running = ui.handle_input()?;
-
-        // Update all robots autonomously
-        for i in 0..station.robots.len() {
-            // Create a slice of other robots (excluding the current one)
-            let (left, right) = station.robots.split_at_mut(i);
-            let (current, right) = right.split_first_mut().unwrap();
-            let other_robots: Vec<_> = left.iter().chain(right.iter()).cloned().collect();
-            
-            current.autonomous_update(&mut map, station.x, station.y, &other_robots);
+// Print the seed, size and content hash of the world `run` would start on with these settings,
+// so a world can be quoted in a bug report and checked on another machine. Generated worlds are
+// sized to the terminal like an interactive run
+fn print_hash(config: &SimConfig, scenario: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let simulation = match (scenario, &config.map_file.value) {
+        (Some(path), _) => load_scenario(path),
+        (None, Some(path)) => {
+            let (map, suggested_station) = load_ascii_map(path)?;
+            Simulation::setup(map, suggested_station)
+        }
+        (None, None) => {
+            let seed = config.seed.value.unwrap_or_else(|| rand::thread_rng().gen());
+            println!("Seed: {}", seed);
+            let (map_width, map_height) = match crossterm::terminal::size() {
+                Ok((width, height)) => ui::generated_map_size(ratatui::layout::Rect::new(0, 0, width, height)),
+                Err(_) => (DUMP_MAP_WIDTH, DUMP_MAP_HEIGHT),
+            };
+            let map_config = MapConfig { terrain: config.terrain.value, underground: config.underground.value, ..MapConfig::default() };
+            Simulation::setup(Map::with_config(map_width, map_height, seed, &map_config), None)
         }
+    };
+    println!("Map size: {}x{}", simulation.map.width, simulation.map.height);
+    println!("Map hash: {:016x}", simulation.map.content_hash());
+    Ok(())
+}
 
-        // Handle robot-station interactions
-        let mut robots_to_update = Vec::new();
-        for i in 0..station.robots.len() {
-            let robot = &station.robots[i];
-            if robot.x == station.x && robot.y == station.y {
-                robots_to_update.push(i);
+// Developer tool: run two worlds headless in lockstep and report where they first differ
+fn diff_runs(left: &str, right: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let (mut left_run, mut right_run) = (diff::load_run(left)?, diff::load_run(right)?);
+    match diff::find_divergence(&mut left_run, &mut right_run, diff::DIFF_RUNS_MAX_TICKS, |_| {}) {
+        Some(divergence) => println!("{}", divergence.report()),
+        None => println!("No divergence in {} ticks", diff::DIFF_RUNS_MAX_TICKS),
+    }
+    Ok(())
+}
+
+// Balance tool: run many headless worlds and summarize how they ended
+fn run_batch(args: &BatchArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let plan = batch::BatchPlan {
+        runs: args.runs,
+        ticks: args.ticks.unwrap_or(batch::DEFAULT_BATCH_TICKS),
+        base_seed: args.overrides.seed.unwrap_or_else(|| rand::thread_rng().gen()),
+        wrap: args.overrides.wrap.unwrap_or(false),
+        terrain: args.overrides.terrain.unwrap_or_default(),
+        threads: args.threads.unwrap_or_else(|| thread::available_parallelism().map_or(1, |threads| threads.get())),
+    };
+    println!("Running {} worlds of {} ticks from base seed {} (threads: {})", plan.runs, plan.ticks, plan.base_seed, plan.threads);
+    let results = batch::run_batch(&plan, |done, result| {
+        println!(
+            "[{}/{}] seed {} done in {:.1}s: score {}",
+            done, plan.runs, result.outcome.seed, result.wall_time.as_secs_f64(), result.outcome.score
+        );
+    });
+    if let Some(path) = &args.out {
+        batch::write_csv(std::path::Path::new(path), &results)
+            .map_err(|err| format!("could not write batch results to '{}': {}", path, err))?;
+        println!("Wrote {} rows to {}", results.len(), path);
+    }
+    print!("{}", batch::BatchSummary::of(&results));
+    Ok(())
+}
+
+// Run the simulation until the user quits (or `tick_limit` is reached): each frame polls
+// input once, runs a batch of `ticks_per_frame` ticks and renders the latest state once.
+// A tutorial gets to show at most one prompt per frame
+fn run_frames<F: Frontend>(
+    frontend: &mut F,
+    simulation: &mut Simulation,
+    mut settings: FrameSettings,
+    frame_time: Duration,
+    tick_limit: Option<u64>,
+    mut tutorial: Option<&mut Tutorial>,
+) -> std::io::Result<()> {
+    loop {
+        let frame_start = Instant::now();
+
+        // Handle user input between batches so quit/pause stay responsive
+        let action = frontend.handle_input()?;
+        if action == InputAction::Quit {
+            return Ok(());
+        }
+        if action == InputAction::ToggleRecall {
+            let recall = !simulation.station().recall;
+            for station in &mut simulation.stations {
+                station.set_recall(recall, simulation.clock.now());
             }
         }
+        if action == InputAction::OrderOutpost {
+            simulation.order_outpost();
+        }
+        settings.apply(action);
 
-        // Process interactions for robots at station
-        for &robot_index in &robots_to_update {
-            // 1. Unload resources
-            let (energy_payload, minerals_payload, science_payload) = station.robots[robot_index].unload_payload();
-            if energy_payload > 0 || minerals_payload > 0 || science_payload > 0 {
-                station.collect_resources(energy_payload, minerals_payload, science_payload);
-            }
+        if !settings.paused {
+            // The last batch is cut short so a limited run ends exactly on its limit
+            let remaining = tick_limit.map_or(u64::MAX, |limit| limit.saturating_sub(simulation.clock.now()));
+            simulation.run_ticks(settings.ticks_per_frame.min(remaining.min(u32::MAX as u64) as u32));
+        }
 
-            // 2. Share map data
-            let updates = station.robots[robot_index].get_exploration_updates();
-            if !updates.is_empty() {
-                station.share_data(&updates);
-            }
+        // Show the next tutorial prompt once its trigger is met, holding the run if it asks to
+        if let Some(step) = tutorial.as_deref_mut().and_then(|tutorial| tutorial.update(simulation)) {
+            settings.paused |= step.pause;
+            frontend.show_prompt(step);
+        }
 
-            // 3. Refuel robot at station (consume station energy)
-            let refuel_cost = robot::INITIAL_ROBOT_ENERGY.saturating_sub(station.robots[robot_index].energy);
-            if refuel_cost > 0 && station.energy >= refuel_cost {
-                station.energy -= refuel_cost;
-                station.robots[robot_index].energy = robot::INITIAL_ROBOT_ENERGY;
-            }
+        // Display the map and station
+        frontend.render(simulation, &settings)?;
 
-            // 4. Update robot state to continue exploring
-            station.robots[robot_index].state = robot::RobotState::Exploring;
+        if tick_limit.is_some_and(|limit| simulation.clock.now() >= limit) {
+            return Ok(());
         }
 
-        // Handle dead robots - respawn them at the station (if station has energy)
-        for robot in &mut station.robots {
-            if robot.energy == 0 {
-                robot.x = station.x;
-                robot.y = station.y;
-                robot.state = robot::RobotState::AtStation;
-                robot.steps_since_last_find = 0;
-                
-                // Respawn robot only if station has enough energy
-                if station.energy >= robot::INITIAL_ROBOT_ENERGY {
-                    station.energy -= robot::INITIAL_ROBOT_ENERGY;
-                    robot.energy = robot::INITIAL_ROBOT_ENERGY;
-                }
+        // Limit the refresh rate
+        frontend.wait(simulation, &settings, frame_time.saturating_sub(frame_start.elapsed()))?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::station::Station;
+    use crate::tutorial::{Trigger, TutorialStep};
+    use std::collections::VecDeque;
+
+    // Frontend double replaying scripted key presses and recording each render
+    struct ScriptedFrontend {
+        actions: VecDeque<InputAction>,
+        rendered_ticks: Vec<u64>,
+        prompts: Vec<(u64, &'static str)>, // Tick of the last render and the prompt shown after it
+    }
+
+    impl ScriptedFrontend {
+        fn new(actions: &[InputAction]) -> Self {
+            Self {
+                actions: actions.iter().copied().collect(),
+                rendered_ticks: Vec::new(),
+                prompts: Vec::new(),
             }
         }
+    }
 
-        // Station decides to create new robots
-        if station.should_create_robot() {
-            let (new_robot_x, new_robot_y) = find_clear_spot_for_robot(&map, station.x, station.y);
-            
-            if let Some(cell) = map.get_cell(new_robot_x, new_robot_y) {
-                if cell.cell_type != map::CellType::Obstacle && !(new_robot_x == station.x && new_robot_y == station.y) {
-                    station.create_robot(new_robot_x, new_robot_y);
-                }
-            }
+    impl Frontend for ScriptedFrontend {
+        fn handle_input(&mut self) -> std::io::Result<InputAction> {
+            Ok(self.actions.pop_front().unwrap_or(InputAction::Quit))
         }
 
-        // Display the map and station
-        ui.render(&map, &station)?;
+        fn render(&mut self, simulation: &Simulation, _settings: &FrameSettings) -> std::io::Result<()> {
+            self.rendered_ticks.push(simulation.clock.now());
+            Ok(())
+        }
 
-        // Limit the refresh rate
-        let elapsed = frame_start.elapsed();
-        if elapsed < frame_time {
-            thread::sleep(frame_time - elapsed);
+        fn show_prompt(&mut self, step: &TutorialStep) {
+            self.prompts.push((self.rendered_ticks.last().copied().unwrap_or(0), step.message));
         }
     }
 
-    // Clean up and restore the terminal
-    ui.cleanup()?;
+    fn test_simulation() -> Simulation {
+        let map = Map::new(20, 20, 7);
+        let station = Station::new(10, 10);
+        Simulation::new(map, station)
+    }
 
-    println!("Autonomous exploration simulation ended.");
-    println!("Final station statistics:");
-    println!("Station Energy: {}", station.energy);
-    println!("Station Minerals: {}", station.minerals);
-    println!("Station Science Points: {}", station.science_points);
-    println!("Total Robots Created: {}", station.robots.len());
+    #[test]
+    fn test_example_map_parses() {
+        let (map, station) = Map::from_ascii(include_str!("../maps/example.txt")).unwrap();
+        let (station_x, station_y) = station.expect("example map marks a station");
+        assert_ne!(map.get_cell(station_x, station_y).unwrap().cell_type, map::CellType::Obstacle);
+    }
 
-    Ok(())
-}
+    #[test]
+    fn test_each_render_advances_ticks_per_frame() {
+        let mut simulation = test_simulation();
+        let mut frontend = ScriptedFrontend::new(&[InputAction::None; 3]);
+        let settings = FrameSettings { ticks_per_frame: 10, paused: false };
 
-// Helper function to find a clear spot for the robot
-// Tries to find spots in expanding circles around the station
-fn find_clear_spot_for_robot(map: &Map, station_x: usize, station_y: usize) -> (usize, usize) {
-    find_clear_spot_for_robot_avoiding_others(map, station_x, station_y, &[])
-}
+        run_frames(&mut frontend, &mut simulation, settings, Duration::ZERO, None, None).unwrap();
 
-// Helper function to find a clear spot near a target position
-fn find_clear_spot_near_target(map: &Map, target_x: usize, target_y: usize, existing_robots: &[Robot]) -> (usize, usize) {
-    // First try the exact target position
-    if let Some(cell) = map.get_cell(target_x, target_y) {
-        let position_occupied = existing_robots.iter().any(|r| r.x == target_x && r.y == target_y);
-        if cell.cell_type != map::CellType::Obstacle && !position_occupied {
-            return (target_x, target_y);
-        }
+        assert_eq!(frontend.rendered_ticks, vec![10, 20, 30]);
     }
-    
-    // Try positions in expanding circles around target
-    for radius in 1..=5 {
-        for dx in -(radius as i32)..=(radius as i32) {
-            for dy in -(radius as i32)..=(radius as i32) {
-                if dx.abs() != radius && dy.abs() != radius {
-                    continue; // Only check the perimeter
-                }
-                
-                let new_x = (target_x as i32 + dx) as usize;
-                let new_y = (target_y as i32 + dy) as usize;
-                
-                if new_x < map.width && new_y < map.height {
-                    if let Some(cell) = map.get_cell(new_x, new_y) {
-                        let position_occupied = existing_robots.iter().any(|robot| robot.x == new_x && robot.y == new_y);
-                        if cell.cell_type != map::CellType::Obstacle && !position_occupied {
-                            return (new_x, new_y);
-                        }
-                    }
-                }
-            }
-        }
+
+    #[test]
+    fn test_quit_stops_at_next_batch_boundary() {
+        let mut simulation = test_simulation();
+        let mut frontend = ScriptedFrontend::new(&[InputAction::None, InputAction::Quit, InputAction::None]);
+        let settings = FrameSettings { ticks_per_frame: 5, paused: false };
+
+        run_frames(&mut frontend, &mut simulation, settings, Duration::ZERO, None, None).unwrap();
+
+        // The batch in flight completes, but no further batch starts after quit
+        assert_eq!(simulation.clock.now(), 5);
+        assert_eq!(frontend.rendered_ticks, vec![5]);
     }
-    
-    // Fallback to general search
-    find_clear_spot_for_robot_avoiding_others(map, target_x, target_y, existing_robots)
-}
 
-// Helper function to find a clear spot for a robot, avoiding other robots
-fn find_clear_spot_for_robot_avoiding_others(map: &Map, station_x: usize, station_y: usize, existing_robots: &[Robot]) -> (usize, usize) {
-    // First try positions around the station in a spiral pattern
-    for radius in 1..=5 {
-        for dx in -(radius as i32)..=(radius as i32) {
-            for dy in -(radius as i32)..=(radius as i32) {
-                if dx.abs() != radius && dy.abs() != radius {
-                    continue; // Only check the perimeter of each radius
-                }
-                
-                let new_x = (station_x as i32 + dx) as usize;
-                let new_y = (station_y as i32 + dy) as usize;
-                
-                if new_x < map.width && new_y < map.height {
-                    // Check if position is occupied by station
-                    if new_x == station_x && new_y == station_y {
-                        continue;
-                    }
-                    
-                    // Check if position is occupied by existing robots
-                    let position_occupied = existing_robots.iter().any(|r| r.x == new_x && r.y == new_y);
-                    if position_occupied {
-                        continue;
-                    }
-                    
-                    // Check if position is obstacle
-                    if let Some(cell) = map.get_cell(new_x, new_y) {
-                        if cell.cell_type != map::CellType::Obstacle {
-                            return (new_x, new_y);
-                        }
-                    }
-                }
-            }
-        }
+    #[test]
+    fn test_speed_keys_change_batch_size() {
+        let mut simulation = test_simulation();
+        let mut frontend = ScriptedFrontend::new(&[
+            InputAction::IncreaseTicksPerFrame,
+            InputAction::DecreaseTicksPerFrame,
+            InputAction::TogglePause,
+        ]);
+
+        run_frames(&mut frontend, &mut simulation, FrameSettings::default(), Duration::ZERO, None, None).unwrap();
+
+        // 2 ticks, then 1 tick, then paused (rendered but not advanced)
+        assert_eq!(frontend.rendered_ticks, vec![2, 3, 3]);
     }
-    
-    // Fallback: scan the entire map
-    for r_y in 0..map.height {
-        for r_x in 0..map.width {
-            if let Some(cell) = map.get_cell(r_x, r_y) {
-                let position_occupied = (r_x == station_x && r_y == station_y) ||
-                    existing_robots.iter().any(|robot| robot.x == r_x && robot.y == r_y);
-                
-                if cell.cell_type != map::CellType::Obstacle && !position_occupied {
-                    return (r_x, r_y);
-                }
-            }
-        }
+
+    #[test]
+    fn test_recall_key_toggles_the_recall() {
+        let mut simulation = test_simulation();
+        let mut frontend = ScriptedFrontend::new(&[InputAction::ToggleRecall, InputAction::None]);
+        run_frames(&mut frontend, &mut simulation, FrameSettings::default(), Duration::ZERO, None, None).unwrap();
+        assert!(simulation.station().recall);
+
+        let mut frontend = ScriptedFrontend::new(&[InputAction::ToggleRecall]);
+        run_frames(&mut frontend, &mut simulation, FrameSettings::default(), Duration::ZERO, None, None).unwrap();
+        assert!(!simulation.station().recall);
+    }
+
+    #[test]
+    fn test_tick_limit_ends_run_exactly() {
+        let mut simulation = test_simulation();
+        let mut frontend = ScriptedFrontend::new(&[InputAction::None; 10]);
+        let settings = FrameSettings { ticks_per_frame: 4, paused: false };
+
+        run_frames(&mut frontend, &mut simulation, settings, Duration::ZERO, Some(10), None).unwrap();
+
+        // The final batch is trimmed and the run stops without waiting for quit
+        assert_eq!(frontend.rendered_ticks, vec![4, 8, 10]);
+    }
+
+    #[test]
+    fn test_tutorial_prompt_pauses_until_resumed() {
+        let mut simulation = test_simulation();
+        let mut tutorial = Tutorial::new(vec![
+            TutorialStep { trigger: Trigger::AtTick(4), message: "look", focus: None, pause: true },
+            TutorialStep { trigger: Trigger::AtTick(6), message: "carry on", focus: None, pause: false },
+        ]);
+        let mut frontend = ScriptedFrontend::new(&[
+            InputAction::None,
+            InputAction::None,
+            InputAction::None,
+            InputAction::TogglePause,
+            InputAction::None,
+        ]);
+        let settings = FrameSettings { ticks_per_frame: 2, paused: false };
+
+        run_frames(&mut frontend, &mut simulation, settings, Duration::ZERO, None, Some(&mut tutorial)).unwrap();
+
+        // The first prompt holds the run at tick 4 until Space; the second does not pause
+        assert_eq!(frontend.rendered_ticks, vec![2, 4, 4, 6, 8]);
+        assert_eq!(frontend.prompts, vec![(2, "look"), (4, "carry on")]);
     }
-    (0, 0) // Default fallback if no clear spot is found (should ideally not happen)
 }
@@ -0,0 +1,152 @@
+use crate::robot::Robot;
+
+// A robot position on a layered map: (x, y, z)
+type Position = (usize, usize, usize);
+
+// Where one robot was before the last batch of ticks and where that batch left it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Move {
+    from: Position, // Same as `to` when the robot snaps into place
+    to: Position,
+}
+
+// Robot positions before and after the last batch of ticks, so the map can sweep robots along
+// the way instead of jumping several cells per frame. Purely cosmetic: the simulation never sees it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RobotMotion {
+    tick: u64, // Tick the current positions were captured at
+    moves: Vec<Move>,
+}
+
+impl RobotMotion {
+    // Capture the robots after a batch; returns false when no tick ran since the last capture.
+    // Robots are matched by their place in the fleet (the stations' fleets one after the other). One that ended further away than the batch
+    // could carry it (a respawn, a wrap around the edge, a climb through a shaft) or that is new
+    // snaps into place rather than sweeping
+    pub fn update<'a>(&mut self, robots: impl IntoIterator<Item = &'a Robot>, tick: u64) -> bool {
+        let robots: Vec<&Robot> = robots.into_iter().collect();
+        if tick == self.tick && robots.len() == self.moves.len() {
+            return false;
+        }
+        let ticks = tick.saturating_sub(self.tick) as usize;
+        self.moves = robots
+            .iter()
+            .enumerate()
+            .map(|(index, robot)| {
+                let to = (robot.x, robot.y, robot.z);
+                let from = match self.moves.get(index) {
+                    Some(previous) if previous.to.2 == to.2 && chebyshev(previous.to, to) <= ticks => previous.to,
+                    _ => to,
+                };
+                Move { from, to }
+            })
+            .collect();
+        self.tick = tick;
+        true
+    }
+
+    // Whether any robot has cells to sweep through
+    pub fn is_moving(&self) -> bool {
+        self.moves.iter().any(|step| step.from != step.to)
+    }
+
+    // Where each robot is drawn at `phase`: 0 at its position before the batch, 1 where it is now
+    pub fn positions(&self, phase: f64) -> Vec<Position> {
+        self.moves
+            .iter()
+            .map(|step| {
+                let (x, y) = interpolated_cell((step.from.0, step.from.1), (step.to.0, step.to.1), phase);
+                (x, y, step.to.2)
+            })
+            .collect()
+    }
+}
+
+fn chebyshev(a: Position, b: Position) -> usize {
+    a.0.abs_diff(b.0).max(a.1.abs_diff(b.1))
+}
+
+// Cells of the line from `from` to `to`, both ends included (integer Bresenham)
+pub fn line_cells(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+    let (to_x, to_y) = (to.0 as i64, to.1 as i64);
+    let (dx, dy) = ((to_x - x).abs(), -(to_y - y).abs());
+    let (step_x, step_y) = ((to_x - x).signum(), (to_y - y).signum());
+    let mut error = dx + dy;
+    let mut cells = vec![from];
+    while (x, y) != (to_x, to_y) {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+        cells.push((x as usize, y as usize));
+    }
+    cells
+}
+
+// The cell `phase` (0 to 1) of the way along the line from `from` to `to`
+pub fn interpolated_cell(from: (usize, usize), to: (usize, usize), phase: f64) -> (usize, usize) {
+    let cells = line_cells(from, to);
+    let index = (phase.clamp(0.0, 1.0) * (cells.len() - 1) as f64).round() as usize;
+    cells[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robot::RobotType;
+
+    fn robot_at(x: usize, y: usize) -> Robot {
+        Robot::new_with_type(x, y, RobotType::Explorer)
+    }
+
+    #[test]
+    fn test_line_cells() {
+        assert_eq!(line_cells((2, 2), (2, 2)), vec![(2, 2)]);
+        assert_eq!(line_cells((0, 0), (3, 0)), vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(line_cells((3, 1), (0, 4)), vec![(3, 1), (2, 2), (1, 3), (0, 4)]);
+        assert_eq!(line_cells((0, 0), (4, 2)), vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]);
+        // Every step moves to a neighbouring cell
+        let cells = line_cells((5, 9), (0, 1));
+        assert_eq!((cells[0], cells[cells.len() - 1], cells.len()), ((5, 9), (0, 1), 9));
+        assert!(cells.windows(2).all(|pair| chebyshev((pair[0].0, pair[0].1, 0), (pair[1].0, pair[1].1, 0)) == 1));
+    }
+
+    #[test]
+    fn test_interpolated_cell_phases() {
+        let (from, to) = ((1, 1), (5, 1));
+        assert_eq!(interpolated_cell(from, to, 0.0), (1, 1));
+        assert_eq!(interpolated_cell(from, to, 0.25), (2, 1));
+        assert_eq!(interpolated_cell(from, to, 0.5), (3, 1));
+        assert_eq!(interpolated_cell(from, to, 1.0), (5, 1));
+        assert_eq!(interpolated_cell(from, to, 7.0), (5, 1)); // Clamped
+    }
+
+    #[test]
+    fn test_motion_sweeps_short_moves_and_snaps_jumps() {
+        let mut motion = RobotMotion::default();
+        assert!(motion.update(&[robot_at(1, 1), robot_at(10, 10)], 0));
+        assert!(!motion.is_moving()); // Nothing to sweep from yet
+        assert!(!motion.update(&[robot_at(1, 1), robot_at(10, 10)], 0));
+
+        // Five ticks: four cells is a walk, forty is a respawn
+        let mut spawned = robot_at(30, 3);
+        spawned.z = crate::map::UNDERGROUND;
+        assert!(motion.update(&[robot_at(5, 1), robot_at(50, 10), spawned], 5));
+        assert!(motion.is_moving());
+        assert_eq!(motion.positions(0.0), vec![(1, 1, 0), (50, 10, 0), (30, 3, 1)]);
+        assert_eq!(motion.positions(0.5), vec![(3, 1, 0), (50, 10, 0), (30, 3, 1)]);
+        assert_eq!(motion.positions(1.0), vec![(5, 1, 0), (50, 10, 0), (30, 3, 1)]);
+
+        // Going down a shaft snaps too
+        let mut below = robot_at(6, 1);
+        below.z = crate::map::UNDERGROUND;
+        motion.update(&[below, robot_at(50, 10), robot_at(30, 3)], 6);
+        assert_eq!(motion.positions(0.0)[0], (6, 1, 1));
+    }
+}
@@ -0,0 +1,286 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::map::{Cell, Map};
+use crate::robot::Direction;
+
+// A* pathfinding node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PathNode {
+    x: usize,
+    y: usize,
+    g_cost: u32,  // Cost from start
+    h_cost: u32,  // Heuristic cost to goal
+    f_cost: u32,  // Total cost (g + h)
+}
+
+impl PathNode {
+    fn new(x: usize, y: usize, g_cost: u32, h_cost: u32) -> Self {
+        Self {
+            x,
+            y,
+            g_cost,
+            h_cost,
+            f_cost: g_cost + h_cost,
+        }
+    }
+}
+
+impl Ord for PathNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reverse ordering for min-heap behavior
+        other.f_cost.cmp(&self.f_cost)
+            .then_with(|| other.h_cost.cmp(&self.h_cost))
+    }
+}
+
+impl PartialOrd for PathNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// What a search found, and how many nodes it took off the open set to find it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Search {
+    pub path: Option<Vec<(usize, usize)>>, // From start to goal, both included
+    pub expanded: usize,
+}
+
+// A* over the surface with the cost of stepping onto (x, y) the given way left to the caller.
+// Cells `blocked` reports are never entered; the start is not checked. Unlike
+// Map::neighbors this goes west and east first: the expansion order settles ties between equally
+// cheap paths, and so the routes taken. Once `max_expanded` nodes are taken off the open set it
+// settles for the path to the one nearest the goal so far, which then ends short of the goal
+// (none when that is the start). A start in rock or a blocked goal fails at once
+pub fn astar(
+    map: &Map,
+    start: (usize, usize),
+    goal: (usize, usize),
+    blocked: &impl Fn(usize, usize) -> bool,
+    step_cost: &impl Fn(usize, usize, Direction) -> u32,
+    max_expanded: usize,
+) -> Search {
+    let start_in_rock = map.get_cell(start.0, start.1).is_some_and(|cell| cell.cell_type.blocks_movement());
+    if start != goal && (start_in_rock || blocked(goal.0, goal.1)) {
+        return Search { path: None, expanded: 0 };
+    }
+
+    let mut open_set = BinaryHeap::new();
+    let mut came_from = HashMap::new();
+    let mut g_score = HashMap::new();
+    let mut expanded = 0;
+
+    let start_node = PathNode::new(start.0, start.1, 0, map.distance(start.0, start.1, goal.0, goal.1));
+    let mut nearest = start_node;
+    open_set.push(start_node);
+    g_score.insert(start, 0);
+
+    while let Some(current) = open_set.pop() {
+        if expanded == max_expanded {
+            let partial = ((nearest.x, nearest.y) != start).then(|| reconstruct_path(&came_from, (nearest.x, nearest.y)));
+            return Search { path: partial, expanded };
+        }
+        expanded += 1;
+        if (current.x, current.y) == goal {
+            return Search { path: Some(reconstruct_path(&came_from, goal)), expanded };
+        }
+        if (current.h_cost, current.g_cost) < (nearest.h_cost, nearest.g_cost) {
+            nearest = current;
+        }
+
+        let neighbors = [Direction::West, Direction::East, Direction::North, Direction::South].into_iter().filter_map(|direction| {
+            let (dx, dy) = direction.offset();
+            map.neighbor(current.x, current.y, dx, dy).map(|(nx, ny)| (nx, ny, direction))
+        });
+        for (nx, ny, direction) in neighbors {
+            if blocked(nx, ny) {
+                continue;
+            }
+            let tentative_g_score = g_score.get(&(current.x, current.y)).unwrap_or(&u32::MAX) + step_cost(nx, ny, direction);
+            if tentative_g_score < *g_score.get(&(nx, ny)).unwrap_or(&u32::MAX) {
+                came_from.insert((nx, ny), (current.x, current.y));
+                g_score.insert((nx, ny), tentative_g_score);
+                open_set.push(PathNode::new(nx, ny, tentative_g_score, map.distance(nx, ny, goal.0, goal.1)));
+            }
+        }
+    }
+    Search { path: None, expanded }
+}
+
+// Dijkstra out from `start` to the cheapest cell `is_goal` accepts (the start left out), with the
+// cost of stepping onto each cell left to the caller. Gives up, with no path, once it has taken
+// `max_expanded` nodes off the open set, so huge maps cannot stall a tick
+pub fn flood(
+    map: &Map,
+    start: (usize, usize),
+    blocked: &impl Fn(usize, usize) -> bool,
+    step_cost: &impl Fn(usize, usize, Direction) -> u32,
+    is_goal: &impl Fn(usize, usize) -> bool,
+    max_expanded: usize,
+) -> Search {
+    let mut open_set = BinaryHeap::new();
+    let mut came_from = HashMap::new();
+    let mut g_score = HashMap::new();
+    let mut expanded = 0;
+
+    open_set.push(PathNode::new(start.0, start.1, 0, 0));
+    g_score.insert(start, 0);
+
+    while let Some(current) = open_set.pop() {
+        if current.g_cost > *g_score.get(&(current.x, current.y)).unwrap_or(&u32::MAX) {
+            continue; // Reached more cheaply since it was queued
+        }
+        if expanded == max_expanded {
+            break;
+        }
+        expanded += 1;
+        if (current.x, current.y) != start && is_goal(current.x, current.y) {
+            return Search { path: Some(reconstruct_path(&came_from, (current.x, current.y))), expanded };
+        }
+        for direction in Direction::ALL {
+            let (dx, dy) = direction.offset();
+            let Some((nx, ny)) = map.neighbor(current.x, current.y, dx, dy) else {
+                continue;
+            };
+            if blocked(nx, ny) {
+                continue;
+            }
+            let tentative_g_score = current.g_cost + step_cost(nx, ny, direction);
+            if tentative_g_score < *g_score.get(&(nx, ny)).unwrap_or(&u32::MAX) {
+                came_from.insert((nx, ny), (current.x, current.y));
+                g_score.insert((nx, ny), tentative_g_score);
+                open_set.push(PathNode::new(nx, ny, tentative_g_score, 0));
+            }
+        }
+    }
+    Search { path: None, expanded }
+}
+
+// Energy needed to enter a cell before load (1 off the map)
+pub fn terrain_cost(map: &Map, x: usize, y: usize) -> u32 {
+    map.get_cell(x, y).map_or(1, |cell| cell.movement_cost)
+}
+
+// Cost of walking a path, the start excluded, with `step_cost` for each cell entered
+pub fn path_cost(map: &Map, path: &[(usize, usize)], step_cost: impl Fn(&Cell) -> u32) -> u32 {
+    path.iter().skip(1).filter_map(|&(x, y)| map.get_cell(x, y)).map(step_cost).sum()
+}
+
+// Walk came_from back from the goal
+fn reconstruct_path(came_from: &HashMap<(usize, usize), (usize, usize)>, mut current: (usize, usize)) -> Vec<(usize, usize)> {
+    let mut path = vec![current];
+    while let Some(&parent) = came_from.get(&current) {
+        current = parent;
+        path.push(current);
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::CellType;
+
+    fn open(map: &Map) -> impl Fn(usize, usize) -> bool + '_ {
+        |x, y| map.get_cell(x, y).is_none_or(|cell| cell.cell_type.blocks_movement())
+    }
+
+    // The whole way between two cells, each entered at its terrain cost
+    fn terrain_path(map: &Map, start: (usize, usize), goal: (usize, usize), blocked: &impl Fn(usize, usize) -> bool) -> Option<Vec<(usize, usize)>> {
+        astar(map, start, goal, blocked, &|x, y, _| terrain_cost(map, x, y), usize::MAX).path
+    }
+
+    #[test]
+    fn test_start_is_the_goal() {
+        let (map, _) = Map::from_ascii("...\n...\n").unwrap();
+        assert_eq!(terrain_path(&map, (1, 1), (1, 1), &open(&map)), Some(vec![(1, 1)]));
+    }
+
+    #[test]
+    fn test_no_path_to_a_walled_off_or_blocked_goal() {
+        let (map, _) = Map::from_ascii("..#..\n..#..\n..#..\n").unwrap();
+        let walled = astar(&map, (0, 1), (4, 1), &open(&map), &|_, _, _| 1, usize::MAX);
+        assert_eq!(walled.path, None);
+        assert_eq!(walled.expanded, 6); // Every cell on the near side, once
+        // Rock or a blocked cell at either end gives up without expanding anything
+        assert_eq!(astar(&map, (0, 0), (2, 0), &open(&map), &|_, _, _| 1, usize::MAX), Search { path: None, expanded: 0 });
+        assert_eq!(astar(&map, (2, 1), (0, 0), &open(&map), &|_, _, _| 1, usize::MAX), Search { path: None, expanded: 0 });
+        assert_eq!(terrain_path(&map, (0, 0), (1, 0), &|x, y| (x, y) == (1, 0)), None);
+    }
+
+    #[test]
+    fn test_path_hugs_the_map_edge_around_a_wall() {
+        let (map, _) = Map::from_ascii(".....\n####.\n.....\n.####\n.....\n").unwrap();
+        let path = terrain_path(&map, (0, 0), (4, 4), &open(&map)).unwrap();
+        assert_eq!(path.len(), 17);
+        assert!(path.contains(&(4, 1)) && path.contains(&(0, 3)));
+        assert!(path.windows(2).all(|step| map.distance(step[0].0, step[0].1, step[1].0, step[1].1) == 1));
+    }
+
+    #[test]
+    fn test_rough_ground_is_walked_around_when_cheaper() {
+        let (mut map, _) = Map::from_ascii("...\n...\n").unwrap();
+        map.get_cell_mut(1, 0).unwrap().movement_cost = 5;
+        let path = terrain_path(&map, (0, 0), (2, 0), &open(&map)).unwrap();
+        assert_eq!(path, vec![(0, 0), (0, 1), (1, 1), (2, 1), (2, 0)]);
+        assert_eq!(path_cost(&map, &path, |cell| cell.movement_cost), 4);
+        assert_eq!(path_cost(&map, &[(0, 0), (1, 0), (2, 0)], |cell| cell.movement_cost), 6);
+    }
+
+    #[test]
+    fn test_flood_finds_the_cheapest_goal_within_its_budget() {
+        let (mut map, _) = Map::from_ascii(".....\n.###.\n.....\n").unwrap();
+        map.get_cell_mut(1, 0).unwrap().movement_cost = 5;
+        let is_goal = |x, y| (x, y) == (4, 1) || (x, y) == (0, 2);
+        let cost = |x, y, _| terrain_cost(&map, x, y);
+        // The cheaper goal wins: from (2, 0) the rough cell west makes (0, 2) the dearer one
+        let found = flood(&map, (0, 0), &open(&map), &cost, &is_goal, 100);
+        assert_eq!(found.path, Some(vec![(0, 0), (0, 1), (0, 2)]));
+        let found = flood(&map, (2, 0), &open(&map), &cost, &is_goal, 100);
+        assert_eq!(found.path, Some(vec![(2, 0), (3, 0), (4, 0), (4, 1)]));
+        // The start itself never counts, and the budget caps the nodes taken off the open set
+        assert_eq!(flood(&map, (4, 1), &open(&map), &cost, &|x, y| (x, y) == (4, 1), 100).path, None);
+        let tripped = flood(&map, (2, 0), &open(&map), &cost, &|x, y| (x, y) == (0, 2), 3);
+        assert_eq!((tripped.path, tripped.expanded), (None, 3));
+    }
+
+    #[test]
+    fn test_search_on_a_large_map_stays_within_an_expansion_budget() {
+        let (mut map, _) = Map::from_ascii(&vec![".".repeat(200); 200].join("\n")).unwrap();
+        // A long wall with a gap at the far end
+        for y in 0..199 {
+            map.get_cell_mut(100, y).unwrap().cell_type = CellType::Obstacle;
+        }
+        let around = astar(&map, (0, 0), (199, 0), &open(&map), &|x, y, _| terrain_cost(&map, x, y), usize::MAX);
+        assert_eq!(around.path.as_ref().unwrap().len(), 199 + 2 * 199 + 1);
+        // The heuristic pulls the search along the wall: it floods the near side, then barely
+        // strays from the path beyond the gap
+        assert!(around.expanded <= 101 * 200 + 200, "{} nodes expanded", around.expanded);
+        let across = astar(&map, (0, 199), (199, 199), &open(&map), &|x, y, _| terrain_cost(&map, x, y), usize::MAX);
+        assert!(across.expanded <= 2 * 200, "{} nodes expanded", across.expanded);
+        // A budget the search stays within changes nothing
+        let budgeted = astar(&map, (0, 0), (199, 0), &open(&map), &|x, y, _| terrain_cost(&map, x, y), around.expanded);
+        assert_eq!(budgeted, around);
+    }
+
+    #[test]
+    fn test_search_out_of_budget_settles_for_the_way_nearest_the_goal() {
+        let (mut map, _) = Map::from_ascii(&vec![".".repeat(200); 200].join("\n")).unwrap();
+        // The goal is sealed off behind a full wall: the open set would drain the whole near side
+        for y in 0..200 {
+            map.get_cell_mut(100, y).unwrap().cell_type = CellType::Obstacle;
+        }
+        let cost = |x, y, _| terrain_cost(&map, x, y);
+        assert_eq!(astar(&map, (0, 100), (150, 100), &open(&map), &cost, usize::MAX).expanded, 100 * 200);
+
+        let partial = astar(&map, (0, 100), (150, 100), &open(&map), &cost, 500);
+        assert_eq!(partial.expanded, 500);
+        let path = partial.path.unwrap();
+        assert_eq!((path[0], path.last().copied()), ((0, 100), Some((99, 100)))); // Right up to the wall
+        assert!(path.windows(2).all(|step| map.distance(step[0].0, step[0].1, step[1].0, step[1].1) == 1));
+        // No step closer to the goal: nothing to settle for
+        assert_eq!(astar(&map, (99, 100), (150, 100), &open(&map), &cost, 500).path, None);
+    }
+}
@@ -0,0 +1,53 @@
+// A point on the map grid, or off it: offsets are applied in signed space, so stepping past an
+// edge gives a position the map does not contain instead of wrapping a usize or clamping to
+// the border. Map::contains and Map::locate turn it back into cell coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Position {
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    // The position of a cell
+    pub fn of_cell(x: usize, y: usize) -> Self {
+        Self::new(x as i32, y as i32)
+    }
+
+    // The position (dx, dy) away
+    pub fn offset(self, dx: i32, dy: i32) -> Self {
+        Self::new(self.x + dx, self.y + dy)
+    }
+
+    // Manhattan distance on an unbounded grid
+    pub fn manhattan(self, other: Position) -> u32 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    // The square of positions up to `radius` steps away on each axis, without this one, row by row
+    pub fn surroundings(self, radius: i32) -> impl Iterator<Item = Position> {
+        (-radius..=radius)
+            .flat_map(move |dy| (-radius..=radius).map(move |dx| (dx, dy)))
+            .filter(|&offset| offset != (0, 0))
+            .map(move |(dx, dy)| self.offset(dx, dy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offsets_go_negative_instead_of_wrapping() {
+        let corner = Position::of_cell(0, 0);
+        assert_eq!(corner.offset(-2, 1), Position::new(-2, 1));
+        assert_eq!(corner.offset(-2, 1).manhattan(Position::of_cell(3, 3)), 7);
+        let around: Vec<Position> = corner.surroundings(1).collect();
+        assert_eq!(around.len(), 8);
+        assert_eq!((around[0], around[7]), (Position::new(-1, -1), Position::new(1, 1)));
+        assert!(!around.contains(&corner));
+    }
+}
@@ -0,0 +1,325 @@
+use crossterm::{
+    event::{self, Event, KeyCode},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+use std::io::{stdout, Result};
+
+use crate::map::{Cell, CellType, Map, MapConfig, Terrain};
+use crate::station::Station;
+use crate::ui::{self, ViewMode};
+use crate::viewport::Viewport;
+
+// Rows reserved under the thumbnail for the generation stats
+const STATS_PANEL_HEIGHT: u16 = 6;
+
+// Seeds that can be paged through, wrapping around at both ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedCursor {
+    pub first: u32,
+    pub last: u32,
+    pub current: u32,
+}
+
+impl SeedCursor {
+    // A single seed pages freely through all seeds; a range stays within its bounds
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let parse_seed = |value: &str| {
+            value
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| format!("invalid seed '{}' (expected a number or a range like 10..20)", value))
+        };
+        match text.split_once("..") {
+            Some((first, last)) => {
+                let (first, last) = (parse_seed(first)?, parse_seed(last)?);
+                if first > last {
+                    return Err(format!("empty seed range {}..{}", first, last));
+                }
+                Ok(Self { first, last, current: first })
+            }
+            None => Ok(Self { first: 0, last: u32::MAX, current: parse_seed(text)? }),
+        }
+    }
+
+    pub fn next(&mut self) {
+        self.current = if self.current == self.last { self.first } else { self.current + 1 };
+    }
+
+    pub fn previous(&mut self) {
+        self.current = if self.current == self.first { self.last } else { self.current - 1 };
+    }
+}
+
+// Generation statistics shown next to a preview
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapStats {
+    pub obstacle_percentage: f64,
+    pub energy_total: u32,
+    pub mineral_total: u32,
+    pub science_points: u32, // Total value of the science points
+    pub largest_open_region: usize,
+    pub passable_cells: usize,
+    pub station_site: (usize, usize),
+}
+
+impl MapStats {
+    pub fn compute(map: &Map) -> Self {
+        let summary = map.summary();
+        let total = map.width * map.height;
+        let obstacle_percentage = if total == 0 { 0.0 } else { summary.obstacle_cells as f64 * 100.0 / total as f64 };
+        Self {
+            obstacle_percentage,
+            energy_total: summary.energy_total,
+            mineral_total: summary.mineral_total,
+            science_points: summary.science_value,
+            largest_open_region: map.largest_open_region(),
+            passable_cells: summary.passable_cells,
+            station_site: map.station_site(),
+        }
+    }
+
+    fn lines(&self, seed: u32) -> Vec<Line<'static>> {
+        let station = format!("({}, {})", self.station_site.0, self.station_site.1);
+        let open_share = if self.passable_cells == 0 {
+            0.0
+        } else {
+            self.largest_open_region as f64 * 100.0 / self.passable_cells as f64
+        };
+        vec![
+            Line::from(format!("Seed {}   Station site: {}", seed, station)),
+            Line::from(format!(
+                "Obstacles: {:.1}%   Largest open region: {} cells ({:.0}% of open ground)",
+                self.obstacle_percentage, self.largest_open_region, open_share
+            )),
+            Line::from(format!(
+                "Energy: {}   Minerals: {}   Science points: {}",
+                self.energy_total, self.mineral_total, self.science_points
+            )),
+        ]
+    }
+}
+
+// Cell range [start, end) of sector `index` when `len` cells are split into `count` sectors
+pub fn sector_bounds(index: usize, count: usize, len: usize) -> (usize, usize) {
+    (index * len / count, (index + 1) * len / count)
+}
+
+// Shrink a map to at most `columns` x `rows`, one cell per sector: obstacle when rock covers most
+// of the sector, otherwise its most notable resource (science, then minerals, then energy, with
+// amounts summed), otherwise empty ground at the sector's average movement cost
+pub fn downsample(map: &Map, columns: usize, rows: usize) -> Map {
+    let columns = columns.clamp(1, map.width.max(1));
+    let rows = rows.clamp(1, map.height.max(1));
+    let mut cells = Vec::with_capacity(columns * rows);
+    for sector_y in 0..rows {
+        let (y0, y1) = sector_bounds(sector_y, rows, map.height);
+        for sector_x in 0..columns {
+            let (x0, x1) = sector_bounds(sector_x, columns, map.width);
+            let (mut obstacles, mut science, mut minerals, mut energy, mut cost) = (0, 0, 0, 0, 0);
+            for y in y0..y1 {
+                for cell in map.row_slice(y, x0, x1) {
+                    match cell.cell_type {
+                        CellType::Obstacle => obstacles += 1,
+                        CellType::SciencePoint(amount) => science += amount,
+                        CellType::Mineral(amount) => minerals += amount,
+                        CellType::Energy(amount) => energy += amount,
+                        CellType::Empty | CellType::Radiation(_) => {}
+                    }
+                    cost += cell.movement_cost;
+                }
+            }
+            let area = ((x1 - x0) * (y1 - y0)).max(1);
+            let cell_type = if obstacles * 2 > area {
+                CellType::Obstacle
+            } else if science > 0 {
+                CellType::SciencePoint(science)
+            } else if minerals > 0 {
+                CellType::Mineral(minerals)
+            } else if energy > 0 {
+                CellType::Energy(energy)
+            } else {
+                CellType::Empty
+            };
+            cells.push(Cell::with_movement_cost(cell_type, (cost + area as u32 / 2) / area as u32)); // Rounded average
+        }
+    }
+    Map::from_cells(columns, rows, cells, map.seed)
+}
+
+// Thumbnail rows drawn with the main map renderer, with the proposed station marked
+fn thumbnail_lines(map: &Map, stats: &MapStats, columns: usize, rows: usize) -> Vec<Line<'static>> {
+    let thumbnail = downsample(map, columns, rows);
+    // Scale the station site into thumbnail coordinates
+    let (x, y) = stats.station_site;
+    let (station_x, station_y) = (x * thumbnail.width / map.width, y * thumbnail.height / map.height);
+    let station = Station::new(station_x, station_y);
+    ui::map_lines(&thumbnail, &station, ViewMode::Omniscient, Viewport::default(), columns, rows)
+        .into_iter()
+        .map(colorize)
+        .collect()
+}
+
+// Give each map glyph its own color
+fn colorize(line: Line<'static>) -> Line<'static> {
+    let text: String = line.spans.iter().map(|span| span.content.as_ref()).collect();
+    let spans = text
+        .chars()
+        .map(|glyph| {
+            let style = match glyph {
+                '▓' => Style::default().fg(Color::DarkGray),
+                '░' => Style::default().fg(Color::Yellow),
+                'e' => Style::default().fg(Color::LightYellow),
+                'm' => Style::default().fg(Color::Cyan),
+                's' => Style::default().fg(Color::Magenta),
+                'H' => Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+                _ => Style::default(),
+            };
+            Span::styled(glyph.to_string(), style)
+        })
+        .collect::<Vec<_>>();
+    Line::from(spans)
+}
+
+// Page through generated worlds; returns the accepted seed, or None if the user quit
+pub fn run(mut seeds: SeedCursor, terrain: Terrain) -> Result<Option<u32>> {
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+    enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen)?;
+    terminal.hide_cursor()?;
+    terminal.clear()?;
+
+    let result = preview_loop(&mut terminal, &mut seeds, terrain);
+
+    disable_raw_mode()?;
+    execute!(stdout(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn preview_loop(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>, seeds: &mut SeedCursor, terrain: Terrain) -> Result<Option<u32>> {
+    // Maps are generated at the size the real run would use, so the accepted seed plays the same world
+    let (map_width, map_height) = ui::generated_map_size(terminal.size()?);
+    let mut generated: Option<(u32, Map, MapStats)> = None;
+    loop {
+        if generated.as_ref().is_none_or(|(seed, _, _)| *seed != seeds.current) {
+            let map = Map::with_config(map_width, map_height, seeds.current, &MapConfig { terrain, ..MapConfig::default() });
+            let stats = MapStats::compute(&map);
+            generated = Some((seeds.current, map, stats));
+        }
+        let (seed, map, stats) = generated.as_ref().unwrap();
+
+        terminal.draw(|frame| {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(STATS_PANEL_HEIGHT)])
+                .split(frame.size());
+            let inner = chunks[0].inner(&Margin::new(1, 1));
+            let lines = thumbnail_lines(map, stats, inner.width as usize, inner.height as usize);
+            frame.render_widget(
+                Paragraph::new(lines).block(Block::default().title(format!("Preview {}x{}", map.width, map.height)).borders(Borders::ALL)),
+                chunks[0],
+            );
+            frame.render_widget(
+                Paragraph::new(stats.lines(*seed))
+                    .block(Block::default().title("Generation (n: next, p: previous, Enter: start, q: quit)").borders(Borders::ALL)),
+                chunks[1],
+            );
+        })?;
+
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Char('n') | KeyCode::Right => seeds.next(),
+                KeyCode::Char('p') | KeyCode::Left => seeds.previous(),
+                KeyCode::Enter => return Ok(Some(seeds.current)),
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sector_bounds_cover_every_cell_once() {
+        // 10 cells into 3 sectors: no gaps, no overlaps
+        let bounds: Vec<_> = (0..3).map(|i| sector_bounds(i, 3, 10)).collect();
+        assert_eq!(bounds, vec![(0, 3), (3, 6), (6, 10)]);
+        assert_eq!(sector_bounds(0, 1, 7), (0, 7));
+        assert_eq!(sector_bounds(4, 5, 5), (4, 5)); // One cell per sector at full size
+    }
+
+    #[test]
+    fn test_downsample_fixture() {
+        let (map, _) = Map::from_ascii(
+            "##..\n\
+             #...\n\
+             ..m.\n\
+             .s~~\n",
+        )
+        .unwrap();
+        let thumbnail = downsample(&map, 2, 2);
+        assert_eq!((thumbnail.width, thumbnail.height), (2, 2));
+        assert_eq!(thumbnail.get_cell(0, 0).unwrap().cell_type, CellType::Obstacle); // 3 of 4 cells are rock
+        assert_eq!(thumbnail.get_cell(1, 0).unwrap().cell_type, CellType::Empty);
+        assert_eq!(thumbnail.get_cell(0, 1).unwrap().cell_type, CellType::SciencePoint(1));
+        assert_eq!(thumbnail.get_cell(1, 1).unwrap().cell_type, CellType::Mineral(50));
+        assert_eq!(thumbnail.get_cell(1, 1).unwrap().movement_cost, 2); // (1 + 1 + 2 + 2) / 4 rounded
+
+        // Asking for more sectors than cells keeps the original size
+        let full = downsample(&map, 40, 40);
+        assert_eq!((full.width, full.height), (4, 4));
+    }
+
+    #[test]
+    fn test_stats_for_fixture_map() {
+        let (map, _) = Map::from_ascii(
+            "..#e\n\
+             ..#m\n\
+             ###s\n",
+        )
+        .unwrap();
+        let stats = MapStats::compute(&map);
+        assert!((stats.obstacle_percentage - 5.0 * 100.0 / 12.0).abs() < 1e-9);
+        assert_eq!(stats.energy_total, 50);
+        assert_eq!(stats.mineral_total, 50);
+        assert_eq!(stats.science_points, 1);
+        assert_eq!(stats.largest_open_region, 4);
+        assert_eq!(stats.passable_cells, 7);
+        assert_eq!(stats.station_site, (1, 0));
+    }
+
+    #[test]
+    fn test_thumbnail_marks_station() {
+        let map = Map::new(120, 40, 9);
+        let stats = MapStats::compute(&map);
+        let lines = thumbnail_lines(&map, &stats, 30, 10);
+        assert_eq!(lines.len(), 10);
+        let text: Vec<String> = lines.iter().map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect()).collect();
+        assert!(text.iter().all(|row| row.chars().count() == 30));
+        assert_eq!(text.iter().map(|row| row.matches('H').count()).sum::<usize>(), 1);
+    }
+
+    #[test]
+    fn test_seed_cursor() {
+        let mut range = SeedCursor::parse("10..12").unwrap();
+        range.previous();
+        assert_eq!(range.current, 12); // Wraps to the end of the range
+        range.next();
+        assert_eq!(range.current, 10);
+
+        let mut single = SeedCursor::parse("0").unwrap();
+        single.previous();
+        assert_eq!(single.current, u32::MAX);
+
+        assert!(SeedCursor::parse("12..10").is_err());
+        assert!(SeedCursor::parse("abc").is_err());
+    }
+}
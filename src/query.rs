@@ -0,0 +1,367 @@
+use std::fmt;
+
+use crate::diff::RobotSnapshot;
+use crate::map::Map;
+use crate::robot::StatusContext;
+use crate::simulation::Simulation;
+use crate::station::EXPLORER_REGION_GRID;
+
+// One robot as queries see it: its snapshot, its number in the fleet (counted from 1, as in
+// the UI), the column and row of the station's region grid it stands in (also from 1), the extra
+// energy its cargo costs it per move and a line on what it is doing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FleetEntry {
+    pub id: usize,
+    pub robot: RobotSnapshot,
+    pub sector: (usize, usize),
+    pub load_factor: u32,
+    pub status: String,
+}
+
+impl FleetEntry {
+    // Column headings matching `row`
+    pub fn header() -> String {
+        format!(
+            "{:>4} {:<17} {:<19} {:>9} {:>6} {:>5} {:>8} {:>6} {}",
+            "id", "type", "state", "pos", "energy", "cargo", "load", "sector", "status"
+        )
+    }
+
+    // The entry as one table row
+    pub fn row(&self) -> String {
+        let robot = &self.robot;
+        format!(
+            "{:>4} {:<17} {:<19} {:>9} {:>6} {:>5} {:>8} {:>6} {}",
+            self.id,
+            robot.robot_type,
+            variant_name(&robot.state),
+            format!("({},{})", robot.x, robot.y),
+            robot.energy,
+            robot.cargo(),
+            format!("+{}/move", self.load_factor),
+            format!("({},{})", self.sector.0, self.sector.1),
+            self.status
+        )
+    }
+}
+
+// Every robot of the simulation, in fleet order
+pub fn fleet(simulation: &Simulation) -> Vec<FleetEntry> {
+    let ctx = StatusContext { map: &simulation.map, station_x: simulation.station().x, station_y: simulation.station().y };
+    simulation
+        .station()
+        .robots
+        .iter()
+        .enumerate()
+        .map(|(index, robot)| FleetEntry {
+            id: index + 1,
+            robot: RobotSnapshot::capture(robot),
+            sector: sector_of(&simulation.map, robot.x, robot.y),
+            load_factor: robot.load_factor(),
+            status: robot.status_line(&ctx),
+        })
+        .collect()
+}
+
+fn sector_of(map: &Map, x: usize, y: usize) -> (usize, usize) {
+    let (grid_w, grid_h) = EXPLORER_REGION_GRID;
+    let region = map.region_of(x, y, grid_w, grid_h);
+    let (grid_w, _) = map.region_grid(grid_w, grid_h);
+    (region % grid_w + 1, region / grid_w + 1)
+}
+
+// Name of an enum variant as printed by Debug, without its fields
+fn variant_name(debug: &str) -> &str {
+    debug.split(|c: char| !c.is_alphanumeric()).next().unwrap_or(debug)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Id,
+    Type,
+    State,
+    Energy,
+    Cargo, // Minerals and science carried
+    X,
+    Y,
+    Pos,
+    Sector,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "id" => Field::Id,
+            "type" => Field::Type,
+            "state" => Field::State,
+            "energy" => Field::Energy,
+            "cargo" => Field::Cargo,
+            "x" => Field::X,
+            "y" => Field::Y,
+            "pos" => Field::Pos,
+            "sector" => Field::Sector,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equal, // `:` or `=`
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Operator {
+    // Longest spellings first so `<=` is not read as `<`
+    const SPELLINGS: [(&'static str, Operator); 7] = [
+        ("<=", Operator::LessOrEqual),
+        (">=", Operator::GreaterOrEqual),
+        ("!=", Operator::NotEqual),
+        ("<", Operator::Less),
+        (">", Operator::Greater),
+        ("=", Operator::Equal),
+        (":", Operator::Equal),
+    ];
+
+    fn holds<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Operator::Equal => left == right,
+            Operator::NotEqual => left != right,
+            Operator::Less => left < right,
+            Operator::LessOrEqual => left <= right,
+            Operator::Greater => left > right,
+            Operator::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Number(u64),
+    Text(String), // Lowercase prefix of a type or state name
+    Pair(usize, usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Condition {
+    field: Field,
+    operator: Operator,
+    value: Value,
+}
+
+impl Condition {
+    fn parse(term: &str) -> Result<Self, String> {
+        let name_len = term.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(term.len());
+        let (name, rest) = term.split_at(name_len);
+        if name.is_empty() {
+            return Err(format!("'{}' does not start with a field name", term));
+        }
+        let field = Field::parse(&name.to_ascii_lowercase())
+            .ok_or_else(|| format!("unknown field '{}' (try id, type, state, energy, cargo, x, y, pos or sector)", name))?;
+        let (spelling, operator) = Operator::SPELLINGS
+            .into_iter()
+            .find(|(spelling, _)| rest.starts_with(spelling))
+            .ok_or_else(|| format!("'{}' needs an operator (:, =, !=, <, <=, > or >=) after '{}'", term, name))?;
+        let text = &rest[spelling.len()..];
+        if text.is_empty() {
+            return Err(format!("'{}' needs a value after '{}'", term, spelling));
+        }
+        let value = match field {
+            Field::Type | Field::State => Value::Text(text.to_ascii_lowercase()),
+            Field::Pos | Field::Sector => parse_pair(text).ok_or_else(|| format!("{} takes a pair like (3,2), not '{}'", name, text))?,
+            _ => Value::Number(text.parse().map_err(|_| format!("{} takes a number, not '{}'", name, text))?),
+        };
+        let ordered = matches!(operator, Operator::Less | Operator::LessOrEqual | Operator::Greater | Operator::GreaterOrEqual);
+        if ordered && !matches!(value, Value::Number(_)) {
+            return Err(format!("{} can only be compared with :, = or !=", name));
+        }
+        Ok(Self { field, operator, value })
+    }
+
+    fn matches(&self, entry: &FleetEntry) -> bool {
+        let robot = &entry.robot;
+        match &self.value {
+            Value::Number(number) => {
+                let actual = match self.field {
+                    Field::Id => entry.id as u64,
+                    Field::Energy => robot.energy as u64,
+                    Field::Cargo => robot.cargo() as u64,
+                    Field::X => robot.x as u64,
+                    _ => robot.y as u64,
+                };
+                self.operator.holds(actual, *number)
+            }
+            Value::Text(prefix) => {
+                let actual = if self.field == Field::Type { &robot.robot_type } else { &robot.state };
+                self.operator.holds(variant_name(actual).to_ascii_lowercase().starts_with(prefix.as_str()), true)
+            }
+            Value::Pair(x, y) => {
+                let actual = if self.field == Field::Pos { (robot.x, robot.y) } else { entry.sector };
+                self.operator.holds(actual, (*x, *y))
+            }
+        }
+    }
+}
+
+fn parse_pair(text: &str) -> Option<Value> {
+    let inner = text.strip_prefix('(')?.strip_suffix(')')?;
+    let (x, y) = inner.split_once(',')?;
+    Some(Value::Pair(x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+// A fleet filter such as `type:explorer state:returning energy<30`: whitespace-separated
+// conditions that must all hold. Text fields (type, state) match names by case-insensitive
+// prefix, so `state:returning` matches ReturningToStation. An empty query matches every robot
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    source: String,
+    conditions: Vec<Condition>,
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let conditions = split_terms(text)?.iter().map(|term| Condition::parse(term)).collect::<Result<_, _>>()?;
+        Ok(Self { source: text.trim().to_string(), conditions })
+    }
+
+    pub fn matches(&self, entry: &FleetEntry) -> bool {
+        self.conditions.iter().all(|condition| condition.matches(entry))
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+// Split on whitespace outside parentheses, so `sector:(3, 2)` stays one term
+fn split_terms(text: &str) -> Result<Vec<String>, String> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    for c in text.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Err("')' without a matching '('".to_string()),
+            ')' => depth -= 1,
+            c if c.is_whitespace() && depth == 0 => {
+                if !current.is_empty() {
+                    terms.push(std::mem::take(&mut current));
+                }
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            _ => {}
+        }
+        current.push(c);
+    }
+    if depth > 0 {
+        return Err("'(' is never closed".to_string());
+    }
+    if !current.is_empty() {
+        terms.push(current);
+    }
+    Ok(terms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robot::{Robot, RobotState, RobotType};
+
+    fn entry(id: usize, robot_type: RobotType, state: RobotState, energy: u32, cargo: (u32, u32)) -> FleetEntry {
+        let mut robot = Robot::new_with_type(4, 7, robot_type);
+        robot.state = state;
+        robot.energy = energy;
+        (robot.minerals, robot.science_points) = cargo;
+        FleetEntry { id, robot: RobotSnapshot::capture(&robot), sector: (3, 2), load_factor: 0, status: String::new() }
+    }
+
+    fn explorer() -> FleetEntry {
+        entry(2, RobotType::Explorer, RobotState::ReturningToStation, 25, (40, 15))
+    }
+
+    fn holds(query: &str, entry: &FleetEntry) -> bool {
+        Query::parse(query).unwrap_or_else(|err| panic!("'{}' did not parse: {}", query, err)).matches(entry)
+    }
+
+    #[test]
+    fn test_each_operator() {
+        let robot = explorer();
+        assert!(holds("energy:25", &robot) && holds("energy=25", &robot));
+        assert!(holds("energy!=24", &robot) && !holds("energy!=25", &robot));
+        assert!(holds("energy<30", &robot) && !holds("energy<25", &robot));
+        assert!(holds("energy<=25", &robot) && !holds("energy<=24", &robot));
+        assert!(holds("energy>20", &robot) && !holds("energy>25", &robot));
+        assert!(holds("energy>=25", &robot) && !holds("energy>=26", &robot));
+    }
+
+    #[test]
+    fn test_each_field() {
+        let robot = explorer();
+        assert!(holds("id:2", &robot) && !holds("id:1", &robot));
+        assert!(holds("type:explorer", &robot) && holds("TYPE:Expl", &robot) && !holds("type:scientist", &robot));
+        assert!(holds("type!=miner", &robot));
+        assert!(holds("state:returning", &robot) && !holds("state:exploring", &robot));
+        assert!(holds("cargo>50", &robot) && !holds("cargo>55", &robot));
+        assert!(holds("x:4", &robot) && holds("y>=7", &robot));
+        assert!(holds("pos:(4,7)", &robot) && holds("pos!=(7,4)", &robot));
+        assert!(holds("sector:(3, 2)", &robot) && !holds("sector:(2,3)", &robot));
+
+        // State names match without their fields
+        let miner = entry(5, RobotType::Miner, RobotState::Working { x: 1, y: 2, ticks_left: 3 }, 90, (0, 0));
+        assert!(holds("state:working", &miner) && !holds("state:x", &miner));
+    }
+
+    #[test]
+    fn test_conditions_combine_with_and() {
+        let robot = explorer();
+        assert!(holds("type:explorer state:returning energy<30", &robot));
+        assert!(!holds("type:explorer state:returning energy<20", &robot));
+        assert!(holds("cargo>50 sector:(3,2)", &robot));
+        assert!(holds("", &robot) && holds("   ", &robot));
+    }
+
+    #[test]
+    fn test_malformed_queries() {
+        let error = |query: &str| Query::parse(query).unwrap_err();
+        assert!(error("wear<3").contains("unknown field 'wear'"));
+        assert!(error("energy").contains("needs an operator"));
+        assert!(error("energy<").contains("needs a value"));
+        assert!(error("energy<lots").contains("takes a number"));
+        assert!(error("energy<-1").contains("takes a number"));
+        assert!(error("<30").contains("does not start with a field name"));
+        assert!(error("type<explorer").contains("only be compared with"));
+        assert!(error("pos>(1,2)").contains("only be compared with"));
+        assert!(error("sector:3").contains("takes a pair"));
+        assert!(error("sector:(3,2").contains("never closed"));
+        assert!(error("sector:3,2)").contains("without a matching"));
+    }
+
+    #[test]
+    fn test_fleet_entries_and_rows() {
+        let (map, _) = Map::from_ascii(".........\n.........\n.........\n").unwrap();
+        let mut station = crate::station::Station::new(0, 0);
+        station.robots.push(Robot::new_with_type(8, 2, RobotType::Scientist));
+        let mut hauler = Robot::new_with_type(1, 1, RobotType::MineralCollector);
+        hauler.minerals = 2 * crate::robot::ENERGY_COSTS.load_per_extra_move;
+        station.robots.push(hauler);
+        let simulation = Simulation::new(map, station);
+        let fleet = fleet(&simulation);
+        assert_eq!((fleet[0].id, fleet[0].sector), (1, (3, 3)));
+        assert!(fleet[0].row().contains("Scientist"));
+        assert!(fleet[0].row().contains("Exploring"));
+        assert!(fleet[0].row().contains(" +0/move "));
+        assert_eq!(fleet[1].load_factor, 2);
+        assert!(fleet[1].row().contains(" +2/move "));
+        assert_eq!(fleet[0].status, "looking for science around (8,2), 100 energy");
+        assert!(fleet[0].row().ends_with(" looking for science around (8,2), 100 energy"));
+        // The status column starts under its heading
+        assert_eq!(fleet[0].row().find(" looking"), FleetEntry::header().find(" status"));
+    }
+}
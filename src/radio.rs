@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+
+use crate::map::{self, CellType, Map};
+use crate::motion::line_cells;
+use crate::robot::Robot;
+
+// How far a radio link reaches, in cells as the crow flies
+pub const RADIO_RANGE: usize = 12;
+
+// Most links a message may take from the station: the direct one, then robots relaying it
+pub const RELAY_HOP_LIMIT: u32 = 4;
+
+// Whether no rock stands on the straight line between two cells (the ends themselves may be
+// rock: a ridge is seen, what lies behind it is not). Neighbours always see each other
+pub fn has_line_of_sight(map: &Map, from: (usize, usize), to: (usize, usize)) -> bool {
+    if from.0.abs_diff(to.0) <= 1 && from.1.abs_diff(to.1) <= 1 {
+        return true;
+    }
+    let cells = line_cells(from, to);
+    cells[1..cells.len() - 1]
+        .iter()
+        .all(|&(x, y)| map.get_cell(x, y).is_some_and(|cell| cell.cell_type != CellType::Obstacle))
+}
+
+// Whether two cells are in radio contact: in range, checked first as it is cheap, and in sight
+pub fn in_radio_contact(map: &Map, from: (usize, usize), to: (usize, usize)) -> bool {
+    within_radius(from, to, RADIO_RANGE) && has_line_of_sight(map, from, to)
+}
+
+fn within_radius(from: (usize, usize), to: (usize, usize), radius: usize) -> bool {
+    from.0.abs_diff(to.0).pow(2) + from.1.abs_diff(to.1).pow(2) <= radius.pow(2)
+}
+
+// For each robot, the fewest links a message from the station at `station` takes to reach it:
+// 1 in direct contact, more through robots relaying it, None out of reach within `hop_limit`
+// links. Robots out of energy or underground neither receive nor relay. Each robot is linked
+// to those reached before it at most once, so the work is bounded by the fleet size squared
+pub fn radio_hops(map: &Map, station: (usize, usize), robots: &[Robot], hop_limit: u32) -> Vec<Option<u32>> {
+    let on_air = |robot: &Robot| robot.energy > 0 && robot.z == map::SURFACE;
+    let mut hops = vec![None; robots.len()];
+    let mut queue = VecDeque::from([(station, 0)]);
+    while let Some((from, links)) = queue.pop_front() {
+        if links == hop_limit {
+            continue;
+        }
+        for (index, robot) in robots.iter().enumerate() {
+            if hops[index].is_none() && on_air(robot) && in_radio_contact(map, from, (robot.x, robot.y)) {
+                hops[index] = Some(links + 1);
+                queue.push_back(((robot.x, robot.y), links + 1));
+            }
+        }
+    }
+    hops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robot::RobotType;
+
+    // A ridge down column 4 between the station at (1, 2) and the far side
+    const RIDGE: &str = "..........\n....#.....\n....#.....\n....#.....\n..........\n";
+
+    #[test]
+    fn test_line_of_sight_stops_at_rock() {
+        let (map, _) = Map::from_ascii(RIDGE).unwrap();
+        assert!(!has_line_of_sight(&map, (1, 2), (8, 2)));
+        assert!(has_line_of_sight(&map, (1, 2), (4, 2))); // The ridge itself is in sight
+        assert!(has_line_of_sight(&map, (3, 1), (4, 2))); // Neighbours
+        assert!(has_line_of_sight(&map, (1, 0), (8, 0)));
+    }
+
+    #[test]
+    fn test_a_relay_restores_contact_over_a_ridge() {
+        let (map, _) = Map::from_ascii(RIDGE).unwrap();
+        let far = Robot::new_with_type(8, 2, RobotType::Explorer);
+        assert_eq!(radio_hops(&map, (1, 2), std::slice::from_ref(&far), RELAY_HOP_LIMIT), vec![None]);
+
+        // A robot below the ridge sees both sides
+        let relay = Robot::new_with_type(4, 4, RobotType::Explorer);
+        let fleet = [far.clone(), relay.clone()];
+        assert_eq!(radio_hops(&map, (1, 2), &fleet, RELAY_HOP_LIMIT), vec![Some(2), Some(1)]);
+        // Not with a single link allowed, nor once the relay is out of energy
+        assert_eq!(radio_hops(&map, (1, 2), &fleet, 1), vec![None, Some(1)]);
+        let mut flat = relay;
+        flat.energy = 0;
+        assert_eq!(radio_hops(&map, (1, 2), &[far, flat], RELAY_HOP_LIMIT), vec![None, None]);
+    }
+
+    #[test]
+    fn test_range_limits_each_link() {
+        let (map, _) = Map::from_ascii(&format!("{}\n", ".".repeat(40))).unwrap();
+        let chain: Vec<Robot> = [12, 24, 36].iter().map(|&x| Robot::new_with_type(x, 0, RobotType::Explorer)).collect();
+        assert_eq!(radio_hops(&map, (0, 0), &chain, RELAY_HOP_LIMIT), vec![Some(1), Some(2), Some(3)]);
+        assert_eq!(radio_hops(&map, (0, 0), &chain[1..], RELAY_HOP_LIMIT), vec![None, None]);
+    }
+}
@@ -1,62 +1,181 @@
-use crate::map::{CellType, Map, RobotExplorationUpdate}; // Updated import
-use rand::Rng;
-use std::collections::{BinaryHeap, HashMap};
-use std::cmp::Ordering;
-
+use crate::behavior::{self, Action, Behavior, BehaviorConfig, Context};
+use crate::expedition::{Leg, MAX_PLANNED_CHARGE};
+use crate::pathfinding;
+use crate::map::{self, Cache, Cell, CellType, CollectedResource, LayeredPosition, Map, RobotExplorationUpdate, SearchMode, MINERAL_HARVEST_RATE, VERY_ROUGH_MOVEMENT_COST}; // Updated import
+use crate::position::Position;
+use crate::radio;
+use crate::station::StationId;
+use crate::tasks::Task;
+use crate::upgrades::Upgrades;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+// Battery of most robot types, see ROBOT_SPECS
 pub const INITIAL_ROBOT_ENERGY: u32 = 100;
 
-// A* pathfinding node
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct PathNode {
-    x: usize,
-    y: usize,
-    g_cost: u32,  // Cost from start
-    h_cost: u32,  // Heuristic cost to goal
-    f_cost: u32,  // Total cost (g + h)
+// Energy prices of robot actions
+pub struct EnergyCosts {
+    pub load_per_extra_move: u32, // Every this many cargo units add 1 to the step cost
+    pub return_margin: u32,       // Energy kept in reserve on top of the estimated trip home
 }
 
-impl PathNode {
-    fn new(x: usize, y: usize, g_cost: u32, h_cost: u32) -> Self {
-        Self {
-            x,
-            y,
-            g_cost,
-            h_cost,
-            f_cost: g_cost + h_cost,
+pub const ENERGY_COSTS: EnergyCosts = EnergyCosts {
+    load_per_extra_move: 40,
+    return_margin: 5,
+};
+
+// Direction de déplacement du robot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    // Clockwise from north, the order robots try directions in
+    pub const ALL: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
+    // The (dx, dy) of one step this way, y growing southwards
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
         }
     }
-}
 
-impl Ord for PathNode {
-    fn cmp(&self, other: &Self) -> Ordering {
-        // Reverse ordering for min-heap behavior
-        other.f_cost.cmp(&self.f_cost)
-            .then_with(|| other.h_cost.cmp(&self.h_cost))
+    // The way straight back
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
     }
-}
 
-impl PartialOrd for PathNode {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    // The direction of a one-step offset (None for no move, a longer or a diagonal one)
+    pub fn from_delta(dx: i32, dy: i32) -> Option<Direction> {
+        Direction::ALL.into_iter().find(|direction| direction.offset() == (dx, dy))
     }
 }
 
-// Direction de déplacement du robot
-#[derive(Debug, Clone, Copy)]
-pub enum Direction {
-    North,
-    East,
-    South,
-    West,
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Direction::North => "north",
+            Direction::East => "east",
+            Direction::South => "south",
+            Direction::West => "west",
+        })
+    }
 }
 
 // Different types of robots with specialized behaviors
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RobotType {
     Explorer,        // Focuses on exploring unknown areas
     EnergyCollector, // Prioritizes energy collection
     MineralCollector, // Prioritizes mineral collection
     Scientist,       // Focuses on science points
+    Miner,           // Drills through rock walling in known deposits
+    Carrier,         // Takes cargo off collectors in the field and hauls it home
+    Repairer,        // Fixes broken-down and worn robots where they stand
+}
+
+impl RobotType {
+    // What a robot of this type does when it has no particular place to go
+    fn activity(self) -> &'static str {
+        match self {
+            RobotType::Explorer => "exploring",
+            RobotType::EnergyCollector => "looking for energy",
+            RobotType::MineralCollector => "looking for minerals",
+            RobotType::Scientist => "looking for science",
+            RobotType::Miner => "looking for walled-in deposits",
+            RobotType::Carrier => "waiting for a loaded collector",
+            RobotType::Repairer => "waiting for a robot to repair",
+        }
+    }
+
+    // Types whose cargo carriers come and take
+    pub fn is_collector(self) -> bool {
+        matches!(self, RobotType::EnergyCollector | RobotType::MineralCollector | RobotType::Scientist)
+    }
+
+    // Whether this type harvests deposits of this kind
+    pub fn harvests(self, cell_type: &CellType) -> bool {
+        match self {
+            RobotType::EnergyCollector => matches!(cell_type, CellType::Energy(_)),
+            RobotType::MineralCollector | RobotType::Miner => matches!(cell_type, CellType::Mineral(_)),
+            RobotType::Scientist => matches!(cell_type, CellType::SciencePoint(_)),
+            RobotType::Explorer | RobotType::Carrier | RobotType::Repairer => false,
+        }
+    }
+
+    // Battery, movement, sensing and hauling figures of this type
+    pub fn spec(self) -> RobotSpec {
+        ROBOT_SPECS[self as usize]
+    }
+}
+
+// What a status line needs besides the robot: the map its targets are looked up on and where
+// the station stands. Only known data is read; nothing is searched for
+#[derive(Clone, Copy)]
+pub struct StatusContext<'a> {
+    pub map: &'a Map,
+    pub station_x: usize,
+    pub station_y: usize,
+}
+
+// Deposits the robots still running have claimed
+fn claimed_targets(robots: &[RobotView]) -> Vec<(usize, usize)> {
+    robots.iter().filter(|robot| robot.energy > 0).filter_map(|robot| robot.current_target).collect()
+}
+
+// Score taken off a step that ends within CROWDING_RADIUS of another running robot on the same
+// layer, per robot: `penalty` for one at the edge of the radius, rising by as much with every
+// step closer. Robots heading out spread instead of following each other in a line
+const CROWDING_RADIUS: u32 = 3;
+
+fn crowding_penalty(map: &Map, x: usize, y: usize, z: usize, other_robots: &[RobotView], penalty: i32) -> i32 {
+    other_robots
+        .iter()
+        .filter(|robot| robot.energy > 0 && robot.z == z)
+        .map(|robot| map.distance(x, y, robot.x, robot.y))
+        .filter(|&distance| distance <= CROWDING_RADIUS)
+        .map(|distance| penalty * (CROWDING_RADIUS + 1 - distance) as i32)
+        .sum()
+}
+
+// Cells a robot remembers having walked through lately, most recent last. Scoring marks steps
+// back onto them down (see BehaviorConfig), and random moves avoid them when they can
+pub const TRAIL_LENGTH: usize = 40;
+
+// Unexplored cells up to `radius` steps from (x, y) on each axis, the cell itself left out.
+// Cells past an edge do not count (or wrap on a torus)
+fn unexplored_around(map: &Map, x: usize, y: usize, radius: i32, known: &HashSet<(usize, usize)>) -> usize {
+    Position::of_cell(x, y)
+        .surroundings(radius)
+        .filter_map(|around| map.locate(around))
+        .filter(|&(check_x, check_y)| map.get_cell(check_x, check_y).is_some_and(|cell| !cell.explored) && !known.contains(&(check_x, check_y)))
+        .count()
+}
+
+// What a status line calls the contents of a cell
+fn describe_cell(map: &Map, x: usize, y: usize) -> &'static str {
+    match map.get_cell(x, y).map(|cell| &cell.cell_type) {
+        Some(CellType::Energy(_)) => "energy deposit",
+        Some(CellType::Mineral(_)) => "mineral vein",
+        Some(CellType::SciencePoint(_)) => "science site",
+        _ => "deposit",
+    }
 }
 
 // Robot behavior state
@@ -65,22 +184,316 @@ pub enum RobotState {
     Exploring,
     ReturningToStation,
     AtStation,
+    Idle,                                       // Called home by the station: docked until it queues a suitable task
+    Broken,                                     // Worn down: immobile until a repairer fixes it
+    Stranded,                                   // Halted: cannot afford the trip home, waiting for help
+    LowPower { ticks_left: u32 },               // Stranded on its last energy: powered down but for the beacon, until rescued or for good
+    Rescuing { target: usize, x: usize, y: usize }, // Bringing energy to the stranded robot `target` at (x, y)
+    Retrieving { x: usize, y: usize },              // Fetching the cargo cache at (x, y)
+    Working { x: usize, y: usize, ticks_left: u32 }, // Drilling out the rock at (x, y)
+    Analyzing { x: usize, y: usize, ticks_left: u32 }, // A scientist studying the science point at (x, y), immobile
+    Decommissioned,                             // Scrapped by the station: parked there for good, out of the fleet
+}
+
+// What a robot of a given type is built with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RobotSpec {
+    pub max_energy: u32,      // Battery the station charges the robot to
+    pub move_cost: u32,       // Energy per step on plains with an empty cargo hold
+    pub sensor_radius: usize, // Cells explored around the robot each step, in every direction: a square of 2r+1 a side
+    pub speed: u32,           // Steps taken per tick while exploring or heading home, each paid for separately
+    pub cargo_capacity: u32,  // Cargo the robot heads home with: energy above the battery for energy collectors, minerals
+                              // for mineral collectors and miners, science for scientists, any cargo for carriers.
+                              // Explorers only take detours for deposits while they carry less
+}
+
+// Specs of each robot type, in RobotType order. Balancing the fleet only takes changing this table
+pub const ROBOT_SPECS: [RobotSpec; 7] = [
+    RobotSpec { max_energy: INITIAL_ROBOT_ENERGY, move_cost: 1, sensor_radius: 2, speed: 2, cargo_capacity: 30 },  // Explorer: a fast scout
+    RobotSpec { max_energy: INITIAL_ROBOT_ENERGY, move_cost: 1, sensor_radius: 1, speed: 1, cargo_capacity: 70 },  // EnergyCollector
+    RobotSpec { max_energy: INITIAL_ROBOT_ENERGY, move_cost: 1, sensor_radius: 1, speed: 1, cargo_capacity: 35 },  // MineralCollector
+    RobotSpec { max_energy: INITIAL_ROBOT_ENERGY, move_cost: 1, sensor_radius: 1, speed: 1, cargo_capacity: 6 },   // Scientist
+    RobotSpec { max_energy: INITIAL_ROBOT_ENERGY, move_cost: 1, sensor_radius: 1, speed: 1, cargo_capacity: 35 },  // Miner
+    RobotSpec { max_energy: 150, move_cost: 1, sensor_radius: 1, speed: 1, cargo_capacity: 120 },                  // Carrier: long trips to meet collectors
+    RobotSpec { max_energy: 120, move_cost: 1, sensor_radius: 1, speed: 1, cargo_capacity: 0 },                    // Repairer
+];
+
+// Carriers meet collectors holding at least CARRIER_PICKUP_LOAD cargo and take it off their
+// hands, heading home once they hold their cargo capacity. A collector a carrier offloaded keeps
+// collecting for CARRIER_GRACE_TICKS past its usual cargo threshold, up to CARRIER_GRACE_LOAD,
+// expecting the carrier back
+pub const CARRIER_PICKUP_LOAD: u32 = 20;
+pub const CARRIER_GRACE_TICKS: u32 = 100;
+const CARRIER_GRACE_LOAD: u32 = 80;
+
+// Wear and tear: a step next to radiation costs RADIATION_WEAR integrity, and a step onto rough
+// ground has a one in ROUGH_WEAR_ODDS chance of costing ROUGH_WEAR. A robot worn down to 0
+// breaks down where it stands until a repairer fixes it
+pub const MAX_INTEGRITY: u32 = 100;
+const RADIATION_WEAR: u32 = 5;
+const ROUGH_WEAR: u32 = 10;
+const ROUGH_WEAR_ODDS: u32 = 10;
+
+// What a robot has done since it was built, respawns included
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LifetimeStats {
+    pub distance: u32,           // Steps taken
+    pub collections: u32,        // Harvests
+    pub energy_consumed: u32,    // Battery spent on steps, shaft climbs and drilling
+    pub energy_delivered: u32,   // Cargo the station took in, not what was carried
+    pub minerals_delivered: u32,
+    pub science_delivered: u32,
+    pub cells_discovered: u32,   // Cells it was the first to explore
+    pub respawns: u32,
+}
+
+impl LifetimeStats {
+    pub fn header() -> String {
+        format!("{:>8} {:>11} {:>9} {:>15} {:>10} {:>8}", "distance", "collections", "consumed", "delivered E/M/S", "discovered", "respawns")
+    }
+
+    pub fn row(&self) -> String {
+        format!(
+            "{:>8} {:>11} {:>9} {:>15} {:>10} {:>8}",
+            self.distance,
+            self.collections,
+            self.energy_consumed,
+            format!("{}/{}/{}", self.energy_delivered, self.minerals_delivered, self.science_delivered),
+            self.cells_discovered,
+            self.respawns
+        )
+    }
+}
+
+impl std::iter::Sum for LifetimeStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |total, stats| Self {
+            distance: total.distance + stats.distance,
+            collections: total.collections + stats.collections,
+            energy_consumed: total.energy_consumed + stats.energy_consumed,
+            energy_delivered: total.energy_delivered + stats.energy_delivered,
+            minerals_delivered: total.minerals_delivered + stats.minerals_delivered,
+            science_delivered: total.science_delivered + stats.science_delivered,
+            cells_discovered: total.cells_discovered + stats.cells_discovered,
+            respawns: total.respawns + stats.respawns,
+        })
+    }
+}
+
+// Lifetime wear, which no repair undoes: a robot past the behavior config's wear_after_steps
+// steps or wear_after_collections harvests is worn, paying 1 more energy a step, and refueling
+// it costs the station WORN_REFUEL_SURCHARGE_PERCENT more than the energy it takes
+pub const WORN_REFUEL_SURCHARGE_PERCENT: u32 = 25;
+
+// Repairers see to broken robots first, then to ones worn below REPAIR_BELOW_INTEGRITY. The
+// station pays a refined mineral for every REPAIR_POINTS_PER_MINERAL integrity restored
+const REPAIR_BELOW_INTEGRITY: u32 = 50;
+const REPAIR_POINTS_PER_MINERAL: u32 = 5;
+
+// Collectors claim a deposit once it is this many steps away on each axis, the radius they
+// score deposits in
+const CLAIM_RADIUS: i32 = 2;
+
+// Every LONG_RANGE_SCAN_INTERVAL ticks a robot in the field scans the circle of this radius for
+// deposits in line of sight, queueing them for its report without exploring the cells
+const LONG_RANGE_SCAN_RADIUS: i32 = 5;
+pub const LONG_RANGE_SCAN_INTERVAL: u64 = 10;
+
+// A trailing explorer keeps this many cells from its leader, counted along the grid
+const FORMATION_BAND: std::ops::RangeInclusive<u32> = 3..=6;
+
+// Unreported cells a robot holds at most. Past that, empty ground makes room first so deposits
+// are never forgotten, and from URGENT_PENDING_PERCENT of it on the robot turns back ever sooner
+pub const MAX_PENDING_UPDATES: usize = 200;
+const URGENT_PENDING_PERCENT: usize = 75;
+
+// A stranded robot's distress beacon still drains 1 energy every this many ticks
+pub const STRANDED_DRAIN_INTERVAL: u32 = 10;
+// A stranded robot down to this much energy goes into low power: it stops draining and waits
+// this many ticks for a rescue before powering down for good, a wreck the station rebuilds
+pub const LOW_POWER_FLOOR: u32 = 5;
+pub const LOW_POWER_TIMEOUT: u32 = 200;
+
+// Explorers get the region pull bonus for steps toward their assigned region until they are
+// this close to the region center
+const REGION_ARRIVAL_RADIUS: u32 = 5;
+
+// A mineral harvester with no deposit in sight checks every TUNNEL_PLAN_INTERVAL ticks for a
+// shaft at most TUNNEL_SHAFT_RADIUS cells away to go down to the deposits in the tunnels
+const TUNNEL_PLAN_INTERVAL: u64 = 10;
+const TUNNEL_SHAFT_RADIUS: u32 = 15;
+
+// Once this share of the map is explored, explorers get a bonus for cells nobody has visited
+// for REVISIT_AFTER_TICKS; the station sends idle explorers back to such cells too
+const NEARLY_EXPLORED_PERCENT: f64 = 95.0;
+pub const REVISIT_AFTER_TICKS: u64 = 300;
+const STALE_CELL_BONUS: i32 = 50;
+
+// On the way home an explorer may step off its path to grab a known deposit at most this many
+// cells from it, up to MAX_RETURN_DETOURS times per trip and only while its cargo stays light
+const RETURN_DETOUR_RADIUS: i32 = 2;
+const MAX_RETURN_DETOURS: u32 = 2;
+
+// Drilling out one rock takes DRILL_TICKS ticks at DRILL_ENERGY_PER_TICK energy each and yields
+// MINERALS_PER_CLEARED_OBSTACLE minerals. Miners look for fenced deposits this far away
+const DRILL_TICKS: u32 = 4;
+const DRILL_ENERGY_PER_TICK: u32 = 2;
+pub const MINERALS_PER_CLEARED_OBSTACLE: u32 = 5;
+const MINER_SEARCH_RADIUS: u32 = 20;
+
+// A scientist analyzes a science point on the spot for ANALYSIS_TICKS ticks, coming away with
+// ANALYSIS_YIELD_FACTOR times the science another robot picking it up would get
+pub const ANALYSIS_TICKS: u32 = 3;
+pub const ANALYSIS_YIELD_FACTOR: u32 = 2;
+
+// Steps in a row a robot may find another robot on the next cell of its way before the two
+// are let through each other, see Robot::swap_places
+pub const DEADLOCK_STEPS: u32 = 3;
+
+// Faults a robot in the field picks up now and then. It works on in a degraded way until it
+// docks and the station repairs it for MALFUNCTION_REPAIR_MINERALS refined minerals per fault
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Malfunction {
+    SlowDrive,                // Half speed: scouts take one step a tick, the others one every other tick
+    SensorDamage,             // Half the sensor radius
+    StuckActuator(Direction), // The robot never picks that way itself; a planned walk forces it at STUCK_STEP_COST_FACTOR
+}
+
+pub const MALFUNCTION_REPAIR_MINERALS: u32 = 3;
+const STUCK_STEP_COST_FACTOR: u32 = 3;
+
+impl Malfunction {
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        match rng.gen_range(0..3) {
+            0 => Malfunction::SlowDrive,
+            1 => Malfunction::SensorDamage,
+            _ => Malfunction::StuckActuator(Direction::ALL[rng.gen_range(0..4)]),
+        }
+    }
+}
+
+// Steps of a Levy flight, the straight run some random moves start: a heavy-tailed length, at
+// least the shortest with odds falling as 1 / length, cut off at the longest
+const LEVY_FLIGHT_STEPS: std::ops::RangeInclusive<u8> = 3..=12;
+// Resolution of the uniform draw the flight length is derived from
+const LEVY_DRAW_SCALE: u32 = 1000;
+
+// Plans in a row that may fail to get a walking robot moving before it heads for a nearer cell first
+const REPLAN_LIMIT: u32 = 3;
+
+// Nearest ring swept by try_unstuck when looking for a place to head for; the farthest is tunable
+const UNSTUCK_MIN_RADIUS: i32 = 8;
+
+// Best-scoring spots try_unstuck looks for a path to before settling for the nearest unexplored one
+const UNSTUCK_PATH_ATTEMPTS: usize = 5;
+
+// Cells the flood for the nearest unexplored cell may settle before giving up, so a huge map
+// cannot stall a tick
+const FLOOD_NODE_LIMIT: usize = 10_000;
+
+// Fixed-point scale used by UNIT_CIRCLE_16
+const FIXED_POINT_ONE: i32 = 1 << 16;
+
+// cos/sin of the 16 multiples of 22.5 degrees, scaled by FIXED_POINT_ONE. Decisions use this
+// integer table instead of float trig so runs replay identically on every platform
+const UNIT_CIRCLE_16: [(i32, i32); 16] = [
+    (65536, 0), (60547, 25080), (46341, 46341), (25080, 60547),
+    (0, 65536), (-25080, 60547), (-46341, 46341), (-60547, 25080),
+    (-65536, 0), (-60547, -25080), (-46341, -46341), (-25080, -60547),
+    (0, -65536), (25080, -60547), (46341, -46341), (60547, -25080),
+];
+
+// Cell offset `radius` cells away in direction `angle` (an index into UNIT_CIRCLE_16),
+// truncated toward zero
+fn ring_offset(radius: i32, angle: usize) -> (i32, i32) {
+    let (cos, sin) = UNIT_CIRCLE_16[angle];
+    (radius * cos / FIXED_POINT_ONE, radius * sin / FIXED_POINT_ONE)
+}
+
+// Seed for a robot's decision RNG, derived from where and what it spawned as
+fn spawn_seed(x: usize, y: usize, robot_type: RobotType) -> u64 {
+    ((x as u64) << 32) ^ ((y as u64) << 8) ^ robot_type as u64
+}
+
+// A robot is known by its place in the station's fleet, which it keeps for good: robots are
+// only ever added to the fleet
+pub type RobotId = usize;
+
+// What a robot sees of the others around it when it decides its move: a small copy, so a fleet
+// update does not clone every robot for every other one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RobotView {
+    pub id: RobotId,
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+    pub energy: u32,
+    pub state: RobotState,
+    pub robot_type: RobotType,
+    pub target: Option<(usize, usize)>,         // Where it is heading, as target_x and target_y
+    pub current_target: Option<(usize, usize)>, // The deposit it claimed
+    pub cargo_load: u32,
+    pub integrity: u32,
+    pub science_room: bool, // A scientist that can take on more science
 }
 
 // Structure representing an exploration robot
 #[derive(Clone)]
 pub struct Robot {
+    pub id: RobotId, // Given by Station::add_robot
+    pub home_station: StationId, // Station whose fleet it belongs to, which it docks, refuels and respawns for
     pub x: usize,
     pub y: usize,
-    pub energy: u32,
+    pub z: usize, // Map layer: map::SURFACE, or map::UNDERGROUND on maps with tunnels
+    pub spec: RobotSpec, // Battery, step cost, sensor radius and cargo capacity it was built with
+    pub harvest_boost: u32, // Percent added to each harvest by station upgrades
+    pub upgrades: Upgrades, // Station upgrades fitted so far
+    pub energy: u32,        // Battery: what moving and working spend
+    pub energy_cargo: u32,  // Harvested energy carried home for the station, kept apart from the battery
     pub minerals: u32,
     pub science_points: u32,
-    pub pending_exploration_updates: RobotExplorationUpdate, // Added field
+    pending_exploration_updates: RobotExplorationUpdate, // Unique cells not yet reported, in discovery order
+    pending_index: HashMap<(usize, usize), usize>,       // Position of each pending cell in the list above
+    reported_cells: HashMap<(usize, usize), CellType>,   // What this robot last told the station about each cell
     pub robot_type: RobotType, // New field
     pub state: RobotState,     // New field
     pub target_x: Option<usize>, // Target coordinates for pathfinding
     pub target_y: Option<usize>,
     pub steps_since_last_find: u32, // For exploration strategy
+    pub stranded_ticks: u32,        // Ticks spent halted in the Stranded state
+    pub detour: Option<(usize, usize)>, // Deposit being grabbed on the way home
+    pub trip_detours: u32,              // Detours taken since the robot last reached the station
+    pub expedition: VecDeque<Leg>,      // Stops still ahead on a trip the station planned via refuel points
+    pub tunnel_route: VecDeque<LayeredPosition>, // Steps left of a trip down a shaft to an underground deposit and back up
+    pub needs_replan: bool,             // A planned refuel stop was found empty; the station plans again
+    pub current_path: Vec<(usize, usize)>, // Steps still ahead on the planned route, the next one last
+    pub carrier_grace: u32,                // Ticks left collecting past the cargo threshold after a carrier's visit
+    pub current_target: Option<(usize, usize)>, // Deposit this robot claimed on its way there; other robots leave it alone
+    pub relocating_to: Option<(usize, usize)>,  // Free spot a stuck robot is walking to along current_path
+    pub current_task: Option<Task>,             // Work taken off the station's queue, until it is done
+    pub integrity: u32,                         // Wear left before the robot breaks down, up to MAX_INTEGRITY
+    pub repair_bill: u32,                       // Refined minerals a repairer's work since its last docking costs the station
+    analyzed_cells: Vec<(usize, usize)>,        // Science points analyzed on site, not yet reported
+    pub heading_to: Option<(usize, usize)>,     // Goal of the walk this tick, if the robot walked towards one
+    pub blocked_steps: u32,                     // Steps in a row that found a robot on the next cell of the way
+    failed_replans: u32,                        // Plans in a row that did not get the robot moving
+    waypoint: Option<((usize, usize), (usize, usize))>, // Open cell the robot heads for first, and the goal it is on the way to
+    pub fallback_steps: u32,                    // Walks that fell back on the greedy directions, a sign of poor planning
+    pub recent_positions: VecDeque<(usize, usize)>, // Breadcrumb trail of the last cells walked through
+    pub trail_length: usize,                    // Cells the trail holds; 0 turns it off
+    pub momentum: Option<(Direction, u8)>,      // Way and steps left of a Levy flight under way
+    pub behavior_config: BehaviorConfig,        // Thresholds and scoring weights it decides by
+    pub malfunctions: Vec<Malfunction>,         // Faults waiting for repairs at the station
+    pub sensed_deposits: Vec<(usize, usize)>,   // Deposits the long-range sensor saw, in scan order
+    pub known_by_station: HashSet<(usize, usize)>, // Cells near the station it had mapped at the explorer's last docking
+    pub buddy: Option<(usize, usize)>,          // Where the leader this explorer trails stands, while it is out
+    pub wreck_reports: Vec<(usize, usize)>,     // Where its buddy was seen going down, not reported yet
+    pub regions_swept: u32,                     // Regions a sweeping explorer finished covering
+    pub retrofit_ticks: u32,                    // Downtime left while the station refits it as another type
+    pub lifetime: LifetimeStats,                // Running totals since it was built, which lifetime wear goes by
+    pub behavior: Box<dyn Behavior>,            // Picks the robot's moves once the shared rules have had their say
+    path_searches: std::cell::Cell<u64>,   // A* searches run by this robot
+    rng: ChaCha8Rng,                // Decision randomness, seeded from the spawn so runs replay identically
 }
 
 impl Robot {
@@ -93,136 +506,788 @@ impl Robot {
     // Create a new robot with a specific type
     pub fn new_with_type(x: usize, y: usize, robot_type: RobotType) -> Self {
         Self {
+            id: 0,
+            home_station: 0,
             x,
             y,
-            energy: INITIAL_ROBOT_ENERGY,
+            z: map::SURFACE,
+            spec: robot_type.spec(),
+            harvest_boost: 0,
+            upgrades: Upgrades::default(),
+            energy: robot_type.spec().max_energy,
+            energy_cargo: 0,
             minerals: 0,
             science_points: 0,
             pending_exploration_updates: Vec::new(),
+            pending_index: HashMap::new(),
+            reported_cells: HashMap::new(),
             robot_type,
             state: RobotState::Exploring,
             target_x: None,
             target_y: None,
             steps_since_last_find: 0,
+            stranded_ticks: 0,
+            detour: None,
+            trip_detours: 0,
+            expedition: VecDeque::new(),
+            tunnel_route: VecDeque::new(),
+            needs_replan: false,
+            current_path: Vec::new(),
+            carrier_grace: 0,
+            current_target: None,
+            relocating_to: None,
+            current_task: None,
+            integrity: MAX_INTEGRITY,
+            repair_bill: 0,
+            analyzed_cells: Vec::new(),
+            heading_to: None,
+            blocked_steps: 0,
+            failed_replans: 0,
+            waypoint: None,
+            fallback_steps: 0,
+            recent_positions: VecDeque::new(),
+            trail_length: TRAIL_LENGTH,
+            momentum: None,
+            behavior_config: BehaviorConfig::default(),
+            malfunctions: Vec::new(),
+            sensed_deposits: Vec::new(),
+            known_by_station: HashSet::new(),
+            buddy: None,
+            wreck_reports: Vec::new(),
+            regions_swept: 0,
+            retrofit_ticks: 0,
+            lifetime: LifetimeStats::default(),
+            behavior: behavior::builtin(robot_type),
+            path_searches: std::cell::Cell::new(0),
+            rng: ChaCha8Rng::seed_from_u64(spawn_seed(x, y, robot_type)),
+        }
+    }
+
+    // Refit the robot as another type: that type's spec, with the upgrades fitted, and built-in
+    // behavior replace the old ones, and whatever it was doing as the old type is dropped. The
+    // battery keeps its charge, up to the new size
+    pub fn retrofit(&mut self, robot_type: RobotType, upgrades: Upgrades) {
+        self.robot_type = robot_type;
+        self.upgrades = upgrades;
+        self.spec = upgrades.apply(robot_type.spec());
+        self.harvest_boost = upgrades.harvest_boost();
+        self.energy = self.energy.min(self.spec.max_energy);
+        self.behavior = behavior::builtin(robot_type);
+        self.state = RobotState::AtStation;
+        (self.target_x, self.target_y) = (None, None);
+        self.current_target = None;
+        self.current_task = None;
+        self.detour = None;
+        self.expedition.clear();
+        self.needs_replan = false;
+        self.current_path.clear();
+        self.relocating_to = None;
+        self.waypoint = None;
+        self.sensed_deposits.clear();
+        self.known_by_station.clear();
+        self.buddy = None;
+        self.steps_since_last_find = 0;
+    }
+
+    // What the other robots see of this one
+    pub fn view(&self) -> RobotView {
+        RobotView {
+            id: self.id,
+            x: self.x,
+            y: self.y,
+            z: self.z,
+            energy: self.energy,
+            state: self.state,
+            robot_type: self.robot_type,
+            target: self.target_x.zip(self.target_y),
+            current_target: self.current_target,
+            cargo_load: self.cargo_load(),
+            integrity: self.integrity,
+            science_room: self.has_science_room(),
+        }
+    }
+
+    // Take the shaft the robot stands on to the other layer, paying the climb; false when
+    // there is no shaft here or the battery cannot cover it
+    pub fn climb_shaft(&mut self, map: &Map) -> bool {
+        if !map.is_shaft(self.x, self.y) || self.energy < map::SHAFT_CLIMB_COST {
+            return false;
+        }
+        self.spend(map::SHAFT_CLIMB_COST);
+        self.z = 1 - self.z;
+        true
+    }
+
+    // Combined cargo carried: minerals, science and harvested energy
+    pub fn cargo_load(&self) -> u32 {
+        self.minerals + self.science_points + self.energy_cargo
+    }
+
+    // Extra energy per step caused by the current load
+    pub fn load_factor(&self) -> u32 {
+        self.cargo_load() / ENERGY_COSTS.load_per_extra_move
+    }
+
+    // Energy spent by a single step on plains with the current load
+    pub fn move_cost(&self) -> u32 {
+        self.spec.move_cost + self.load_factor() + self.wear_penalty()
+    }
+
+    // Energy spent stepping onto a given cell with the current load
+    pub fn step_cost(&self, cell: &Cell) -> u32 {
+        self.spec.move_cost * cell.movement_cost + self.load_factor() + self.wear_penalty()
+    }
+
+    // How far along the robot is to being worn, in percent of the nearer threshold
+    pub fn wear_percent(&self) -> u32 {
+        let steps = self.lifetime.distance as u64 * 100 / self.behavior_config.wear_after_steps.max(1) as u64;
+        let collections = self.lifetime.collections as u64 * 100 / self.behavior_config.wear_after_collections.max(1) as u64;
+        steps.max(collections).min(100) as u32
+    }
+
+    pub fn is_worn(&self) -> bool {
+        self.wear_percent() >= 100
+    }
+
+    fn wear_penalty(&self) -> u32 {
+        u32::from(self.is_worn())
+    }
+
+    // Station energy it takes to put `amount` into the battery
+    pub fn refuel_price(&self, amount: u32) -> u32 {
+        match self.is_worn() {
+            true => amount + (amount * WORN_REFUEL_SURCHARGE_PERCENT).div_ceil(100),
+            false => amount,
         }
     }
 
+    // Energy needed to walk home with the current load, following the terrain-aware path
+    fn estimated_return_cost(&self, map: &Map, station_x: usize, station_y: usize) -> u32 {
+        self.estimated_trip_cost(map, self.x, self.y, station_x, station_y)
+    }
+
+    // Energy needed to walk between two positions with the current load
+    fn estimated_trip_cost(&self, map: &Map, from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> u32 {
+        match self.find_path(from_x, from_y, to_x, to_y, map, &[]) {
+            Some(path) => self.path_cost(&path, map),
+            None => self.heuristic(map, from_x, from_y, to_x, to_y) * self.move_cost(),
+        }
+    }
+
+    // Energy an unloaded robot spends walking between two positions and the steps it takes, if
+    // there is a path at all
+    pub fn unloaded_trip(&self, map: &Map, from: (usize, usize), to: (usize, usize)) -> Option<(u32, u32)> {
+        let path = self.find_path(from.0, from.1, to.0, to.1, map, &[])?;
+        let steps = path.len().saturating_sub(1) as u32;
+        Some((self.path_cost(&path, map) - steps * self.load_factor(), steps))
+    }
+
+    // Energy spent walking a path with the current load (its first position is the start)
+    fn path_cost(&self, path: &[(usize, usize)], map: &Map) -> u32 {
+        pathfinding::path_cost(map, path, |cell| self.step_cost(cell))
+    }
+
+    // Cheap check that the robot can get home even if every step were on the roughest terrain
+    // (with slack for detours), so the pathfinding estimate can be skipped
+    fn clearly_within_return_range(&self, map: &Map, station_x: usize, station_y: usize) -> bool {
+        let distance = self.heuristic(map, self.x, self.y, station_x, station_y);
+        let worst_step = self.spec.move_cost * VERY_ROUGH_MOVEMENT_COST + self.load_factor();
+        self.energy > 2 * distance * worst_step + ENERGY_COSTS.return_margin
+    }
+
+    // Energy this robot is missing to make it home with the usual reserve
+    pub fn rescue_shortfall(&self, map: &Map, station_x: usize, station_y: usize) -> u32 {
+        (self.estimated_return_cost(map, station_x, station_y) + ENERGY_COSTS.return_margin).saturating_sub(self.energy)
+    }
+
+    // Energy the robot can give away and still make it home with the usual reserve
+    pub fn spare_energy(&self, map: &Map, station_x: usize, station_y: usize) -> u32 {
+        self.energy.saturating_sub(self.estimated_return_cost(map, station_x, station_y) + ENERGY_COSTS.return_margin)
+    }
+
+    // Where the robot can next top up: the next refuel stop of its expedition, or the station
+    fn next_haven(&self, station_x: usize, station_y: usize) -> (usize, usize) {
+        self.expedition.iter().find(|leg| leg.refuel).map_or((station_x, station_y), |leg| (leg.x, leg.y))
+    }
+
+    // Whether the robot can no longer afford the trip to its next haven at all
+    fn is_stranded(&self, map: &Map, station_x: usize, station_y: usize) -> bool {
+        let (station_x, station_y) = self.next_haven(station_x, station_y);
+        (self.x, self.y) != (station_x, station_y)
+            && !self.clearly_within_return_range(map, station_x, station_y)
+            && self.energy < self.estimated_return_cost(map, station_x, station_y)
+    }
+
     // Autonomous behavior - main AI loop
-    pub fn autonomous_update(&mut self, map: &mut Map, station_x: usize, station_y: usize, other_robots: &[Robot]) {
+    // `tick` is the simulation tick, recorded on the cells the robot explores. A robot exploring
+    // or heading home takes up to its speed in steps, stopping early once its state changes
+    pub fn autonomous_update(&mut self, map: &mut Map, station_x: usize, station_y: usize, other_robots: &[RobotView], tick: u64) {
         // Skip update if robot has no energy
         if self.energy == 0 {
             return;
         }
+        self.carrier_grace = self.carrier_grace.saturating_sub(1);
+        self.heading_to = None;
+        if tick.is_multiple_of(LONG_RANGE_SCAN_INTERVAL) && self.state != RobotState::AtStation && self.z == map::SURFACE {
+            self.long_range_scan(map);
+        }
+        let speed = self.speed();
+        if speed == 0 && tick % 2 == 1 && matches!(self.state, RobotState::Exploring | RobotState::ReturningToStation) {
+            return; // A slowed robot of speed 1 sits out every other tick
+        }
+        for step in 0..speed.max(1) {
+            let state = self.state;
+            if step > 0 && !matches!(state, RobotState::Exploring | RobotState::ReturningToStation) {
+                break;
+            }
+            self.autonomous_step(map, station_x, station_y, other_robots, tick);
+            if self.energy == 0 || self.state != state {
+                break;
+            }
+        }
+        if self.heading_to.is_none() {
+            self.blocked_steps = 0;
+        }
+    }
+
+    // One step of the update: a move with whatever harvesting, exploring and deciding goes with it
+    fn autonomous_step(&mut self, map: &mut Map, station_x: usize, station_y: usize, other_robots: &[RobotView], tick: u64) {
+        // A trip underground was paid for when it was planned: it is seen through before anything else
+        if self.follow_tunnel_route(map) {
+            return;
+        }
+        if self.state != RobotState::ReturningToStation {
+            self.detour = None;
+        }
+        if self.state != RobotState::Exploring {
+            self.expedition.clear();
+            self.current_target = None;
+            self.relocating_to = None;
+        }
+
+        // Cargo that makes the trip home unaffordable is left behind in a cache (taking this tick)
+        if matches!(self.state, RobotState::Exploring | RobotState::ReturningToStation | RobotState::Retrieving { .. } | RobotState::Working { .. })
+            && self.is_stranded(map, station_x, station_y)
+            && self.drop_cargo_to_get_home(map, station_x, station_y)
+        {
+            self.state = RobotState::ReturningToStation;
+            self.target_x = Some(station_x);
+            self.target_y = Some(station_y);
+            return;
+        }
+
+        // Halt rather than burn the last energy on a trip that cannot be finished
+        if matches!(self.state, RobotState::Exploring | RobotState::ReturningToStation | RobotState::Retrieving { .. } | RobotState::Working { .. })
+            && self.is_stranded(map, station_x, station_y)
+        {
+            self.state = RobotState::Stranded;
+            self.stranded_ticks = 0;
+            self.enter_low_power_at_the_floor();
+            return;
+        }
 
         match self.state {
             RobotState::Exploring => {
-                self.autonomous_explore(map, station_x, station_y, other_robots);
+                self.autonomous_explore(map, station_x, station_y, other_robots, tick);
             }
             RobotState::ReturningToStation => {
-                self.move_towards_station(map, station_x, station_y, other_robots);
+                self.return_to_station(map, station_x, station_y, other_robots, tick);
+            }
+            RobotState::AtStation if self.robot_type == RobotType::Scientist => {
+                // Docked scientists wait for the station to send them out; it may keep them
+                // home to analyze data
+            }
+            RobotState::AtStation if self.retrofit_ticks > 0 => {
+                // Being refitted: the station sends it out once done
+            }
+            RobotState::Idle => {
+                // Waits for the station to hand it a task
+            }
+            RobotState::Broken => {
+                // Waits for a repairer
             }
+            RobotState::Decommissioned => {}
             RobotState::AtStation => {
                 // Robot is at station, will be handled by main loop
                 // Reset state to exploring after interaction
                 self.state = RobotState::Exploring;
             }
+            RobotState::Stranded => {
+                if !self.is_stranded(map, station_x, station_y) {
+                    // Topped up by a rescuer: head home
+                    self.state = RobotState::ReturningToStation;
+                    self.target_x = Some(station_x);
+                    self.target_y = Some(station_y);
+                } else {
+                    self.stranded_ticks += 1;
+                    if self.stranded_ticks.is_multiple_of(STRANDED_DRAIN_INTERVAL) {
+                        self.energy -= 1;
+                    }
+                    self.enter_low_power_at_the_floor();
+                }
+            }
+            RobotState::LowPower { ticks_left } => {
+                if !self.is_stranded(map, station_x, station_y) {
+                    // Revived by a rescuer: head home
+                    self.state = RobotState::ReturningToStation;
+                    self.target_x = Some(station_x);
+                    self.target_y = Some(station_y);
+                } else if ticks_left > 1 {
+                    self.state = RobotState::LowPower { ticks_left: ticks_left - 1 };
+                } else {
+                    self.energy = 0; // Nobody came: powered down for good
+                }
+            }
+            RobotState::Rescuing { x, y, .. } => {
+                // Wait next to the stranded robot for the handover
+                if self.heuristic(map, self.x, self.y, x, y) > 1 {
+                    // Plan through the stranded robot's own cell; the move itself still avoids robots
+                    self.step_towards(map, x, y, &[], other_robots);
+                }
+            }
+            RobotState::Retrieving { x, y } => {
+                // Give up on the cache (the station can send someone else) before the trip home gets tight
+                if !self.clearly_within_return_range(map, station_x, station_y)
+                    && self.energy <= self.estimated_return_cost(map, station_x, station_y) + ENERGY_COSTS.return_margin
+                {
+                    self.state = RobotState::ReturningToStation;
+                    self.target_x = Some(station_x);
+                    self.target_y = Some(station_y);
+                } else if (self.x, self.y) != (x, y) {
+                    self.step_towards(map, x, y, other_robots, other_robots);
+                }
+            }
+            RobotState::Working { x, y, ticks_left } => {
+                self.spend(DRILL_ENERGY_PER_TICK);
+                if ticks_left > 1 {
+                    self.state = RobotState::Working { x, y, ticks_left: ticks_left - 1 };
+                } else {
+                    if map.clear_obstacle(x, y) {
+                        self.minerals += MINERALS_PER_CLEARED_OBSTACLE;
+                        map.explore(x, y, tick);
+                        self.record_exploration((x, y), CellType::Empty);
+                    }
+                    self.state = RobotState::Exploring;
+                }
+            }
+            RobotState::Analyzing { x, y, ticks_left } => {
+                if ticks_left > 1 {
+                    self.state = RobotState::Analyzing { x, y, ticks_left: ticks_left - 1 };
+                } else {
+                    // Another robot may have picked the point up in the meantime
+                    if let Some(CollectedResource::Science(value)) = map.collect_resource_boosted(x, y, self.harvest_boost) {
+                        self.science_points += value * ANALYSIS_YIELD_FACTOR;
+                        self.analyzed_cells.push((x, y));
+                    }
+                    self.explore(map, tick);
+                    self.state = RobotState::Exploring;
+                }
+            }
+        }
+    }
+
+    // Whether the robot is halted in the field for a rescuer to bring it energy
+    pub fn is_waiting_for_rescue(&self) -> bool {
+        matches!(self.state, RobotState::Stranded | RobotState::LowPower { .. })
+    }
+
+    // A stranded robot down to the floor stops draining and waits in low power
+    fn enter_low_power_at_the_floor(&mut self) {
+        if self.state == RobotState::Stranded && self.energy <= LOW_POWER_FLOOR {
+            self.state = RobotState::LowPower { ticks_left: LOW_POWER_TIMEOUT };
         }
     }
 
+    // Leave the minerals and science on board in a field cache if carrying them is what makes
+    // the trip home unaffordable. Returns whether anything was dropped
+    fn drop_cargo_to_get_home(&mut self, map: &mut Map, station_x: usize, station_y: usize) -> bool {
+        if self.minerals == 0 && self.science_points == 0 {
+            return false;
+        }
+        let (minerals, science) = (self.minerals, self.science_points);
+        self.minerals = 0;
+        self.science_points = 0;
+        if self.is_stranded(map, station_x, station_y) {
+            // Stuck either way: keep the cargo for a rescue
+            self.minerals = minerals;
+            self.science_points = science;
+            return false;
+        }
+        map.drop_cache(self.x, self.y, minerals, science);
+        true
+    }
+
+    // Load the contents of a cache picked up in the field
+    pub fn load_cache(&mut self, cache: &Cache) {
+        self.minerals += cache.minerals;
+        self.science_points += cache.science;
+    }
+
+    // Charge an adjacent robot's battery from the energy cargo, then from up to `battery_spare`
+    // of the own battery
+    pub fn transfer_energy_to(&mut self, other: &mut Robot, amount: u32, battery_spare: u32, map: &Map) -> u32 {
+        if self.heuristic(map, self.x, self.y, other.x, other.y) > 1 {
+            return 0;
+        }
+        let from_cargo = amount.min(self.energy_cargo);
+        let from_battery = (amount - from_cargo).min(battery_spare).min(self.energy);
+        self.energy_cargo -= from_cargo;
+        self.energy -= from_battery;
+        other.energy += from_cargo + from_battery;
+        from_cargo + from_battery
+    }
+
+    // Hand all cargo (minerals, science and harvested energy) to an adjacent robot. A collector
+    // sent home by its cargo goes back to collecting. Returns the (energy, minerals, science)
+    // handed over
+    pub fn transfer_cargo_to(&mut self, other: &mut Robot, map: &Map) -> (u32, u32, u32) {
+        if self.heuristic(map, self.x, self.y, other.x, other.y) > 1 {
+            return (0, 0, 0);
+        }
+        let cargo = (
+            std::mem::take(&mut self.energy_cargo),
+            std::mem::take(&mut self.minerals),
+            std::mem::take(&mut self.science_points),
+        );
+        other.energy_cargo += cargo.0;
+        other.minerals += cargo.1;
+        other.science_points += cargo.2;
+        if cargo != (0, 0, 0) {
+            self.carrier_grace = CARRIER_GRACE_TICKS;
+            if self.state == RobotState::ReturningToStation {
+                self.state = RobotState::Exploring;
+            }
+        }
+        cargo
+    }
+
+    // Walk towards the next stop of the expedition, harvesting each refuel stop until it is empty,
+    // and head home once the last stop is reached
+    fn follow_expedition(&mut self, map: &mut Map, station_x: usize, station_y: usize, other_robots: &[RobotView], tick: u64) {
+        let Some(&leg) = self.expedition.front() else {
+            return;
+        };
+        // Refuel stops charge the battery itself
+        let harvested = if leg.refuel && (self.x, self.y) == (leg.x, leg.y) {
+            self.refuel_here(map)
+        } else {
+            self.harvest_here(map, other_robots)
+        };
+        self.explore(map, tick);
+        if (self.x, self.y) != (leg.x, leg.y) {
+            self.step_towards(map, leg.x, leg.y, other_robots, other_robots);
+            return;
+        }
+        if leg.refuel && !harvested {
+            // Someone got to the deposit first: the station works out a new plan from here
+            self.expedition.clear();
+            self.needs_replan = true;
+            return;
+        }
+        if leg.refuel && self.found_something_at_current_position(map) && self.energy < MAX_PLANNED_CHARGE {
+            return;
+        }
+        self.expedition.pop_front();
+        if self.expedition.is_empty() {
+            self.state = RobotState::ReturningToStation;
+            self.target_x = Some(station_x);
+            self.target_y = Some(station_y);
+        }
+    }
+
+    // Energy the robot turns back at: the plains estimate of the trip home plus the reserve,
+    // with the configured percentage of slack
+    fn return_budget(&self, map: &Map, station_x: usize, station_y: usize) -> u32 {
+        let estimate = self.heuristic(map, self.x, self.y, station_x, station_y) * self.move_cost() + ENERGY_COSTS.return_margin;
+        estimate * (self.behavior_config.return_budget_percent + self.report_urgency()) / 100
+    }
+
     // Check if robot should return to station
-    fn should_return_to_station(&self) -> bool {
-        // Return if energy is critically low
-        if self.energy <= 20 { // Slightly increased for safety
+    fn should_return_to_station(&self, map: &Map, station_x: usize, station_y: usize) -> bool {
+        // Return once the energy left is close to what the trip home costs, however far that is
+        if self.energy <= self.return_budget(map, station_x, station_y) {
+            return true;
+        }
+
+        // Return before the trip home, over the actual terrain and at the current load, becomes unaffordable
+        if !self.clearly_within_return_range(map, station_x, station_y)
+            && self.energy <= self.estimated_return_cost(map, station_x, station_y) + ENERGY_COSTS.return_margin
+        {
             return true;
         }
 
+        // A collector a carrier offloaded recently keeps collecting, expecting the carrier back
+        if self.carrier_grace > 0 && self.cargo_load() < CARRIER_GRACE_LOAD {
+            return false;
+        }
+
         // Return based on robot type and cargo
         match self.robot_type {
-            RobotType::Explorer => {
-                // Let explorers venture further but return before energy gets too low
-                self.energy <= 25 || self.pending_exploration_updates.len() > 30 // Balanced thresholds
-            }
-            RobotType::EnergyCollector => {
-                // Return when carrying significant energy or low on energy
-                self.energy <= 25 || (self.energy > INITIAL_ROBOT_ENERGY + 70) // Slightly reduced cargo threshold
-            }
-            RobotType::MineralCollector => {
-                // Return when carrying minerals or energy is low
-                self.minerals > 35 || self.energy <= 25 // Slightly reduced thresholds
-            }
-            RobotType::Scientist => {
-                // Return when has science points or energy is low
-                self.science_points > 6 || self.energy <= 25 // Slightly reduced thresholds
-            }
+            // Explorers head home to report once they hold enough unreported cells
+            RobotType::Explorer => self.pending_update_count() > self.behavior_config.explorer_report_updates,
+            // The hold takes no more energy once full
+            RobotType::EnergyCollector => self.cargo_load() >= self.spec.cargo_capacity,
+            RobotType::MineralCollector | RobotType::Miner => self.minerals > self.spec.cargo_capacity,
+            RobotType::Scientist => self.science_points > self.spec.cargo_capacity,
+            RobotType::Carrier => self.cargo_load() >= self.spec.cargo_capacity,
+            // Repairers go home when there is nothing left to repair
+            RobotType::Repairer => false,
         }
     }
 
     // Autonomous exploration based on robot type
-    fn autonomous_explore(&mut self, map: &mut Map, station_x: usize, station_y: usize, other_robots: &[Robot]) {
+    fn autonomous_explore(&mut self, map: &mut Map, station_x: usize, station_y: usize, other_robots: &[RobotView], tick: u64) {
+        // The station planned this trip's refuel stops, so the usual return checks do not apply
+        if !self.expedition.is_empty() {
+            self.follow_expedition(map, station_x, station_y, other_robots, tick);
+            return;
+        }
+
         // Check if robot should return to station
-        if self.should_return_to_station() {
+        if self.should_return_to_station(map, station_x, station_y) {
             self.state = RobotState::ReturningToStation;
             self.target_x = Some(station_x);
             self.target_y = Some(station_y);
             return;
         }
 
+        if self.robot_type == RobotType::Carrier {
+            self.ferry(map, station_x, station_y, other_robots);
+            return;
+        }
+        if self.robot_type == RobotType::Repairer {
+            self.seek_repairs(map, station_x, station_y, other_robots);
+            return;
+        }
+
+        // Miners open up a fenced deposit before harvesting it
+        if self.robot_type == RobotType::Miner && self.start_drilling(map, station_x, station_y) {
+            return;
+        }
+
+        // Scientists stop to analyze a science point instead of just picking it up
+        if self.robot_type == RobotType::Scientist && matches!(map.get_cell(self.x, self.y), Some(cell) if matches!(cell.cell_type, CellType::SciencePoint(_))) {
+            self.state = RobotState::Analyzing { x: self.x, y: self.y, ticks_left: ANALYSIS_TICKS };
+            return;
+        }
+
         // Try to collect resource at current position first
-        self.collect_resource(map);
+        let harvested = self.harvest_here(map, other_robots);
         
         // Explore current position
-        self.explore(map);
-
-        // Choose next move based on robot type
-        let next_direction = match self.robot_type {
-            RobotType::Explorer => self.choose_explorer_direction(map, other_robots),
-            RobotType::EnergyCollector => self.choose_energy_collector_direction(map, other_robots),
-            RobotType::MineralCollector => self.choose_mineral_collector_direction(map, other_robots),
-            RobotType::Scientist => self.choose_scientist_direction(map, other_robots),
-        };
+        self.explore(map, tick);
 
-        if let Some(direction) = next_direction {
-            if self.move_in_direction(direction, map, other_robots) {
-                if self.found_something_at_current_position(map) {
+        // A mineral harvester with no deposit in sight now and then looks for one down a shaft nearby
+        if !harvested && self.current_target.is_none() && tick.is_multiple_of(TUNNEL_PLAN_INTERVAL) && self.plan_tunnel_trip(map, station_x, station_y) {
+            self.follow_tunnel_route(map);
+            return;
+        }
+
+        // A robot that got stuck walks to the free spot it picked, one step at a time
+        if let Some((x, y)) = self.relocating_to {
+            self.step_towards(map, x, y, other_robots, other_robots);
+            if self.current_path.is_empty() && !self.waiting_on_a_robot() {
+                self.relocating_to = None; // Arrived, or the way is lost
+            }
+            return;
+        }
+
+        // A task from the station comes before the robot's own choices
+        if let Some(task) = self.current_task {
+            if self.follow_task(task, map, station_x, station_y, other_robots) {
+                return;
+            }
+        }
+
+        // The behavior picks the move; it is taken out for the call so it can work on the robot
+        let ctx = Context { other_robots, station: (station_x, station_y), tick, harvested };
+        let mut behavior = std::mem::replace(&mut self.behavior, Box::new(behavior::Idle));
+        let action = behavior.decide(self, map, &ctx);
+        self.behavior = behavior;
+        match action {
+            Action::Move(direction) => {
+                if self.move_in_direction(direction, map, other_robots) && self.found_something_at_current_position(map) {
                     self.steps_since_last_find = 0;
                 } else {
                     self.steps_since_last_find += 1;
                 }
-            } else {
-                self.steps_since_last_find += 1;
             }
-        } else {
-            // No good direction found, try random movement
-            if !self.move_randomly(map, other_robots) {
-                // Even random movement failed, increment stuck counter
-                self.steps_since_last_find += 1;
+            // Stay on a deposit that still holds more, harvesting again next tick
+            Action::Collect => {
+                self.steps_since_last_find = 0;
+                return;
             }
+            Action::Return => {
+                self.state = RobotState::ReturningToStation;
+                self.target_x = Some(station_x);
+                self.target_y = Some(station_y);
+                return;
+            }
+            Action::Wait => self.steps_since_last_find += 1,
         }
 
-        // If stuck for too long, try teleporting to a nearby free space
-        if self.steps_since_last_find > 5 { // Reduced from 8 to 5 for more aggressive unstuck
+        // If stuck for too long, set off for a nearby free space. A trailing explorer goes where
+        // its leader takes it instead
+        if self.steps_since_last_find > self.behavior_config.unstuck_after_steps && self.buddy.is_none() {
             self.try_unstuck(map, other_robots);
         }
     }
 
-    // Try to get unstuck by finding a nearby free position
-    fn try_unstuck(&mut self, map: &Map, other_robots: &[Robot]) {
-        // Try to find a completely unexplored area to jump to
-        let mut best_position = None;
-        let mut best_score = -1i32;
+    // Whether the station's task is done: a deposit is worked until it is used up, and a region or
+    // outpost site is reached once the robot is within the arrival radius of it
+    pub fn task_done(&self, task: Task, map: &Map) -> bool {
+        match task {
+            Task::CollectAt { x, y } => !map.get_cell(x, y).is_some_and(|cell| self.robot_type.harvests(&cell.cell_type)),
+            Task::ExploreRegion { x, y } | Task::BuildOutpostAt { x, y } => map.distance(self.x, self.y, x, y) <= REGION_ARRIVAL_RADIUS,
+            Task::ReturnHome => false,
+        }
+    }
+
+    // Take a step on the station's task; a task done, or that no path leads to, is dropped and
+    // false returned so the robot carries on by itself
+    fn follow_task(&mut self, task: Task, map: &mut Map, station_x: usize, station_y: usize, other_robots: &[RobotView]) -> bool {
+        let Some((x, y)) = task.destination() else {
+            // Called home
+            self.state = RobotState::ReturningToStation;
+            self.target_x = Some(station_x);
+            self.target_y = Some(station_y);
+            return true;
+        };
+        if self.task_done(task, map) {
+            self.current_task = None;
+            return false;
+        }
+        if let Task::CollectAt { .. } = task {
+            self.current_target = Some((x, y)); // Claimed, so nobody else heads there
+        }
+        if (self.x, self.y) == (x, y) {
+            return false; // On the deposit: the behavior stays to harvest it
+        }
+        self.step_towards(map, x, y, other_robots, other_robots);
+        if self.current_path.is_empty() && (self.x, self.y) != (x, y) && !self.waiting_on_a_robot() {
+            self.current_task = None; // The way is lost
+        }
+        true
+    }
+
+    // Plan a mineral harvester's trip down the nearest shaft within TUNNEL_SHAFT_RADIUS to the
+    // deposit closest to its foot and back to where the robot stands, if the hold has room and the
+    // battery covers the way there, the way back with a full hold and the trip home from here
+    fn plan_tunnel_trip(&mut self, map: &Map, station_x: usize, station_y: usize) -> bool {
+        let has_room = self.minerals <= self.spec.cargo_capacity;
+        if self.z != map::SURFACE || !has_room || !self.robot_type.harvests(&CellType::Mineral(1)) {
+            return false;
+        }
+        let Some(underground) = &map.underground else {
+            return false;
+        };
+        let shaft = underground
+            .shafts
+            .iter()
+            .filter(|&&(x, y)| map.distance(self.x, self.y, x, y) <= TUNNEL_SHAFT_RADIUS)
+            .min_by_key(|&&(x, y)| (map.distance(self.x, self.y, x, y), y, x));
+        let Some((x, y)) = shaft.and_then(|&(x, y)| map.nearest_underground_mineral(x, y)) else {
+            return false;
+        };
+        let Some(path) = map.find_layered_path((self.x, self.y, self.z), (x, y, map::UNDERGROUND)) else {
+            return false;
+        };
+        let there: u32 = path.windows(2).map(|step| self.layered_step_cost(map, step[1])).sum();
+        let full_load = (self.spec.cargo_capacity + MINERAL_HARVEST_RATE) / ENERGY_COSTS.load_per_extra_move;
+        let back = there + full_load * (path.len() as u32 - 1);
+        if self.energy < there + back + self.return_budget(map, station_x, station_y) {
+            return false;
+        }
+        self.tunnel_route = path.iter().skip(1).chain(path.iter().rev().skip(1)).copied().collect();
+        true
+    }
+
+    // Energy spent moving to `to` on a tunnel trip: the climb when it is the other end of a shaft,
+    // else the step onto its cell
+    fn layered_step_cost(&self, map: &Map, to: LayeredPosition) -> u32 {
+        let (x, y, z) = to;
+        if z != self.z {
+            return map::SHAFT_CLIMB_COST;
+        }
+        map.layer_cell(x, y, z).map_or(self.move_cost(), |cell| self.step_cost(cell))
+    }
+
+    // Take the next step of a tunnel trip: mine the deposit underfoot while the hold has room,
+    // else walk on or climb the shaft. False when the robot is on no trip
+    fn follow_tunnel_route(&mut self, map: &mut Map) -> bool {
+        let Some(&(x, y, z)) = self.tunnel_route.front() else {
+            return false;
+        };
+        if self.z == map::UNDERGROUND && self.minerals <= self.spec.cargo_capacity {
+            if let Some(minerals) = map.mine_underground(self.x, self.y, self.harvest_boost) {
+                self.minerals += minerals;
+                self.lifetime.collections += 1;
+                return true;
+            }
+        }
+        if z != self.z {
+            if !self.climb_shaft(map) {
+                self.spend(self.energy); // Too weak to climb out: the robot is lost down the shaft
+                self.tunnel_route.clear();
+                return true;
+            }
+        } else {
+            let cost = self.layered_step_cost(map, (x, y, z));
+            self.x = x;
+            self.y = y;
+            self.spend(cost);
+            self.lifetime.distance += 1;
+        }
+        self.tunnel_route.pop_front();
+        true
+    }
+
+    // Start drilling a rock next to the fenced deposit the miner stands on, if the drilling and
+    // the trip home are both affordable
+    fn start_drilling(&mut self, map: &Map, station_x: usize, station_y: usize) -> bool {
+        if !map.is_fenced_deposit(self.x, self.y) {
+            return false;
+        }
+        let Some(&(x, y)) = map.rock_neighbors(self.x, self.y).first() else {
+            return false;
+        };
+        let drilling_cost = DRILL_TICKS * DRILL_ENERGY_PER_TICK;
+        if self.energy <= drilling_cost + self.estimated_return_cost(map, station_x, station_y) + ENERGY_COSTS.return_margin {
+            return false;
+        }
+        self.state = RobotState::Working { x, y, ticks_left: DRILL_TICKS };
+        true
+    }
+
+    // Try to get unstuck by walking somewhere new. Explorers head straight for the nearest
+    // unexplored cell; other robots sweep rings around them for a spot first
+    fn try_unstuck(&mut self, map: &Map, other_robots: &[RobotView]) {
+        if self.robot_type != RobotType::Explorer && self.relocate_by_sweep(map, other_robots) {
+            return;
+        }
+        // The sweep stays on the map, so in a pocket of rock it can come up empty or with nothing
+        // in reach: then settle for the nearest unexplored cell the robot could walk to
+        self.head_for_nearest_unexplored(map, other_robots);
+    }
+
+    // Score free spots on rings around the robot, unexplored ground far off first, and set off
+    // along an A* path to the best one it can actually reach. False when none is reachable
+    fn relocate_by_sweep(&mut self, map: &Map, other_robots: &[RobotView]) -> bool {
+        // Look for a completely unexplored area to head for
+        let mut candidates: Vec<(i32, (usize, usize))> = Vec::new();
         
-        // Search in a much wider radius for unexplored areas  
-        for radius in 8..=25 { // Increased search radius significantly
-            for angle in 0..16 { // More angles for better coverage
-                let angle_rad = angle as f32 * std::f32::consts::PI / 8.0;
-                let dx = (radius as f32 * angle_rad.cos()) as i32;
-                let dy = (radius as f32 * angle_rad.sin()) as i32;
-                
-                let new_x = (self.x as i32 + dx).max(0).min(map.width as i32 - 1) as usize;
-                let new_y = (self.y as i32 + dy).max(0).min(map.height as i32 - 1) as usize;
+        // Search in a much wider radius for unexplored areas; ring points past an edge are
+        // skipped (or wrap on a torus) rather than piled up on the border
+        let here = Position::of_cell(self.x, self.y);
+        for radius in UNSTUCK_MIN_RADIUS..=self.behavior_config.unstuck_radius {
+            for angle in 0..UNIT_CIRCLE_16.len() { // More angles for better coverage
+                let (dx, dy) = ring_offset(radius, angle);
+                let Some((new_x, new_y)) = map.locate(here.offset(dx, dy)) else {
+                    continue;
+                };
                 
                 if let Some(cell) = map.get_cell(new_x, new_y) {
-                    if cell.cell_type != CellType::Obstacle && 
+                    if !cell.cell_type.blocks_movement() && 
                        !other_robots.iter().any(|r| r.x == new_x && r.y == new_y && r.energy > 0) {
                         
                         let mut score = 0;
@@ -231,69 +1296,92 @@ impl Robot {
                         }
                         
                         // Count unexplored neighbors in a wider area
-                        for dy_check in -2..=2 {
-                            for dx_check in -2..=2 {
-                                let check_x = (new_x as i32 + dx_check).max(0).min(map.width as i32 - 1) as usize;
-                                let check_y = (new_y as i32 + dy_check).max(0).min(map.height as i32 - 1) as usize;
-                                
-                                if let Some(neighbor) = map.get_cell(check_x, check_y) {
-                                    if !neighbor.explored {
-                                        score += 15; // Bonus for unexplored neighbors
-                                    }
-                                }
-                            }
-                        }
+                        score += 15 * unexplored_around(map, new_x, new_y, 2, &self.known_by_station) as i32; // Bonus for unexplored neighbors
                         
-                        // Bonus for being far from current position (encourage long jumps)
-                        let distance_from_current = ((new_x as i32 - self.x as i32).abs() + (new_y as i32 - self.y as i32).abs()) as i32;
+                        // Bonus for being far from current position (encourage long trips)
+                        let distance_from_current = map.distance(self.x, self.y, new_x, new_y) as i32;
                         score += distance_from_current;
+
+                        // Not back where it just came from
+                        score -= self.trail_penalty(new_x, new_y, self.behavior_config.trail_penalty);
                         
-                        if score > best_score {
-                            best_score = score;
-                            best_position = Some((new_x, new_y));
-                        }
+                        candidates.push((score, (new_x, new_y)));
                     }
                 }
             }
             
             // If we found a great position, break early
-            if best_position.is_some() && best_score > 200 {
+            if candidates.iter().any(|&(score, _)| score > 200) {
                 break;
             }
         }
+        // Best first; among equals, the first one the sweep came across
+        candidates.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
         
-        // Teleport to the best position found
-        if let Some((new_x, new_y)) = best_position {
-            self.x = new_x;
-            self.y = new_y;
-            self.steps_since_last_find = 0;
-            // Small energy cost for teleportation
-            self.energy = self.energy.saturating_sub(3);
-        }
+        // Set off towards the best candidate a path leads to
+        candidates
+            .iter()
+            .take(UNSTUCK_PATH_ATTEMPTS)
+            .any(|&(_, (x, y))| self.set_off_to(x, y, map, other_robots))
+    }
+
+    // Set off along the cheapest way to the nearest cell that is unexplored, as far as the robot
+    // and the station know, found by a flood out from the robot. False when there is none in
+    // reach, or none within FLOOD_NODE_LIMIT cells of the search
+    fn head_for_nearest_unexplored(&mut self, map: &Map, other_robots: &[RobotView]) -> bool {
+        self.path_searches.set(self.path_searches.get() + 1);
+        let blocked = |x, y| !self.is_valid_move(x, y, map, other_robots);
+        let step_cost = |x, y, direction| self.planned_step_cost(map, x, y, direction);
+        let unexplored = |x, y| map.get_cell(x, y).is_some_and(|cell: &Cell| !cell.explored) && !self.known_by_station.contains(&(x, y));
+        let Some(path) = pathfinding::flood(map, (self.x, self.y), &blocked, &step_cost, &unexplored, FLOOD_NODE_LIMIT).path else {
+            return false;
+        };
+        self.set_off_along(path);
+        true
+    }
+
+    // Plan a path to a relocation spot and walk it on the next updates; false when none leads there
+    fn set_off_to(&mut self, x: usize, y: usize, map: &Map, other_robots: &[RobotView]) -> bool {
+        let Some(path) = self.find_path(self.x, self.y, x, y, map, other_robots) else {
+            return false;
+        };
+        self.set_off_along(path);
+        true
+    }
+
+    // Walk a path from the robot's cell to a relocation spot on the next updates
+    fn set_off_along(&mut self, mut path: Vec<(usize, usize)>) {
+        self.relocating_to = path.last().copied();
+        path.reverse();
+        path.pop(); // The robot's own cell
+        self.current_path = path;
+        self.steps_since_last_find = 0;
     }
 
-    // Check if current position has something of interest
-    fn found_something_at_current_position(&self, map: &Map) -> bool {
+    // Check if current position has something of interest (partially harvested deposits included)
+    pub fn found_something_at_current_position(&self, map: &Map) -> bool {
         if let Some(cell) = map.get_cell(self.x, self.y) {
-            match cell.cell_type {
-                CellType::Energy(_) | CellType::Mineral(_) | CellType::SciencePoint => true,
-                _ => false,
-            }
+            matches!(cell.cell_type, CellType::Energy(_) | CellType::Mineral(_) | CellType::SciencePoint(_))
         } else {
             false
         }
     }
 
     // Explorer: prioritizes unexplored areas
-    fn choose_explorer_direction(&self, map: &Map, other_robots: &[Robot]) -> Option<Direction> {
-        let directions = [Direction::North, Direction::East, Direction::South, Direction::West];
+    pub fn choose_explorer_direction(&mut self, map: &Map, other_robots: &[RobotView], tick: u64) -> Option<Direction> {
+        let directions = self.working_directions();
+        // With little left to discover, long-unvisited cells become worth a look again
+        let revisit_tick = (map.exploration_stats().percentage >= NEARLY_EXPLORED_PERCENT).then_some(tick);
         let mut best_direction = None;
         let mut best_score = -1000i32; // Lower threshold to encourage more movement
 
         for direction in directions {
             if let Some((new_x, new_y)) = self.get_next_position(direction, map) {
                 if self.is_valid_move(new_x, new_y, map, other_robots) {
-                    let score = self.calculate_explorer_score(new_x, new_y, map);
+                    let score = self.calculate_explorer_score(new_x, new_y, map, revisit_tick)
+                        - crowding_penalty(map, new_x, new_y, self.z, other_robots, self.behavior_config.crowding_penalty)
+                        - self.trail_penalty(new_x, new_y, self.behavior_config.trail_penalty)
+                        + self.formation_score(new_x, new_y, map);
                     if score > best_score {
                         best_score = score;
                         best_direction = Some(direction);
@@ -302,48 +1390,191 @@ impl Robot {
             }
         }
         
-        // If no good direction found, encourage movement away from station
-        if best_direction.is_none() || best_score < 0 {
-            return self.choose_direction_away_from_explored_areas(map, other_robots);
+        // With nothing worth a step nearby, set off for the nearest unexplored cell, or leave it to
+        // a random move when there is none in reach; a trailing explorer sticks with its formation
+        if best_direction.is_none() || (best_score < 0 && self.buddy.is_none()) {
+            if !self.head_for_nearest_unexplored(map, other_robots) {
+                return None;
+            }
+            let (x, y) = self.current_path.pop()?;
+            if self.current_path.is_empty() {
+                self.relocating_to = None; // One step away
+            }
+            return self.get_direction_to_position(x, y, map);
         }
         
         best_direction
     }
 
     // Energy collector: prioritizes energy sources
-    fn choose_energy_collector_direction(&self, map: &Map, other_robots: &[Robot]) -> Option<Direction> {
+    pub fn choose_energy_collector_direction(&mut self, map: &Map, other_robots: &[RobotView]) -> Option<Direction> {
         self.choose_resource_direction(map, other_robots, |cell_type| {
             matches!(cell_type, CellType::Energy(_))
         })
     }
 
     // Mineral collector: prioritizes mineral sources
-    fn choose_mineral_collector_direction(&self, map: &Map, other_robots: &[Robot]) -> Option<Direction> {
+    pub fn choose_mineral_collector_direction(&mut self, map: &Map, other_robots: &[RobotView]) -> Option<Direction> {
         self.choose_resource_direction(map, other_robots, |cell_type| {
             matches!(cell_type, CellType::Mineral(_))
         })
     }
 
     // Scientist: prioritizes science points
-    fn choose_scientist_direction(&self, map: &Map, other_robots: &[Robot]) -> Option<Direction> {
+    pub fn choose_scientist_direction(&mut self, map: &Map, other_robots: &[RobotView]) -> Option<Direction> {
         self.choose_resource_direction(map, other_robots, |cell_type| {
-            matches!(cell_type, CellType::SciencePoint)
+            matches!(cell_type, CellType::SciencePoint(_))
         })
     }
 
+    // Miner: heads for the nearest known fenced deposit, seeking minerals otherwise
+    pub fn choose_miner_direction(&mut self, map: &Map, other_robots: &[RobotView]) -> Option<Direction> {
+        let target = map
+            .iter_cells()
+            .filter(|(x, y, cell)| {
+                cell.explored
+                    && (*x, *y) != (self.x, self.y)
+                    && self.heuristic(map, self.x, self.y, *x, *y) <= MINER_SEARCH_RADIUS
+                    && map.is_fenced_deposit(*x, *y)
+            })
+            .min_by_key(|(x, y, _)| self.heuristic(map, self.x, self.y, *x, *y))
+            .map(|(x, y, _)| (x, y));
+        let next = target
+            .and_then(|(x, y)| self.find_path(self.x, self.y, x, y, map, other_robots))
+            .and_then(|path| path.get(1).copied());
+        match next {
+            Some((x, y)) => self.get_direction_to_position(x, y, map),
+            None => self.choose_mineral_collector_direction(map, other_robots),
+        }
+    }
+
+    // Carrier: walk up to the nearest loaded collector no other carrier is meeting, then wait next
+    // to it for the simulation to hand its cargo over. With nobody to meet, bring home whatever
+    // is on board and wait at the station
+    fn ferry(&mut self, map: &mut Map, station_x: usize, station_y: usize, other_robots: &[RobotView]) {
+        let claimed: Vec<(usize, usize)> = other_robots
+            .iter()
+            .filter(|robot| robot.robot_type == RobotType::Carrier && robot.state == RobotState::Exploring)
+            .filter_map(|robot| robot.target)
+            .collect();
+        let collector = other_robots
+            .iter()
+            .filter(|robot| {
+                robot.robot_type.is_collector()
+                    && matches!(robot.state, RobotState::Exploring | RobotState::ReturningToStation)
+                    && robot.energy > 0
+                    && robot.z == self.z
+                    && robot.cargo_load >= CARRIER_PICKUP_LOAD
+                    && !claimed.contains(&(robot.x, robot.y))
+            })
+            .min_by_key(|robot| (map.distance(self.x, self.y, robot.x, robot.y), robot.id));
+        let Some(collector) = collector else {
+            self.target_x = None;
+            self.target_y = None;
+            if (self.x, self.y) != (station_x, station_y) {
+                self.state = RobotState::ReturningToStation;
+                self.target_x = Some(station_x);
+                self.target_y = Some(station_y);
+            }
+            return;
+        };
+        let (x, y) = (collector.x, collector.y);
+        self.target_x = Some(x);
+        self.target_y = Some(y);
+        if map.distance(self.x, self.y, x, y) > 1 {
+            // Plan through the collector's own cell; the move itself still avoids robots
+            self.step_towards(map, x, y, &[], other_robots);
+        }
+    }
+
+    // Repairer: walk up to the nearest broken robot, or failing that the nearest worn one, that no
+    // other repairer is seeing to, then wait next to it for the simulation to repair it. With
+    // nothing to repair, head home and wait at the station
+    fn seek_repairs(&mut self, map: &mut Map, station_x: usize, station_y: usize, other_robots: &[RobotView]) {
+        let claimed: Vec<(usize, usize)> = other_robots
+            .iter()
+            .filter(|robot| robot.robot_type == RobotType::Repairer && robot.state == RobotState::Exploring)
+            .filter_map(|robot| robot.target)
+            .collect();
+        let patient = other_robots
+            .iter()
+            .filter(|robot| {
+                (robot.state == RobotState::Broken || robot.integrity < REPAIR_BELOW_INTEGRITY)
+                    && robot.energy > 0
+                    && robot.z == self.z
+                    && !claimed.contains(&(robot.x, robot.y))
+            })
+            .min_by_key(|robot| (robot.state != RobotState::Broken, map.distance(self.x, self.y, robot.x, robot.y), robot.id));
+        let Some(patient) = patient else {
+            self.target_x = None;
+            self.target_y = None;
+            if (self.x, self.y) != (station_x, station_y) {
+                self.state = RobotState::ReturningToStation;
+                self.target_x = Some(station_x);
+                self.target_y = Some(station_y);
+            }
+            return;
+        };
+        let (x, y) = (patient.x, patient.y);
+        self.target_x = Some(x);
+        self.target_y = Some(y);
+        if map.distance(self.x, self.y, x, y) > 1 {
+            // Plan through the patient's own cell; the move itself still avoids robots
+            self.step_towards(map, x, y, &[], other_robots);
+        }
+    }
+
+    // Restore an adjacent robot to full integrity, a broken one going back to work, and add the
+    // cost to the repair bill. Returns the integrity restored
+    pub fn repair(&mut self, other: &mut Robot, map: &Map) -> u32 {
+        if self.heuristic(map, self.x, self.y, other.x, other.y) > 1 {
+            return 0;
+        }
+        let restored = MAX_INTEGRITY.saturating_sub(other.integrity);
+        other.integrity = MAX_INTEGRITY;
+        if other.state == RobotState::Broken {
+            other.state = RobotState::Exploring;
+        }
+        self.repair_bill += restored.div_ceil(REPAIR_POINTS_PER_MINERAL);
+        restored
+    }
+
+    // Wear from the step just taken onto (x, y); breaks the robot down once nothing is left
+    fn wear(&mut self, map: &Map, x: usize, y: usize) {
+        let next_to_radiation = Position::of_cell(x, y)
+            .surroundings(1)
+            .filter_map(|around| map.locate(around))
+            .any(|(x, y)| map.get_cell(x, y).is_some_and(|cell| matches!(cell.cell_type, CellType::Radiation(_))));
+        let mut wear = if next_to_radiation { RADIATION_WEAR } else { 0 };
+        if map.get_cell(x, y).is_some_and(|cell| cell.is_rough()) && self.rng.gen_ratio(1, ROUGH_WEAR_ODDS) {
+            wear += ROUGH_WEAR;
+        }
+        self.integrity = self.integrity.saturating_sub(wear);
+        if self.integrity == 0 {
+            self.state = RobotState::Broken;
+        }
+    }
+
     // Generic resource-seeking behavior
-    fn choose_resource_direction<F>(&self, map: &Map, other_robots: &[Robot], is_target: F) -> Option<Direction>
+    fn choose_resource_direction<F>(&mut self, map: &Map, other_robots: &[RobotView], is_target: F) -> Option<Direction>
     where
         F: Fn(&CellType) -> bool,
     {
-        let directions = [Direction::North, Direction::East, Direction::South, Direction::West];
+        let claimed = claimed_targets(other_robots);
+        if let Some(direction) = self.claimed_target_direction(map, other_robots, &claimed, &is_target) {
+            return Some(direction);
+        }
+
+        let directions = self.working_directions();
         let mut best_direction = None;
         let mut best_score = -1i32;
 
         for direction in directions {
             if let Some((new_x, new_y)) = self.get_next_position(direction, map) {
                 if self.is_valid_move(new_x, new_y, map, other_robots) {
-                    let score = self.calculate_resource_score(new_x, new_y, map, &is_target);
+                    let score = self.calculate_resource_score(new_x, new_y, map, &claimed, &is_target)
+                        - crowding_penalty(map, new_x, new_y, self.z, other_robots, self.behavior_config.crowding_penalty)
+                        - self.trail_penalty(new_x, new_y, self.behavior_config.collector_trail_penalty);
                     if score > best_score {
                         best_score = score;
                         best_direction = Some(direction);
@@ -354,51 +1585,77 @@ impl Robot {
         best_direction
     }
 
+    // Keep or make a claim on a deposit within the scoring radius and head for it. A claim lapses
+    // once the deposit is used up or another robot holds it; the nearest unclaimed deposit in
+    // range, if any, is claimed instead
+    fn claimed_target_direction<F>(&mut self, map: &Map, other_robots: &[RobotView], claimed: &[(usize, usize)], is_target: &F) -> Option<Direction>
+    where
+        F: Fn(&CellType) -> bool,
+    {
+        let claimable = |(x, y): (usize, usize)| {
+            !claimed.contains(&(x, y)) && map.get_cell(x, y).is_some_and(|cell| is_target(&cell.cell_type))
+        };
+        self.current_target = self.current_target.filter(|&target| claimable(target));
+        if self.current_target.is_none() {
+            // Deposits in reach first, then the ones the long-range sensor saw
+            let here = Position::of_cell(self.x, self.y);
+            self.current_target = here
+                .surroundings(CLAIM_RADIUS)
+                .filter_map(|around| map.locate(around))
+                .chain(self.sensed_deposits.iter().copied())
+                .filter(|&target| claimable(target))
+                .min_by_key(|&(x, y)| map.distance(self.x, self.y, x, y));
+        }
+        let (target_x, target_y) = self.current_target?;
+        let path = self.find_path(self.x, self.y, target_x, target_y, map, other_robots)?;
+        let &(next_x, next_y) = path.get(1)?;
+        self.get_direction_to_position(next_x, next_y, map)
+    }
+
     // Calculate score for explorer (prioritizes unexplored areas)
-    fn calculate_explorer_score(&self, x: usize, y: usize, map: &Map) -> i32 {
+    // `revisit_tick` is the current tick when stale explored cells should earn a bonus
+    fn calculate_explorer_score(&self, x: usize, y: usize, map: &Map, revisit_tick: Option<u64>) -> i32 {
+        let weights = &self.behavior_config;
         let mut score = 0;
         
         if let Some(cell) = map.get_cell(x, y) {
-            // Heavily reward unexplored cells
-            if !cell.explored {
-                score += 150; // Increased reward
+            // Heavily reward unexplored cells; those the station already knows of are as good as explored
+            if !cell.explored && !self.known_by_station.contains(&(x, y)) {
+                score += weights.unexplored_bonus;
             } else {
-                score -= 30; // Increased penalty for explored areas
+                score -= weights.explored_penalty;
+                if let (Some(tick), Some(visited)) = (revisit_tick, cell.last_visited) {
+                    if tick.saturating_sub(visited) >= REVISIT_AFTER_TICKS {
+                        score += STALE_CELL_BONUS;
+                    }
+                }
             }
             
-            // Calculate distance from current position (not from start)
-            let distance_from_current = ((x as i32 - self.x as i32).abs() + (y as i32 - self.y as i32).abs()) as i32;
+            // Calculate distance from current position (not from start), around the edge on a torus
+            let distance_from_current = map.distance(self.x, self.y, x, y) as i32;
             
             // Bonus for moving away from current position (encourage exploration)
             score += distance_from_current * 3;
             
-            // Look around for unexplored neighbors in a wider area
-            let mut unexplored_neighbors = 0;
-            for dy in -2..=2 {
-                for dx in -2..=2 {
-                    if dx == 0 && dy == 0 { continue; }
-                    let check_x = (x as i32 + dx).max(0).min(map.width as i32 - 1) as usize;
-                    let check_y = (y as i32 + dy).max(0).min(map.height as i32 - 1) as usize;
-                    
-                    if let Some(neighbor_cell) = map.get_cell(check_x, check_y) {
-                        if !neighbor_cell.explored {
-                            unexplored_neighbors += 1;
-                        }
-                    }
-                }
-            }
-            
             // Reward positions with many unexplored neighbors
-            score += unexplored_neighbors * 15;
+            score += unexplored_around(map, x, y, 2, &self.known_by_station) as i32 * weights.unexplored_neighbor_bonus;
             
-            // Extra bonus for edge positions (likely to lead to new areas)
-            if x == 0 || x == map.width - 1 || y == 0 || y == map.height - 1 {
-                score += 40;
+            // Extra bonus for edge positions (likely to lead to new areas); a torus has no edges
+            if !map.wrap && (x == 0 || x == map.width - 1 || y == 0 || y == map.height - 1) {
+                score += weights.edge_bonus;
             }
             
             // Bonus for corner positions (often unexplored)
-            if (x == 0 || x == map.width - 1) && (y == 0 || y == map.height - 1) {
-                score += 20;
+            if !map.wrap && (x == 0 || x == map.width - 1) && (y == 0 || y == map.height - 1) {
+                score += weights.corner_bonus;
+            }
+
+            // Head for the region the station assigned, so explorers spread out
+            if let (Some(target_x), Some(target_y)) = (self.target_x, self.target_y) {
+                let current_distance = map.distance(self.x, self.y, target_x, target_y);
+                if current_distance > REGION_ARRIVAL_RADIUS && map.distance(x, y, target_x, target_y) < current_distance {
+                    score += weights.region_pull_bonus;
+                }
             }
         }
         
@@ -406,16 +1663,16 @@ impl Robot {
     }
 
     // Calculate score for resource collectors
-    fn calculate_resource_score<F>(&self, x: usize, y: usize, map: &Map, is_target: &F) -> i32
+    fn calculate_resource_score<F>(&mut self, x: usize, y: usize, map: &Map, claimed: &[(usize, usize)], is_target: &F) -> i32
     where
         F: Fn(&CellType) -> bool,
     {
         let mut score = 0;
 
-        // Check current cell
+        // Check current cell; deposits other robots claimed are theirs
         if let Some(cell) = map.get_cell(x, y) {
-            if is_target(&cell.cell_type) {
-                score += 25; // High priority for target resource
+            if is_target(&cell.cell_type) && !claimed.contains(&(x, y)) {
+                score += self.behavior_config.target_bonus;
             }
             
             if !cell.explored {
@@ -424,74 +1681,215 @@ impl Robot {
         }
 
         // Check surrounding cells for target resources (wider radius)
-        for dy in -2..=2 {
-            for dx in -2..=2 {
-                if dx == 0 && dy == 0 { continue; }
-                let check_x = (x as i32 + dx) as usize;
-                let check_y = (y as i32 + dy) as usize;
-                
-                if let Some(cell) = map.get_cell(check_x, check_y) {
-                    if is_target(&cell.cell_type) {
-                        let distance = (dx.abs() + dy.abs()) as i32;
-                        score += 8 - distance; // Closer resources get higher score
-                    }
-                    if !cell.explored {
-                        score += 1; // Small exploration bonus
-                    }
+        let center = Position::of_cell(x, y);
+        for around in center.surroundings(2) {
+            let Some(check) = map.locate(around) else {
+                continue;
+            };
+            if let Some(cell) = map.get_cell(check.0, check.1) {
+                if is_target(&cell.cell_type) && !claimed.contains(&check) {
+                    let distance = center.manhattan(around) as i32;
+                    score += 8 - distance; // Closer resources get higher score
+                }
+                if !cell.explored {
+                    score += 1; // Small exploration bonus
                 }
             }
         }
 
         // Add randomness to prevent clustering
-        let mut rng = rand::thread_rng();
-        score += rng.gen_range(-1..=1);
+        score += self.rng.gen_range(-1..=1);
 
         score
     }
 
+    // Head home, letting explorers grab deposits lying just off the path while energy allows
+    fn return_to_station(&mut self, map: &mut Map, station_x: usize, station_y: usize, other_robots: &[RobotView], tick: u64) {
+        if self.detour.is_none() && self.robot_type == RobotType::Explorer && self.trip_detours < MAX_RETURN_DETOURS {
+            self.detour = self.find_return_detour(map, station_x, station_y);
+            if self.detour.is_some() {
+                self.trip_detours += 1;
+            }
+        }
+        let Some((x, y)) = self.detour else {
+            self.move_towards_station(map, station_x, station_y, other_robots);
+            return;
+        };
+
+        if (self.x, self.y) == (x, y) {
+            // One harvest, then back on the way home
+            self.collect_resource(map);
+            self.explore(map, tick);
+            self.detour = None;
+        } else if !Self::is_detour_resource(map, x, y)
+            || self.estimated_trip_cost(map, self.x, self.y, x, y)
+                + self.estimated_trip_cost(map, x, y, station_x, station_y)
+                + ENERGY_COSTS.return_margin
+                > self.energy
+        {
+            // Taken by someone else, or no longer affordable
+            self.detour = None;
+            self.move_towards_station(map, station_x, station_y, other_robots);
+        } else {
+            self.step_towards(map, x, y, other_robots, other_robots);
+        }
+    }
+
+    // Whether a cell holds a deposit worth a detour
+    fn is_detour_resource(map: &Map, x: usize, y: usize) -> bool {
+        map.get_cell(x, y).is_some_and(|cell| {
+            cell.explored && matches!(cell.cell_type, CellType::Energy(_) | CellType::Mineral(_) | CellType::SciencePoint(_))
+        })
+    }
+
+    // The cheapest known deposit within RETURN_DETOUR_RADIUS of the path home whose detour fits in
+    // the energy left over after the trip and its margin. Cargo deposits are skipped once the
+    // robot carries its cargo capacity
+    fn find_return_detour(&self, map: &Map, station_x: usize, station_y: usize) -> Option<(usize, usize)> {
+        let path = self.find_path(self.x, self.y, station_x, station_y, map, &[])?;
+        let direct_cost = self.path_cost(&path, map);
+        let spare = self.energy.checked_sub(direct_cost + ENERGY_COSTS.return_margin)?;
+        let light = self.cargo_load() < self.spec.cargo_capacity;
+
+        let mut candidates = Vec::new();
+        for &(path_x, path_y) in &path {
+            for dy in -RETURN_DETOUR_RADIUS..=RETURN_DETOUR_RADIUS {
+                for dx in -RETURN_DETOUR_RADIUS..=RETURN_DETOUR_RADIUS {
+                    if dx.abs() + dy.abs() > RETURN_DETOUR_RADIUS {
+                        continue;
+                    }
+                    let Some((x, y)) = map.neighbor(path_x, path_y, dx, dy) else {
+                        continue;
+                    };
+                    let worth_it = Self::is_detour_resource(map, x, y)
+                        && (light || matches!(map.get_cell(x, y).map(|cell| &cell.cell_type), Some(CellType::Energy(_))));
+                    if worth_it && (x, y) != (self.x, self.y) && !candidates.contains(&(x, y)) {
+                        candidates.push((x, y));
+                    }
+                }
+            }
+        }
+
+        candidates
+            .into_iter()
+            .filter_map(|(x, y)| {
+                let detour_cost = self.estimated_trip_cost(map, self.x, self.y, x, y)
+                    + self.estimated_trip_cost(map, x, y, station_x, station_y);
+                let extra = detour_cost.saturating_sub(direct_cost);
+                (extra <= spare).then_some((extra, (x, y)))
+            })
+            .min_by_key(|&(extra, _)| extra)
+            .map(|(_, position)| position)
+    }
+
     // Move towards station using A* pathfinding
-    fn move_towards_station(&mut self, map: &mut Map, station_x: usize, station_y: usize, other_robots: &[Robot]) {
+    fn move_towards_station(&mut self, map: &mut Map, station_x: usize, station_y: usize, other_robots: &[RobotView]) {
         // Check if already at station
         if self.x == station_x && self.y == station_y {
             self.state = RobotState::AtStation;
+            self.trip_detours = 0;
             return;
         }
+        self.step_towards(map, station_x, station_y, other_robots, other_robots);
+    }
 
-        // Use A* pathfinding to find optimal path
-        if let Some(path) = self.find_path(self.x, self.y, station_x, station_y, map, other_robots) {
-            // If path found and has more than one step (current position + next step)
-            if path.len() > 1 {
-                let next_pos = path[1]; // Skip current position (path[0])
-                let direction = self.get_direction_to_position(next_pos.0, next_pos.1);
-                
-                if let Some(dir) = direction {
-                    if self.move_in_direction(dir, map, other_robots) {
-                        return;
-                    }
+    // A* searches this robot has run, for planning and for cost estimates
+    pub fn path_searches(&self) -> u64 {
+        self.path_searches.get()
+    }
+
+    // The next step of the route to (goal_x, goal_y) planned earlier, if it still leads there
+    // and can be taken
+    fn cached_step(&self, map: &Map, goal_x: usize, goal_y: usize, other_robots: &[RobotView]) -> Option<Direction> {
+        if self.current_path.first() != Some(&(goal_x, goal_y)) {
+            return None; // No route, or one to another goal
+        }
+        let &(x, y) = self.current_path.last()?;
+        if !self.is_valid_move(x, y, map, other_robots) {
+            return None;
+        }
+        self.get_direction_to_position(x, y, map)
+    }
+
+    // Take one step towards a goal along an A* path planned around `path_around`. The path is
+    // kept and followed on later calls. When its next step is taken by a robot the robot waits a
+    // tick for it to move on before planning again; any other blocked step is planned around at
+    // once. After REPLAN_LIMIT plans in a row fail to get it moving, it heads for an open cell
+    // nearer the goal first, as it does along a plan cut short by the node budget. The greedy
+    // walk below is the last resort
+    fn step_towards(&mut self, map: &mut Map, goal_x: usize, goal_y: usize, path_around: &[RobotView], other_robots: &[RobotView]) {
+        let goal = (goal_x, goal_y);
+        let (goal_x, goal_y) = match self.waypoint {
+            Some((goal, waypoint)) if goal == (goal_x, goal_y) && waypoint != (self.x, self.y) => waypoint,
+            _ => {
+                self.waypoint = None;
+                (goal_x, goal_y)
+            }
+        };
+        self.heading_to = Some((goal_x, goal_y));
+        if self.current_path.first() == Some(&(goal_x, goal_y)) && self.blocked_steps == 0 {
+            if let Some(&(x, y)) = self.current_path.last() {
+                if self.robot_at(x, y, other_robots) {
+                    self.blocked_steps = 1;
+                    return;
                 }
             }
         }
+        let mut direction = self.cached_step(map, goal_x, goal_y, other_robots);
+        let replanned = direction.is_none();
+        if replanned {
+            // With robots blocking every way round, walk up to them; they may move on, or be swapped with
+            self.current_path = self.plan_path(self.x, self.y, goal_x, goal_y, map, path_around)
+                .or_else(|| self.plan_path(self.x, self.y, goal_x, goal_y, map, &[]))
+                .unwrap_or_default();
+            if let Some(&end) = self.current_path.last().filter(|&&end| end != (goal_x, goal_y) && end != (self.x, self.y)) {
+                // Cut short: walk it to its end as a waypoint, then plan on from there
+                self.waypoint = Some((goal, end));
+            }
+            self.current_path.reverse();
+            self.current_path.pop(); // The robot's own cell
+            direction = self.current_path.last().and_then(|&(x, y)| self.get_direction_to_position(x, y, map));
+        }
+        if let Some(dir) = direction {
+            if self.move_in_direction(dir, map, other_robots) {
+                self.current_path.pop();
+                self.blocked_steps = 0;
+                self.failed_replans = 0;
+                return;
+            }
+        }
+        self.current_path.clear();
+        if replanned {
+            self.failed_replans += 1;
+        }
+        if self.failed_replans >= REPLAN_LIMIT && self.waypoint.is_none() {
+            self.failed_replans = 0;
+            let distance = map.distance(self.x, self.y, goal_x, goal_y);
+            let waypoint = map.find_nearest((self.x, self.y), SearchMode::Reachable, |x, y, _| {
+                map.distance(x, y, goal_x, goal_y) < distance && !self.robot_at(x, y, other_robots)
+            });
+            if let Some(waypoint) = waypoint {
+                self.waypoint = Some(((goal_x, goal_y), waypoint));
+            }
+        }
+
+        // A robot on the next cell of the way counts towards a deadlock
+        let in_the_way = self.next_cell_towards(goal_x, goal_y, map).is_some_and(|(x, y)| self.robot_at(x, y, other_robots));
+        self.blocked_steps = if in_the_way { self.blocked_steps + 1 } else { 0 };
+        self.fallback_steps += 1;
         
         // Fallback to simple directional movement if A* fails
-        let dx = if self.x < station_x { 1 } else if self.x > station_x { -1 } else { 0 };
-        let dy = if self.y < station_y { 1 } else if self.y > station_y { -1 } else { 0 };
-
-        let directions = if dx > 0 && dy > 0 {
-            vec![Direction::East, Direction::South, Direction::North, Direction::West]
-        } else if dx > 0 && dy < 0 {
-            vec![Direction::East, Direction::North, Direction::South, Direction::West]
-        } else if dx < 0 && dy > 0 {
-            vec![Direction::West, Direction::South, Direction::North, Direction::East]
-        } else if dx < 0 && dy < 0 {
-            vec![Direction::West, Direction::North, Direction::South, Direction::East]
-        } else if dx > 0 {
-            vec![Direction::East, Direction::North, Direction::South, Direction::West]
-        } else if dx < 0 {
-            vec![Direction::West, Direction::North, Direction::South, Direction::East]
-        } else if dy > 0 {
-            vec![Direction::South, Direction::East, Direction::West, Direction::North]
-        } else {
-            vec![Direction::North, Direction::East, Direction::West, Direction::South]
+        let dx = if self.x < goal_x { 1 } else if self.x > goal_x { -1 } else { 0 };
+        let dy = if self.y < goal_y { 1 } else if self.y > goal_y { -1 } else { 0 };
+
+        // Towards the goal first, then sideways, and straight away from it last
+        let directions = match (Direction::from_delta(dx, 0), Direction::from_delta(0, dy)) {
+            (Some(horizontal), Some(vertical)) => [horizontal, vertical, vertical.opposite(), horizontal.opposite()],
+            (Some(horizontal), None) => [horizontal, Direction::North, Direction::South, horizontal.opposite()],
+            (None, vertical) => {
+                let vertical = vertical.unwrap_or(Direction::North);
+                [vertical, Direction::East, Direction::West, vertical.opposite()]
+            }
         };
 
         // Try directions in order of preference
@@ -505,149 +1903,343 @@ impl Robot {
         self.move_randomly(map, other_robots);
     }
 
-    // Get next position for a given direction
-    fn get_next_position(&self, direction: Direction, map: &Map) -> Option<(usize, usize)> {
-        match direction {
-            Direction::North => {
-                if self.y == 0 { None } else { Some((self.x, self.y - 1)) }
-            }
-            Direction::East => {
-                if self.x >= map.width - 1 { None } else { Some((self.x + 1, self.y)) }
-            }
-            Direction::South => {
-                if self.y >= map.height - 1 { None } else { Some((self.x, self.y + 1)) }
-            }
-            Direction::West => {
-                if self.x == 0 { None } else { Some((self.x - 1, self.y)) }
-            }
+    // A robot in the way only holds up a walk until the two have had the chance to swap places
+    fn waiting_on_a_robot(&self) -> bool {
+        (1..=DEADLOCK_STEPS).contains(&self.blocked_steps)
+    }
+
+    // The next cell on the way to a goal, other robots left out of account
+    pub fn next_cell_towards(&self, goal_x: usize, goal_y: usize, map: &Map) -> Option<(usize, usize)> {
+        self.find_path(self.x, self.y, goal_x, goal_y, map, &[]).and_then(|path| path.get(1).copied())
+    }
+
+    // Trade places with the robot on the next cell of the way, as one move with each paying for
+    // its step. The simulation calls this for two robots that are each in the other's way
+    pub fn swap_places(&mut self, other: &mut Robot, map: &Map) {
+        ((self.x, self.y), (other.x, other.y)) = ((other.x, other.y), (self.x, self.y));
+        for robot in [self, other] {
+            let cost = map.get_cell(robot.x, robot.y).map_or(robot.move_cost(), |cell| robot.step_cost(cell));
+            robot.spend(cost);
+            robot.lifetime.distance += 1;
+            robot.wear(map, robot.x, robot.y);
+            robot.blocked_steps = 0;
+            robot.current_path.clear();
+            robot.leave_trail();
         }
     }
 
+    // Get next position for a given direction
+    pub fn get_next_position(&self, direction: Direction, map: &Map) -> Option<(usize, usize)> {
+        let (dx, dy) = direction.offset();
+        map.neighbor(self.x, self.y, dx, dy)
+    }
+
     // Check if a move to given coordinates is valid
-    fn is_valid_move(&self, x: usize, y: usize, map: &Map, other_robots: &[Robot]) -> bool {
+    pub fn is_valid_move(&self, x: usize, y: usize, map: &Map, other_robots: &[RobotView]) -> bool {
         if let Some(cell) = map.get_cell(x, y) {
-            // Check for obstacles
-            if matches!(cell.cell_type, CellType::Obstacle) {
+            // Check for obstacles and radiation
+            if cell.cell_type.blocks_movement() {
                 return false;
             }
             
-            // Check for other robots at the same position
-            for robot in other_robots {
-                if robot.x == x && robot.y == y && robot.energy > 0 {
-                    return false;
-                }
-            }
-            
-            true
+            !self.robot_at(x, y, other_robots)
         } else {
             false
         }
     }
 
-    // Move randomly when no better option is available
-    fn move_randomly(&mut self, map: &mut Map, other_robots: &[Robot]) -> bool {
-        let directions = [Direction::North, Direction::East, Direction::South, Direction::West];
-        let mut rng = rand::thread_rng();
-        
-        // Shuffle directions and try them
-        let mut shuffled_directions = directions;
+    // Steps a tick, halved by a slow drive (0 for a robot that then only moves every other tick)
+    pub fn speed(&self) -> u32 {
+        if self.malfunctions.contains(&Malfunction::SlowDrive) { self.spec.speed / 2 } else { self.spec.speed }
+    }
+
+    // Cells sensed around the robot in every direction, halved by sensor damage
+    pub fn sensor_radius(&self) -> usize {
+        if self.malfunctions.contains(&Malfunction::SensorDamage) { self.spec.sensor_radius / 2 } else { self.spec.sensor_radius }
+    }
+
+    // Whether a stuck actuator keeps the robot from picking `direction` itself
+    pub fn is_jammed(&self, direction: Direction) -> bool {
+        self.malfunctions.contains(&Malfunction::StuckActuator(direction))
+    }
+
+    // The directions the robot picks among for its own moves
+    fn working_directions(&self) -> Vec<Direction> {
+        Direction::ALL
+            .into_iter()
+            .filter(|&direction| !self.is_jammed(direction))
+            .collect()
+    }
+
+    // Score of a trailing explorer's step: a bonus inside FORMATION_BAND of its leader, a penalty
+    // growing with the square of the cells outside it either way. 0 for a robot trailing nobody
+    fn formation_score(&self, x: usize, y: usize, map: &Map) -> i32 {
+        let Some((leader_x, leader_y)) = self.buddy else {
+            return 0;
+        };
+        let distance = map.distance(x, y, leader_x, leader_y);
+        let outside = FORMATION_BAND.start().saturating_sub(distance) + distance.saturating_sub(*FORMATION_BAND.end());
+        match outside {
+            0 => self.behavior_config.formation_bonus,
+            _ => -((outside * outside) as i32) * self.behavior_config.formation_penalty,
+        }
+    }
+
+    // Whether a working robot stands at (x, y) on this robot's layer (docked robots, idle ones
+    // included, are inside the station)
+    fn robot_at(&self, x: usize, y: usize, other_robots: &[RobotView]) -> bool {
+        other_robots.iter().any(|robot| {
+            (robot.x, robot.y, robot.z) == (x, y, self.z) && robot.energy > 0 && !matches!(robot.state, RobotState::AtStation | RobotState::Idle)
+        })
+    }
+
+    // A random direction the robot can move in, when no better option is available. With Levy
+    // flights on, one in levy_flight_odds picks commits the next random moves to the same way
+    // until the flight is walked or something is in the way
+    pub fn random_open_direction(&mut self, map: &Map, other_robots: &[RobotView]) -> Option<Direction> {
+        if let Some((direction, steps_left)) = self.momentum.take() {
+            // Called off at anything in the way, its own trail included: that ground is covered
+            let clear = !self.is_jammed(direction)
+                && self.get_next_position(direction, map).is_some_and(|(x, y)| self.is_valid_move(x, y, map, other_robots) && !self.recent_positions.contains(&(x, y)));
+            if clear {
+                self.momentum = (steps_left > 1).then_some((direction, steps_left - 1));
+                return Some(direction);
+            }
+        }
+
+        // Shuffle directions and try them, a jammed one left out
+        let mut shuffled_directions = Direction::ALL;
         for i in 0..shuffled_directions.len() {
-            let j = rng.gen_range(0..shuffled_directions.len());
+            let j = self.rng.gen_range(0..shuffled_directions.len());
             shuffled_directions.swap(i, j);
         }
 
-        for direction in shuffled_directions {
-            if self.move_in_direction(direction, map, other_robots) {
-                return true;
-            }
+        // Cells off the trail first, back along it only when there is no other way
+        let open: Vec<(Direction, (usize, usize))> = shuffled_directions
+            .into_iter()
+            .filter(|&direction| !self.is_jammed(direction))
+            .filter_map(|direction| self.get_next_position(direction, map).map(|next| (direction, next)))
+            .filter(|&(_, (x, y))| self.is_valid_move(x, y, map, other_robots))
+            .collect();
+        let direction = open.iter().find(|(_, next)| !self.recent_positions.contains(next)).or(open.first()).map(|&(direction, _)| direction)?;
+        let odds = self.behavior_config.levy_flight_odds;
+        if odds > 0 && self.rng.gen_range(0..odds) == 0 {
+            self.momentum = Some((direction, self.levy_flight_length() - 1)).filter(|&(_, steps_left)| steps_left > 0);
+        }
+        Some(direction)
+    }
+
+    // Length of a new Levy flight: a uniform draw u in (0, 1] gives shortest / u, so a flight
+    // runs at least n steps with odds shortest / n
+    fn levy_flight_length(&mut self) -> u8 {
+        let draw = self.rng.gen_range(1..=LEVY_DRAW_SCALE);
+        let length = *LEVY_FLIGHT_STEPS.start() as u32 * LEVY_DRAW_SCALE / draw;
+        length.min(*LEVY_FLIGHT_STEPS.end() as u32) as u8
+    }
+
+    // `penalty` when (x, y) is on the robot's breadcrumb trail
+    fn trail_penalty(&self, x: usize, y: usize, penalty: i32) -> i32 {
+        if self.recent_positions.contains(&(x, y)) { penalty } else { 0 }
+    }
+
+    // Drop a breadcrumb on the cell just entered, forgetting the oldest past the trail length
+    fn leave_trail(&mut self) {
+        if self.trail_length == 0 {
+            return;
+        }
+        self.recent_positions.push_back((self.x, self.y));
+        while self.recent_positions.len() > self.trail_length {
+            self.recent_positions.pop_front();
         }
-        false
+    }
+
+    // Move randomly when no better option is available
+    fn move_randomly(&mut self, map: &mut Map, other_robots: &[RobotView]) -> bool {
+        self.random_open_direction(map, other_robots).is_some_and(|direction| self.move_in_direction(direction, map, other_robots))
     }
 
     // Move the robot in a given direction
-    pub fn move_in_direction(&mut self, direction: Direction, map: &Map, other_robots: &[Robot]) -> bool {
-        let (new_x, new_y) = match direction {
-            Direction::North => {
-                if self.y == 0 {
-                    return false;
-                }
-                (self.x, self.y - 1)
-            }
-            Direction::East => {
-                if self.x >= map.width - 1 {
-                    return false;
-                }
-                (self.x + 1, self.y)
-            }
-            Direction::South => {
-                if self.y >= map.height - 1 {
-                    return false;
-                }
-                (self.x, self.y + 1)
-            }
-            Direction::West => {
-                if self.x == 0 {
-                    return false;
-                }
-                (self.x - 1, self.y)
-            }
+    pub fn move_in_direction(&mut self, direction: Direction, map: &Map, other_robots: &[RobotView]) -> bool {
+        // Any other move cuts a Levy flight short
+        if self.momentum.is_some_and(|(flight, _)| flight != direction) {
+            self.momentum = None;
+        }
+        let Some((new_x, new_y)) = self.get_next_position(direction, map) else {
+            return false;
         };
 
         // Check if the new position is valid
         if self.is_valid_move(new_x, new_y, map, other_robots) {
-            // Move the robot and consume energy
+            // Move the robot and consume energy (rough terrain, heavier cargo and a stuck actuator cost more)
+            let mut cost = map.get_cell(new_x, new_y).map_or(self.move_cost(), |cell| self.step_cost(cell));
+            if self.is_jammed(direction) {
+                cost *= STUCK_STEP_COST_FACTOR;
+            }
             self.x = new_x;
             self.y = new_y;
-            self.energy = self.energy.saturating_sub(1);
+            self.spend(cost);
+            self.lifetime.distance += 1;
+            self.wear(map, new_x, new_y);
+            self.leave_trail();
             true
         } else {
             false
         }
     }
 
-    // Collect resources at the current position
+    // Collect what lies underfoot, leaving science points to a scientist that could still carry
+    // them home: it gets more out of them
+    fn harvest_here(&mut self, map: &mut Map, other_robots: &[RobotView]) -> bool {
+        let on_science = map.get_cell(self.x, self.y).is_some_and(|cell| matches!(cell.cell_type, CellType::SciencePoint(_)));
+        let scientist_free = other_robots.iter().any(|robot| {
+            robot.robot_type == RobotType::Scientist && robot.energy > 0 && robot.state != RobotState::Broken && robot.science_room
+        });
+        if on_science && self.robot_type != RobotType::Scientist && scientist_free {
+            return false;
+        }
+        self.collect_resource(map)
+    }
+
+    // Charge the battery from the energy deposit underfoot, as at an expedition's refuel stop
+    fn refuel_here(&mut self, map: &mut Map) -> bool {
+        let on_energy = map.get_cell(self.x, self.y).is_some_and(|cell| matches!(cell.cell_type, CellType::Energy(_)));
+        if !on_energy {
+            return false;
+        }
+        if let Some(CollectedResource::Energy(amount)) = map.collect_resource_boosted(self.x, self.y, self.harvest_boost) {
+            self.energy += amount;
+        }
+        true
+    }
+
+    // Whether a scientist can take on more science before heading home
+    fn has_science_room(&self) -> bool {
+        self.science_points <= self.spec.cargo_capacity
+    }
+
+    // Collect resources at the current position. Energy goes into the hold, as much as it has
+    // room for; the rest stays in the deposit
     pub fn collect_resource(&mut self, map: &mut Map) -> bool {
-        if let Some((resource_type, amount)) = map.collect_resource(self.x, self.y) {
-            match resource_type {
-                CellType::Energy(_) => {
-                    self.energy += amount;
-                }
-                CellType::Mineral(_) => {
-                    self.minerals += amount;
+        let room = self.spec.cargo_capacity.saturating_sub(self.cargo_load());
+        let on_energy = map.get_cell(self.x, self.y).is_some_and(|cell| matches!(cell.cell_type, CellType::Energy(_)));
+        if on_energy && room == 0 {
+            return false;
+        }
+        match map.collect_resource_boosted(self.x, self.y, self.harvest_boost) {
+            Some(CollectedResource::Energy(amount)) => {
+                let kept = amount.min(room);
+                self.energy_cargo += kept;
+                if kept < amount {
+                    map.return_energy(self.x, self.y, amount - kept);
                 }
-                CellType::SciencePoint => {
-                    self.science_points += amount;
+            }
+            Some(CollectedResource::Mineral(amount)) => self.minerals += amount,
+            Some(CollectedResource::Science(value)) => self.science_points += value,
+            None => return false,
+        }
+        self.lifetime.collections += 1;
+        true
+    }
+
+    // Explore every cell within the sensor radius, queueing each for the next report. Returns
+    // whether the robot's own cell could be explored
+    pub fn explore(&mut self, map: &mut Map, tick: u64) -> bool { // Changed to &mut self
+        let radius = self.sensor_radius() as i32;
+        let center = Position::of_cell(self.x, self.y);
+        let mut explored_here = false;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                // Off-map cells are skipped, or wrap around in torus mode
+                let Some((x, y)) = map.locate(center.offset(dx, dy)) else {
+                    continue;
+                };
+                // map.explore_as marks the cell as explored and returns true if the position was valid
+                let first_sighting = map.get_cell(x, y).is_some_and(|cell| !cell.explored);
+                if map.explore_as(x, y, tick, self.id) {
+                    if first_sighting {
+                        self.lifetime.cells_discovered += 1;
+                    }
+                    if let Some(cell_data) = map.get_cell(x, y) {
+                        self.record_exploration((x, y), cell_data.cell_type.clone());
+                    }
+                    explored_here |= (dx, dy) == (0, 0);
                 }
-                _ => return false,
             }
-            true
-        } else {
-            false
         }
+        explored_here
     }
 
-    // Explore the current cell
-    pub fn explore(&mut self, map: &mut Map) -> bool { // Changed to &mut self
-        let (current_x, current_y) = (self.x, self.y);
-        // map.explore marks the cell as explored by the map system
-        // and returns true if the exploration attempt was valid/changed state.
-        if map.explore(current_x, current_y) {
-            // If explored successfully, get the cell's data to add to robot's pending updates.
-            if let Some(cell_data) = map.get_cell(current_x, current_y) {
-                self.pending_exploration_updates.push(((current_x, current_y), cell_data.cell_type.clone()));
+    // Look for deposits up to LONG_RANGE_SCAN_RADIUS away (half that with damaged sensors) that
+    // no rock hides. Unexplored ones are queued for the next report, leaving the cells unexplored,
+    // and every one seen becomes a deposit the robot may claim
+    pub fn long_range_scan(&mut self, map: &Map) {
+        let radius = if self.malfunctions.contains(&Malfunction::SensorDamage) { LONG_RANGE_SCAN_RADIUS / 2 } else { LONG_RANGE_SCAN_RADIUS };
+        let center = Position::of_cell(self.x, self.y);
+        let is_deposit = |cell: &Cell| cell.cell_type.is_deposit();
+        self.sensed_deposits.retain(|&(x, y)| map.get_cell(x, y).is_some_and(is_deposit));
+        for seen in center.surroundings(radius) {
+            let (dx, dy) = (seen.x - center.x, seen.y - center.y);
+            if dx * dx + dy * dy > radius * radius {
+                continue;
             }
-            true
-        } else {
-            false
+            let Some((x, y)) = map.locate(seen) else {
+                continue;
+            };
+            let Some(cell) = map.get_cell(x, y).filter(|&cell| is_deposit(cell)) else {
+                continue;
+            };
+            if !radio::has_line_of_sight(map, (self.x, self.y), (x, y)) {
+                continue;
+            }
+            if !cell.explored {
+                self.record_exploration((x, y), cell.cell_type.clone());
+            }
+            if !self.sensed_deposits.contains(&(x, y)) {
+                self.sensed_deposits.push((x, y));
+            }
+        }
+    }
+
+    // Queue a cell for the next report. Revisiting a pending cell refreshes it in place, and
+    // cells the station already has from this robot, unchanged, are not queued again. With the
+    // queue full the oldest cell without a deposit makes room; a cell without one is dropped
+    // rather than push out a deposit
+    fn record_exploration(&mut self, position: (usize, usize), cell_type: CellType) {
+        if let Some(&index) = self.pending_index.get(&position) {
+            self.pending_exploration_updates[index].1 = cell_type;
+            return;
+        }
+        if self.reported_cells.get(&position) == Some(&cell_type) {
+            return;
+        }
+        if self.pending_exploration_updates.len() >= MAX_PENDING_UPDATES {
+            let evicted = match self.pending_exploration_updates.iter().position(|(_, pending)| !pending.is_deposit()) {
+                Some(index) => index,
+                None if cell_type.is_deposit() => 0,
+                None => return,
+            };
+            self.pending_exploration_updates.remove(evicted);
+            self.pending_index = self.pending_exploration_updates.iter().enumerate().map(|(index, &(position, _))| (position, index)).collect();
         }
+        self.pending_index.insert(position, self.pending_exploration_updates.len());
+        self.pending_exploration_updates.push((position, cell_type));
+    }
+
+    // Extra slack, in percent, on the trip home for a robot whose report queue is filling up:
+    // nothing below URGENT_PENDING_PERCENT of the cap, rising to 100 at the cap
+    fn report_urgency(&self) -> u32 {
+        let filled = self.pending_update_count() * 100 / MAX_PENDING_UPDATES;
+        (filled.saturating_sub(URGENT_PENDING_PERCENT) * 100 / (100 - URGENT_PENDING_PERCENT)) as u32
+    }
+
+    // Number of distinct cells waiting to be reported
+    pub fn pending_update_count(&self) -> usize {
+        self.pending_exploration_updates.len()
     }
 
-    // Method for the robot to unload its collected payload
+    // Method for the robot to unload its collected payload; the battery is left alone
     pub fn unload_payload(&mut self) -> (u32, u32, u32) {
-        let energy_payload = self.energy.saturating_sub(INITIAL_ROBOT_ENERGY);
-        // The robot keeps at least INITIAL_ROBOT_ENERGY if it had more,
-        // or its current energy if it was already below INITIAL_ROBOT_ENERGY.
-        self.energy = self.energy.saturating_sub(energy_payload);
+        let energy_payload = std::mem::take(&mut self.energy_cargo);
 
         let minerals_payload = self.minerals;
         self.minerals = 0;
@@ -655,200 +2247,194 @@ impl Robot {
         let science_payload = self.science_points;
         self.science_points = 0;
 
+        // Counted as delivered; whatever the station turns away comes back through keep_cargo
+        self.lifetime.energy_delivered += energy_payload;
+        self.lifetime.minerals_delivered += minerals_payload;
+        self.lifetime.science_delivered += science_payload;
         (energy_payload, minerals_payload, science_payload)
     }
 
-    // Method for the robot to provide its exploration updates
-    pub fn get_exploration_updates(&mut self) -> RobotExplorationUpdate {
-        std::mem::take(&mut self.pending_exploration_updates)
+    // Take back cargo the station had no room for
+    pub fn keep_cargo(&mut self, energy: u32, minerals: u32, science: u32) {
+        self.energy_cargo += energy;
+        self.minerals += minerals;
+        self.science_points += science;
+        self.lifetime.energy_delivered -= energy;
+        self.lifetime.minerals_delivered -= minerals;
+        self.lifetime.science_delivered -= science;
     }
 
-    // Check if the robot has still energy
-    #[allow(dead_code)]
-    pub fn is_active(&self) -> bool {
-        self.energy > 0
+    // Running totals since the robot was built
+    pub fn lifetime_stats(&self) -> LifetimeStats {
+        self.lifetime
     }
 
-    // Display the robot's statistics
-    #[allow(dead_code)]
-    pub fn display_stats(&self) -> String {
-        format!(
-            "Robot at ({}, {}) | Energy: {} | Minerals: {} | Science Points: {} | Updates: {}",
-            self.x, self.y, self.energy, self.minerals, self.science_points, self.pending_exploration_updates.len()
-        )
+    // Draw energy from the battery for work done, counting it as consumed
+    fn spend(&mut self, amount: u32) {
+        let spent = amount.min(self.energy);
+        self.energy -= spent;
+        self.lifetime.energy_consumed += spent;
     }
 
-    // Add this new method to encourage exploration away from known areas
-    fn choose_direction_away_from_explored_areas(&self, map: &Map, other_robots: &[Robot]) -> Option<Direction> {
-        let directions = [Direction::North, Direction::East, Direction::South, Direction::West];
-        let mut best_direction = None;
-        let mut max_unexplored_potential = 0;
+    // Science points analyzed on site since the last report
+    pub fn take_analyzed_cells(&mut self) -> Vec<(usize, usize)> {
+        std::mem::take(&mut self.analyzed_cells)
+    }
 
-        for direction in directions {
-            if let Some((new_x, new_y)) = self.get_next_position(direction, map) {
-                if self.is_valid_move(new_x, new_y, map, other_robots) {
-                    // Calculate potential for finding unexplored areas in this direction
-                    let potential = self.calculate_unexplored_potential(new_x, new_y, direction, map);
-                    if potential > max_unexplored_potential {
-                        max_unexplored_potential = potential;
-                        best_direction = Some(direction);
-                    }
-                }
-            }
+    // Method for the robot to provide its exploration updates, deposits first so they land even
+    // if only part of a report gets through
+    pub fn get_exploration_updates(&mut self) -> RobotExplorationUpdate {
+        self.pending_index.clear();
+        let (mut updates, rest): (RobotExplorationUpdate, RobotExplorationUpdate) =
+            std::mem::take(&mut self.pending_exploration_updates).into_iter().partition(|(_, cell_type)| cell_type.is_deposit());
+        updates.extend(rest);
+        for (position, cell_type) in &updates {
+            self.reported_cells.insert(*position, cell_type.clone());
         }
-        
-        best_direction
+        updates
     }
 
-    fn calculate_unexplored_potential(&self, x: usize, y: usize, direction: Direction, map: &Map) -> usize {
-        let mut potential = 0;
-        
-        // Look ahead in this direction for unexplored clusters
-        let mut current_x = x;
-        let mut current_y = y;
-        
-        for step in 1..=15 { // Look further ahead
-            if let Some((next_x, next_y)) = self.get_next_position_from(current_x, current_y, direction, map) {
-                current_x = next_x;
-                current_y = next_y;
-                
-                if let Some(cell) = map.get_cell(current_x, current_y) {
-                    if !cell.explored {
-                        // Found an unexplored cell, count surrounding unexplored area
-                        let cluster_size = self.count_unexplored_cluster(current_x, current_y, map);
-                        potential += cluster_size * (16 - step); // Weight by inverse distance
-                        break; // Found a good target
-                    }
-                }
-            } else {
-                // Hit a boundary - might be unexplored area beyond
-                potential += 50;
-                break;
-            }
-        }
-        
-        potential
+    // Check if the robot has still energy
+    #[allow(dead_code)]
+    pub fn is_active(&self) -> bool {
+        self.energy > 0 && self.state != RobotState::Broken
     }
 
-    fn count_unexplored_cluster(&self, center_x: usize, center_y: usize, map: &Map) -> usize {
-        let mut count = 0;
-        
-        // Check a 5x5 area around the center
-        for dy in -2..=2 {
-            for dx in -2..=2 {
-                let check_x = (center_x as i32 + dx).max(0).min(map.width as i32 - 1) as usize;
-                let check_y = (center_y as i32 + dy).max(0).min(map.height as i32 - 1) as usize;
-                
-                if let Some(cell) = map.get_cell(check_x, check_y) {
-                    if !cell.explored && cell.cell_type != CellType::Obstacle {
-                        count += 1;
+    // One line on what the robot is doing and why, e.g. "heading to mineral vein at (42,17),
+    // 9 cells away, 61 energy". Cheap: built from the state and targets the robot already holds
+    pub fn status_line(&self, ctx: &StatusContext) -> String {
+        let energy = format!("{} energy", self.energy);
+        let station = (ctx.station_x, ctx.station_y);
+        if self.energy == 0 && self.state != RobotState::Decommissioned {
+            return format!("out of energy at ({},{})", self.x, self.y);
+        }
+        if let Some(&(x, y, _)) = self.tunnel_route.back() {
+            let layer = if self.z == map::UNDERGROUND { "mining in the tunnels" } else { "heading down a shaft" };
+            return format!("{}, back on the surface at ({},{}) in {} steps, {}", layer, x, y, self.tunnel_route.len(), energy);
+        }
+        match self.state {
+            RobotState::Exploring => match self.expedition.front() {
+                Some(leg) => {
+                    let stop = if leg.refuel { "refuel stop" } else { "expedition target" };
+                    let remaining = self.expedition.len();
+                    let stops = if remaining == 1 { "last stop".to_string() } else { format!("{} stops left", remaining) };
+                    format!("heading to {} at ({},{}), {}, {}, {}", stop, leg.x, leg.y, self.cells_away(ctx.map, (leg.x, leg.y)), stops, energy)
+                }
+                None => match (self.current_task, self.target_x, self.target_y) {
+                    (Some(Task::CollectAt { x, y }), _, _) => format!(
+                        "sent by the station to {} at ({},{}), {}, {}",
+                        describe_cell(ctx.map, x, y),
+                        x,
+                        y,
+                        self.cells_away(ctx.map, (x, y)),
+                        energy
+                    ),
+                    (Some(Task::BuildOutpostAt { x, y }), _, _) => {
+                        format!("sent by the station to found an outpost at ({},{}), {}, {}", x, y, self.cells_away(ctx.map, (x, y)), energy)
+                    }
+                    // Explorers keep their region center as target; the station is left over from the last trip
+                    (_, Some(x), Some(y)) if self.robot_type == RobotType::Explorer && (x, y) != station => {
+                        format!("exploring towards ({},{}), {}, {}", x, y, self.cells_away(ctx.map, (x, y)), energy)
+                    }
+                    (_, Some(x), Some(y)) if self.robot_type == RobotType::Repairer && (x, y) != station => {
+                        format!("on the way to repair a robot at ({},{}), {}, {}", x, y, self.cells_away(ctx.map, (x, y)), energy)
                     }
+                    (_, Some(x), Some(y)) if self.robot_type == RobotType::Carrier && (x, y) != station => {
+                        format!("meeting a collector at ({},{}), {}, {}", x, y, self.cells_away(ctx.map, (x, y)), energy)
+                    }
+                    _ => format!("{} around ({},{}), {}", self.robot_type.activity(), self.x, self.y, energy),
+                },
+            },
+            RobotState::ReturningToStation => {
+                let cargo = match self.cargo_load() {
+                    0 => String::new(),
+                    load => format!(", carrying {}", load),
+                };
+                match self.detour {
+                    Some((x, y)) => format!(
+                        "grabbing {} at ({},{}) on the way home, {}, {}{}",
+                        describe_cell(ctx.map, x, y),
+                        x,
+                        y,
+                        self.cells_away(ctx.map, (x, y)),
+                        energy,
+                        cargo
+                    ),
+                    None => format!("returning to the station, {}, {}{}", self.cells_away(ctx.map, station), energy, cargo),
                 }
             }
-        }
-        
-        count
-    }
-
-    fn get_next_position_from(&self, x: usize, y: usize, direction: Direction, map: &Map) -> Option<(usize, usize)> {
-        match direction {
-            Direction::North => {
-                if y == 0 { None } else { Some((x, y - 1)) }
+            RobotState::AtStation if self.retrofit_ticks > 0 => {
+                format!("being refitted as a {:?}, {} ticks left, {}", self.robot_type, self.retrofit_ticks, energy)
+            }
+            RobotState::AtStation => format!("docked at the station, {}", energy),
+            RobotState::Idle => format!("idle at the station, waiting for a task, {}", energy),
+            RobotState::Broken => format!("broken down at ({},{}), waiting for a repairer, {}", self.x, self.y, energy),
+            RobotState::Decommissioned => "decommissioned, scrapped for minerals".to_string(),
+            RobotState::Stranded => format!("stranded at ({},{}), waiting for help for {} ticks, {}", self.x, self.y, self.stranded_ticks, energy),
+            RobotState::LowPower { ticks_left } => {
+                format!("in low power at ({},{}), powering down for good in {} ticks, {}", self.x, self.y, ticks_left, energy)
+            }
+            RobotState::Rescuing { target, x, y } => {
+                format!("bringing energy to robot #{} at ({},{}), {}, {}", target + 1, x, y, self.cells_away(ctx.map, (x, y)), energy)
             }
-            Direction::East => {
-                if x >= map.width - 1 { None } else { Some((x + 1, y)) }
+            RobotState::Retrieving { x, y } => {
+                format!("fetching the cargo cache at ({},{}), {}, {}", x, y, self.cells_away(ctx.map, (x, y)), energy)
             }
-            Direction::South => {
-                if y >= map.height - 1 { None } else { Some((x, y + 1)) }
+            RobotState::Working { x, y, ticks_left } => {
+                let ticks = if ticks_left == 1 { "1 tick".to_string() } else { format!("{} ticks", ticks_left) };
+                format!("drilling out the rock at ({},{}), {} left, {}", x, y, ticks, energy)
             }
-            Direction::West => {
-                if x == 0 { None } else { Some((x - 1, y)) }
+            RobotState::Analyzing { x, y, ticks_left } => {
+                let ticks = if ticks_left == 1 { "1 tick".to_string() } else { format!("{} ticks", ticks_left) };
+                format!("analyzing the science point at ({},{}), {} left, {}", x, y, ticks, energy)
             }
         }
     }
 
-    // Get direction from current position to target position
-    fn get_direction_to_position(&self, target_x: usize, target_y: usize) -> Option<Direction> {
-        let dx = target_x as i32 - self.x as i32;
-        let dy = target_y as i32 - self.y as i32;
-        
-        match (dx.signum(), dy.signum()) {
-            (1, 0) => Some(Direction::East),
-            (-1, 0) => Some(Direction::West),
-            (0, 1) => Some(Direction::South),
-            (0, -1) => Some(Direction::North),
-            _ => None, // Diagonal or same position
+    // "N cells away" as the crow flies; positions off the map (a stale save) give "distance unknown"
+    fn cells_away(&self, map: &Map, to: (usize, usize)) -> String {
+        let on_map = |x: usize, y: usize| x < map.width && y < map.height;
+        if !on_map(self.x, self.y) || !on_map(to.0, to.1) {
+            return "distance unknown".to_string();
+        }
+        match map.distance(self.x, self.y, to.0, to.1) {
+            0 => "here".to_string(),
+            1 => "1 cell away".to_string(),
+            cells => format!("{} cells away", cells),
         }
     }
 
-    // A* pathfinding implementation
-    fn find_path(&self, start_x: usize, start_y: usize, goal_x: usize, goal_y: usize, map: &Map, other_robots: &[Robot]) -> Option<Vec<(usize, usize)>> {
-        let mut open_set = BinaryHeap::new();
-        let mut came_from = HashMap::new();
-        let mut g_score = HashMap::new();
-        
-        let start_node = PathNode::new(start_x, start_y, 0, self.heuristic(start_x, start_y, goal_x, goal_y));
-        open_set.push(start_node);
-        g_score.insert((start_x, start_y), 0);
-        
-        while let Some(current) = open_set.pop() {
-            // If we reached the goal
-            if current.x == goal_x && current.y == goal_y {
-                return Some(self.reconstruct_path(came_from, (current.x, current.y)));
-            }
-            
-            // Check all neighbors
-            let neighbors = [
-                (current.x.wrapping_sub(1), current.y), // West
-                (current.x + 1, current.y),             // East
-                (current.x, current.y.wrapping_sub(1)), // North
-                (current.x, current.y + 1),             // South
-            ];
-            
-            for (nx, ny) in neighbors {
-                // Skip invalid positions
-                if nx >= map.width || ny >= map.height {
-                    continue;
-                }
-                
-                // Skip obstacles and other robots
-                if !self.is_valid_move(nx, ny, map, other_robots) {
-                    continue;
-                }
-                
-                let tentative_g_score = g_score.get(&(current.x, current.y)).unwrap_or(&u32::MAX) + 1;
-                let current_g_score = g_score.get(&(nx, ny)).unwrap_or(&u32::MAX);
-                
-                if tentative_g_score < *current_g_score {
-                    came_from.insert((nx, ny), (current.x, current.y));
-                    g_score.insert((nx, ny), tentative_g_score);
-                    
-                    let h_cost = self.heuristic(nx, ny, goal_x, goal_y);
-                    let neighbor_node = PathNode::new(nx, ny, tentative_g_score, h_cost);
-                    open_set.push(neighbor_node);
-                }
-            }
-        }
-        
-        None // No path found
+    // Direction of an adjacent target position (None when it is not one step away)
+    pub fn get_direction_to_position(&self, target_x: usize, target_y: usize, map: &Map) -> Option<Direction> {
+        Direction::ALL
+            .into_iter()
+            .find(|&direction| self.get_next_position(direction, map) == Some((target_x, target_y)))
     }
-    
-    // Manhattan distance heuristic
-    fn heuristic(&self, x1: usize, y1: usize, x2: usize, y2: usize) -> u32 {
-        ((x1 as i32 - x2 as i32).abs() + (y1 as i32 - y2 as i32).abs()) as u32
+
+    // A* pathfinding implementation: the whole way to the goal, or none when the search runs out
+    // of its node budget first
+    fn find_path(&self, start_x: usize, start_y: usize, goal_x: usize, goal_y: usize, map: &Map, other_robots: &[RobotView]) -> Option<Vec<(usize, usize)>> {
+        self.plan_path(start_x, start_y, goal_x, goal_y, map, other_robots).filter(|path| path.last() == Some(&(goal_x, goal_y)))
+    }
+
+    // The way to the goal, or past the node budget the way to the cell nearest it the search got to
+    fn plan_path(&self, start_x: usize, start_y: usize, goal_x: usize, goal_y: usize, map: &Map, other_robots: &[RobotView]) -> Option<Vec<(usize, usize)>> {
+        self.path_searches.set(self.path_searches.get() + 1);
+        // Obstacles and other robots are walked around; a stuck actuator is forced at extra cost
+        let blocked = |x, y| !self.is_valid_move(x, y, map, other_robots);
+        let step_cost = |x, y, direction| self.planned_step_cost(map, x, y, direction);
+        let limit = self.behavior_config.path_node_limit;
+        pathfinding::astar(map, (start_x, start_y), (goal_x, goal_y), &blocked, &step_cost, limit).path
+    }
+
+    // What a planned walk reckons stepping onto (x, y) the given way costs
+    fn planned_step_cost(&self, map: &Map, x: usize, y: usize, direction: Direction) -> u32 {
+        pathfinding::terrain_cost(map, x, y) * if self.is_jammed(direction) { STUCK_STEP_COST_FACTOR } else { 1 }
     }
     
-    // Reconstruct path from came_from map
-    fn reconstruct_path(&self, came_from: HashMap<(usize, usize), (usize, usize)>, mut current: (usize, usize)) -> Vec<(usize, usize)> {
-        let mut path = vec![current];
-        
-        while let Some(&parent) = came_from.get(&current) {
-            current = parent;
-            path.push(current);
-        }
-        
-        path.reverse();
-        path
+    // Manhattan distance heuristic
+    fn heuristic(&self, map: &Map, x1: usize, y1: usize, x2: usize, y2: usize) -> u32 {
+        map.distance(x1, y1, x2, y2)
     }
 }
 
@@ -856,6 +2442,23 @@ impl Robot {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_direction_offsets_opposites_and_deltas() {
+        let offsets: Vec<(i32, i32)> = Direction::ALL.iter().map(|direction| direction.offset()).collect();
+        assert_eq!(offsets, vec![(0, -1), (1, 0), (0, 1), (-1, 0)]);
+        for direction in Direction::ALL {
+            let (dx, dy) = direction.offset();
+            assert_eq!(direction.opposite().offset(), (-dx, -dy));
+            assert_eq!(direction.opposite().opposite(), direction);
+            assert_eq!(Direction::from_delta(dx, dy), Some(direction));
+        }
+        // No move, diagonals and longer strides have no direction
+        for (dx, dy) in [(0, 0), (1, 1), (1, -1), (-1, 1), (-1, -1), (2, 0), (0, -2)] {
+            assert_eq!(Direction::from_delta(dx, dy), None);
+        }
+        assert_eq!(Direction::West.to_string(), "west");
+    }
+
     #[test]
     fn test_robot_creation() {
         let robot = Robot::new(5, 10);
@@ -868,6 +2471,51 @@ mod tests {
         assert_eq!(robot.state, RobotState::Exploring);
     }
 
+    #[test]
+    fn test_status_lines() {
+        let (map, _) = Map::from_ascii(".........m\n..........\n..........\n").unwrap();
+        let ctx = StatusContext { map: &map, station_x: 0, station_y: 0 };
+        let mut robot = Robot::new_with_type(3, 2, RobotType::MineralCollector);
+        // Fresh spawn: no target, nothing planned
+        assert_eq!(robot.status_line(&ctx), "looking for minerals around (3,2), 100 energy");
+
+        robot.state = RobotState::ReturningToStation;
+        robot.energy = 61;
+        robot.minerals = 12;
+        assert_eq!(robot.status_line(&ctx), "returning to the station, 5 cells away, 61 energy, carrying 12");
+        robot.detour = Some((9, 0));
+        assert_eq!(robot.status_line(&ctx), "grabbing mineral vein at (9,0) on the way home, 8 cells away, 61 energy, carrying 12");
+
+        robot.state = RobotState::Exploring;
+        robot.expedition.extend([Leg { x: 4, y: 2, refuel: true }, Leg { x: 9, y: 2, refuel: false }]);
+        assert_eq!(robot.status_line(&ctx), "heading to refuel stop at (4,2), 1 cell away, 2 stops left, 61 energy");
+        robot.expedition.pop_front();
+        assert_eq!(robot.status_line(&ctx), "heading to expedition target at (9,2), 6 cells away, last stop, 61 energy");
+
+        robot.state = RobotState::Rescuing { target: 4, x: 3, y: 2 };
+        assert_eq!(robot.status_line(&ctx), "bringing energy to robot #5 at (3,2), here, 61 energy");
+        robot.state = RobotState::Working { x: 3, y: 1, ticks_left: 1 };
+        assert_eq!(robot.status_line(&ctx), "drilling out the rock at (3,1), 1 tick left, 61 energy");
+        robot.state = RobotState::Stranded;
+        robot.stranded_ticks = 30;
+        assert_eq!(robot.status_line(&ctx), "stranded at (3,2), waiting for help for 30 ticks, 61 energy");
+        robot.state = RobotState::LowPower { ticks_left: 12 };
+        assert_eq!(robot.status_line(&ctx), "in low power at (3,2), powering down for good in 12 ticks, 61 energy");
+        robot.state = RobotState::AtStation;
+        assert_eq!(robot.status_line(&ctx), "docked at the station, 61 energy");
+        robot.energy = 0;
+        assert_eq!(robot.status_line(&ctx), "out of energy at (3,2)");
+
+        // An explorer names its region; a stale position from another map does not panic
+        let mut explorer = Robot::new_with_type(40, 40, RobotType::Explorer);
+        (explorer.target_x, explorer.target_y) = (Some(7), Some(1));
+        assert_eq!(explorer.status_line(&ctx), "exploring towards (7,1), distance unknown, 100 energy");
+        explorer.state = RobotState::Retrieving { x: 2, y: 2 };
+        explorer.x = 2;
+        explorer.y = 1;
+        assert_eq!(explorer.status_line(&ctx), "fetching the cargo cache at (2,2), 1 cell away, 100 energy");
+    }
+
     #[test]
     fn test_robot_creation_with_type() {
         let robot = Robot::new_with_type(3, 7, RobotType::EnergyCollector);
@@ -877,36 +2525,278 @@ mod tests {
         assert_eq!(robot.state, RobotState::Exploring);
     }
 
+    #[test]
+    fn test_climbing_a_shaft_changes_layer() {
+        let map = Map::new_two_layer(60, 30, 2);
+        let (shaft_x, shaft_y) = map.underground.as_ref().unwrap().shafts[0];
+        let mut robot = Robot::new_with_type(shaft_x, shaft_y, RobotType::Miner);
+        let other = Robot::new_with_type(shaft_x, shaft_y, RobotType::Explorer);
+        assert!(!robot.is_valid_move(shaft_x, shaft_y, &map, &[other.view()]));
+
+        assert!(robot.climb_shaft(&map));
+        assert_eq!((robot.z, robot.energy), (map::UNDERGROUND, INITIAL_ROBOT_ENERGY - map::SHAFT_CLIMB_COST));
+        // Robots on the other layer are not in the way
+        assert!(robot.is_valid_move(shaft_x, shaft_y, &map, &[other.view()]));
+        assert!(robot.climb_shaft(&map));
+        assert_eq!(robot.z, map::SURFACE);
+
+        robot.x = (0..map.width).find(|&x| !map.is_shaft(x, shaft_y)).unwrap();
+        assert!(!robot.climb_shaft(&map)); // Only at shafts
+    }
+
+    #[test]
+    fn test_mineral_collector_mines_underground_and_climbs_back() {
+        let mut map = Map::new_two_layer(100, 50, 2);
+        let (station_x, station_y) = map.station_site();
+        let shafts = map.underground.as_ref().unwrap().shafts.clone();
+        let ((shaft_x, shaft_y), (deposit_x, deposit_y)) =
+            shafts.into_iter().find_map(|(x, y)| map.nearest_underground_mineral(x, y).map(|deposit| ((x, y), deposit))).unwrap();
+        let deposit = |map: &Map| map.layer_cell(deposit_x, deposit_y, map::UNDERGROUND).unwrap().cell_type.clone();
+        let before = deposit(&map);
+        let mut robot = Robot::new_with_type(shaft_x, shaft_y, RobotType::MineralCollector);
+        robot.energy = 1000;
+
+        assert!(robot.plan_tunnel_trip(&map, station_x, station_y));
+        let mut went_down = false;
+        for tick in 1..500 {
+            robot.autonomous_update(&mut map, station_x, station_y, &[], tick);
+            went_down |= robot.z == map::UNDERGROUND;
+            if went_down && robot.tunnel_route.is_empty() {
+                break;
+            }
+        }
+        assert!(went_down);
+        assert_eq!((robot.x, robot.y, robot.z), (shaft_x, shaft_y, map::SURFACE));
+        assert!(robot.minerals > 0);
+        assert_ne!(deposit(&map), before);
+
+        // Without the battery for the round trip it stays on the surface
+        let mut weak = Robot::new_with_type(shaft_x, shaft_y, RobotType::MineralCollector);
+        weak.energy = 5;
+        assert!(!weak.plan_tunnel_trip(&map, station_x, station_y));
+    }
+
     #[test]
     fn test_should_return_to_station_low_energy() {
-        let mut robot = Robot::new(0, 0);
-        robot.energy = 15; // Below threshold of 20
-        assert!(robot.should_return_to_station());
+        let mut robot = Robot::new(4, 4);
+        robot.energy = 15; // 8 steps home and the reserve make 13, 16 with the slack
+        assert!(robot.should_return_to_station(&Map::new(5, 5, 123), 0, 0));
+    }
+
+    #[test]
+    fn test_fast_robot_covers_twice_the_ground() {
+        let mut map = Map::from_ascii(&format!("{}\n", ".".repeat(50))).unwrap().0;
+        let ground_covered = |speed: u32, map: &mut Map| {
+            let mut robot = Robot::new(45, 0);
+            robot.spec.speed = speed;
+            robot.state = RobotState::ReturningToStation;
+            for tick in 0..20 {
+                robot.autonomous_update(map, 0, 0, &[], tick);
+            }
+            (45 - robot.x, INITIAL_ROBOT_ENERGY - robot.energy)
+        };
+        assert_eq!(ground_covered(1, &mut map), (20, 20));
+        assert_eq!(ground_covered(2, &mut map), (40, 40)); // Every step paid for
+    }
+
+    #[test]
+    fn test_trail_keeps_an_explorer_off_ground_it_just_walked() {
+        let cells_covered = |trail_length: usize| {
+            // A small field already mapped, where nothing draws the explorer one way or another
+            let (mut map, _) = Map::from_ascii(&vec![".".repeat(10); 10].join("\n")).unwrap();
+            for (_, _, cell) in map.iter_cells_mut() {
+                cell.explored = true;
+            }
+            let mut robot = Robot::new_with_type(5, 5, RobotType::Explorer);
+            robot.trail_length = trail_length;
+            robot.spec.speed = 1; // Every cell walked is seen below
+            let mut visited = std::collections::HashSet::new();
+            for tick in 0..100 {
+                // Only the route matters here: no trips home to refuel or report
+                robot.energy = robot.spec.max_energy;
+                robot.get_exploration_updates();
+                robot.autonomous_update(&mut map, 5, 5, &[], tick);
+                visited.insert((robot.x, robot.y));
+                assert!(robot.recent_positions.len() <= trail_length);
+            }
+            visited.len()
+        };
+        let (with_trail, without_trail) = (cells_covered(TRAIL_LENGTH), cells_covered(0));
+        assert!(with_trail > without_trail * 5 / 4, "{} cells with the trail, {} without", with_trail, without_trail);
+    }
+
+    #[test]
+    fn test_malfunctions_slow_blind_and_steer_a_robot() {
+        let (mut map, _) = Map::from_ascii(&vec![".".repeat(20); 20].join("\n")).unwrap();
+        let mut explorer = Robot::new_with_type(10, 10, RobotType::Explorer);
+        explorer.malfunctions = vec![Malfunction::SlowDrive, Malfunction::SensorDamage];
+        assert_eq!((explorer.speed(), explorer.sensor_radius()), (1, 1));
+        explorer.explore(&mut map, 0);
+        assert!(map.get_cell(11, 11).unwrap().explored);
+        assert!(!map.get_cell(12, 10).unwrap().explored);
+
+        // A slowed robot of speed 1 only moves every other tick
+        let mut collector = Robot::new_with_type(10, 10, RobotType::MineralCollector);
+        collector.malfunctions.push(Malfunction::SlowDrive);
+        collector.autonomous_update(&mut map, 0, 0, &[], 1);
+        assert_eq!((collector.x, collector.y), (10, 10));
+
+        // A stuck actuator is never picked, and forcing it costs extra
+        let mut stuck = Robot::new_with_type(10, 10, RobotType::Explorer);
+        stuck.malfunctions.push(Malfunction::StuckActuator(Direction::East));
+        for _ in 0..50 {
+            assert_ne!(stuck.random_open_direction(&map, &[]), Some(Direction::East));
+        }
+        assert_ne!(stuck.choose_explorer_direction(&map, &[], 0), Some(Direction::East));
+        let energy = stuck.energy;
+        assert!(stuck.move_in_direction(Direction::West, &map, &[]));
+        let west_cost = energy - stuck.energy;
+        assert!(stuck.move_in_direction(Direction::East, &map, &[]));
+        assert_eq!(energy - west_cost - stuck.energy, west_cost * STUCK_STEP_COST_FACTOR);
+    }
+
+    #[test]
+    fn test_long_range_scan_reports_deposits_in_view_only() {
+        let (mut map, _) = Map::from_ascii("...#m\n.....\n....m\n").unwrap();
+        let mut robot = Robot::new_with_type(0, 0, RobotType::MineralCollector);
+        robot.autonomous_update(&mut map, 0, 0, &[], LONG_RANGE_SCAN_INTERVAL + 1);
+        assert!(robot.get_exploration_updates().iter().all(|&(position, _)| position != (4, 2)));
+
+        (robot.x, robot.y) = (0, 0);
+        robot.autonomous_update(&mut map, 0, 0, &[], LONG_RANGE_SCAN_INTERVAL);
+        let updates = robot.get_exploration_updates();
+        assert!(updates.iter().any(|(position, cell_type)| *position == (4, 2) && matches!(cell_type, CellType::Mineral(_))));
+        assert!(updates.iter().all(|&(position, _)| position != (4, 0))); // Behind the rock
+        assert!(!map.get_cell(4, 2).unwrap().explored);
+        assert_eq!(robot.sensed_deposits, vec![(4, 2)]);
+        assert_eq!(robot.current_target, Some((4, 2))); // Claimed from afar
+    }
+
+    #[test]
+    fn test_trailing_explorer_keeps_its_distance_from_the_leader() {
+        let farthest_from = |buddy: Option<(usize, usize)>| {
+            let (mut map, _) = Map::from_ascii(&vec![".".repeat(40); 40].join("\n")).unwrap();
+            let leader = Robot::new_with_type(20, 20, RobotType::Explorer);
+            let mut trailer = Robot::new_with_type(21, 20, RobotType::Explorer);
+            trailer.buddy = buddy;
+            let mut distances = Vec::new();
+            for tick in 1..60 {
+                trailer.energy = trailer.spec.max_energy;
+                trailer.get_exploration_updates();
+                trailer.autonomous_update(&mut map, 20, 20, &[leader.view()], tick);
+                distances.push(map.distance(trailer.x, trailer.y, leader.x, leader.y));
+            }
+            distances
+        };
+        let kept = farthest_from(Some((20, 20)));
+        assert!(kept[10..].iter().all(|distance| FORMATION_BAND.contains(distance)), "distances: {:?}", kept);
+        // Alone it wanders off after unexplored ground
+        assert!(farthest_from(None).into_iter().max().unwrap() > *FORMATION_BAND.end());
+    }
+
+    #[test]
+    fn test_lower_report_threshold_sends_explorers_home_sooner() {
+        let ticks_out = |config: BehaviorConfig| {
+            let (mut map, _) = Map::from_ascii(&vec![".".repeat(40); 40].join("\n")).unwrap();
+            let mut robot = Robot::new_with_type(20, 20, RobotType::Explorer);
+            robot.behavior_config = config;
+            (0..100).find(|&tick| {
+                robot.autonomous_update(&mut map, 20, 20, &[], tick);
+                robot.state == RobotState::ReturningToStation
+            })
+        };
+        let eager = ticks_out(BehaviorConfig { explorer_report_updates: 20, ..BehaviorConfig::default() }).unwrap();
+        let usual = ticks_out(BehaviorConfig::default()).unwrap();
+        assert!(eager < usual, "eager explorer turned back at tick {}, the usual one at {}", eager, usual);
+    }
+
+    #[test]
+    fn test_return_budget_scales_with_the_distance_home() {
+        let map = Map::from_ascii(&format!("{}\n", ".".repeat(50))).unwrap().0;
+        let mut near = Robot::new(5, 0);
+        near.energy = 30;
+        assert!(!near.should_return_to_station(&map, 0, 0));
+        let mut far = Robot::new(40, 0);
+        far.energy = 45;
+        assert!(far.should_return_to_station(&map, 0, 0));
+    }
+
+    #[test]
+    fn test_lifetime_wear_raises_the_step_cost_and_the_refuel_price() {
+        let (map, _) = Map::from_ascii(&".".repeat(20)).unwrap();
+        let mut robot = Robot::new_with_type(0, 0, RobotType::MineralCollector);
+        robot.behavior_config = BehaviorConfig { wear_after_steps: 10, wear_after_collections: 3, ..BehaviorConfig::default() };
+        for _ in 0..9 {
+            assert!(robot.move_in_direction(Direction::East, &map, &[]));
+        }
+        assert_eq!((robot.energy, robot.wear_percent(), robot.refuel_price(40)), (INITIAL_ROBOT_ENERGY - 9, 90, 40));
+        assert!(robot.move_in_direction(Direction::East, &map, &[]));
+        assert!(robot.is_worn());
+        assert!(robot.move_in_direction(Direction::East, &map, &[]));
+        assert_eq!(robot.energy, INITIAL_ROBOT_ENERGY - 10 - 2);
+        assert_eq!(robot.refuel_price(40), 50);
+        assert_eq!(robot.refuel_price(41), 41 + 11); // The surcharge rounds up
+
+        // Harvests wear a robot out just the same
+        let (mut map, _) = Map::from_ascii("m").unwrap();
+        let mut robot = Robot::new_with_type(0, 0, RobotType::MineralCollector);
+        robot.behavior_config = BehaviorConfig { wear_after_collections: 3, ..BehaviorConfig::default() };
+        while robot.collect_resource(&mut map) && robot.lifetime.collections < 3 {}
+        robot.minerals = 0; // Unloaded, so only the wear adds to the step
+        assert_eq!((robot.lifetime.collections, robot.move_cost()), (3, 2));
+    }
+
+    #[test]
+    fn test_lifetime_stats_count_steps_discoveries_and_deliveries() {
+        let (mut map, _) = Map::from_ascii("...~.\n").unwrap();
+        let mut robot = Robot::new_with_type(0, 0, RobotType::MineralCollector);
+        robot.explore(&mut map, 0);
+        for _ in 0..3 {
+            assert!(robot.move_in_direction(Direction::East, &map, &[]));
+            robot.explore(&mut map, 0);
+        }
+        robot.explore(&mut map, 0); // Nothing new
+        let stats = robot.lifetime_stats();
+        assert_eq!((stats.distance, stats.cells_discovered), (3, 5));
+        // Two plain steps and one onto rough ground
+        assert_eq!(stats.energy_consumed, 2 + map.get_cell(3, 0).unwrap().movement_cost);
+        assert_eq!(stats.energy_consumed, INITIAL_ROBOT_ENERGY - robot.energy);
+
+        robot.minerals = 20;
+        robot.science_points = 2;
+        robot.unload_payload();
+        robot.keep_cargo(0, 5, 0); // Turned away by a full station
+        let stats = robot.lifetime_stats();
+        assert_eq!((stats.energy_delivered, stats.minerals_delivered, stats.science_delivered), (0, 15, 2));
+        assert_eq!(robot.minerals, 5);
     }
 
     #[test]
     fn test_should_return_to_station_full_cargo() {
         let mut robot = Robot::new_with_type(0, 0, RobotType::EnergyCollector);
-        robot.energy = INITIAL_ROBOT_ENERGY + 80; // Above the threshold (INITIAL_ROBOT_ENERGY + 70)
-        assert!(robot.should_return_to_station());
+        robot.energy_cargo = 69;
+        assert!(!robot.should_return_to_station(&Map::new(5, 5, 123), 0, 0));
+        robot.energy_cargo = 70; // A full hold
+        assert!(robot.should_return_to_station(&Map::new(5, 5, 123), 0, 0));
     }
 
     #[test]
     fn test_unload_payload() {
         let mut robot = Robot::new(0, 0);
-        // Robot starts with INITIAL_ROBOT_ENERGY (100), add extra energy and resources
-        robot.energy = INITIAL_ROBOT_ENERGY + 50; // 150 total
+        robot.energy = INITIAL_ROBOT_ENERGY - 20;
+        robot.energy_cargo = 50;
         robot.minerals = 30;
         robot.science_points = 5;
 
         let (energy, minerals, science) = robot.unload_payload();
         
-        assert_eq!(energy, 50); // Only the extra energy above INITIAL_ROBOT_ENERGY
+        assert_eq!(energy, 50); // The whole energy cargo
         assert_eq!(minerals, 30);
         assert_eq!(science, 5);
         
-        // Robot should keep the initial energy after unloading
-        assert_eq!(robot.energy, INITIAL_ROBOT_ENERGY);
+        // The battery is not part of the payload
+        assert_eq!(robot.energy, INITIAL_ROBOT_ENERGY - 20);
+        assert_eq!(robot.energy_cargo, 0);
         assert_eq!(robot.minerals, 0);
         assert_eq!(robot.science_points, 0);
     }
@@ -915,14 +2805,116 @@ mod tests {
     fn test_get_exploration_updates() {
         let mut robot = Robot::new(0, 0);
         // Add some exploration data
-        robot.pending_exploration_updates.push(((1, 1), CellType::Energy(100)));
-        robot.pending_exploration_updates.push(((2, 2), CellType::Mineral(50)));
+        robot.record_exploration((1, 1), CellType::Energy(100));
+        robot.record_exploration((2, 2), CellType::Mineral(50));
 
         let updates = robot.get_exploration_updates();
         assert_eq!(updates.len(), 2);
         
         // Updates should be cleared after getting them
-        assert!(robot.pending_exploration_updates.is_empty());
+        assert_eq!(robot.pending_update_count(), 0);
+    }
+
+    #[test]
+    fn test_pacing_over_two_cells_queues_two_updates() {
+        let (mut map, _) = Map::from_ascii("....\n").unwrap();
+        let mut robot = Robot::new(1, 0);
+        robot.spec.sensor_radius = 0; // Just the cell underfoot
+        for step in 0..10 {
+            robot.x = 1 + step % 2;
+            robot.explore(&mut map, 0);
+        }
+        assert_eq!(robot.get_exploration_updates(), vec![((1, 0), CellType::Empty), ((2, 0), CellType::Empty)]);
+
+        // Once reported, unchanged cells are not queued again
+        robot.explore(&mut map, 0);
+        assert_eq!(robot.pending_update_count(), 0);
+    }
+
+    #[test]
+    fn test_one_step_explores_the_sensor_square() {
+        let mut map = Map::new(9, 9, 1);
+        let mut collector = Robot::new_with_type(4, 4, RobotType::MineralCollector);
+        assert!(collector.explore(&mut map, 0));
+        assert_eq!(collector.pending_update_count(), 9);
+        let mut explorer = Robot::new_with_type(4, 4, RobotType::Explorer);
+        explorer.explore(&mut map, 0);
+        assert_eq!(explorer.pending_update_count(), 25);
+        let explored = |map: &Map| (0..9).flat_map(|y| (0..9).map(move |x| (x, y))).filter(|&(x, y)| map.get_cell(x, y).unwrap().explored).count();
+        assert_eq!(explored(&map), 25);
+        assert!(!map.get_cell(1, 4).unwrap().explored && map.get_cell(2, 6).unwrap().explored);
+
+        // In a corner, off-map cells are left out
+        let mut map = Map::new(9, 9, 1);
+        let mut explorer = Robot::new_with_type(0, 0, RobotType::Explorer);
+        explorer.explore(&mut map, 0);
+        assert_eq!((explorer.pending_update_count(), explored(&map)), (9, 9));
+    }
+
+    #[test]
+    fn test_changed_cells_are_reported_again() {
+        let (mut map, _) = Map::from_ascii("e.\n").unwrap();
+        let mut robot = Robot::new(0, 0);
+        robot.explore(&mut map, 0);
+        robot.get_exploration_updates();
+
+        map.get_cell_mut(0, 0).unwrap().cell_type = CellType::Energy(10);
+        robot.explore(&mut map, 0);
+        map.get_cell_mut(0, 0).unwrap().cell_type = CellType::Empty;
+        robot.explore(&mut map, 0);
+        // One entry, holding the latest state of the cell
+        assert_eq!(robot.get_exploration_updates(), vec![((0, 0), CellType::Empty)]);
+    }
+
+    #[test]
+    fn test_pending_updates_are_capped_without_losing_deposits() {
+        let mut robot = Robot::new(0, 0);
+        robot.record_exploration((0, 0), CellType::Empty);
+        robot.record_exploration((1, 0), CellType::Mineral(50));
+        for x in 0..500 {
+            robot.record_exploration((x, 1), CellType::Empty);
+        }
+        assert_eq!(robot.pending_update_count(), MAX_PENDING_UPDATES);
+        robot.record_exploration((0, 1), CellType::Energy(10)); // Refreshed in place
+        assert_eq!(robot.pending_update_count(), MAX_PENDING_UPDATES);
+
+        // A queue of deposits turns empty ground away, and a new deposit makes room for itself
+        for x in 0..MAX_PENDING_UPDATES {
+            robot.record_exploration((x, 2), CellType::SciencePoint(1));
+        }
+        robot.record_exploration((0, 3), CellType::Empty);
+        robot.record_exploration((0, 4), CellType::Energy(5));
+        let updates = robot.get_exploration_updates();
+        assert_eq!(updates.len(), MAX_PENDING_UPDATES);
+        assert!(updates.iter().all(|(_, cell_type)| cell_type.is_deposit()));
+        assert!(!updates.contains(&((1, 0), CellType::Mineral(50))));
+        assert_eq!(updates.last(), Some(&((0, 4), CellType::Energy(5))));
+    }
+
+    #[test]
+    fn test_deposits_are_reported_first() {
+        let mut robot = Robot::new(0, 0);
+        robot.record_exploration((0, 0), CellType::Empty);
+        robot.record_exploration((1, 0), CellType::Mineral(50));
+        robot.record_exploration((2, 0), CellType::Obstacle);
+        robot.record_exploration((3, 0), CellType::Energy(10));
+        let order: Vec<_> = robot.get_exploration_updates().into_iter().map(|(position, _)| position.0).collect();
+        assert_eq!(order, vec![1, 3, 0, 2]);
+    }
+
+    #[test]
+    fn test_a_filling_report_queue_brings_a_robot_home_sooner() {
+        let map = Map::from_ascii(&format!("{}\n", ".".repeat(50))).unwrap().0;
+        let mut robot = Robot::new(40, 0);
+        let calm = robot.return_budget(&map, 0, 0);
+        for x in 0..MAX_PENDING_UPDATES * 3 / 4 {
+            robot.record_exploration((x, 1), CellType::Empty);
+        }
+        assert_eq!(robot.return_budget(&map, 0, 0), calm);
+        for x in 0..MAX_PENDING_UPDATES {
+            robot.record_exploration((x, 2), CellType::Empty);
+        }
+        assert!(robot.return_budget(&map, 0, 0) > calm * 3 / 2);
     }
 
     #[test]
@@ -970,11 +2962,613 @@ mod tests {
         assert!(robot.get_next_position(Direction::West, &map).is_none());
     }
 
+    #[test]
+    fn test_moving_west_from_the_edge_wraps_on_a_torus() {
+        let (mut map, _) = Map::from_ascii("....\n....\n").unwrap();
+        map.wrap = true;
+        let mut robot = Robot::new(0, 1);
+        assert!(robot.move_in_direction(Direction::West, &map, &[]));
+        assert_eq!((robot.x, robot.y), (map.width - 1, 1));
+        assert!(robot.move_in_direction(Direction::South, &map, &[]));
+        assert_eq!((robot.x, robot.y), (map.width - 1, 0));
+    }
+
+    #[test]
+    fn test_path_takes_the_shorter_wrapped_route() {
+        let (mut map, _) = Map::from_ascii(&".".repeat(20)).unwrap();
+        let robot = Robot::new(1, 0);
+        assert_eq!(robot.find_path(1, 0, 18, 0, &map, &[]).unwrap().len(), 18);
+
+        map.wrap = true;
+        let path = robot.find_path(1, 0, 18, 0, &map, &[]).unwrap();
+        assert_eq!(path, vec![(1, 0), (0, 0), (19, 0), (18, 0)]);
+        assert_eq!(robot.estimated_return_cost(&map, 18, 0), 3);
+    }
+
+    #[test]
+    fn test_levy_flights_cover_more_ground_than_single_random_steps() {
+        let coverage = |levy_flight_odds: u32| -> usize {
+            // Summed over a spread of spawns: on any one the trail alone often does as well
+            (0..20).map(|i| (5 + i * 2, 50 - i * 2)).map(|(x, y)| {
+                let (mut map, _) = Map::from_ascii(&vec![".".repeat(60); 60].join("\n")).unwrap();
+                let mut robot = Robot::new(x, y);
+                robot.energy = 1000;
+                robot.behavior_config.levy_flight_odds = levy_flight_odds;
+                let mut visited = HashSet::from([(x, y)]);
+                for _ in 0..200 {
+                    robot.move_randomly(&mut map, &[]);
+                    visited.insert((robot.x, robot.y));
+                }
+                visited.len()
+            }).sum()
+        };
+        let (steps, flights) = (coverage(0), coverage(2));
+        assert!(flights > steps, "{} cells with Levy flights, {} without", flights, steps);
+    }
+
+    #[test]
+    fn test_levy_flight_runs_straight_and_stops_at_an_obstacle() {
+        let (map, _) = Map::from_ascii("..........\n.........#\n..........\n").unwrap();
+        let mut robot = Robot::new(5, 1);
+        robot.momentum = Some((Direction::East, 5));
+        assert_eq!(robot.random_open_direction(&map, &[]), Some(Direction::East));
+        assert_eq!(robot.momentum, Some((Direction::East, 4)));
+        robot.x = 8; // Rock dead ahead: the flight is called off
+        assert_ne!(robot.random_open_direction(&map, &[]), Some(Direction::East));
+        assert_eq!(robot.momentum, None);
+    }
+
+    #[test]
+    fn test_move_cost_scales_with_load() {
+        let mut robot = Robot::new(0, 0);
+        assert_eq!(robot.move_cost(), 1);
+
+        robot.minerals = 39;
+        assert_eq!(robot.move_cost(), 1);
+
+        robot.minerals = 40;
+        assert_eq!(robot.move_cost(), 2);
+
+        robot.minerals = 50;
+        robot.science_points = 10;
+        robot.energy_cargo = 25; // Harvested energy counts as load too
+        assert_eq!(robot.cargo_load(), 85);
+        assert_eq!(robot.move_cost(), 3);
+    }
+
+    #[test]
+    fn test_loaded_trip_costs_more_than_empty_trip() {
+        let (map, _) = Map::from_ascii("..........\n").unwrap();
+        let mut empty = Robot::new(0, 0);
+        let mut loaded = Robot::new_with_type(0, 0, RobotType::MineralCollector);
+        loaded.minerals = 80;
+
+        for _ in 0..5 {
+            assert!(empty.move_in_direction(Direction::East, &map, &[]));
+            assert!(loaded.move_in_direction(Direction::East, &map, &[]));
+        }
+
+        assert_eq!(INITIAL_ROBOT_ENERGY - empty.energy, 5);
+        assert_eq!(INITIAL_ROBOT_ENERGY - loaded.energy, 15); // 80 cargo => +2 per move
+    }
+
+    #[test]
+    fn test_return_budget_accounts_for_load() {
+        // 30 cells from the station with 50 energy: affordable empty, not with 40 cargo
+        let (map, _) = Map::from_ascii(&".".repeat(40)).unwrap();
+        let mut robot = Robot::new_with_type(30, 0, RobotType::MineralCollector);
+        robot.energy = 50;
+        robot.minerals = 30; // Below the collector's cargo trigger
+        assert!(!robot.should_return_to_station(&map, 0, 0));
+
+        robot.minerals = 40; // Now 2 energy per move: 60 needed to get home
+        assert!(robot.should_return_to_station(&map, 0, 0));
+    }
+
+    #[test]
+    fn test_return_budget_prevents_stranding() {
+        // Walk a loaded robot away from the station until it decides to turn back,
+        // then check it can still afford the trip home
+        let (map, _) = Map::from_ascii(&".".repeat(60)).unwrap();
+        let mut robot = Robot::new_with_type(0, 0, RobotType::MineralCollector);
+        robot.minerals = 30;
+        robot.science_points = 10; // 40 cargo => 2 energy per move
+        while !robot.should_return_to_station(&map, 0, 0) {
+            assert!(robot.move_in_direction(Direction::East, &map, &[]));
+        }
+        assert!(robot.energy >= robot.estimated_return_cost(&map, 0, 0));
+    }
+
+    #[test]
+    fn test_rough_terrain_costs_more_energy() {
+        let (map, _) = Map::from_ascii(".~^\n").unwrap();
+        let mut robot = Robot::new(0, 0);
+        assert!(robot.move_in_direction(Direction::East, &map, &[]));
+        assert_eq!(robot.energy, INITIAL_ROBOT_ENERGY - 2);
+        assert!(robot.move_in_direction(Direction::East, &map, &[]));
+        assert_eq!(robot.energy, INITIAL_ROBOT_ENERGY - 5);
+    }
+
+    #[test]
+    fn test_path_avoids_rough_terrain() {
+        // Going straight through the rough band costs 12, going around it costs 8
+        let (map, _) = Map::from_ascii(concat!(
+            ".......\n",
+            "..^^^..\n",
+            ".......\n",
+        )).unwrap();
+        let robot = Robot::new(0, 1);
+        let path = robot.find_path(0, 1, 6, 1, &map, &[]).unwrap();
+        assert!(!path.contains(&(3, 1)));
+        assert_eq!(robot.estimated_return_cost(&map, 6, 1), 8);
+    }
+
+    #[test]
+    fn test_steps_next_to_radiation_wear_a_robot_down_until_it_breaks() {
+        let (mut map, _) = Map::from_ascii("rrrrrr\n......\n").unwrap();
+        let mut robot = Robot::new_with_type(0, 1, RobotType::MineralCollector);
+        robot.integrity = 12;
+        assert!(robot.move_in_direction(Direction::East, &map, &[]));
+        assert_eq!((robot.integrity, robot.state), (12 - RADIATION_WEAR, RobotState::Exploring));
+        robot.move_in_direction(Direction::East, &map, &[]);
+        robot.move_in_direction(Direction::East, &map, &[]);
+        assert_eq!((robot.integrity, robot.state), (0, RobotState::Broken));
+
+        // Immobile, though not out of energy
+        let energy = robot.energy;
+        robot.autonomous_update(&mut map, 0, 1, &[], 0);
+        assert_eq!((robot.x, robot.energy, robot.is_active()), (3, energy, false));
+
+        let mut repairer = Robot::new_with_type(5, 1, RobotType::Repairer);
+        assert_eq!(repairer.repair(&mut robot, &map), 0); // Too far
+        repairer.x = 4;
+        assert_eq!(repairer.repair(&mut robot, &map), MAX_INTEGRITY);
+        assert_eq!((robot.integrity, robot.state), (MAX_INTEGRITY, RobotState::Exploring));
+        assert_eq!(repairer.repair_bill, MAX_INTEGRITY / REPAIR_POINTS_PER_MINERAL);
+    }
+
+    #[test]
+    fn test_cargo_changes_hands_only_between_neighbours() {
+        let map = Map::new(10, 10, 1);
+        let mut collector = Robot::new_with_type(4, 4, RobotType::MineralCollector);
+        (collector.energy_cargo, collector.minerals, collector.science_points) = (40, 12, 3);
+        collector.state = RobotState::ReturningToStation;
+        let mut carrier = Robot::new_with_type(6, 4, RobotType::Carrier);
+        let total = |robots: [&Robot; 2]| robots.map(|robot| (robot.energy_cargo, robot.minerals, robot.science_points));
+
+        assert_eq!(collector.transfer_cargo_to(&mut carrier, &map), (0, 0, 0)); // Two cells apart
+        carrier.x = 5;
+        let before = total([&collector, &carrier]);
+        assert_eq!(collector.transfer_cargo_to(&mut carrier, &map), (40, 12, 3));
+        let after = total([&collector, &carrier]);
+        for resource in [|(e, _, _): (u32, u32, u32)| e, |(_, m, _)| m, |(_, _, s)| s] {
+            assert_eq!(resource(before[0]) + resource(before[1]), resource(after[0]) + resource(after[1]));
+        }
+        assert_eq!(collector.cargo_load(), 0);
+        assert_eq!(collector.energy, INITIAL_ROBOT_ENERGY);
+        // Relieved of its cargo, the collector goes back to work for a while
+        assert_eq!((collector.state, collector.carrier_grace), (RobotState::Exploring, CARRIER_GRACE_TICKS));
+        collector.minerals = 50;
+        assert!(!collector.should_return_to_station(&map, 4, 4));
+        collector.carrier_grace = 0;
+        assert!(collector.should_return_to_station(&map, 4, 4));
+    }
+
+    #[test]
+    fn test_walk_follows_a_plan_cut_short_by_the_node_budget_then_plans_on() {
+        let (mut map, _) = Map::from_ascii(&vec![".".repeat(80); 20].join("\n")).unwrap();
+        // A wall in the way, open only at the far end
+        for y in 0..19 {
+            map.get_cell_mut(40, y).unwrap().cell_type = CellType::Obstacle;
+        }
+        let mut robot = Robot::new_with_type(0, 2, RobotType::MineralCollector);
+        robot.behavior_config.path_node_limit = 300;
+        let goal = (60, 2);
+        assert_eq!(robot.find_path(0, 2, goal.0, goal.1, &map, &[]), None); // Too far to plan in full
+        let searches = robot.path_searches();
+
+        robot.step_towards(&mut map, goal.0, goal.1, &[], &[]);
+        let Some((planned_for, end)) = robot.waypoint else {
+            panic!("no waypoint at the end of the plan");
+        };
+        assert_eq!((planned_for, end.0), (goal, 39)); // Up against the wall
+        while (robot.x, robot.y) != end {
+            robot.step_towards(&mut map, goal.0, goal.1, &[], &[]);
+        }
+        assert_eq!((robot.path_searches() - searches, robot.fallback_steps), (1, 0)); // Planned once
+        robot.step_towards(&mut map, goal.0, goal.1, &[], &[]);
+        assert_eq!(robot.path_searches() - searches, 2);
+    }
+
+    #[test]
+    fn test_return_path_is_planned_once() {
+        let (mut map, _) = Map::from_ascii(&format!("{}\n{}\n", ".".repeat(12), ".".repeat(12))).unwrap();
+        let mut robot = Robot::new_with_type(10, 0, RobotType::MineralCollector);
+        robot.state = RobotState::ReturningToStation;
+        for tick in 0..11 {
+            robot.autonomous_update(&mut map, 0, 0, &[], tick);
+        }
+        assert_eq!((robot.x, robot.y, robot.state), (0, 0, RobotState::AtStation)); // Docks the tick after arriving
+        assert_eq!(robot.path_searches(), 1);
+
+        // A robot stepping onto the route makes the next step invalid: wait a tick for it to move
+        // on, then plan again around it
+        let mut robot = Robot::new_with_type(10, 0, RobotType::MineralCollector);
+        robot.state = RobotState::ReturningToStation;
+        robot.autonomous_update(&mut map, 0, 0, &[], 0);
+        assert_eq!(robot.current_path.last(), Some(&(8, 0)));
+        let blocker = Robot::new_with_type(8, 0, RobotType::Explorer);
+        robot.autonomous_update(&mut map, 0, 0, &[blocker.view()], 1);
+        assert_eq!((robot.x, robot.y, robot.path_searches()), (9, 0, 1));
+        robot.autonomous_update(&mut map, 0, 0, &[blocker.view()], 2);
+        assert_eq!((robot.x, robot.y, robot.path_searches()), (9, 1, 2));
+        assert_eq!(robot.current_path.first(), Some(&(0, 0)));
+    }
+
+    #[test]
+    fn test_return_path_is_replanned_around_a_new_obstacle() {
+        // The way home runs along the top row; the wall below it has gaps at both ends
+        let (mut map, _) = Map::from_ascii("..........\n#.######..\n..........\n").unwrap();
+        let mut robot = Robot::new_with_type(9, 0, RobotType::MineralCollector);
+        robot.state = RobotState::ReturningToStation;
+        robot.autonomous_update(&mut map, 0, 0, &[], 0);
+        assert_eq!((robot.x, robot.y), (8, 0));
+
+        // Debris lands on the route: the robot plans the detour through the gap and the bottom row
+        map.get_cell_mut(5, 0).unwrap().cell_type = CellType::Obstacle;
+        let mut tick = 1;
+        while robot.state != RobotState::AtStation && tick < 60 {
+            robot.energy = robot.spec.max_energy; // Only the route matters here
+            robot.autonomous_update(&mut map, 0, 0, &[], tick);
+            tick += 1;
+        }
+        assert_eq!(robot.state, RobotState::AtStation);
+        assert_eq!(robot.fallback_steps, 0);
+    }
+
+    #[test]
+    fn test_return_estimate_uses_terrain() {
+        // Same distance home, but rough ground makes the far robot turn back sooner
+        let (plains, _) = Map::from_ascii(&".".repeat(20)).unwrap();
+        let (rough, _) = Map::from_ascii(&"~".repeat(20)).unwrap();
+        let mut robot = Robot::new(15, 0);
+        robot.energy = 30;
+        assert!(!robot.should_return_to_station(&plains, 0, 0));
+        assert!(robot.should_return_to_station(&rough, 0, 0));
+    }
+
     #[test]
     fn test_heuristic() {
+        let mut map = Map::new(10, 10, 123);
         let robot = Robot::new(0, 0);
-        assert_eq!(robot.heuristic(0, 0, 3, 4), 7); // Manhattan distance
-        assert_eq!(robot.heuristic(1, 1, 1, 1), 0); // Same position
-        assert_eq!(robot.heuristic(5, 2, 1, 6), 8); // |5-1| + |2-6| = 4 + 4 = 8
+        assert_eq!(robot.heuristic(&map, 0, 0, 3, 4), 7); // Manhattan distance
+        assert_eq!(robot.heuristic(&map, 1, 1, 1, 1), 0); // Same position
+        assert_eq!(robot.heuristic(&map, 5, 2, 1, 6), 8); // |5-1| + |2-6| = 4 + 4 = 8
+
+        map.wrap = true;
+        assert_eq!(robot.heuristic(&map, 0, 0, 9, 8), 3); // Around both edges
+    }
+
+    #[test]
+    fn test_explorer_revisits_stale_cells_when_map_is_explored() {
+        let (mut map, _) = Map::from_ascii(".....\n").unwrap();
+        for x in 0..5 {
+            map.explore(x, 0, 500);
+        }
+        map.explore(1, 0, 100);
+        let mut robot = Robot::new_with_type(2, 0, RobotType::Explorer);
+
+        // The cell to the west has gone unvisited for long enough, the one to the east has not
+        let west = robot.calculate_explorer_score(1, 0, &map, Some(500));
+        let east = robot.calculate_explorer_score(3, 0, &map, Some(500));
+        assert_eq!(west - east, STALE_CELL_BONUS);
+        assert!(matches!(robot.choose_explorer_direction(&map, &[], 500), Some(Direction::West)));
+        // Too soon for a revisit
+        assert_eq!(robot.calculate_explorer_score(1, 0, &map, Some(100 + REVISIT_AFTER_TICKS - 1)), east);
+    }
+
+    #[test]
+    fn test_edge_cells_count_each_neighbor_once() {
+        let (mut map, _) = Map::from_ascii(&format!("........m.\n{}", "..........\n".repeat(9))).unwrap();
+        let robot = Robot::new_with_type(1, 1, RobotType::Explorer);
+        // Unexplored corner two steps away: 8 neighbors on the map, plus the edge and corner bonuses.
+        // Clamping them to the border used to count 24
+        assert_eq!(robot.calculate_explorer_score(0, 0, &map, None), 150 + 2 * 3 + 8 * 15 + 40 + 20);
+        assert_eq!(robot.calculate_explorer_score(5, 5, &map, None), 150 + 8 * 3 + 24 * 15);
+
+        // On a torus the neighborhood wraps, and the cell across the edge is one step away
+        map.wrap = true;
+        let robot = Robot::new_with_type(0, 0, RobotType::Explorer);
+        assert_eq!(robot.calculate_explorer_score(9, 0, &map, None), 150 + 3 + 24 * 15);
+        let mut collector = Robot::new_with_type(0, 0, RobotType::MineralCollector);
+        let score = collector.calculate_resource_score(0, 0, &map, &[], &|cell: &CellType| matches!(cell, CellType::Mineral(_)));
+        // Own cell unexplored, the vein two steps west across the edge, 24 unexplored neighbors, +-1 noise
+        assert!((5 + 6 + 24 - 1..=5 + 6 + 24 + 1).contains(&score), "score {}", score);
+    }
+
+    // An explorer heading home along row 3 of an open, fully explored 20x9 map to a station at
+    // (0, 3), with deposits of 10 at the given positions. Returns the map once it is home
+    fn return_past_deposits(deposits: &[(usize, usize)], kind: char) -> (Robot, Map) {
+        let mut rows = vec![vec!['.'; 20]; 9];
+        for &(x, y) in deposits {
+            rows[y][x] = kind;
+        }
+        let text: Vec<String> = rows.iter().map(|row| row.iter().collect()).collect();
+        let (mut map, _) = Map::from_ascii_with_amount(&text.join("\n"), 10).unwrap();
+        map.iter_cells_mut().for_each(|(_, _, cell)| cell.explored = true);
+
+        let mut robot = Robot::new(15, 3);
+        robot.state = RobotState::ReturningToStation;
+        for tick in 0..100 {
+            if robot.state == RobotState::AtStation {
+                break;
+            }
+            robot.autonomous_update(&mut map, 0, 3, &[], tick);
+        }
+        assert_eq!(robot.state, RobotState::AtStation);
+        (robot, map)
+    }
+
+    #[test]
+    fn test_explorer_grabs_deposit_next_to_its_way_home() {
+        let (robot, map) = return_past_deposits(&[(8, 2)], 'm');
+        assert_eq!(robot.minerals, 10);
+        assert_eq!(map.get_cell(8, 2).unwrap().cell_type, CellType::Empty);
+        assert_eq!(robot.trip_detours, 0); // Reset on arrival
+    }
+
+    #[test]
+    fn test_explorer_skips_deposit_far_from_its_way_home() {
+        let (robot, map) = return_past_deposits(&[(8, 8)], 'm');
+        assert_eq!(robot.minerals, 0);
+        assert_eq!(map.get_cell(8, 8).unwrap().cell_type, CellType::Mineral(10));
+    }
+
+    #[test]
+    fn test_return_detours_are_capped_per_trip() {
+        let deposits = [(4, 2), (8, 4), (12, 2)];
+        let (robot, map) = return_past_deposits(&deposits, 'e');
+        let taken = deposits.iter().filter(|&&(x, y)| map.get_cell(x, y).unwrap().cell_type == CellType::Empty).count();
+        assert_eq!(taken, MAX_RETURN_DETOURS as usize);
+        assert!(robot.energy > INITIAL_ROBOT_ENERGY - 20);
+    }
+
+    #[test]
+    fn test_no_detour_without_spare_energy() {
+        let (mut map, _) = Map::from_ascii_with_amount("...............\n.......m.......\n", 10).unwrap();
+        map.iter_cells_mut().for_each(|(_, _, cell)| cell.explored = true);
+        let mut robot = Robot::new(14, 0);
+        robot.energy = 14 + ENERGY_COSTS.return_margin + 1; // Only one step to spare, the detour costs two
+        assert_eq!(robot.find_return_detour(&map, 0, 0), None);
+        robot.energy += 1;
+        assert_eq!(robot.find_return_detour(&map, 0, 0), Some((7, 1)));
+    }
+
+    #[test]
+    fn test_harvested_energy_is_delivered_whatever_the_walk_home_costs() {
+        let (mut map, _) = Map::from_ascii_with_amount(&format!("e{}\n", ".".repeat(30)), 80).unwrap();
+        let mut collector = Robot::new_with_type(0, 0, RobotType::EnergyCollector);
+        collector.spec.cargo_capacity = 100; // Room for the whole deposit
+        while collector.collect_resource(&mut map) {}
+        assert_eq!((collector.energy_cargo, collector.energy), (80, INITIAL_ROBOT_ENERGY));
+
+        for _ in 0..30 {
+            assert!(collector.move_in_direction(Direction::East, &map, &[]));
+        }
+        assert!(collector.energy <= INITIAL_ROBOT_ENERGY - 30);
+        let battery = collector.energy;
+        assert_eq!(collector.unload_payload(), (80, 0, 0));
+        assert_eq!(collector.energy, battery);
+
+        // A hold with less room leaves the rest in the deposit
+        let (mut map, _) = Map::from_ascii_with_amount("e\n", 80).unwrap();
+        let mut collector = Robot::new_with_type(0, 0, RobotType::EnergyCollector);
+        while collector.collect_resource(&mut map) {}
+        assert_eq!(collector.energy_cargo, collector.spec.cargo_capacity);
+        assert_eq!(map.get_cell(0, 0).unwrap().cell_type, CellType::Energy(80 - collector.spec.cargo_capacity));
+    }
+
+    #[test]
+    fn test_scientist_analyzes_a_science_point_for_double_the_science() {
+        let (mut map, _) = Map::from_ascii(".....\n.....\n..s..\n").unwrap();
+        let mut scientist = Robot::new_with_type(2, 2, RobotType::Scientist);
+        scientist.autonomous_update(&mut map, 0, 0, &[], 1);
+        assert_eq!(scientist.state, RobotState::Analyzing { x: 2, y: 2, ticks_left: ANALYSIS_TICKS });
+        for tick in 2..=ANALYSIS_TICKS as u64 + 1 {
+            assert_eq!((scientist.x, scientist.y, scientist.science_points), (2, 2, 0));
+            scientist.autonomous_update(&mut map, 0, 0, &[], tick);
+        }
+        assert_eq!(scientist.state, RobotState::Exploring);
+        assert_eq!(scientist.science_points, ANALYSIS_YIELD_FACTOR * map::ASCII_SCIENCE_AMOUNT);
+        assert_eq!(map.get_cell(2, 2).unwrap().cell_type, CellType::Empty);
+        assert_eq!(scientist.take_analyzed_cells(), vec![(2, 2)]);
+
+        // Other robots leave the point to a scientist with room to spare, and get half as much
+        // out of it without one
+        let (mut map, _) = Map::from_ascii(".....\n.....\n..s..\n").unwrap();
+        let mut collector = Robot::new_with_type(2, 2, RobotType::MineralCollector);
+        collector.autonomous_update(&mut map, 0, 0, &[Robot::new_with_type(4, 0, RobotType::Scientist).view()], 1);
+        assert_eq!(map.get_cell(2, 2).unwrap().cell_type, CellType::SciencePoint(map::ASCII_SCIENCE_AMOUNT));
+        (collector.x, collector.y) = (2, 2);
+        collector.autonomous_update(&mut map, 0, 0, &[], 2);
+        assert_eq!(collector.science_points, map::ASCII_SCIENCE_AMOUNT);
+    }
+
+    #[test]
+    fn test_miner_drills_out_fenced_deposit() {
+        // The deposit at (1, 1) is walled in on its east and south sides
+        let (mut map, _) = Map::from_ascii("..#\n.m#\n.##\n").unwrap();
+        map.explore(1, 1, 0);
+        let mut robot = Robot::new_with_type(1, 1, RobotType::Miner);
+
+        robot.autonomous_update(&mut map, 0, 0, &[], 1);
+        assert_eq!(robot.state, RobotState::Working { x: 2, y: 1, ticks_left: DRILL_TICKS });
+        for tick in 2..=DRILL_TICKS as u64 + 1 {
+            robot.autonomous_update(&mut map, 0, 0, &[], tick);
+        }
+        assert_eq!(robot.state, RobotState::Exploring);
+        assert_eq!(map.get_cell(2, 1).unwrap().cell_type, CellType::Empty);
+        assert_eq!(robot.minerals, MINERALS_PER_CLEARED_OBSTACLE);
+        assert_eq!(robot.energy, INITIAL_ROBOT_ENERGY - DRILL_TICKS * DRILL_ENERGY_PER_TICK);
+        assert!(robot.get_exploration_updates().contains(&((2, 1), CellType::Empty)));
+
+        // One wall left: no longer fenced, so the miner harvests it like any deposit
+        robot.autonomous_update(&mut map, 0, 0, &[], 6);
+        assert_eq!(map.get_cell(2, 2).unwrap().cell_type, CellType::Obstacle);
+        assert_eq!(robot.minerals, MINERALS_PER_CLEARED_OBSTACLE + crate::map::MINERAL_HARVEST_RATE);
+    }
+
+    #[test]
+    fn test_miner_heads_for_fenced_deposit_and_skips_unaffordable_drilling() {
+        let (mut map, _) = Map::from_ascii("......\n....m#\n....##\n").unwrap();
+        map.iter_cells_mut().for_each(|(_, _, cell)| cell.explored = true);
+        let mut robot = Robot::new_with_type(0, 1, RobotType::Miner);
+        for tick in 0..4 {
+            robot.autonomous_update(&mut map, 0, 0, &[], tick);
+        }
+        assert_eq!((robot.x, robot.y), (4, 1));
+        robot.autonomous_update(&mut map, 0, 0, &[], 4);
+        assert!(matches!(robot.state, RobotState::Working { x: 5, y: 1, .. }));
+
+        // Too little energy to drill and still get home
+        let mut tired = Robot::new_with_type(4, 1, RobotType::Miner);
+        tired.energy = DRILL_TICKS * DRILL_ENERGY_PER_TICK + 5 + ENERGY_COSTS.return_margin;
+        assert!(!tired.start_drilling(&map, 0, 0));
+    }
+
+    #[test]
+    fn test_robot_stays_until_deposit_is_depleted() {
+        let (mut map, _) = Map::from_ascii(".....\n.....\n.....\n").unwrap();
+        map.get_cell_mut(2, 1).unwrap().cell_type = CellType::Energy(50);
+        let mut robot = Robot::new_with_type(2, 1, RobotType::EnergyCollector);
+
+        // Two partial harvests leave the robot in place with the deposit still interesting
+        for remaining in [30, 10] {
+            robot.autonomous_update(&mut map, 0, 0, &[], 0);
+            assert_eq!((robot.x, robot.y), (2, 1));
+            assert_eq!(map.get_cell(2, 1).unwrap().cell_type, CellType::Energy(remaining));
+            assert!(robot.found_something_at_current_position(&map));
+        }
+
+        // The final harvest empties the cell and the robot moves on
+        robot.autonomous_update(&mut map, 0, 0, &[], 0);
+        assert_eq!(map.get_cell(2, 1).unwrap().cell_type, CellType::Empty);
+        assert_ne!((robot.x, robot.y), (2, 1));
+        assert_eq!(robot.energy_cargo, 50);
+        assert_eq!(robot.energy, INITIAL_ROBOT_ENERGY - robot.step_cost(map.get_cell(robot.x, robot.y).unwrap()));
+    }
+
+    #[test]
+    fn test_unstuck_offsets_match_fixture() {
+        // Committed offsets (recorded from the original f32 sweep), one line per radius
+        let fixture = include_str!("../fixtures/unstuck_ring_offsets.txt");
+        let mut lines = fixture.lines();
+        for radius in UNSTUCK_MIN_RADIUS..=BehaviorConfig::default().unstuck_radius {
+            let offsets: Vec<String> = (0..UNIT_CIRCLE_16.len())
+                .map(|angle| {
+                    let (dx, dy) = ring_offset(radius, angle);
+                    format!("{},{}", dx, dy)
+                })
+                .collect();
+            assert_eq!(format!("{}: {}", radius, offsets.join(" ")), lines.next().unwrap());
+        }
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn test_unstuck_falls_back_to_nearest_reachable_cell() {
+        // A small pocket in solid rock: every cell the sweep looks at is rock
+        let mut text = String::new();
+        for y in 0..40 {
+            let row: String = (0..40).map(|x| if (19..=21).contains(&x) && y == 20 { '.' } else { '#' }).collect();
+            text.push_str(&row);
+            text.push('\n');
+        }
+        let (mut map, _) = Map::from_ascii(&text).unwrap();
+        map.explore(20, 20, 0);
+        map.explore(21, 20, 0);
+        let mut robot = Robot::new(20, 20);
+        robot.try_unstuck(&map, &[]);
+        assert_eq!(robot.relocating_to, Some((19, 20)));
+        assert_eq!((robot.x, robot.y, robot.energy), (20, 20, INITIAL_ROBOT_ENERGY)); // Not there yet
+
+        // With nothing unexplored in reach the robot stays put
+        let mut robot = Robot::new(21, 20);
+        map.explore(19, 20, 0);
+        robot.try_unstuck(&map, &[]);
+        assert_eq!(robot.relocating_to, None);
+    }
+
+    #[test]
+    fn test_unstuck_robot_walks_around_a_wall() {
+        // Everything west of the wall is explored; the way east is round its southern end
+        let mut text = String::new();
+        for y in 0..12 {
+            let row: String = (0..30).map(|x| if x == 10 && y < 11 { '#' } else { '.' }).collect();
+            text.push_str(&row);
+            text.push('\n');
+        }
+        let (mut map, _) = Map::from_ascii(&text).unwrap();
+        for y in 0..12 {
+            for x in 0..10 {
+                map.explore(x, y, 0);
+            }
+        }
+        let mut robot = Robot::new_with_type(5, 5, RobotType::MineralCollector);
+        robot.try_unstuck(&map, &[]);
+        let (goal_x, goal_y) = robot.relocating_to.unwrap();
+        assert!(goal_x > 10);
+
+        let mut tick = 1;
+        while robot.relocating_to.is_some() {
+            let (x, y, energy) = (robot.x, robot.y, robot.energy);
+            robot.autonomous_update(&mut map, 0, 0, &[], tick);
+            // One step a tick, paid for like any other
+            assert_eq!(map.distance(x, y, robot.x, robot.y), 1);
+            assert_eq!(robot.energy, energy - robot.spec.move_cost);
+            assert_ne!(map.get_cell(robot.x, robot.y).unwrap().cell_type, CellType::Obstacle);
+            tick += 1;
+        }
+        assert_eq!((robot.x, robot.y), (goal_x, goal_y));
+        assert!(tick > 12); // Round the wall, not through it
+    }
+
+    #[test]
+    fn test_explorer_beelines_for_the_one_unexplored_corner() {
+        let (mut map, _) = Map::from_ascii(&vec![".".repeat(40); 20].join("\n")).unwrap();
+        map.iter_cells_mut().for_each(|(x, y, cell)| cell.explored = x < 37 || y < 17);
+        let mut robot = Robot::new_with_type(2, 2, RobotType::Explorer);
+        robot.energy = 1000; // Enough not to turn home, nor a report to make
+        robot.behavior_config.explorer_report_updates = 1000;
+        let corner = (37, 17);
+        for tick in 0..50 {
+            let (distance, walked) = (map.distance(robot.x, robot.y, corner.0, corner.1), robot.lifetime.distance);
+            robot.autonomous_update(&mut map, 2, 2, &[], tick);
+            // Every step straight towards it, from the very first
+            assert!(robot.lifetime.distance > walked);
+            assert_eq!(map.distance(robot.x, robot.y, corner.0, corner.1), distance - (robot.lifetime.distance - walked));
+            if map.get_cell(corner.0, corner.1).unwrap().explored {
+                break;
+            }
+        }
+        assert!(map.get_cell(corner.0, corner.1).unwrap().explored);
+    }
+
+    #[test]
+    fn test_unstuck_decisions_match_fixture() {
+        // Relocation targets of the ring sweep recorded on a fixed map; a change here means
+        // decisions drifted. Explorers flood for unexplored ground instead. Every start is open
+        // ground: no path is planned out of rock
+        let map = Map::new(80, 40, 12);
+        let jumps: Vec<(usize, usize)> = [(40, 20), (5, 4), (75, 35)]
+            .iter()
+            .map(|&(x, y)| {
+                let mut robot = Robot::new_with_type(x, y, RobotType::MineralCollector);
+                robot.try_unstuck(&map, &[]);
+                robot.relocating_to.unwrap()
+            })
+            .collect();
+        // Near a corner the ring points past the edge are skipped, not piled up on (0, 0)
+        assert_eq!(jumps, vec![(47, 23), (5, 12), (68, 32)]);
     }
 }
@@ -0,0 +1,367 @@
+use serde::Deserialize;
+use std::fmt;
+
+use crate::map::{Cell, CellType, Map, MapConfig, Terrain, ASCII_RADIATION_TICKS, ASCII_RESOURCE_AMOUNT, ASCII_SCIENCE_AMOUNT};
+use crate::robot::{Robot, RobotType};
+use crate::simulation::Simulation;
+use crate::station::Station;
+
+// Smallest world a scenario may describe
+const MIN_SCENARIO_SIZE: usize = 5;
+
+// A reproducible starting point: the world, the station and the initial robot roster.
+// Without a seed the map starts as open plains, shaped only by the cell overrides
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: String,
+    pub width: usize,
+    pub height: usize,
+    pub seed: Option<u32>,
+    pub terrain: Option<String>, // Kind of generated terrain, open unless given
+    pub station: StationSetup,
+    #[serde(default, rename = "cell")]
+    pub cells: Vec<CellOverride>,
+    #[serde(default, rename = "robot")]
+    pub robots: Vec<RobotSetup>,
+}
+
+// Where the station stands and what it starts with; missing amounts keep the usual defaults
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StationSetup {
+    pub x: usize,
+    pub y: usize,
+    pub energy: Option<u32>,
+    pub refined_minerals: Option<u32>,
+    pub raw_minerals: Option<u32>,
+    pub science_points: Option<u32>,
+}
+
+// Cells set by hand after generation: the one at (x, y), or the rectangle it is the corner of
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CellOverride {
+    pub x: usize,
+    pub y: usize,
+    #[serde(default = "one")]
+    pub width: usize,
+    #[serde(default = "one")]
+    pub height: usize,
+    pub kind: CellKind,
+    pub amount: Option<u32>, // Deposit size (science value for science points, ticks for radiation)
+}
+
+fn one() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CellKind {
+    Empty,
+    Rock,
+    Energy,
+    Mineral,
+    Science,
+    Radiation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RobotSetup {
+    #[serde(rename = "type")]
+    pub robot_type: RobotKind,
+    pub x: usize,
+    pub y: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RobotKind {
+    Explorer,
+    EnergyCollector,
+    MineralCollector,
+    Scientist,
+    Miner,
+    Carrier,
+    Repairer,
+}
+
+impl From<RobotKind> for RobotType {
+    fn from(kind: RobotKind) -> Self {
+        match kind {
+            RobotKind::Explorer => RobotType::Explorer,
+            RobotKind::EnergyCollector => RobotType::EnergyCollector,
+            RobotKind::MineralCollector => RobotType::MineralCollector,
+            RobotKind::Scientist => RobotType::Scientist,
+            RobotKind::Miner => RobotType::Miner,
+            RobotKind::Carrier => RobotType::Carrier,
+            RobotKind::Repairer => RobotType::Repairer,
+        }
+    }
+}
+
+// Errors raised while loading or starting a scenario
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScenarioError {
+    Read { path: String, reason: String },
+    Parse(String),
+    Invalid(Vec<String>), // Every problem found, so the file can be fixed in one go
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Read { path, reason } => write!(f, "could not read scenario '{}': {}", path, reason),
+            ScenarioError::Parse(message) => write!(f, "could not parse scenario: {}", message),
+            ScenarioError::Invalid(problems) => {
+                write!(f, "invalid scenario:")?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+impl Scenario {
+    pub fn parse(text: &str) -> Result<Self, ScenarioError> {
+        toml::from_str(text).map_err(|err| ScenarioError::Parse(err.to_string()))
+    }
+
+    pub fn load(path: &str) -> Result<Self, ScenarioError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| ScenarioError::Read { path: path.to_string(), reason: err.to_string() })?;
+        Self::parse(&text)
+    }
+
+    // The scenario's starting world, after checking that everything in it fits on the map
+    pub fn build_simulation(&self) -> Result<Simulation, ScenarioError> {
+        let map = self.build_map()?;
+        let problems = self.placement_problems(&map);
+        if !problems.is_empty() {
+            return Err(ScenarioError::Invalid(problems));
+        }
+
+        let setup = &self.station;
+        let mut station = Station::new(setup.x, setup.y);
+        station.energy = setup.energy.unwrap_or(station.energy);
+        station.refined_minerals = setup.refined_minerals.unwrap_or(station.refined_minerals);
+        station.raw_minerals = setup.raw_minerals.unwrap_or(station.raw_minerals);
+        station.science_points = setup.science_points.unwrap_or(station.science_points);
+        for robot in &self.robots {
+            station.add_robot(Robot::new_with_type(robot.x, robot.y, robot.robot_type.into()));
+        }
+        for index in 0..station.robots.len() {
+            station.assign_explorer_region(index, &map, 0);
+        }
+        Ok(Simulation::new(map, station))
+    }
+
+    // The generated or blank map with the cell overrides applied
+    fn build_map(&self) -> Result<Map, ScenarioError> {
+        let mut problems = Vec::new();
+        if self.width < MIN_SCENARIO_SIZE || self.height < MIN_SCENARIO_SIZE {
+            problems.push(format!(
+                "the map must be at least {}x{}, not {}x{}",
+                MIN_SCENARIO_SIZE, MIN_SCENARIO_SIZE, self.width, self.height
+            ));
+            return Err(ScenarioError::Invalid(problems));
+        }
+        let terrain = match self.terrain.as_deref().map(Terrain::parse).transpose() {
+            Ok(terrain) => terrain,
+            Err(err) => {
+                problems.push(err);
+                None
+            }
+        };
+        if terrain.is_some() && self.seed.is_none() {
+            problems.push("terrain only applies to generated maps: give a seed too".to_string());
+        }
+        for (i, cell) in self.cells.iter().enumerate() {
+            let (right, bottom) = (cell.x + cell.width, cell.y + cell.height);
+            if cell.width == 0 || cell.height == 0 || right > self.width || bottom > self.height {
+                problems.push(format!(
+                    "cell override {} covering ({}, {}) to ({}, {}) does not fit the {}x{} map",
+                    i + 1, cell.x, cell.y, right.saturating_sub(1), bottom.saturating_sub(1), self.width, self.height
+                ));
+            }
+            match (cell.kind, cell.amount) {
+                (CellKind::Empty | CellKind::Rock, Some(_)) => {
+                    problems.push(format!("cell override {}: only deposits take an amount", i + 1));
+                }
+                (_, Some(0)) => problems.push(format!("cell override {}: the amount must be positive", i + 1)),
+                _ => {}
+            }
+        }
+        if !problems.is_empty() {
+            return Err(ScenarioError::Invalid(problems));
+        }
+
+        let mut map = match self.seed {
+            Some(seed) => {
+                let config = MapConfig { terrain: terrain.unwrap_or_default(), ..MapConfig::default() };
+                Map::with_config(self.width, self.height, seed, &config)
+            }
+            None => Map::from_cells(self.width, self.height, vec![Cell::new(CellType::Empty); self.width * self.height], 0),
+        };
+        for cell in &self.cells {
+            let cell_type = match cell.kind {
+                CellKind::Empty => CellType::Empty,
+                CellKind::Rock => CellType::Obstacle,
+                CellKind::Energy => CellType::Energy(cell.amount.unwrap_or(ASCII_RESOURCE_AMOUNT)),
+                CellKind::Mineral => CellType::Mineral(cell.amount.unwrap_or(ASCII_RESOURCE_AMOUNT)),
+                CellKind::Science => CellType::SciencePoint(cell.amount.unwrap_or(ASCII_SCIENCE_AMOUNT)),
+                CellKind::Radiation => CellType::Radiation(cell.amount.unwrap_or(ASCII_RADIATION_TICKS)),
+            };
+            for y in cell.y..cell.y + cell.height {
+                for x in cell.x..cell.x + cell.width {
+                    if let Some(target) = map.get_cell_mut(x, y) {
+                        *target = Cell::new(cell_type.clone());
+                    }
+                }
+            }
+        }
+        Ok(map)
+    }
+
+    // Station and robots placed outside the map, on rock or on top of each other
+    fn placement_problems(&self, map: &Map) -> Vec<String> {
+        let mut problems = Vec::new();
+        let on_rock = |x: usize, y: usize| map.get_cell(x, y).is_some_and(|cell| cell.cell_type.blocks_movement());
+        let station = (self.station.x, self.station.y);
+        if !map.is_valid_position(station.0, station.1) {
+            problems.push(format!("the station at {:?} is outside the {}x{} map", station, map.width, map.height));
+        } else if on_rock(station.0, station.1) {
+            problems.push(format!("the station at {:?} stands on an obstacle", station));
+        }
+        for (i, robot) in self.robots.iter().enumerate() {
+            let position = (robot.x, robot.y);
+            let name = format!("robot {} ({:?}) at {:?}", i + 1, RobotType::from(robot.robot_type), position);
+            if !map.is_valid_position(robot.x, robot.y) {
+                problems.push(format!("{} is outside the {}x{} map", name, map.width, map.height));
+            } else if on_rock(robot.x, robot.y) {
+                problems.push(format!("{} stands on an obstacle", name));
+            } else if position == station {
+                problems.push(format!("{} stands on the station", name));
+            } else if let Some(other) = self.robots[..i].iter().position(|other| (other.x, other.y) == position) {
+                problems.push(format!("{} shares its cell with robot {}", name, other + 1));
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::ActivityTally;
+
+    const CROSSROADS: &str = include_str!("../scenarios/crossroads.toml");
+    const LEAN_START: &str = include_str!("../scenarios/lean_start.toml");
+
+    // Why a scenario cannot start
+    fn build_error(text: &str) -> ScenarioError {
+        match Scenario::parse(text).unwrap().build_simulation() {
+            Ok(_) => panic!("the scenario was expected to be rejected"),
+            Err(err) => err,
+        }
+    }
+
+    // Run a bundled scenario headless and return the finished world
+    fn run(text: &str, ticks: u32) -> Simulation {
+        let mut simulation = Scenario::parse(text).unwrap().build_simulation().unwrap();
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+        simulation.run_ticks(ticks);
+        simulation
+    }
+
+    #[test]
+    fn test_crossroads_scenario() {
+        let scenario = Scenario::parse(CROSSROADS).unwrap();
+        let simulation = scenario.build_simulation().unwrap();
+        let station = &simulation.station();
+        assert_eq!((station.x, station.y), (20, 10));
+        assert_eq!((station.energy, station.refined_minerals), (1000, 0));
+        let roster: Vec<_> = station.robots.iter().map(|robot| (robot.robot_type, robot.x, robot.y)).collect();
+        assert_eq!(roster, vec![
+            (RobotType::Explorer, 19, 10),
+            (RobotType::Explorer, 21, 10),
+            (RobotType::EnergyCollector, 20, 9),
+            (RobotType::MineralCollector, 20, 11),
+        ]);
+        // The walls from the overrides are in place around the open plains
+        assert_eq!(simulation.map.get_cell(10, 3).unwrap().cell_type, CellType::Obstacle);
+        assert_eq!(simulation.map.get_cell(0, 0).unwrap().cell_type, CellType::Empty);
+        assert_eq!(simulation.map.get_cell(2, 2).unwrap().cell_type, CellType::Energy(120));
+
+        let simulation = run(CROSSROADS, 300);
+        let tally = simulation.events.subscriber::<ActivityTally>().unwrap();
+        assert!(tally.deliveries > 0);
+        assert!(simulation.map.exploration_stats().percentage > 50.0);
+    }
+
+    #[test]
+    fn test_lean_start_scenario() {
+        let simulation = Scenario::parse(LEAN_START).unwrap().build_simulation().unwrap();
+        assert_eq!(simulation.map.seed, 42);
+        assert_eq!(simulation.station().robots.len(), 2);
+        assert_eq!(simulation.station().energy, 150);
+
+        // The same file replays identically
+        let positions = |simulation: &Simulation| simulation.station().robots.iter().map(|r| (r.x, r.y, r.energy)).collect::<Vec<_>>();
+        let (first, second) = (run(LEAN_START, 200), run(LEAN_START, 200));
+        assert_eq!(positions(&first), positions(&second));
+        assert!(first.map.exploration_stats().explored_cells > 0);
+    }
+
+    #[test]
+    fn test_misplaced_station_and_robots_are_all_reported() {
+        let text = "width = 10\nheight = 8\n\
+            [station]\nx = 12\ny = 3\n\
+            [[cell]]\nx = 4\ny = 4\nkind = \"rock\"\n\
+            [[robot]]\ntype = \"explorer\"\nx = 4\ny = 4\n\
+            [[robot]]\ntype = \"scientist\"\nx = 1\ny = 1\n\
+            [[robot]]\ntype = \"miner\"\nx = 1\ny = 1\n\
+            [[robot]]\ntype = \"miner\"\nx = 3\ny = 9\n";
+        let err = build_error(text);
+        assert_eq!(err, ScenarioError::Invalid(vec![
+            "the station at (12, 3) is outside the 10x8 map".to_string(),
+            "robot 1 (Explorer) at (4, 4) stands on an obstacle".to_string(),
+            "robot 3 (Miner) at (1, 1) shares its cell with robot 2".to_string(),
+            "robot 4 (Miner) at (3, 9) is outside the 10x8 map".to_string(),
+        ]));
+        assert!(err.to_string().starts_with("invalid scenario:\n  - the station at (12, 3)"));
+    }
+
+    #[test]
+    fn test_map_problems_are_reported() {
+        assert_eq!(
+            build_error("width = 3\nheight = 20\n[station]\nx = 1\ny = 1\n"),
+            ScenarioError::Invalid(vec!["the map must be at least 5x5, not 3x20".to_string()])
+        );
+        assert_eq!(
+            build_error("width = 10\nheight = 10\nterrain = \"caves\"\n[station]\nx = 1\ny = 1\n\
+                [[cell]]\nx = 8\ny = 0\nwidth = 3\nkind = \"energy\"\n\
+                [[cell]]\nx = 0\ny = 0\nkind = \"rock\"\namount = 5\n"),
+            ScenarioError::Invalid(vec![
+                "terrain only applies to generated maps: give a seed too".to_string(),
+                "cell override 1 covering (8, 0) to (10, 0) does not fit the 10x10 map".to_string(),
+                "cell override 2: only deposits take an amount".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_unknown_fields_and_robot_types_are_parse_errors() {
+        let err = Scenario::parse("width = 10\nheight = 10\n[station]\nx = 1\ny = 1\nfuel = 3\n").unwrap_err();
+        assert!(matches!(err, ScenarioError::Parse(message) if message.contains("fuel")));
+        let err = Scenario::parse("width = 10\nheight = 10\n[station]\nx = 1\ny = 1\n[[robot]]\ntype = \"pilot\"\nx = 2\ny = 2\n").unwrap_err();
+        assert!(matches!(err, ScenarioError::Parse(message) if message.contains("pilot")));
+    }
+}
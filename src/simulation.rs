@@ -0,0 +1,2138 @@
+use std::mem::{self, Discriminant};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::audit::{AuditSample, ExplorationAudit};
+use crate::clock::SimClock;
+use crate::decisions::{self, BuildVerdict, Explanation, RefineVerdict};
+use crate::events::{EventBus, SimEvent};
+use crate::hash::StableHasher;
+use crate::map::{self, CacheRules, Map, SearchMode, DEFAULT_CACHE_RULES};
+use crate::robot::{self, Malfunction, Robot, RobotType, RobotView};
+use crate::snapshots::SnapshotRecorder;
+use crate::station::{self, Station, StationId};
+use crate::station_log::StationEvent;
+use crate::tasks::Task;
+
+// A robot out in the field malfunctions with a chance of 1 in this many per tick
+pub const DEFAULT_MALFUNCTION_ODDS: u32 = 20_000;
+
+// Debris shifts one step every this many ticks
+pub const DEBRIS_MOVE_INTERVAL: u64 = 5;
+
+// Radiation counts down, and energy deposits recharge, every this many ticks
+const RADIATION_DECAY_INTERVAL: u64 = 10;
+const ENERGY_REGENERATION_INTERVAL: u64 = 50;
+
+// The station brings its task queue up to date with what it knows every this many ticks
+const TASK_REFRESH_INTERVAL: u64 = 10;
+
+// Every this many ticks the stations forget deposits nobody reported in STALE_DEPOSIT_TICKS
+const STALE_PRUNE_INTERVAL: u64 = 100;
+const STALE_DEPOSIT_TICKS: u64 = 5000;
+
+// Robots every generated world starts with, and where each heads from the station to find its
+// starting spot - explorers first for better coverage
+const INITIAL_ROSTER: [(RobotType, (i32, i32)); 9] = [
+    (RobotType::Explorer, (0, -1)),
+    (RobotType::Explorer, (8, 0)),
+    (RobotType::Explorer, (0, 8)),
+    (RobotType::Explorer, (-8, 0)),
+    (RobotType::Explorer, (6, -6)),
+    (RobotType::Explorer, (-6, 6)),
+    (RobotType::EnergyCollector, (6, 6)),
+    (RobotType::MineralCollector, (-6, -6)),
+    (RobotType::Scientist, (0, -12)),
+];
+
+// Points awarded per unit in the run score
+const SCORE_PER_EXPLORED_CELL: u64 = 10;
+const SCORE_PER_SCIENCE_POINT: u64 = 100;
+const SCORE_PER_MINERAL: u64 = 2;
+
+// Stations of a world with several bases stand at least this many cells apart along one axis
+pub const MIN_STATION_SEPARATION: usize = 20;
+
+// Exploration milestone recorded for the end-of-run summary
+const HALF_EXPLORED_PERCENT: f64 = 50.0;
+
+// Owns the whole world state and advances it independently of the UI
+pub struct Simulation {
+    pub map: Map,
+    pub stations: Vec<Station>, // Bases, each with its own fleet; the first is the main one
+    pub clock: SimClock<ScheduledWork>,
+    pub last_event_position: Option<(usize, usize)>, // Where robot activity last happened
+    pub events: EventBus,                             // Drained at the end of every tick
+    pub cache_rules: CacheRules,                      // Lifetime and decay of cargo dropped in the field
+    pub half_explored_at: Option<u64>,                // Ticks it took to explore half the passable map
+    pub audit: ExplorationAudit,                      // Explorer energy against the value of their discoveries
+    pub snapshots: SnapshotRecorder,                  // Map thumbnails taken at milestones for the report
+    pub explain_station: bool,                        // Publish an explanation of every station decision that changes
+    pub malfunction_odds: u32,                        // 1 in this many per robot in the field and tick; 0 for none
+    explained: Vec<ExplainedVerdicts>,                // Kinds of each station's standing verdicts last explained
+    debris_rng: ChaCha8Rng,                           // Drives debris movement, seeded from the map
+    malfunction_rng: ChaCha8Rng,                      // Drives malfunctions, another stream of the same seed
+}
+
+// Work the simulation defers to a later tick on its clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduledWork {
+    Snapshot, // A map thumbnail for the report
+}
+
+// The verdicts the station reaches every tick are only explained when their kind changes
+#[derive(Default)]
+struct ExplainedVerdicts {
+    build: Option<Discriminant<BuildVerdict>>,
+    refine: Option<Discriminant<RefineVerdict>>,
+}
+
+// Whether `verdict` is of another kind than the one last explained, remembering it if so
+fn verdict_changed<T>(last: &mut Option<Discriminant<T>>, verdict: &T) -> bool {
+    let kind = Some(mem::discriminant(verdict));
+    *last != kind && { *last = kind; true }
+}
+
+// Publish an explained decision and keep it in the station's own log too
+fn explain(events: &mut EventBus, station: &mut Station, explanation: Explanation) {
+    station.events.push(explanation.tick, StationEvent::Decision(explanation.clone()));
+    events.publish(SimEvent::StationDecision(explanation));
+}
+
+impl Simulation {
+    pub fn new(map: Map, station: Station) -> Self {
+        let debris_rng = ChaCha8Rng::seed_from_u64(map.seed as u64);
+        let mut malfunction_rng = ChaCha8Rng::seed_from_u64(map.seed as u64);
+        malfunction_rng.set_stream(1);
+        Self {
+            debris_rng,
+            malfunction_rng,
+            malfunction_odds: DEFAULT_MALFUNCTION_ODDS,
+            map,
+            stations: vec![station],
+            clock: SimClock::default(),
+            last_event_position: None,
+            half_explored_at: None,
+            audit: ExplorationAudit::default(),
+            snapshots: SnapshotRecorder::default(),
+            explain_station: false,
+            explained: vec![ExplainedVerdicts::default()],
+            events: EventBus::new(),
+            cache_rules: DEFAULT_CACHE_RULES,
+        }
+    }
+
+    // Build the starting world on a map: place the station (at the suggested position if any,
+    // else at the map's station site with its starting zone cleared) and the initial robot
+    // roster around it
+    pub fn setup(mut map: Map, suggested_station: Option<(usize, usize)>) -> Self {
+        let (station_x, station_y) = suggested_station.unwrap_or_else(|| {
+            let (x, y) = map.station_site();
+            map.clear_starting_zone(x, y, map::STARTING_ZONE_RADIUS);
+            (x, y)
+        });
+        let station = staffed_station(&map, 0, (station_x, station_y), Vec::new());
+        Self::new(map, station)
+    }
+
+    // Found another base with the initial roster of its own, at the open cell nearest the main
+    // station that keeps MIN_STATION_SEPARATION from every station. None when the map has no such
+    // cell
+    pub fn add_outpost(&mut self) -> Option<StationId> {
+        let (x, y) = self.map.outpost_site(&self.station_sites(), MIN_STATION_SEPARATION)?;
+        self.add_outpost_at(x, y)
+    }
+
+    // Found a base with the initial roster of its own at (x, y), unless the cell is blocked or
+    // closer than MIN_STATION_SEPARATION to a station
+    fn add_outpost_at(&mut self, x: usize, y: usize) -> Option<StationId> {
+        let sites = self.station_sites();
+        let blocked = self.map.get_cell(x, y).is_none_or(|cell| cell.cell_type.blocks_movement());
+        if blocked || sites.iter().any(|&(sx, sy)| x.abs_diff(sx).max(y.abs_diff(sy)) < MIN_STATION_SEPARATION) {
+            return None;
+        }
+        self.map.clear_starting_zone(x, y, map::STARTING_ZONE_RADIUS);
+        let taken = sites.into_iter().chain(self.robots().map(|robot| (robot.x, robot.y))).collect();
+        let id = self.stations.len();
+        let station = staffed_station(&self.map, id, (x, y), taken);
+        self.stations.push(station);
+        self.explained.push(ExplainedVerdicts::default());
+        Some(id)
+    }
+
+    // Have the main station send a robot to found an outpost at the next free site, kept apart
+    // from the stations and the outposts already ordered. Returns the site, None when the map has
+    // no room for another
+    pub fn order_outpost(&mut self) -> Option<(usize, usize)> {
+        let main = &self.stations[0];
+        let ordered = main.task_queue.iter().chain(main.robots.iter().filter_map(|robot| robot.current_task.as_ref())).filter_map(|task| match *task {
+            Task::BuildOutpostAt { x, y } => Some((x, y)),
+            _ => None,
+        });
+        let sites: Vec<(usize, usize)> = self.station_sites().into_iter().chain(ordered).collect();
+        let (x, y) = self.map.outpost_site(&sites, MIN_STATION_SEPARATION)?;
+        self.stations[0].task_queue.push_back(Task::BuildOutpostAt { x, y });
+        Some((x, y))
+    }
+
+    // The main station, which the single-base views and reports show
+    pub fn station(&self) -> &Station {
+        &self.stations[0]
+    }
+
+    // Where each station stands, in station order
+    pub fn station_sites(&self) -> Vec<(usize, usize)> {
+        self.stations.iter().map(|station| (station.x, station.y)).collect()
+    }
+
+    // Every robot of every station, station by station
+    pub fn robots(&self) -> impl Iterator<Item = &Robot> {
+        self.stations.iter().flat_map(|station| &station.robots)
+    }
+
+    // Advance the world by a single tick
+    pub fn tick(&mut self) {
+        let mut sites = self.station_sites();
+        let map = &mut self.map;
+
+        // Drift the debris; robots whose next step it blocks plan a new route on their next move
+        if self.clock.every(DEBRIS_MOVE_INTERVAL) && !map.debris.is_empty() {
+            let mut blocked: Vec<(usize, usize)> = self.stations.iter().flat_map(|station| &station.robots).map(|robot| (robot.x, robot.y)).collect();
+            blocked.extend(&sites);
+            map.update_dynamic_obstacles(&mut self.debris_rng, &blocked);
+        }
+
+        // Decay radiation; whatever the stations knew about a cleared cell is out of date now
+        if self.clock.every(RADIATION_DECAY_INTERVAL) {
+            for cell in map.decay_radiation(&mut self.debris_rng, RADIATION_DECAY_INTERVAL as u32) {
+                for station in &mut self.stations {
+                    station.known_map.remove(&cell);
+                    station.discovered_by.remove(&cell);
+                }
+            }
+        }
+        if self.clock.every(ENERGY_REGENERATION_INTERVAL) {
+            map.regenerate_energy();
+        }
+        if self.clock.every(STALE_PRUNE_INTERVAL) {
+            let older_than = self.clock.now().saturating_sub(STALE_DEPOSIT_TICKS);
+            for station in &mut self.stations {
+                station.prune_stale(older_than);
+            }
+        }
+
+        // Age the field caches once for the whole world; a lost cache is gone for every station
+        for cache in map.age_caches(&self.cache_rules) {
+            for station in &mut self.stations {
+                station.known_caches.retain(|&position| position != (cache.x, cache.y));
+            }
+            self.events.publish(SimEvent::CacheLost { x: cache.x, y: cache.y, minerals: cache.minerals, science: cache.science });
+        }
+
+        // Every fleet moves before any station works, so the audit sees the whole swarm's tick
+        let mut audit_sample = AuditSample::default();
+        let mut outpost_sites = Vec::new();
+        for home in 0..self.stations.len() {
+            outpost_sites.extend(self.update_fleet(home, &sites, &mut audit_sample));
+        }
+        // Robots that reached the site they were sent to found their outposts
+        for (robot, x, y) in outpost_sites {
+            if let Some(station) = self.add_outpost_at(x, y) {
+                self.events.publish(SimEvent::OutpostFounded { robot, station, x, y });
+                sites.push((x, y));
+            }
+        }
+
+        // Audit exploration; a lasting deficit advises the fleet it has too many explorers
+        if let Some(balance) = self.audit.record(audit_sample) {
+            let explorers = self.robots().filter(|robot| robot.robot_type == RobotType::Explorer).count();
+            self.events.publish(SimEvent::ExplorerSurplus { explorers, balance });
+            for station in self.stations.iter_mut().filter(|station| station.hold_explorers_on_advisory) {
+                station.explorers_on_hold = true;
+            }
+        }
+
+        for home in 0..self.stations.len() {
+            self.run_station(home, &sites);
+        }
+
+        // Deliver this tick's events; the latest located one marks where activity last happened
+        for event in self.events.drain() {
+            match event {
+                SimEvent::ResourceCollected { x, y, .. }
+                | SimEvent::RobotCreated { x, y, .. }
+                | SimEvent::DistressSignal { x, y, .. }
+                | SimEvent::CacheDropped { x, y, .. }
+                | SimEvent::Unloaded { x, y, .. }
+                | SimEvent::OutpostFounded { x, y, .. } => {
+                    self.last_event_position = Some((x, y));
+                }
+                _ => {}
+            }
+        }
+
+        self.clock.advance();
+        let snapshot_ticks: Vec<u64> =
+            self.clock.take_due().into_iter().filter(|&(_, work)| work == ScheduledWork::Snapshot).map(|(tick, _)| tick).collect();
+        let explored = if self.half_explored_at.is_none() || self.snapshots.awaits_exploration() {
+            self.map.exploration_stats().percentage
+        } else {
+            0.0 // Nothing left to measure it for
+        };
+        if self.half_explored_at.is_none() && explored >= HALF_EXPLORED_PERCENT {
+            self.half_explored_at = Some(self.clock.now());
+        }
+        self.snapshots.observe(&self.map, &self.stations[0], self.clock.now(), explored, &snapshot_ticks);
+    }
+
+    // Move one station's fleet: malfunctions, explorer pairs and every robot's own update, adding
+    // what explorers spent and found to `audit_sample`. Returns (robot, x, y) for every robot that
+    // reached the outpost site it was sent to
+    fn update_fleet(&mut self, home: StationId, sites: &[(usize, usize)], audit_sample: &mut AuditSample) -> Vec<(usize, usize, usize)> {
+        let foreign: Vec<RobotView> = self.stations.iter().filter(|station| station.id != home).flat_map(|station| station.robots.iter().map(Robot::view)).collect();
+        let map = &mut self.map;
+        let station = &mut self.stations[home];
+
+        // Robots out in the field now and then pick up a fault they work on with until repaired
+        if self.malfunction_odds > 0 {
+            for (index, robot) in station.robots.iter_mut().enumerate() {
+                let in_the_field = robot.energy > 0 && !matches!(robot.state, robot::RobotState::AtStation | robot::RobotState::Idle | robot::RobotState::Broken);
+                if in_the_field && self.malfunction_rng.gen_range(0..self.malfunction_odds) == 0 {
+                    let malfunction = Malfunction::random(&mut self.malfunction_rng);
+                    if !robot.malfunctions.contains(&malfunction) {
+                        robot.malfunctions.push(malfunction);
+                        self.events.publish(SimEvent::RobotMalfunctioned { robot: index, malfunction });
+                    }
+                }
+            }
+        }
+
+        // With the buddy system on, explorers go out in pairs: the trailer keeps formation with its
+        // leader while both are out
+        for (leader, trailer) in station.pair_up_explorers() {
+            self.events.publish(SimEvent::ExplorersPaired { leader, trailer });
+        }
+        for robot in station.robots.iter_mut() {
+            robot.buddy = None;
+        }
+        for &(leader, trailer) in &station.explorer_pairs {
+            let leader = &station.robots[leader];
+            let out = leader.energy > 0 && leader.state == robot::RobotState::Exploring && leader.z == station.robots[trailer].z;
+            station.robots[trailer].buddy = out.then_some((leader.x, leader.y));
+        }
+
+        // Update all robots autonomously; robots of the other stations are in the way too, and
+        // each robot heads for its own station when it goes home
+        let robot_types: Vec<RobotType> = station.robots.iter().map(|robot| robot.robot_type).collect();
+        let mut views: Vec<RobotView> = station.robots.iter().map(Robot::view).collect();
+        let mut other_robots: Vec<RobotView> = Vec::with_capacity(views.len() + foreign.len());
+        let mut outpost_sites = Vec::new();
+        for i in 0..station.robots.len() {
+            // What the current robot sees of the others, as they stand after their own update
+            other_robots.clear();
+            other_robots.extend(views[..i].iter().chain(&views[i + 1..]).chain(&foreign));
+            let current = &mut station.robots[i];
+            // A robot sent to found an outpost is done once it stands near the site
+            if let Some(Task::BuildOutpostAt { x, y }) = current.current_task.filter(|&task| current.task_done(task, map)) {
+                current.current_task = None;
+                outpost_sites.push((i, x, y));
+            }
+
+            let battery_before = current.energy;
+            let cargo_before = (current.energy_cargo, current.minerals, current.science_points);
+            let position_before = (current.x, current.y); // Robots harvest where they stand before moving on
+            let was_stranded = current.is_waiting_for_rescue();
+            let (station_x, station_y) = sites[current.home_station];
+            current.autonomous_update(map, station_x, station_y, &other_robots, self.clock.now());
+
+            if !was_stranded && current.is_waiting_for_rescue() {
+                self.events.publish(SimEvent::DistressSignal { robot: i, x: current.x, y: current.y });
+            }
+
+            // Cargo only leaves a robot in the field when it is dropped in a cache, which is reported
+            if current.minerals < cargo_before.1 || current.science_points < cargo_before.2 {
+                if !station.known_caches.contains(&(current.x, current.y)) {
+                    station.known_caches.push((current.x, current.y));
+                }
+                self.events.publish(SimEvent::CacheDropped { robot: i, x: current.x, y: current.y });
+            }
+
+            // Any cargo gained during the update means a resource was collected, as does a battery
+            // charged at a refuel stop
+            let energy_gained = current.energy_cargo.saturating_sub(cargo_before.0) + current.energy.saturating_sub(battery_before);
+            if energy_gained > 0 || current.minerals > cargo_before.1 || current.science_points > cargo_before.2 {
+                self.events.publish(SimEvent::ResourceCollected { robot: i, x: current.x, y: current.y });
+                let discoverer = station.discovered_by.get(&position_before).and_then(|&robot| robot_types.get(robot));
+                if discoverer == Some(&RobotType::Explorer) {
+                    audit_sample.discovery_value += AuditSample::harvest_value(
+                        energy_gained,
+                        current.minerals.saturating_sub(cargo_before.1),
+                        current.science_points.saturating_sub(cargo_before.2),
+                    );
+                }
+            }
+            if current.robot_type == RobotType::Explorer {
+                audit_sample.explorer_energy += battery_before.saturating_sub(current.energy);
+            }
+            views[i] = current.view();
+            let claim = current.current_target;
+            station.record_claim(i, claim);
+        }
+        outpost_sites
+    }
+
+    // One station's work for the tick: hand-overs between its robots, docking, refueling, data
+    // sharing and respawns for robots back at its site, refining and construction
+    fn run_station(&mut self, home: StationId, sites: &[(usize, usize)]) {
+        let map = &mut self.map;
+        let station = &mut self.stations[home];
+
+        // Rescuers next to their stranded robot hand over the energy it is missing, from their
+        // cargo or, short of that, the battery they can spare
+        for i in 0..station.robots.len() {
+            let robot::RobotState::Rescuing { target, .. } = station.robots[i].state else {
+                continue;
+            };
+            let target_waiting = target != i && station.robots[target].is_waiting_for_rescue() && station.robots[target].energy > 0;
+            let (station_x, station_y) = sites[station.robots[i].home_station];
+            let battery_spare = station.robots[i].spare_energy(map, station_x, station_y);
+            if !target_waiting || station.robots[i].energy_cargo + battery_spare == 0 {
+                station.robots[i].state = robot::RobotState::Exploring; // Rescue no longer needed or possible
+                continue;
+            }
+            let (station_x, station_y) = sites[station.robots[target].home_station];
+            let shortfall = station.robots[target].rescue_shortfall(map, station_x, station_y);
+            let (rescuer, stranded) = pair_mut(&mut station.robots, i, target);
+            let amount = rescuer.transfer_energy_to(stranded, shortfall, battery_spare, map);
+            if amount > 0 {
+                rescuer.state = robot::RobotState::Exploring;
+                self.events.publish(SimEvent::RobotRescued { robot: target, rescuer: i, amount });
+            }
+        }
+        // Carriers next to the collector they came for take its cargo
+        for i in 0..station.robots.len() {
+            let carrier = &station.robots[i];
+            if carrier.robot_type != RobotType::Carrier || carrier.state != robot::RobotState::Exploring || carrier.energy == 0 {
+                continue;
+            }
+            let Some((x, y)) = carrier.target_x.zip(carrier.target_y) else {
+                continue;
+            };
+            let Some(collector) = station.robots.iter().position(|robot| (robot.x, robot.y, robot.z) == (x, y, carrier.z) && robot.robot_type.is_collector()) else {
+                continue;
+            };
+            let (collector_robot, carrier_robot) = pair_mut(&mut station.robots, collector, i);
+            let (energy, minerals, science) = collector_robot.transfer_cargo_to(carrier_robot, map);
+            if (energy, minerals, science) != (0, 0, 0) {
+                self.events.publish(SimEvent::CargoHandedOver { robot: collector, carrier: i, energy, minerals, science });
+            }
+        }
+        // Repairers next to the robot they came for fix it; the station pays when they dock
+        for i in 0..station.robots.len() {
+            let repairer = &station.robots[i];
+            if repairer.robot_type != RobotType::Repairer || repairer.state != robot::RobotState::Exploring || repairer.energy == 0 {
+                continue;
+            }
+            let Some((x, y, z)) = repairer.target_x.zip(repairer.target_y).map(|(x, y)| (x, y, repairer.z)) else {
+                continue;
+            };
+            let patient = station.robots.iter().enumerate().position(|(index, robot)| {
+                index != i && (robot.x, robot.y, robot.z) == (x, y, z) && robot.integrity < robot::MAX_INTEGRITY
+            });
+            let Some(patient) = patient else {
+                continue;
+            };
+            let (patient_robot, repairer_robot) = pair_mut(&mut station.robots, patient, i);
+            let integrity = repairer_robot.repair(patient_robot, map);
+            if integrity > 0 {
+                self.events.publish(SimEvent::RobotRepaired { robot: patient, repairer: i, integrity });
+            }
+        }
+        // Two robots that have long been in each other's way, each wanting the other's cell,
+        // pass by trading places
+        for i in 0..station.robots.len() {
+            let robot = &station.robots[i];
+            if robot.blocked_steps < robot::DEADLOCK_STEPS || robot.energy == 0 {
+                continue;
+            }
+            let Some(next) = robot.heading_to.and_then(|(x, y)| robot.next_cell_towards(x, y, map)) else {
+                continue;
+            };
+            let other = station.robots.iter().enumerate().position(|(index, other)| {
+                index != i
+                    && (other.x, other.y, other.z) == (next.0, next.1, robot.z)
+                    && other.energy > 0
+                    && other.heading_to.and_then(|(x, y)| other.next_cell_towards(x, y, map)) == Some((robot.x, robot.y))
+            });
+            let Some(other) = other else {
+                continue;
+            };
+            let (robot, other_robot) = pair_mut(&mut station.robots, i, other);
+            robot.swap_places(other_robot, map);
+            self.events.publish(SimEvent::RobotsSwapped { robot: i, other });
+        }
+        for (robot, rescuer) in station.dispatch_rescues(map) {
+            self.events.publish(SimEvent::RescueDispatched { robot, rescuer });
+        }
+
+        // Robots sent for a cache load it once they reach it, or go back to work if it is gone
+        for (index, robot) in station.robots.iter_mut().enumerate() {
+            let robot::RobotState::Retrieving { x, y } = robot.state else {
+                continue;
+            };
+            if map.cache_at(x, y).is_none() {
+                robot.state = robot::RobotState::Exploring;
+            } else if (robot.x, robot.y) == (x, y) {
+                let cache = map.take_cache(x, y).expect("cache checked above");
+                robot.load_cache(&cache);
+                robot.state = robot::RobotState::ReturningToStation;
+                let (station_x, station_y) = sites[robot.home_station];
+                robot.target_x = Some(station_x);
+                robot.target_y = Some(station_y);
+                station.known_caches.retain(|&position| position != (x, y));
+                self.events.publish(SimEvent::CacheRecovered { robot: index, x, y, minerals: cache.minerals, science: cache.science });
+            }
+        }
+        for ((x, y), robot) in station.dispatch_pickups(map) {
+            self.events.publish(SimEvent::PickupDispatched { robot, x, y });
+        }
+
+        if self.clock.every(TASK_REFRESH_INTERVAL) {
+            station.refresh_tasks(map);
+        }
+
+        // Handle robot-station interactions; other stations' sites are just ground a robot walks over
+        let mut robots_to_update = Vec::new();
+        for i in 0..station.robots.len() {
+            let robot = &station.robots[i];
+            if (robot.x, robot.y) == sites[robot.home_station] && !matches!(robot.state, robot::RobotState::Broken | robot::RobotState::Decommissioned) {
+                robots_to_update.push(i);
+            }
+        }
+
+        // Process interactions for robots at station
+        for &robot_index in &robots_to_update {
+            // 1. Unload resources
+            let (energy_payload, minerals_payload, science_payload) = station.robots[robot_index].unload_payload();
+            if energy_payload > 0 || minerals_payload > 0 || science_payload > 0 {
+                // Whatever does not fit in storage stays on the robot
+                let (energy, minerals, science) = station.collect_resources(energy_payload, minerals_payload, science_payload);
+                station.robots[robot_index].keep_cargo(energy, minerals, science);
+                let delivered = (energy_payload - energy, minerals_payload - minerals, science_payload - science);
+                if delivered != (0, 0, 0) {
+                    let (energy, minerals, science) = delivered;
+                    station.events.push(self.clock.now(), StationEvent::ResourceDelivered { robot: robot_index, energy, minerals, science });
+                }
+                self.events.publish(SimEvent::Unloaded {
+                    robot: robot_index,
+                    x: station.robots[robot_index].x,
+                    y: station.robots[robot_index].y,
+                    energy: energy_payload - energy,
+                    minerals: minerals_payload - minerals,
+                    science: science_payload - science,
+                });
+                if (energy, minerals, science) != (0, 0, 0) {
+                    self.events.publish(SimEvent::StorageFull { robot: robot_index, energy, minerals, science });
+                }
+            }
+
+            // Repairs done since the last visit are paid for in refined minerals
+            let repair_bill = std::mem::take(&mut station.robots[robot_index].repair_bill);
+            station.refined_minerals = station.refined_minerals.saturating_sub(repair_bill);
+
+            // Malfunctions are fixed when the station can pay for all of them
+            let malfunctions = station.robots[robot_index].malfunctions.len();
+            let minerals = malfunctions as u32 * robot::MALFUNCTION_REPAIR_MINERALS;
+            if malfunctions > 0 && station.refined_minerals >= minerals {
+                station.refined_minerals -= minerals;
+                station.robots[robot_index].malfunctions.clear();
+                self.events.publish(SimEvent::MalfunctionsRepaired { robot: robot_index, malfunctions, minerals });
+            }
+
+            // 2. Share map data
+            let updates = station.robots[robot_index].get_exploration_updates();
+            if !updates.is_empty() {
+                station.share_data(robot_index, &updates, self.clock.now());
+                self.events.publish(SimEvent::DataShared { robot: robot_index, cells: updates.len() });
+            }
+            // An explorer is sent back first thing to where a buddy went down
+            for (x, y) in std::mem::take(&mut station.robots[robot_index].wreck_reports) {
+                station.task_queue.push_front(Task::ExploreRegion { x, y });
+                self.events.publish(SimEvent::BuddyLost { robot: robot_index, x, y });
+            }
+            let analyzed = station.robots[robot_index].take_analyzed_cells();
+            station.analyzed_cells.extend(analyzed);
+            // An explorer leaves knowing what the swarm has mapped, so it does not head back there
+            if station.robots[robot_index].robot_type == RobotType::Explorer {
+                station.robots[robot_index].known_by_station = station.known_cells_near(map);
+            }
+
+            // A worn robot dearer to keep than to replace is scrapped, its report delivered
+            if station.should_decommission(robot_index) {
+                let refund = station.decommission_robot(robot_index);
+                self.events.publish(SimEvent::RobotDecommissioned { robot: robot_index, refund });
+                continue;
+            }
+            // A surplus explorer is refitted as what the station needs more, once the map is nearly explored
+            if let Some((robot_type, reason)) = station.retrofit_choice(robot_index, map) {
+                let from = station.robots[robot_index].robot_type;
+                if station.retrofit_robot(robot_index, robot_type) {
+                    let minerals = station::RETROFIT_MINERAL_COST;
+                    self.events.publish(SimEvent::RobotRetrofitted { robot: robot_index, from, to: robot_type, minerals });
+                    if self.explain_station {
+                        let explanation = Explanation::retrofit(self.clock.now(), robot_index, robot_type, reason, decisions::station_inputs(station));
+                        explain(&mut self.events, station, explanation);
+                    }
+                }
+            }
+            let previous_target = station.robots[robot_index].target_x.zip(station.robots[robot_index].target_y);
+            // An explorer sent to a region by a task keeps heading there
+            let target = match station.robots[robot_index].current_task {
+                None => station.assign_explorer_region(robot_index, map, self.clock.now()),
+                Some(_) => None,
+            };
+            // An explorer held at the station keeps its target; that is not a new decision
+            if let (true, Some(choice)) = (self.explain_station, target.filter(|choice| Some(choice.target) != previous_target)) {
+                explain(&mut self.events, station, Explanation::explorer_target(self.clock.now(), robot_index, &choice));
+            }
+
+            // 3. Fit the upgrades bought since the last visit, then refuel the robot to its own
+            // battery size (consume station energy, more for a worn robot)
+            station.fit_upgrades(robot_index);
+            // A dead robot waits docked to be respawned rather than refuelled; it is no new wreck
+            if station.robots[robot_index].energy == 0 {
+                station.robots[robot_index].state = robot::RobotState::AtStation;
+                continue;
+            }
+            let max_energy = station.robots[robot_index].spec.max_energy;
+            let refuel_price = station.robots[robot_index].refuel_price(max_energy.saturating_sub(station.robots[robot_index].energy));
+            if let Some(refuel_cost) = station.pay_for_refuel(refuel_price) {
+                station.robots[robot_index].energy = max_energy;
+                self.events.publish(SimEvent::Refueled { robot: robot_index, amount: refuel_cost });
+            }
+
+            // 4. A robot done with its last task takes the next suitable one off the queue, and
+            // without one carries on by itself. A collector finding nothing for it queued gets the
+            // nearest known deposit it harvests without waiting for the next refresh. A robot
+            // called home waits idle for a task, and a Scientist with nothing to collect stays
+            // docked to analyze the archived observations, or with none of those either scouts
+            // the least-explored region. While the fleet is recalled every robot stays docked,
+            // and so does one being refitted
+            if station.robots[robot_index].retrofit_ticks > 0 {
+                station.robots[robot_index].retrofit_ticks -= 1;
+                continue;
+            }
+            if station.recall {
+                station.robots[robot_index].state = robot::RobotState::Idle;
+                continue;
+            }
+            let mut task = station.take_task(robot_index, map);
+            let docked = &station.robots[robot_index];
+            if task.is_none() && docked.current_task.is_none() && docked.robot_type.is_collector() {
+                station.refresh_tasks(map);
+                task = station.take_task(robot_index, map);
+            }
+            let idle_scientist = station.is_idle_scientist(&station.robots[robot_index]) && station.unanalyzed_data > 0;
+            if task.is_none() && !idle_scientist {
+                task = station.send_scientist_scouting(robot_index, map);
+            }
+            if let Some(task) = task {
+                self.events.publish(SimEvent::TaskAssigned { robot: robot_index, task });
+            }
+            station.robots[robot_index].state = match station.robots[robot_index].current_task {
+                Some(Task::ReturnHome) => robot::RobotState::Idle,
+                None if idle_scientist => robot::RobotState::AtStation,
+                _ => robot::RobotState::Exploring,
+            };
+
+            // 5. Explorers sent beyond a round trip's reach get refuel stops planned
+            let refuel_stops = station.plan_expedition(robot_index, map);
+            if refuel_stops > 0 {
+                self.events.publish(SimEvent::ExpeditionPlanned { robot: robot_index, refuel_stops });
+            }
+        }
+
+        // A robot that found a planned refuel stop empty gets a new plan from where it stands
+        for index in 0..station.robots.len() {
+            if std::mem::take(&mut station.robots[index].needs_replan) {
+                let refuel_stops = station.plan_expedition(index, map);
+                if refuel_stops > 0 {
+                    self.events.publish(SimEvent::ExpeditionPlanned { robot: index, refuel_stops });
+                }
+            }
+        }
+
+        // Robots about to run dry beg a charged neighbour for power
+        for (robot, donor, amount) in balance_energy(&mut station.robots, map, sites) {
+            self.events.publish(SimEvent::EnergyShared { robot, donor, amount });
+        }
+
+        // A robot going down in the field is seen by its buddy, if that one is out too, who reports
+        // where at its next docking
+        let witnessed: Vec<(usize, (usize, usize))> = (0..station.robots.len())
+            .filter(|&index| station.robots[index].energy == 0 && station.robots[index].state != robot::RobotState::AtStation)
+            .filter_map(|index| {
+                let buddy = station.buddy_of(index)?;
+                let witness = &station.robots[buddy];
+                let out = witness.energy > 0 && witness.state != robot::RobotState::AtStation;
+                out.then_some((buddy, (station.robots[index].x, station.robots[index].y)))
+            })
+            .collect();
+        for (buddy, position) in witnessed {
+            station.robots[buddy].wreck_reports.push(position);
+        }
+
+        // Handle dead robots - respawn them at the station (if station has energy). Broken robots
+        // that still have energy are not dead: they wait where they are for a repairer
+        for (index, robot) in station.robots.iter_mut().enumerate() {
+            if robot.energy == 0 && robot.state != robot::RobotState::Decommissioned {
+                if robot.state != robot::RobotState::AtStation {
+                    if matches!(robot.state, robot::RobotState::LowPower { .. }) {
+                        self.events.publish(SimEvent::RobotPoweredDown { robot: index, x: robot.x, y: robot.y });
+                    }
+                    // Worn parts are stripped off the wreck before it is put back into service
+                    station.parts += station::wreck_parts(robot.robot_type);
+                    self.events.publish(SimEvent::RobotWrecked { robot: index });
+                    station.events.push(self.clock.now(), StationEvent::RobotDied { robot: index });
+                }
+                robot.x = station.x;
+                robot.y = station.y;
+                robot.z = map::SURFACE;
+                robot.tunnel_route.clear();
+                robot.state = robot::RobotState::AtStation;
+                robot.steps_since_last_find = 0;
+                robot.current_task = None; // Back on the queue at the next refresh
+                robot.integrity = robot::MAX_INTEGRITY; // Rebuilt from the wreck
+
+                // Respawn robot only if station has enough energy
+                if station.energy >= robot.spec.max_energy {
+                    station.energy -= robot.spec.max_energy;
+                    robot.energy = robot.spec.max_energy;
+                    robot.lifetime.respawns += 1;
+                    self.events.publish(SimEvent::RobotRespawned { robot: index });
+                    station.events.push(self.clock.now(), StationEvent::RobotRespawned { robot: index });
+                }
+            }
+        }
+
+        // Station's own per-tick work (mineral refinement)
+        if self.explain_station {
+            let verdict = station.refine_verdict();
+            if verdict_changed(&mut self.explained[home].refine, &verdict) {
+                let inputs = vec![("raw_minerals", station.raw_minerals.to_string()), ("energy", station.energy.to_string())];
+                explain(&mut self.events, station, Explanation::refinery(self.clock.now(), verdict, inputs));
+            }
+        }
+        station.tick();
+
+        // Science pays for upgrades, one at most a tick
+        if let Some(upgrade) = station.maybe_purchase_upgrade() {
+            let level = station.upgrades.level(upgrade);
+            self.events.publish(SimEvent::UpgradePurchased { upgrade, level });
+            station.events.push(self.clock.now(), StationEvent::UpgradePurchased { upgrade, level });
+        }
+        // Refined minerals pay for facilities that earn their cost back
+        if let Some(facility) = station.consider_facility_purchase() {
+            let minerals = facility.kind.cost(facility.level - 1);
+            self.events.publish(SimEvent::FacilityBuilt { facility: facility.kind, level: facility.level, minerals });
+            station.events.push(self.clock.now(), StationEvent::FacilityBuilt { facility: facility.kind, level: facility.level });
+        }
+
+        // Station decides to create new robots
+        let verdict = station.build_verdict(self.clock.now());
+        if self.explain_station && verdict_changed(&mut self.explained[home].build, &verdict) {
+            let explanation = Explanation::build(self.clock.now(), verdict, decisions::station_inputs(station));
+            explain(&mut self.events, station, explanation);
+        }
+        if verdict.approves() {
+            let (new_robot_x, new_robot_y) = find_clear_spot_for_robot(map, station.x, station.y);
+
+            if let Some(cell) = map.get_cell(new_robot_x, new_robot_y) {
+                if !cell.cell_type.blocks_movement() && (new_robot_x, new_robot_y) != (station.x, station.y) {
+                    if let Some(reason) = station.create_robot(new_robot_x, new_robot_y, map) {
+                        let robot = station.robots.len() - 1;
+                        self.events.publish(SimEvent::RobotCreated { robot, x: new_robot_x, y: new_robot_y });
+                        station.events.push(self.clock.now(), StationEvent::RobotCreated { robot, robot_type: station.robots[robot].robot_type });
+                        if self.explain_station {
+                            let robot_type = station.robots[robot].robot_type;
+                            let explanation = Explanation::robot_type(self.clock.now(), robot_type, reason, decisions::station_inputs(station));
+                            explain(&mut self.events, station, explanation);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Record map thumbnails for the report at the exploration milestones and the given ticks
+    pub fn record_snapshots(&mut self, ticks: &[u64]) {
+        self.snapshots.start();
+        let mut ticks = ticks.to_vec();
+        ticks.sort_unstable();
+        ticks.dedup();
+        for tick in ticks {
+            self.clock.at(tick, ScheduledWork::Snapshot);
+        }
+    }
+
+    // Advance the world by several ticks in a row (one rendered frame's worth)
+    pub fn run_ticks(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+
+    // Overall run score: area explored plus science and minerals brought home
+    pub fn score(&self) -> u64 {
+        let explored = self.map.exploration_stats().explored_cells as u64;
+        let minerals: u64 = self.stations.iter().map(|station| (station.raw_minerals + station.refined_minerals) as u64).sum();
+        let science: u64 = self.stations.iter().map(|station| (station.science_points + station.upgrades.science_spent()) as u64).sum();
+        explored * SCORE_PER_EXPLORED_CELL + science * SCORE_PER_SCIENCE_POINT + minerals * SCORE_PER_MINERAL
+    }
+
+    // Stable hash of the whole world state: the map's current contents and exploration, field
+    // caches, the stations' stores and every robot. Two runs of the same world that agree on it
+    // after the same number of ticks have not diverged
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        hasher.write_u64(self.clock.now());
+        hasher.write_u64(self.map.content_hash());
+        for (_, _, cell) in self.map.iter_cells() {
+            hasher.write_u8(cell.explored as u8);
+        }
+        for cache in &self.map.caches {
+            for value in [cache.x as u64, cache.y as u64, cache.minerals as u64, cache.science as u64, cache.age] {
+                hasher.write_u64(value);
+            }
+        }
+        for station in &self.stations {
+            for value in [
+                station.energy,
+                station.raw_minerals,
+                station.refined_minerals,
+                station.science_points,
+                station.unanalyzed_data,
+                station.parts,
+                station.known_map.len() as u32,
+            ] {
+                hasher.write_u32(value);
+            }
+            for robot in &station.robots {
+                for value in [robot.x as u64, robot.y as u64, robot.z as u64] {
+                    hasher.write_u64(value);
+                }
+                for value in [robot.energy, robot.energy_cargo, robot.minerals, robot.science_points, robot.malfunctions.len() as u32] {
+                    hasher.write_u32(value);
+                }
+                // Debug names are fixed by the source, so they hash the same everywhere
+                hasher.write(format!("{:?} {:?}", robot.robot_type, robot.state).as_bytes());
+            }
+        }
+        hasher.finish()
+    }
+}
+
+// A robot in the field with less than BEG_BELOW_ENERGY energy left gets up to SHARED_ENERGY
+// from a neighbour charged above DONOR_MIN_PERCENT of its battery
+const BEG_BELOW_ENERGY: u32 = 10;
+const DONOR_MIN_PERCENT: u32 = 60;
+const SHARED_ENERGY: u32 = 20;
+
+// Let each robot running dry take power from the neighbour on its layer with the most to spare.
+// A donor never gives away what it needs to make it home to its own of the station `sites`, so
+// the energy is only moved around. Returns the (robot, donor, amount) of each transfer
+fn balance_energy(robots: &mut [Robot], map: &Map, sites: &[(usize, usize)]) -> Vec<(usize, usize, u32)> {
+    let in_field = |robot: &Robot| robot.energy > 0 && (robot.x, robot.y) != sites[robot.home_station];
+    let mut transfers = Vec::new();
+    for index in 0..robots.len() {
+        let robot = &robots[index];
+        if !in_field(robot) || robot.energy >= BEG_BELOW_ENERGY {
+            continue;
+        }
+        let donor = robots
+            .iter()
+            .enumerate()
+            .filter(|&(other, donor)| {
+                other != index
+                    && in_field(donor)
+                    && donor.z == robot.z
+                    && map.distance(donor.x, donor.y, robot.x, robot.y) == 1
+                    && donor.energy * 100 > donor.spec.max_energy * DONOR_MIN_PERCENT
+            })
+            .map(|(other, donor)| {
+                let (station_x, station_y) = sites[donor.home_station];
+                (other, donor.spare_energy(map, station_x, station_y))
+            })
+            .max_by_key(|&(other, spare)| (spare, std::cmp::Reverse(other)));
+        let Some((donor, spare)) = donor else {
+            continue;
+        };
+        let amount = SHARED_ENERGY.min(spare).min(robot.spec.max_energy - robot.energy);
+        if amount > 0 {
+            let (robot, donor_robot) = pair_mut(robots, index, donor);
+            donor_robot.energy -= amount;
+            robot.energy += amount;
+            transfers.push((index, donor, amount));
+        }
+    }
+    transfers
+}
+
+// Mutable references to two different robots
+fn pair_mut(robots: &mut [Robot], first: usize, second: usize) -> (&mut Robot, &mut Robot) {
+    if first < second {
+        let (left, right) = robots.split_at_mut(second);
+        (&mut left[first], &mut right[0])
+    } else {
+        let (left, right) = robots.split_at_mut(first);
+        (&mut right[0], &mut left[second])
+    }
+}
+
+// Helper function to find a clear spot for the robot: the nearest non-obstacle cell around the
+// station, in expanding rings
+pub fn find_clear_spot_for_robot(map: &Map, station_x: usize, station_y: usize) -> (usize, usize) {
+    map.find_nearest((station_x, station_y), SearchMode::Rings, |x, y, cell| {
+        (x, y) != (station_x, station_y) && !cell.cell_type.blocks_movement()
+    })
+    .unwrap_or((0, 0)) // Only when the whole map is rock
+}
+
+// Helper function to find a clear spot near a target position: the target itself if free,
+// else the nearest non-obstacle cell not `taken` by a station or robot
+pub fn find_clear_spot_near_target(map: &Map, target_x: usize, target_y: usize, taken: &[(usize, usize)]) -> (usize, usize) {
+    map.find_nearest((target_x, target_y), SearchMode::Rings, |x, y, cell| !cell.cell_type.blocks_movement() && !taken.contains(&(x, y)))
+        .unwrap_or((0, 0))
+}
+
+// A station `id` at `site` with the initial roster placed around it clear of the `taken` cells:
+// the first robot starts next to the station, the others spread out around it, and the explorers
+// are sent off to different parts of the map
+fn staffed_station(map: &Map, id: StationId, site: (usize, usize), mut taken: Vec<(usize, usize)>) -> Station {
+    let mut station = Station::new(site.0, site.1);
+    station.id = id;
+    taken.push(site);
+    for (robot_type, (dx, dy)) in INITIAL_ROSTER {
+        let target_x = (station.x as i32 + dx).clamp(0, map.width as i32 - 1) as usize;
+        let target_y = (station.y as i32 + dy).clamp(0, map.height as i32 - 1) as usize;
+        let (robot_x, robot_y) = find_clear_spot_near_target(map, target_x, target_y, &taken);
+        taken.push((robot_x, robot_y));
+        // Created directly, without the resource cost of later robots
+        station.add_robot(Robot::new_with_type(robot_x, robot_y, robot_type));
+    }
+    for index in 0..station.robots.len() {
+        station.assign_explorer_region(index, map, 0);
+    }
+    station
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{ActivityTally, Subscriber};
+    use crate::map::{CellType, MapConfig, Terrain};
+    use crate::station::KnownCell;
+    use crate::station_log::STATION_LOG_CAPACITY;
+
+    #[test]
+    fn test_tick_advances_counter() {
+        let map = Map::new(20, 20, 42);
+        let mut simulation = Simulation::new(map, Station::new(10, 10));
+        simulation.tick();
+        assert_eq!(simulation.clock.now(), 1);
+        simulation.run_ticks(9);
+        assert_eq!(simulation.clock.now(), 10);
+    }
+
+    #[test]
+    fn test_scheduled_snapshots_come_due_on_a_fast_forwarded_clock() {
+        let mut simulation = Simulation::setup(Map::new(40, 20, 3), None);
+        simulation.record_snapshots(&[5000, 1200, 5000]);
+        assert_eq!(simulation.clock.pending(), 2);
+
+        // No need to run the 1200 ticks before the first one
+        simulation.clock.fast_forward(1199);
+        simulation.tick();
+        let taken: Vec<&str> = simulation.snapshots.snapshots.iter().map(|snapshot| snapshot.label.as_str()).collect();
+        assert!(taken.contains(&"tick 1200") && !taken.contains(&"tick 5000"), "{:?}", taken);
+        assert_eq!(simulation.clock.pending(), 1);
+
+        // Ticks a fast-forward skips over still get their due work on the next tick
+        simulation.clock.fast_forward(10_000);
+        simulation.tick();
+        assert_eq!(simulation.snapshots.snapshots.last().map(|snapshot| (snapshot.label.as_str(), snapshot.tick)), Some(("tick 5000", 11_201)));
+    }
+
+    #[test]
+    fn test_radiation_and_energy_upkeep_come_due_on_a_fast_forwarded_clock() {
+        let (mut map, station_position) = Map::from_ascii_with_amount("H.e\n..r\n", 40).unwrap();
+        map.get_cell_mut(2, 1).unwrap().cell_type = CellType::Radiation(15);
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        station.refined_minerals = 0; // No robots built to disturb the cells
+        let mut simulation = Simulation::new(map, station);
+        let cell = |simulation: &Simulation, x, y| simulation.map.get_cell(x, y).unwrap().cell_type.clone();
+
+        simulation.clock.fast_forward(RADIATION_DECAY_INTERVAL);
+        simulation.tick();
+        assert_eq!(cell(&simulation, 2, 1), CellType::Radiation(15 - RADIATION_DECAY_INTERVAL as u32));
+        assert_eq!(cell(&simulation, 2, 0), CellType::Energy(40));
+
+        simulation.clock.fast_forward(ENERGY_REGENERATION_INTERVAL - simulation.clock.now());
+        simulation.tick();
+        assert!(!cell(&simulation, 2, 1).blocks_movement());
+        assert_eq!(cell(&simulation, 2, 0), CellType::Energy(41));
+    }
+
+    #[test]
+    fn test_setup_places_station_and_roster_on_open_ground() {
+        for terrain in [Terrain::Open, Terrain::Caves] {
+            for seed in 0..40 {
+                let (width, height) = (30 + seed as usize % 4 * 20, 15 + seed as usize % 3 * 10);
+                let map_config = MapConfig { terrain, ..MapConfig::default() };
+                let map = Map::with_config(width, height, seed, &map_config);
+                let site = map.station_site();
+                assert_ne!(map.get_cell(site.0, site.1).unwrap().cell_type, CellType::Obstacle, "seed {} {}", seed, terrain);
+                let simulation = Simulation::setup(map, None);
+                let station = &simulation.station();
+                assert_eq!((station.x, station.y), site);
+                for (x, y) in zone(station.x, station.y, map::STARTING_ZONE_RADIUS) {
+                    assert_ne!(simulation.map.get_cell(x, y).unwrap().cell_type, CellType::Obstacle, "seed {} {}", seed, terrain);
+                }
+                assert_eq!(station.robots.len(), INITIAL_ROSTER.len());
+                for (i, robot) in station.robots.iter().enumerate() {
+                    assert_ne!(simulation.map.get_cell(robot.x, robot.y).unwrap().cell_type, CellType::Obstacle);
+                    assert_ne!((robot.x, robot.y), (station.x, station.y));
+                    assert!(station.robots[..i].iter().all(|other| (other.x, other.y) != (robot.x, robot.y)));
+                }
+                // The first robot starts right next to the station
+                assert_eq!((station.robots[0].x, station.robots[0].y), (station.x, station.y - 1));
+            }
+        }
+    }
+
+    // Every cell of the square within `radius` of a center away from the map edges
+    fn zone(center_x: usize, center_y: usize, radius: usize) -> Vec<(usize, usize)> {
+        (center_y - radius..=center_y + radius)
+            .flat_map(|y| (center_x - radius..=center_x + radius).map(move |x| (x, y)))
+            .collect()
+    }
+
+    #[test]
+    fn test_setup_clears_a_zone_on_a_rocky_hand_crafted_map() {
+        // Without a marked station the center is used, even when it is solid rock
+        let (map, station) = Map::from_ascii(&"#########\n".repeat(7)).unwrap();
+        let simulation = Simulation::setup(map, station);
+        assert_eq!((simulation.station().x, simulation.station().y), (4, 3));
+        assert!(zone(4, 3, 2).iter().all(|&(x, y)| simulation.map.get_cell(x, y).unwrap().cell_type == CellType::Empty));
+        for robot in &simulation.station().robots {
+            assert!(zone(4, 3, 2).contains(&(robot.x, robot.y)) && (robot.x, robot.y) != (4, 3));
+        }
+    }
+
+    #[test]
+    fn test_same_seed_replays_identically() {
+        let run = || {
+            let mut simulation = Simulation::setup(Map::new(60, 30, 77), None);
+            simulation.run_ticks(300);
+            let positions: Vec<_> = simulation.station().robots.iter().map(|r| (r.x, r.y, r.energy)).collect();
+            (positions, simulation.score(), simulation.state_hash())
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn test_state_hash_follows_the_run() {
+        let mut simulation = Simulation::setup(Map::new(60, 30, 77), None);
+        let start = simulation.state_hash();
+        assert_eq!(start, Simulation::setup(Map::new(60, 30, 77), None).state_hash());
+        assert_ne!(start, Simulation::setup(Map::new(60, 30, 78), None).state_hash());
+        simulation.run_ticks(50);
+        let later = simulation.state_hash();
+        assert_ne!(later, start);
+        // A robot nudged without anything else changing still shows up
+        simulation.stations[0].robots[0].energy += 1;
+        assert_ne!(simulation.state_hash(), later);
+    }
+
+    #[test]
+    fn test_subscribers_do_not_change_the_run() {
+        struct Faulty;
+        impl Subscriber for Faulty {
+            fn on_event(&mut self, _event: &SimEvent) {
+                panic!("faulty subscriber");
+            }
+        }
+
+        let run = |with_subscribers: bool| {
+            let mut simulation = Simulation::setup(Map::new(60, 30, 77), None);
+            if with_subscribers {
+                simulation.events.set_paranoid(true);
+                simulation.events.subscribe(Box::new(Faulty));
+                simulation.events.subscribe(Box::new(ActivityTally::default()));
+            }
+            simulation.run_ticks(300);
+            let positions: Vec<_> = simulation.station().robots.iter().map(|r| (r.x, r.y, r.energy)).collect();
+            let tally = simulation.events.subscriber::<ActivityTally>().cloned();
+            ((positions, simulation.score(), simulation.last_event_position), tally)
+        };
+
+        let (plain, no_tally) = run(false);
+        let (observed, tally) = run(true);
+        assert_eq!(plain, observed);
+        assert!(no_tally.is_none());
+        let tally = tally.unwrap();
+        assert!(tally.deliveries > 0 && tally.cells_shared > 0);
+    }
+
+    #[test]
+    fn test_explained_decisions_are_a_dry_run() {
+        #[derive(Default)]
+        struct Decisions(Vec<Explanation>);
+        impl Subscriber for Decisions {
+            fn on_event(&mut self, event: &SimEvent) {
+                if let SimEvent::StationDecision(explanation) = event {
+                    self.0.push(explanation.clone());
+                }
+            }
+        }
+
+        let run = |explain: bool| {
+            let mut simulation = Simulation::setup(Map::new(60, 30, 77), None);
+            simulation.explain_station = explain;
+            simulation.events.subscribe(Box::new(Decisions::default()));
+            simulation.run_ticks(300);
+            let positions: Vec<_> = simulation.station().robots.iter().map(|r| (r.x, r.y, r.energy)).collect();
+            let decisions = simulation.events.subscriber::<Decisions>().unwrap().0.clone();
+            let logged: Vec<String> = simulation.station().events.lines(STATION_LOG_CAPACITY, 0, true);
+            ((positions, simulation.score()), (decisions, logged))
+        };
+
+        let (plain, (none, none_logged)) = run(false);
+        let (explained, (decisions, logged)) = run(true);
+        assert_eq!(plain, explained);
+        assert!(none.is_empty() && none_logged.is_empty());
+        // The station log keeps the same decisions for its decision filter
+        assert_eq!(logged.len(), decisions.len());
+        assert!(logged[0].starts_with("tick      0: [decision] refinery: "), "{}", logged[0]);
+        // Standing verdicts are explained when they change, not every tick
+        assert_eq!(decisions.iter().filter(|decision| decision.tick == 0).map(|decision| decision.decision).collect::<Vec<_>>(), ["refinery", "build"]);
+        assert!(decisions.len() < 300);
+        assert!(decisions.iter().any(|decision| decision.decision == "explorer target"));
+        let first_build = decisions.iter().find(|decision| decision.decision == "build").unwrap();
+        assert!(first_build.line().starts_with("[decision] tick 0 build: "), "{}", first_build.line());
+        assert!(first_build.inputs.iter().any(|(name, value)| *name == "robots" && value == "9"));
+    }
+
+    // A two-lane corridor with the station at its west end and an explorer far out east with
+    // `energy` left; the second lane lets robots heading home pass each other
+    fn stranded_scenario(energy: u32) -> Simulation {
+        let row = ".".repeat(40);
+        let (map, station_position) = Map::from_ascii(&format!("H{}\n.{}\n", row, row)).unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        let mut stranded = Robot::new_with_type(40, 0, RobotType::Explorer);
+        stranded.energy = energy;
+        station.robots.push(stranded);
+        let mut simulation = Simulation::new(map, station);
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+        simulation
+    }
+
+    #[test]
+    fn test_stranded_robot_is_rescued() {
+        let mut simulation = stranded_scenario(10);
+        let mut rescuer = Robot::new_with_type(30, 0, RobotType::EnergyCollector);
+        rescuer.energy_cargo = 60;
+        simulation.stations[0].robots.push(rescuer);
+
+        simulation.tick();
+        assert_eq!(simulation.station().robots[0].state, robot::RobotState::Stranded);
+        assert_eq!(simulation.last_event_position, Some((40, 0))); // Distress call
+        assert!(matches!(simulation.station().robots[1].state, robot::RobotState::Rescuing { target: 0, .. }));
+
+        simulation.run_ticks(80);
+        let tally = simulation.events.subscriber::<ActivityTally>().unwrap();
+        assert_eq!((tally.rescues, tally.wrecks), (1, 0));
+        // The rescued robot made it home on its own energy and went back to work
+        let robot = &simulation.station().robots[0];
+        assert!(robot.energy > 0);
+        assert!(robot.state != robot::RobotState::Stranded);
+        assert!(tally.deliveries > 0 || tally.energy_refueled > 0);
+    }
+
+    #[test]
+    fn test_robots_refuel_to_their_own_battery_size() {
+        let (map, station_position) = Map::from_ascii("H...\n....\n").unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        for robot_type in [RobotType::Explorer, RobotType::Carrier] {
+            let mut robot = Robot::new_with_type(station_x, station_y, robot_type);
+            robot.energy = 40;
+            robot.state = robot::RobotState::AtStation;
+            station.add_robot(robot);
+        }
+        let mut simulation = Simulation::new(map, station);
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+        let station_energy = simulation.station().energy;
+
+        simulation.tick();
+        let (explorer, carrier) = (&simulation.station().robots[0], &simulation.station().robots[1]);
+        assert_eq!(carrier.spec.max_energy, 150);
+        assert_eq!((explorer.energy, carrier.energy), (robot::INITIAL_ROBOT_ENERGY, 150));
+        let refueled = simulation.events.subscriber::<ActivityTally>().unwrap().energy_refueled;
+        assert_eq!(refueled, 60 + 110);
+        assert_eq!(simulation.station().energy, station_energy - refueled);
+    }
+
+    #[test]
+    fn test_retrofitted_explorer_stays_docked_then_works_as_its_new_type() {
+        let (mut map, station_position) = Map::from_ascii("H..e\n####\n....\n").unwrap(); // The other explorers walled off
+        map.iter_cells_mut().for_each(|(_, _, cell)| cell.explored = true);
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        station.refined_minerals = station::RETROFIT_MINERAL_COST + 5; // Too little to build a robot
+        station.known_map.insert((3, 0), KnownCell::new(map.get_cell(3, 0).unwrap().cell_type.clone(), 0));
+        let mut docked = Robot::new_with_type(station_x, station_y, RobotType::Explorer);
+        docked.state = robot::RobotState::AtStation;
+        station.add_robot(docked);
+        for x in 1..3 {
+            station.add_robot(Robot::new_with_type(x, 2, RobotType::Explorer));
+        }
+        let mut simulation = Simulation::new(map, station);
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+
+        simulation.tick();
+        assert_eq!(simulation.events.subscriber::<ActivityTally>().unwrap().retrofits, 1);
+        assert_eq!(simulation.station().refined_minerals, 5);
+        assert_eq!(simulation.station().robots[0].robot_type, RobotType::EnergyCollector);
+        for _ in 1..station::RETROFIT_TICKS {
+            assert_eq!(simulation.station().robots[0].state, robot::RobotState::AtStation);
+            simulation.tick();
+        }
+        assert_eq!(simulation.station().robots[0].state, robot::RobotState::AtStation);
+        simulation.tick();
+        assert_eq!(simulation.station().robots[0].state, robot::RobotState::Exploring);
+        // Off to harvest the energy deposit like any energy collector
+        for _ in 0..5 {
+            simulation.tick();
+        }
+        assert!(simulation.station().robots[0].energy_cargo > 0);
+    }
+
+    #[test]
+    fn test_lifetime_stats_count_what_the_station_took_in_and_respawns() {
+        let (map, station_position) = Map::from_ascii("H...\n....\n").unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        station.capacity.minerals = station.refined_minerals + 30; // Room for 30 of the 50 carried
+        let mut collector = Robot::new_with_type(station_x, station_y, RobotType::MineralCollector);
+        (collector.minerals, collector.state) = (50, robot::RobotState::AtStation);
+        station.add_robot(collector);
+        let mut wreck = Robot::new_with_type(2, 1, RobotType::Explorer);
+        wreck.energy = 0;
+        station.add_robot(wreck);
+        let mut simulation = Simulation::new(map, station);
+
+        simulation.tick();
+        let (collector, wreck) = (simulation.station().robots[0].lifetime_stats(), simulation.station().robots[1].lifetime_stats());
+        assert_eq!((collector.minerals_delivered, simulation.station().robots[0].minerals), (30, 20));
+        assert_eq!(collector.respawns, 0);
+        assert_eq!(wreck.respawns, 1);
+    }
+
+    #[test]
+    fn test_dead_robot_waiting_at_an_empty_station_is_one_wreck() {
+        let (map, station_position) = Map::from_ascii("H...\n....\n").unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        station.energy = 0; // Nothing to respawn or refuel with
+        let mut wreck = Robot::new_with_type(2, 1, RobotType::Explorer);
+        wreck.energy = 0;
+        station.add_robot(wreck);
+        let mut simulation = Simulation::new(map, station);
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+
+        simulation.run_ticks(5);
+        assert_eq!(simulation.events.subscriber::<ActivityTally>().unwrap().wrecks, 1);
+        let robot = &simulation.station().robots[0];
+        assert_eq!((robot.x, robot.y, robot.energy, robot.state), (station_x, station_y, 0, robot::RobotState::AtStation));
+    }
+
+    #[test]
+    fn test_wreck_parts_are_stripped_once_while_the_robot_waits_for_energy() {
+        let (map, station_position) = Map::from_ascii("H...\n....\n").unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        station.energy = 0;
+        let mut wreck = Robot::new_with_type(2, 1, RobotType::Explorer);
+        wreck.energy = 0;
+        station.add_robot(wreck);
+        let mut simulation = Simulation::new(map, station);
+
+        simulation.tick();
+        let parts = station::wreck_parts(RobotType::Explorer);
+        assert_eq!(simulation.station().parts, parts);
+        simulation.run_ticks(5);
+        assert_eq!(simulation.station().parts, parts);
+    }
+
+    #[test]
+    fn test_station_logs_deliveries_deaths_and_new_robots() {
+        let (map, station_position) = Map::from_ascii("H..e\n...m\n").unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        station.known_map.insert((3, 0), KnownCell::new(CellType::Energy(50), 0));
+        station.known_map.insert((3, 1), KnownCell::new(CellType::Mineral(50), 0));
+        let mut collector = Robot::new_with_type(station_x, station_y, RobotType::MineralCollector);
+        (collector.minerals, collector.state) = (20, robot::RobotState::AtStation);
+        station.add_robot(collector);
+        let mut wreck = Robot::new_with_type(2, 1, RobotType::Explorer);
+        wreck.energy = 0;
+        station.add_robot(wreck);
+        let mut simulation = Simulation::new(map, station);
+        simulation.tick();
+        simulation.stations[0].energy = 0; // The respawned robot running dry again cannot be brought back
+        simulation.stations[0].robots[1].energy = 0;
+        simulation.run_ticks(2);
+        simulation.stations[0].set_recall(true, simulation.clock.now());
+
+        let log = &simulation.station().events;
+        let events: Vec<(u64, StationEvent)> = log.entries.iter().map(|entry| (entry.tick, entry.event.clone())).collect();
+        assert_eq!(events[..3], [
+            (0, StationEvent::ResourceDelivered { robot: 0, energy: 0, minerals: 20, science: 0 }),
+            (0, StationEvent::RobotDied { robot: 1 }),
+            (0, StationEvent::RobotRespawned { robot: 1 }),
+        ]);
+        assert!(matches!(events[3], (0, StationEvent::RobotCreated { robot: 2, .. })));
+        // Waiting docked for energy is no second death
+        assert_eq!(events.iter().filter(|(_, event)| matches!(event, StationEvent::RobotDied { .. })).count(), 1);
+        assert_eq!(events.last(), Some(&(3, StationEvent::RecallIssued { recall: true })));
+        assert!(log.summary().starts_with("robot created "), "{}", log.summary());
+
+        // Docking with cargo full storage turns away is no delivery
+        let station = &mut simulation.stations[0];
+        station.capacity.minerals = station.raw_minerals + station.refined_minerals;
+        let mut hauler = Robot::new_with_type(station_x, station_y, RobotType::MineralCollector);
+        (hauler.minerals, hauler.state) = (15, robot::RobotState::AtStation);
+        let hauler = station.add_robot(hauler);
+        let logged = station.events.entries.len();
+        simulation.tick();
+        assert_eq!(simulation.station().robots[hauler].minerals, 15);
+        let new_entries: Vec<StationEvent> = simulation.station().events.entries.iter().skip(logged).map(|entry| entry.event.clone()).collect();
+        assert!(!new_entries.iter().any(|event| matches!(event, StationEvent::ResourceDelivered { robot, .. } if *robot == hauler)), "{:?}", new_entries);
+    }
+
+    #[test]
+    fn test_worn_robots_refuel_dearer_and_are_scrapped_once_replaceable() {
+        let (map, station_position) = Map::from_ascii("H...\n....\n").unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        station.refined_minerals = 0; // No replacement affordable yet
+        let mut robot = Robot::new_with_type(station_x, station_y, RobotType::Explorer);
+        robot.energy = 40;
+        robot.lifetime.distance = robot.behavior_config.wear_after_steps;
+        robot.state = robot::RobotState::AtStation;
+        station.add_robot(robot);
+        let mut simulation = Simulation::new(map, station);
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+        let station_energy = simulation.station().energy;
+
+        simulation.tick();
+        assert_eq!(simulation.station().robots[0].energy, robot::INITIAL_ROBOT_ENERGY);
+        assert_eq!(simulation.station().energy, station_energy - 75); // 60 energy and the 25% surcharge
+
+        let robot = &mut simulation.stations[0].robots[0];
+        (robot.x, robot.y, robot.energy, robot.state) = (station_x, station_y, 40, robot::RobotState::AtStation);
+        simulation.stations[0].refined_minerals = 100;
+        simulation.tick();
+        let tally = simulation.events.subscriber::<ActivityTally>().unwrap();
+        assert_eq!(tally.decommissioned, 1);
+        assert_eq!(simulation.station().refined_minerals, 120);
+        for _ in 0..5 {
+            simulation.tick();
+        }
+        // Never respawned or sent out again
+        let scrapped = &simulation.station().robots[0];
+        assert_eq!((scrapped.state, scrapped.energy), (robot::RobotState::Decommissioned, 0));
+        assert_eq!(simulation.station().fleet_size(), simulation.station().robots.len() - 1);
+    }
+
+    #[test]
+    fn test_recalled_fleet_stays_docked_until_the_recall_is_lifted() {
+        let (map, station_position) = Map::from_ascii(&vec![".".repeat(12); 6].join("\n")).unwrap();
+        let _ = station_position;
+        let mut station = Station::new(0, 0);
+        for (index, robot_type) in [RobotType::Explorer, RobotType::EnergyCollector, RobotType::MineralCollector, RobotType::Miner, RobotType::Carrier]
+            .into_iter()
+            .enumerate()
+        {
+            station.add_robot(Robot::new_with_type(3 + index, 2, robot_type));
+        }
+        station.refined_minerals = 1000; // Plenty to build with
+        let mut simulation = Simulation::new(map, station);
+        simulation.stations[0].set_recall(true, 0);
+        for _ in 0..40 {
+            simulation.tick();
+        }
+        assert_eq!(simulation.station().robots.len(), 5);
+        for robot in &simulation.station().robots {
+            assert_eq!((robot.x, robot.y, robot.state), (0, 0, robot::RobotState::Idle), "{:?}", robot.robot_type);
+        }
+
+        simulation.stations[0].set_recall(false, 40);
+        for _ in 0..3 {
+            simulation.tick();
+        }
+        assert!(simulation.station().robots.iter().all(|robot| robot.state != robot::RobotState::Idle));
+        assert!(simulation.station().robots.iter().any(|robot| (robot.x, robot.y) != (0, 0)));
+    }
+
+    #[test]
+    fn test_one_collector_claims_a_deposit_and_the_other_diverts() {
+        let (map, _) = Map::from_ascii_with_amount(".........\n....e....\n.........\n", 20).unwrap();
+        let mut station = Station::new(0, 0);
+        for x in [2, 6] {
+            station.add_robot(Robot::new_with_type(x, 1, RobotType::EnergyCollector));
+        }
+        let mut simulation = Simulation::new(map, station);
+
+        simulation.tick();
+        assert_eq!(simulation.station().claimed_targets, std::collections::HashMap::from([((4, 1), 0)]));
+        assert_eq!(simulation.station().robots[0].current_target, Some((4, 1)));
+        assert_eq!(simulation.station().robots[1].current_target, None);
+        while simulation.map.get_cell(4, 1).unwrap().cell_type != CellType::Empty {
+            assert!(simulation.clock.now() < 10);
+            assert_ne!((simulation.station().robots[1].x, simulation.station().robots[1].y), (4, 1));
+            simulation.tick();
+        }
+
+        // The claim lapses with the deposit
+        simulation.tick();
+        assert!(simulation.station().claimed_targets.is_empty());
+    }
+
+    #[test]
+    fn test_carrier_brings_a_collectors_cargo_home() {
+        let (map, station_position) = Map::from_ascii(&format!("H{}\n", ".".repeat(40))).unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        let mut collector = Robot::new_with_type(30, 0, RobotType::MineralCollector);
+        collector.minerals = 30; // Not enough to send it home yet
+        station.add_robot(collector);
+        station.add_robot(Robot::new_with_type(1, 0, RobotType::Carrier));
+        let minerals = |simulation: &Simulation| {
+            let station = &simulation.station();
+            station.raw_minerals + station.refined_minerals + station.robots.iter().map(|robot| robot.minerals).sum::<u32>()
+        };
+        let mut simulation = Simulation::new(map, station);
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+        let before = minerals(&simulation);
+
+        simulation.run_ticks(90);
+        let tally = simulation.events.subscriber::<ActivityTally>().unwrap();
+        assert_eq!(tally.cargo_handovers, 1);
+        assert!(tally.deliveries >= 1);
+        assert_eq!(minerals(&simulation), before);
+        assert_eq!(simulation.station().raw_minerals + simulation.station().refined_minerals, 500 + 30);
+        assert!(simulation.station().robots[0].carrier_grace > 0 || simulation.station().robots[0].state == robot::RobotState::Exploring);
+    }
+
+    #[test]
+    fn test_queued_collect_task_brings_the_deposit_home() {
+        let (map, station_position) = Map::from_ascii(&format!("H{}m\n", ".".repeat(15))).unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let deposit = map.get_cell(16, 0).unwrap().cell_type.clone();
+        let CellType::Mineral(amount) = deposit else {
+            panic!("expected a mineral vein, found {:?}", deposit);
+        };
+        let mut station = Station::new(station_x, station_y);
+        station.known_map.insert((16, 0), KnownCell::new(deposit, 0));
+        station.task_queue.push_back(Task::CollectAt { x: 16, y: 0 });
+        let mut collector = Robot::new_with_type(station_x, station_y, RobotType::MineralCollector);
+        collector.state = robot::RobotState::AtStation;
+        station.add_robot(collector);
+        let mut simulation = Simulation::new(map, station);
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+        let minerals = |simulation: &Simulation| simulation.station().raw_minerals + simulation.station().refined_minerals;
+        let before = minerals(&simulation);
+
+        simulation.tick();
+        assert_eq!(simulation.station().robots[0].current_task, Some(Task::CollectAt { x: 16, y: 0 }));
+        assert!(simulation.station().task_queue.is_empty());
+
+        simulation.run_ticks(200);
+        assert_eq!(simulation.map.get_cell(16, 0).unwrap().cell_type, CellType::Empty);
+        assert_eq!(simulation.station().robots[0].current_task, None);
+        assert_eq!(minerals(&simulation), before + amount);
+        assert_eq!(simulation.events.subscriber::<ActivityTally>().unwrap().tasks_assigned, 1);
+    }
+
+    #[test]
+    fn test_collectors_mine_the_underground_on_a_two_layer_map() {
+        let underground_minerals = |map: &Map| -> u32 {
+            (0..map.height)
+                .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+                .filter_map(|(x, y)| match map.layer_cell(x, y, map::UNDERGROUND)?.cell_type {
+                    CellType::Mineral(amount) => Some(amount),
+                    _ => None,
+                })
+                .sum()
+        };
+        let mut simulation = Simulation::setup(Map::new_two_layer(100, 50, 1), None);
+        let before = underground_minerals(&simulation.map);
+        simulation.run_ticks(500);
+        assert!(underground_minerals(&simulation.map) < before);
+        // Every robot docked at a station stands on the surface
+        assert!(simulation.robots().filter(|robot| robot.state == robot::RobotState::AtStation).all(|robot| robot.z == map::SURFACE));
+    }
+
+    #[test]
+    fn test_outpost_task_founds_a_station_where_the_robot_arrives() {
+        let row = ".".repeat(40);
+        let (map, station_position) = Map::from_ascii(&format!(".{}\nH{}\n.{}\n", row, row, row)).unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        let mut builder = Robot::new_with_type(station_x, station_y, RobotType::MineralCollector);
+        builder.state = robot::RobotState::AtStation;
+        station.add_robot(builder);
+        let mut simulation = Simulation::new(map, station);
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+
+        let site = simulation.order_outpost().unwrap();
+        assert!(site.0.abs_diff(station_x) >= MIN_STATION_SEPARATION);
+        // The site ordered is kept apart from the ones already on order
+        let second = simulation.order_outpost().unwrap();
+        assert!(second.0.abs_diff(site.0).max(second.1.abs_diff(site.1)) >= MIN_STATION_SEPARATION);
+        simulation.stations[0].task_queue.pop_back();
+
+        simulation.tick();
+        assert_eq!(simulation.station().robots[0].current_task, Some(Task::BuildOutpostAt { x: site.0, y: site.1 }));
+        simulation.run_ticks(3 * MIN_STATION_SEPARATION as u32);
+        assert_eq!(simulation.station_sites(), vec![(station_x, station_y), site]);
+        assert_eq!(simulation.stations[1].robots.len(), INITIAL_ROSTER.len());
+        assert_eq!(simulation.station().robots[0].current_task, None);
+        assert_eq!(simulation.station().robots[0].home_station, 0);
+    }
+
+    #[test]
+    fn test_docking_collector_is_sent_to_the_nearest_known_deposit() {
+        let (map, station_position) = Map::from_ascii("H....m...m....m\n...............\n").unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        for x in [14, 5, 9] {
+            station.known_map.insert((x, 0), KnownCell::new(map.get_cell(x, 0).unwrap().cell_type.clone(), 0));
+        }
+        let mut collector = Robot::new_with_type(station_x, station_y, RobotType::MineralCollector);
+        collector.state = robot::RobotState::AtStation;
+        station.add_robot(collector);
+        let mut simulation = Simulation::new(map, station);
+        simulation.malfunction_odds = 0;
+        simulation.clock.fast_forward(1); // Off the queue's refresh ticks
+
+        simulation.tick();
+        assert_eq!(simulation.station().robots[0].current_task, Some(Task::CollectAt { x: 5, y: 0 }));
+        assert_eq!(simulation.station().claimed_targets.get(&(5, 0)), None);
+        simulation.tick();
+        assert_eq!(simulation.station().claimed_targets.get(&(5, 0)), Some(&0)); // Reserved on the way
+
+        let first_deposit = (0..20).find_map(|_| {
+            simulation.tick();
+            let robot = &simulation.station().robots[0];
+            (robot.minerals > 0).then_some((robot.x, robot.y))
+        });
+        assert_eq!(first_deposit, Some((5, 0)));
+    }
+
+    #[test]
+    fn test_explorers_spread_out_instead_of_following_each_other() {
+        let row = ".".repeat(41);
+        let mut ascii = format!("{}\n", row).repeat(41);
+        ascii.replace_range(20 * 42 + 20..20 * 42 + 21, "H");
+        let (map, station_position) = Map::from_ascii(&ascii).unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        for (x, y) in [(21, 20), (22, 20), (21, 21), (22, 21)] {
+            station.add_robot(Robot::new_with_type(x, y, RobotType::Explorer));
+        }
+        let mut simulation = Simulation::new(map, station);
+
+        simulation.run_ticks(50);
+        let (map, robots) = (&simulation.map, &simulation.station().robots[..4]);
+        let closest = robots
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| robots[i + 1..].iter().map(move |b| map.distance(a.x, a.y, b.x, b.y)))
+            .min()
+            .unwrap();
+        // Without the crowding penalty two of them end up side by side
+        assert!(closest >= 3, "two explorers ended up {} cells apart", closest);
+    }
+
+    #[test]
+    fn test_repairer_fixes_a_broken_robot_and_the_station_pays() {
+        let (map, station_position) = Map::from_ascii(&format!("H{}\n", ".".repeat(20))).unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        let mut broken = Robot::new_with_type(12, 0, RobotType::MineralCollector);
+        (broken.integrity, broken.state) = (0, robot::RobotState::Broken);
+        station.add_robot(broken);
+        station.add_robot(Robot::new_with_type(1, 0, RobotType::Repairer));
+        let mut simulation = Simulation::new(map, station);
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+        let minerals = simulation.station().refined_minerals;
+
+        // Broken, not dead: it waits in place instead of being hauled back to the station
+        simulation.tick();
+        assert_eq!((simulation.station().robots[0].x, simulation.station().robots[0].state), (12, robot::RobotState::Broken));
+
+        simulation.run_ticks(40);
+        let tally = simulation.events.subscriber::<ActivityTally>().unwrap();
+        assert_eq!((tally.repairs, tally.respawns), (1, 0));
+        assert_eq!(simulation.station().robots[0].integrity, robot::MAX_INTEGRITY);
+        assert_ne!(simulation.station().robots[0].state, robot::RobotState::Broken);
+        // The repairer came home and settled the bill
+        assert_eq!(simulation.station().robots[1].repair_bill, 0);
+        assert_eq!(simulation.station().refined_minerals, minerals - 20);
+    }
+
+    #[test]
+    fn test_station_repairs_malfunctions_it_can_pay_for() {
+        let docked_with_faults = |refined_minerals: u32| {
+            let (map, station_position) = Map::from_ascii("H..\n").unwrap();
+            let (station_x, station_y) = station_position.unwrap();
+            let mut station = Station::new(station_x, station_y);
+            station.refined_minerals = refined_minerals;
+            let mut robot = Robot::new_with_type(station_x, station_y, RobotType::MineralCollector);
+            robot.malfunctions = vec![Malfunction::SlowDrive, Malfunction::StuckActuator(robot::Direction::North)];
+            robot.state = robot::RobotState::ReturningToStation;
+            station.robots.push(robot);
+            let mut simulation = Simulation::new(map, station);
+            simulation.malfunction_odds = 0;
+            simulation.events.subscribe(Box::new(ActivityTally::default()));
+            simulation.tick();
+            simulation
+        };
+        let repaired = docked_with_faults(10);
+        assert!(repaired.station().robots[0].malfunctions.is_empty());
+        assert_eq!(repaired.station().refined_minerals, 10 - 2 * robot::MALFUNCTION_REPAIR_MINERALS);
+        assert_eq!(repaired.events.subscriber::<ActivityTally>().unwrap().malfunctions_repaired, 2);
+
+        // Short of minerals for both, it heads out again with both faults
+        let degraded = docked_with_faults(5);
+        assert_eq!(degraded.station().robots[0].malfunctions.len(), 2);
+        assert_eq!(degraded.station().refined_minerals, 5);
+    }
+
+    #[test]
+    fn test_malfunctions_strike_the_same_way_on_a_replay() {
+        let run = || {
+            let mut simulation = Simulation::setup(Map::new(60, 30, 77), None);
+            simulation.malfunction_odds = 50;
+            simulation.events.subscribe(Box::new(ActivityTally::default()));
+            simulation.run_ticks(200);
+            let malfunctions: Vec<_> = simulation.station().robots.iter().map(|r| r.malfunctions.clone()).collect();
+            (simulation.events.subscriber::<ActivityTally>().unwrap().malfunctions, malfunctions, simulation.state_hash())
+        };
+        let (struck, _, _) = run();
+        assert!(struck > 0);
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn test_buddy_reports_where_its_leader_went_down() {
+        let (map, station_position) = Map::from_ascii(&format!("H{}\n{}\n", ".".repeat(30), ".".repeat(31))).unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        station.pair_explorers = true;
+        station.add_robot(Robot::new_with_type(10, 0, RobotType::Explorer));
+        station.add_robot(Robot::new_with_type(12, 1, RobotType::Explorer));
+        let mut simulation = Simulation::new(map, station);
+        simulation.malfunction_odds = 0;
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+
+        simulation.stations[0].robots[0].energy = 0;
+        simulation.tick();
+        assert_eq!(simulation.station().explorer_pairs, vec![(0, 1)]);
+        assert_eq!(simulation.station().robots[1].wreck_reports, vec![(10, 0)]);
+
+        // Back at the station the report sends an explorer to where the leader was lost
+        let trailer = &mut simulation.stations[0].robots[1];
+        (trailer.x, trailer.y, trailer.state) = (station_x, station_y, robot::RobotState::ReturningToStation);
+        simulation.tick();
+        assert!(simulation.station().robots[1].wreck_reports.is_empty());
+        assert_eq!(simulation.events.subscriber::<ActivityTally>().unwrap().buddies_lost, 1);
+        let sent_back = |robot: &Robot| robot.current_task == Some(Task::ExploreRegion { x: 10, y: 0 });
+        assert!(simulation.station().robots.iter().any(sent_back));
+    }
+
+    #[test]
+    fn test_robot_running_dry_is_given_energy_by_a_charged_neighbour() {
+        let (map, _) = Map::from_ascii(&".".repeat(90)).unwrap();
+        let robot_at = |x: usize, energy: u32| {
+            let mut robot = Robot::new_with_type(x, 0, RobotType::MineralCollector);
+            robot.energy = energy;
+            robot
+        };
+        let total = |robots: &[Robot]| robots.iter().map(|robot| robot.energy).sum::<u32>();
+
+        // Near home the donor can spare the full amount; only the needy robot is helped
+        let mut robots = vec![robot_at(10, 90), robot_at(11, 5), robot_at(12, 30)];
+        let before = total(&robots);
+        assert_eq!(balance_energy(&mut robots, &map, &[(0, 0)]), vec![(1, 0, SHARED_ENERGY)]);
+        assert_eq!((robots[0].energy, robots[1].energy), (90 - SHARED_ENERGY, 5 + SHARED_ENERGY));
+        assert_eq!(total(&robots), before);
+        assert!(balance_energy(&mut robots, &map, &[(0, 0)]).is_empty());
+
+        // Far out the donor keeps what it needs to get home
+        let mut robots = vec![robot_at(70, 80), robot_at(71, 5)];
+        let budget = robots[0].energy - robots[0].spare_energy(&map, 0, 0);
+        assert_eq!(balance_energy(&mut robots, &map, &[(0, 0)]), vec![(1, 0, 80 - budget)]);
+        assert_eq!(robots[0].energy, budget);
+        assert_eq!(total(&robots), 85);
+
+        // Nobody gives below the charge threshold, whatever it could spare
+        let mut robots = vec![robot_at(2, 60), robot_at(3, 5)];
+        assert!(balance_energy(&mut robots, &map, &[(0, 0)]).is_empty());
+    }
+
+    #[test]
+    fn test_robots_meeting_in_a_corridor_swap_places() {
+        let (map, station_position) = Map::from_ascii("H##########\n...........\n###########\n").unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        for (x, goal_x) in [(1, 10), (10, 1)] {
+            let mut robot = Robot::new_with_type(x, 1, RobotType::MineralCollector);
+            robot.relocating_to = Some((goal_x, 1));
+            station.add_robot(robot);
+        }
+        let mut simulation = Simulation::new(map, station);
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+
+        // Neither can step aside, so they only get by each other by trading places
+        let mut arrived = [false; 2];
+        for _ in 0..40 {
+            simulation.tick();
+            for ((robot, arrived), goal_x) in simulation.station().robots.iter().zip(&mut arrived).zip([10, 1]) {
+                *arrived |= (robot.x, robot.y) == (goal_x, 1);
+            }
+        }
+        assert_eq!(arrived, [true; 2]);
+        assert!(simulation.events.subscriber::<ActivityTally>().unwrap().swaps >= 1);
+    }
+
+    #[test]
+    fn test_low_power_robot_is_revived_by_a_carrier_with_battery_to_spare() {
+        let mut simulation = stranded_scenario(robot::LOW_POWER_FLOOR - 1);
+        // No harvested energy on board: the carrier gives from its own battery
+        simulation.stations[0].robots.push(Robot::new_with_type(30, 1, RobotType::Carrier));
+
+        simulation.tick();
+        assert!(matches!(simulation.station().robots[0].state, robot::RobotState::LowPower { .. }));
+        assert_eq!(simulation.last_event_position, Some((40, 0))); // Distress call
+        assert!(matches!(simulation.station().robots[1].state, robot::RobotState::Rescuing { target: 0, .. }));
+
+        simulation.run_ticks(80);
+        let tally = simulation.events.subscriber::<ActivityTally>().unwrap();
+        assert_eq!((tally.rescues, tally.power_downs, tally.wrecks), (1, 0, 0));
+        let (robot, carrier) = (&simulation.station().robots[0], &simulation.station().robots[1]);
+        assert!(robot.energy > 0 && !robot.is_waiting_for_rescue());
+        assert_eq!(robot.lifetime.respawns, 0);
+        assert!(carrier.energy > 0);
+    }
+
+    #[test]
+    fn test_unreachable_stranded_robot_becomes_a_wreck() {
+        let mut simulation = stranded_scenario(robot::LOW_POWER_FLOOR + 2);
+        simulation.tick();
+        let robot = &simulation.station().robots[0];
+        assert_eq!(robot.state, robot::RobotState::Stranded);
+        assert_eq!((robot.x, robot.y, robot.energy), (40, 0, robot::LOW_POWER_FLOOR + 2)); // Halted instead of walking on
+
+        // No robot anywhere to help: the distress beacon drains the battery down to the floor,
+        // where the robot goes into low power and stops draining
+        simulation.run_ticks(2 * robot::STRANDED_DRAIN_INTERVAL);
+        let robot = &simulation.station().robots[0];
+        assert!(matches!(robot.state, robot::RobotState::LowPower { .. }));
+        assert_eq!(robot.energy, robot::LOW_POWER_FLOOR);
+        simulation.run_ticks(robot::LOW_POWER_TIMEOUT - 1);
+        assert_eq!(simulation.station().robots[0].energy, robot::LOW_POWER_FLOOR);
+
+        // Until it waited too long and powers down for good
+        simulation.run_ticks(1);
+        let tally = simulation.events.subscriber::<ActivityTally>().unwrap();
+        assert_eq!((tally.rescues, tally.power_downs, tally.wrecks), (0, 1, 1));
+        let robot = &simulation.station().robots[0];
+        assert_eq!((robot.x, robot.y), (simulation.station().x, simulation.station().y)); // Recovered to the station
+        assert_eq!(simulation.station().parts, station::wreck_parts(robot.robot_type));
+    }
+
+    // A two-lane corridor with the station at its west end and a mineral collector far out east
+    // hauling more than it can afford to carry home: 40 minerals double its step cost
+    fn cache_scenario() -> Simulation {
+        let row = ".".repeat(40);
+        let (map, station_position) = Map::from_ascii(&format!("H{}\n.{}\n", row, row)).unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        let mut hauler = Robot::new_with_type(40, 0, RobotType::MineralCollector);
+        hauler.energy = 60;
+        hauler.minerals = 40;
+        station.robots.push(hauler);
+        let mut simulation = Simulation::new(map, station);
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+        simulation
+    }
+
+    #[test]
+    fn test_unaffordable_cargo_is_cached_and_reported() {
+        let mut simulation = cache_scenario();
+        simulation.tick();
+
+        let robot = &simulation.station().robots[0];
+        assert_eq!((robot.minerals, robot.state), (0, robot::RobotState::ReturningToStation));
+        let cache = simulation.map.cache_at(40, 0).expect("cargo left where the robot stood");
+        assert_eq!((cache.minerals, cache.science), (40, 0));
+        assert_eq!(simulation.station().known_caches, vec![(40, 0)]);
+        assert_eq!(simulation.last_event_position, Some((40, 0)));
+        assert_eq!(simulation.events.subscriber::<ActivityTally>().unwrap().caches_dropped, 1);
+    }
+
+    #[test]
+    fn test_dispatched_collector_recovers_cache() {
+        let mut simulation = cache_scenario();
+        simulation.stations[0].robots.push(Robot::new_with_type(30, 0, RobotType::MineralCollector));
+
+        simulation.tick();
+        assert_eq!(simulation.station().robots[1].state, robot::RobotState::Retrieving { x: 40, y: 0 });
+
+        simulation.run_ticks(80);
+        let station = &simulation.station();
+        let tally = simulation.events.subscriber::<ActivityTally>().unwrap();
+        assert_eq!((tally.caches_recovered, tally.caches_lost), (1, 0));
+        assert!(simulation.map.caches.is_empty() && station.known_caches.is_empty());
+        assert_eq!(station.raw_minerals + station.refined_minerals, 500 + 40); // Delivered after all
+    }
+
+    #[test]
+    fn test_unclaimed_cache_expires() {
+        let mut simulation = cache_scenario();
+        simulation.cache_rules = map::CacheRules { lifetime: 20, decay_interval: 0 };
+        simulation.tick();
+        assert_eq!(simulation.map.caches.len(), 1);
+
+        // Nobody can fetch it before it runs out
+        simulation.run_ticks(20);
+        let tally = simulation.events.subscriber::<ActivityTally>().unwrap();
+        assert_eq!((tally.caches_recovered, tally.caches_lost), (0, 1));
+        assert!(simulation.map.caches.is_empty() && simulation.station().known_caches.is_empty());
+        assert_eq!(simulation.station().raw_minerals + simulation.station().refined_minerals, 500);
+    }
+
+    #[test]
+    fn test_caches_age_once_a_tick_however_many_stations() {
+        // Ticks until a cache nobody fetches is lost, with `stations` bases all knowing of it
+        let lifetime = |stations: usize| {
+            let mut simulation = cache_scenario();
+            simulation.cache_rules = map::CacheRules { lifetime: 20, decay_interval: 0 };
+            for _ in 1..stations {
+                simulation.add_outpost().unwrap();
+            }
+            simulation.map.drop_cache(40, 0, 40, 0);
+            for station in &mut simulation.stations {
+                station.robots.clear();
+                station.known_caches.push((40, 0));
+            }
+            let mut ticks = 0;
+            while !simulation.map.caches.is_empty() {
+                simulation.tick();
+                ticks += 1;
+            }
+            assert!(simulation.stations.iter().all(|station| station.known_caches.is_empty()));
+            ticks
+        };
+        assert_eq!(lifetime(2), lifetime(1));
+    }
+
+    #[test]
+    fn test_robot_keeps_cargo_the_station_cannot_store() {
+        let (map, station_position) = Map::from_ascii("H..\n").unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        station.capacity.minerals = station.refined_minerals + 10;
+        let mut robot = Robot::new_with_type(station_x, station_y, RobotType::MineralCollector);
+        robot.minerals = 25;
+        robot.state = robot::RobotState::ReturningToStation;
+        station.robots.push(robot);
+        let mut simulation = Simulation::new(map, station);
+
+        simulation.tick();
+        let station = &simulation.station();
+        assert_eq!(station.robots[0].minerals, 15); // Still partially loaded
+        // The full store pushed the station to spend on a new robot straight away
+        assert_eq!(station.robots.len(), 2);
+        assert!(!station.storage_full);
+        assert!(station.raw_minerals + station.refined_minerals < station.capacity.minerals);
+    }
+
+    #[test]
+    fn test_scientist_delivers_full_science_value() {
+        let (mut map, station_position) = Map::from_ascii("H..\n...\n").unwrap();
+        map.get_cell_mut(1, 0).unwrap().cell_type = CellType::SciencePoint(25);
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        station.robots.push(Robot::new_with_type(1, 0, RobotType::Scientist));
+        let mut simulation = Simulation::new(map, station);
+
+        // Analyze the point, turn back with more than enough science, then dock and unload
+        simulation.run_ticks(4 + robot::ANALYSIS_TICKS);
+
+        // Some of it may have gone on an upgrade already
+        assert_eq!(simulation.station().science_points + simulation.station().upgrades.science_spent(), 25 * robot::ANALYSIS_YIELD_FACTOR);
+        assert!(simulation.station().analyzed_cells.contains(&(1, 0)));
+        assert_eq!(simulation.station().robots[0].science_points, 0);
+    }
+
+    #[test]
+    fn test_idle_scientist_stays_docked_to_analyze_data() {
+        let (map, station_position) = Map::from_ascii("H.........\n").unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        station.unanalyzed_data = 40;
+        let mut scientist = Robot::new_with_type(station_x, station_y, RobotType::Scientist);
+        scientist.state = robot::RobotState::AtStation;
+        station.robots.push(scientist);
+        let mut collector = Robot::new_with_type(5, 0, RobotType::EnergyCollector);
+        collector.state = robot::RobotState::ReturningToStation;
+        station.robots.push(collector);
+        let mut simulation = Simulation::new(map, station);
+
+        let mut collector_docked = false;
+        for _ in 0..40 {
+            simulation.tick();
+            let scientist = &simulation.station().robots[0];
+            assert_eq!((scientist.x, scientist.y, scientist.state), (station_x, station_y, robot::RobotState::AtStation));
+            collector_docked |= simulation.station().robots[1].x == station_x;
+        }
+        assert!(collector_docked, "the parked scientist must not block the station");
+        assert_eq!(simulation.station().science_from_analysis, 2);
+        assert_eq!(simulation.station().science_points, 2);
+
+        // With the archive analyzed the scientist heads out again
+        simulation.stations[0].unanalyzed_data = 0;
+        simulation.run_ticks(2);
+        assert_ne!(simulation.station().robots[0].state, robot::RobotState::AtStation);
+    }
+
+    #[test]
+    fn test_score() {
+        let (mut map, _) = Map::from_ascii("....\n").unwrap();
+        map.explore(0, 0, 0);
+        map.explore(1, 0, 0);
+        let mut station = Station::new(3, 0);
+        station.refined_minerals = 10;
+        station.raw_minerals = 5;
+        station.science_points = 2;
+        let simulation = Simulation::new(map, station);
+        assert_eq!(simulation.score(), 2 * 10 + 2 * 100 + 15 * 2);
+    }
+
+    #[test]
+    fn test_explorer_reaches_a_far_target_through_refuel_stops() {
+        // A corridor 120 long: one charge gets nowhere near the end and back. A deposit on the
+        // corridor and another in a niche off it are known to the station
+        let corridor = format!("H{}e{}\n{}e{}\n", ".".repeat(49), ".".repeat(70), "#".repeat(70), "#".repeat(50));
+        let (map, station_position) = Map::from_ascii_with_amount(&corridor, 80).unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        station.known_map.insert((50, 0), KnownCell::new(CellType::Energy(80), 0));
+        station.known_map.insert((70, 1), KnownCell::new(CellType::Energy(80), 0));
+        station.refined_minerals = 0; // No new robots to crowd the corridor
+        let mut explorer = Robot::new_with_type(1, 0, RobotType::Explorer);
+        explorer.target_x = Some(120);
+        explorer.target_y = Some(0);
+        station.robots.push(explorer);
+        let mut simulation = Simulation::new(map, station);
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+
+        // Out through the corridor deposit; the niche is only worth it on the way back, as
+        // topping up twice on the way out leaves the robot too heavy to make it home
+        assert_eq!(simulation.stations[0].plan_expedition(0, &simulation.map), 2);
+        let stops: Vec<_> = simulation.station().robots[0].expedition.iter().map(|leg| (leg.x, leg.y, leg.refuel)).collect();
+        assert_eq!(stops, vec![(50, 0, true), (120, 0, false), (70, 1, true)]);
+
+        let mut reached_target = false;
+        for _ in 0..400 {
+            simulation.tick();
+            let robot = &simulation.station().robots[0];
+            assert_ne!(robot.state, robot::RobotState::Stranded);
+            reached_target |= (robot.x, robot.y) == (120, 0);
+            if reached_target && (robot.x, robot.y) == (station_x, station_y) {
+                break;
+            }
+        }
+        let robot = &simulation.station().robots[0];
+        assert!(reached_target);
+        assert_eq!((robot.x, robot.y), (station_x, station_y));
+        assert_eq!(simulation.map.get_cell(50, 0).unwrap().cell_type, CellType::Empty);
+        assert_eq!(simulation.map.get_cell(70, 1).unwrap().cell_type, CellType::Empty);
+        assert_eq!(simulation.events.subscriber::<ActivityTally>().unwrap().wrecks, 0);
+    }
+
+    #[test]
+    fn test_robots_keep_off_radiation_and_the_station_forgets_cleared_cells() {
+        // A radiation band lasting 30 ticks across the way east
+        let (mut map, station_position) = Map::from_ascii("H..r....\n...r....\n").unwrap();
+        map.get_cell_mut(3, 0).unwrap().cell_type = CellType::Radiation(30);
+        map.get_cell_mut(3, 1).unwrap().cell_type = CellType::Radiation(30);
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        station.known_map.insert((3, 0), KnownCell::new(CellType::Radiation(30), 0));
+        station.refined_minerals = 0;
+        let mut explorer = Robot::new_with_type(1, 0, RobotType::Explorer);
+        explorer.target_x = Some(7);
+        explorer.target_y = Some(0);
+        explorer.spec.sensor_radius = 0; // Or it sees the band again the moment it clears
+        station.robots.push(explorer);
+        let mut simulation = Simulation::new(map, station);
+
+        for _ in 0..30 {
+            simulation.tick();
+            let robot = &simulation.station().robots[0];
+            assert!(!simulation.map.get_cell(robot.x, robot.y).unwrap().cell_type.blocks_movement());
+        }
+        assert!(simulation.station().known_map.contains_key(&(3, 0)));
+        simulation.tick();
+        assert!(!simulation.station().known_map.contains_key(&(3, 0)));
+        assert!(!simulation.map.get_cell(3, 0).unwrap().cell_type.blocks_movement());
+        assert!(!simulation.map.get_cell(3, 0).unwrap().explored);
+    }
+
+    #[test]
+    fn test_audit_credits_explorer_discoveries_and_the_policy_holds_explorers() {
+        // Collectors harvest a deposit the explorer reported and another one they found themselves
+        let (map, _) = Map::from_ascii_with_amount("m......m\n", 10).unwrap();
+        let mut station = Station::new(3, 0);
+        let explorer = station.add_robot(Robot::new_with_type(3, 0, RobotType::Explorer));
+        let collector = station.add_robot(Robot::new_with_type(0, 0, RobotType::MineralCollector));
+        station.add_robot(Robot::new_with_type(7, 0, RobotType::MineralCollector));
+        station.share_data(explorer, &vec![((0, 0), CellType::Mineral(10))], 0);
+        station.share_data(collector, &vec![((7, 0), CellType::Mineral(10))], 0);
+        station.refined_minerals = 0;
+        station.hold_explorers_on_advisory = true;
+        let mut simulation = Simulation::new(map, station);
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+        simulation.tick();
+        assert_eq!(simulation.audit.total_value, 10 * crate::audit::MINERAL_ENERGY_VALUE);
+
+        // With nothing else to find, the explorer's spending soon triggers the advisory
+        while simulation.audit.advisories == 0 && simulation.clock.now() < 2000 {
+            simulation.tick();
+        }
+        assert_eq!(simulation.audit.advisories, 1);
+        assert!(simulation.audit.balance() < 0);
+        assert_eq!(simulation.events.subscriber::<ActivityTally>().unwrap().explorer_advisories, 1);
+        assert!(simulation.station().explorers_on_hold);
+    }
+
+    #[test]
+    fn test_robot_returns_home_through_moving_debris() {
+        let (map, station_position) = Map::from_ascii(
+            "H.........\n\
+             ..*.*.*...\n\
+             .*.*.*.*..\n\
+             ..*.*.*.*.\n\
+             .........."
+        )
+        .unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut station = Station::new(station_x, station_y);
+        let mut robot = Robot::new(9, 4);
+        robot.state = robot::RobotState::ReturningToStation;
+        station.robots.push(robot);
+        let mut simulation = Simulation::new(map, station);
+        let initial_debris = simulation.map.debris.clone();
+
+        let mut reached = false;
+        for _ in 0..60 {
+            simulation.tick();
+            let robot = &simulation.station().robots[0];
+            if (robot.x, robot.y) == (station_x, station_y) {
+                reached = true;
+                break;
+            }
+        }
+
+        assert!(reached, "robot never made it back to the station");
+        assert_ne!(simulation.map.debris, initial_debris);
+    }
+
+    #[test]
+    fn test_outposts_keep_their_distance_and_own_their_fleets() {
+        let mut simulation = Simulation::setup(Map::new(100, 50, 42), None);
+        assert_eq!(simulation.add_outpost(), Some(1));
+        assert_eq!(simulation.add_outpost(), Some(2));
+        let sites = simulation.station_sites();
+        for (i, a) in sites.iter().enumerate() {
+            for b in &sites[i + 1..] {
+                assert!(a.0.abs_diff(b.0).max(a.1.abs_diff(b.1)) >= MIN_STATION_SEPARATION, "{:?} {:?}", a, b);
+            }
+        }
+        for station in &simulation.stations {
+            assert_eq!(station.robots.len(), INITIAL_ROSTER.len());
+            assert!(station.robots.iter().all(|robot| robot.home_station == station.id));
+        }
+        // No two robots start on the same cell
+        let mut cells: Vec<_> = simulation.robots().map(|robot| (robot.x, robot.y)).collect();
+        cells.sort_unstable();
+        cells.dedup();
+        assert_eq!(cells.len(), 3 * INITIAL_ROSTER.len());
+        // A map too small to keep the stations apart has no room for another
+        let mut cramped = Simulation::setup(Map::new(20, 10, 42), None);
+        assert_eq!(cramped.add_outpost(), None);
+    }
+
+    #[test]
+    fn test_two_stations_both_accumulate_resources() {
+        let mut simulation = Simulation::setup(Map::new(60, 30, 7), None);
+        simulation.add_outpost().unwrap();
+        simulation.run_ticks(300);
+        for station in &simulation.stations {
+            let delivered: u32 = station
+                .robots
+                .iter()
+                .map(|robot| robot.lifetime.energy_delivered + robot.lifetime.minerals_delivered + robot.lifetime.science_delivered)
+                .sum();
+            assert!(delivered > 0, "station {} took in nothing", station.id + 1);
+            assert!(!station.known_map.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_robot_only_docks_at_its_home_station() {
+        let row = ".".repeat(30);
+        let (map, station_position) = Map::from_ascii(&format!(".{}\nH{}\n.{}\n", row, row, row)).unwrap();
+        let (station_x, station_y) = station_position.unwrap();
+        let mut simulation = Simulation::new(map, Station::new(station_x, station_y));
+        simulation.add_outpost().unwrap();
+        simulation.stations[1].robots.clear(); // Nothing else to deliver there
+        let outpost = (simulation.stations[1].x, simulation.stations[1].y);
+        let mut robot = Robot::new_with_type(outpost.0, outpost.1, RobotType::MineralCollector);
+        robot.minerals = 10;
+        robot.state = robot::RobotState::ReturningToStation;
+        simulation.stations[0].add_robot(robot);
+        let main_minerals = simulation.station().refined_minerals;
+        let outpost_minerals = simulation.stations[1].raw_minerals + simulation.stations[1].refined_minerals;
+
+        // Standing on the outpost's site is not docking: the cargo stays on board
+        simulation.tick();
+        assert_eq!(simulation.station().robots[0].minerals, 10);
+        assert_eq!(simulation.station().robots[0].lifetime.minerals_delivered, 0);
+
+        // It walks home and unloads there, into the main station's stores
+        simulation.run_ticks(3 * outpost.0 as u32);
+        assert_eq!(simulation.station().robots[0].lifetime.minerals_delivered, 10);
+        assert_eq!(simulation.station().raw_minerals + simulation.station().refined_minerals, main_minerals + 10);
+        assert_eq!(simulation.stations[1].raw_minerals + simulation.stations[1].refined_minerals, outpost_minerals);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::map::{Cell, CellType, Map};
+use crate::preview::{downsample, sector_bounds};
+use crate::station::Station;
+use crate::ui::{self, ViewMode};
+use crate::viewport::Viewport;
+
+// Exploration percentages a snapshot is taken at
+pub const EXPLORATION_MILESTONES: [u8; 4] = [25, 50, 75, 100];
+
+// Largest thumbnail drawn in the report; smaller maps keep one cell per character
+pub const THUMBNAIL_COLUMNS: usize = 40;
+pub const THUMBNAIL_ROWS: usize = 12;
+
+// A thumbnail of what the fleet knew of the map at a milestone, with the robots on it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub label: String,
+    pub tick: u64,
+    pub lines: Vec<String>,
+}
+
+// Takes a snapshot the first time each exploration milestone is reached, and at the ticks the
+// simulation clock was asked to take one at. Between milestones it only compares numbers, so it
+// is cheap to check every tick. Idle until started, so headless runs do not measure exploration
+// for it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotRecorder {
+    active: bool,
+    next_percent: usize,       // Index of the first exploration milestone not reached yet
+    pub snapshots: Vec<Snapshot>, // In the order they were taken
+}
+
+impl SnapshotRecorder {
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+
+    // Whether an exploration milestone is still ahead, so the caller knows to measure it
+    pub fn awaits_exploration(&self) -> bool {
+        self.active && self.next_percent < EXPLORATION_MILESTONES.len()
+    }
+
+    // Take the snapshots due after `tick` ticks with `explored_percentage` of the map explored,
+    // including those scheduled for `scheduled_ticks`
+    pub fn observe(&mut self, map: &Map, station: &Station, tick: u64, explored_percentage: f64, scheduled_ticks: &[u64]) {
+        if !self.active {
+            return;
+        }
+        let mut labels = Vec::new();
+        while let Some(&percent) = EXPLORATION_MILESTONES.get(self.next_percent) {
+            if explored_percentage < percent as f64 {
+                break;
+            }
+            labels.push(format!("{}% explored", percent));
+            self.next_percent += 1;
+        }
+        labels.extend(scheduled_ticks.iter().map(|milestone| format!("tick {}", milestone)));
+        if labels.is_empty() {
+            return;
+        }
+        let lines = thumbnail(map, station, THUMBNAIL_COLUMNS, THUMBNAIL_ROWS);
+        for label in labels {
+            self.snapshots.push(Snapshot { label, tick, lines: lines.clone() });
+        }
+    }
+
+    // The snapshots as report lines: a heading per snapshot, then its thumbnail in a frame
+    pub fn report_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for snapshot in &self.snapshots {
+            let width = snapshot.lines.first().map_or(0, |line| line.chars().count());
+            lines.push(format!("{} (tick {}):", snapshot.label, snapshot.tick));
+            lines.push(format!("  +{}+", "-".repeat(width)));
+            lines.extend(snapshot.lines.iter().map(|line| format!("  |{}|", line)));
+            lines.push(format!("  +{}+", "-".repeat(width)));
+        }
+        lines
+    }
+}
+
+// What the station and its robots have seen, shrunk to at most `columns` x `rows` with the
+// robots and station drawn at their scaled positions. Sectors nobody has seen stay blank, seen
+// open ground is drawn as '.'
+pub fn thumbnail(map: &Map, station: &Station, columns: usize, rows: usize) -> Vec<String> {
+    // Unseen cells are treated as open ground for the downsampling and blanked afterwards
+    let mut seen = vec![false; map.width * map.height];
+    let mut cells = Vec::with_capacity(map.width * map.height);
+    for (x, y, cell) in map.iter_cells() {
+        let revealed = ui::is_revealed(map, station, x, y);
+        seen[y * map.width + x] = revealed;
+        let cell_type = if revealed { cell.cell_type.clone() } else { CellType::Empty };
+        cells.push(Cell::with_movement_cost(cell_type, cell.movement_cost));
+    }
+    let shrunk = downsample(&Map::from_cells(map.width, map.height, cells, map.seed), columns, rows);
+
+    let scale = |x: usize, y: usize| (x * shrunk.width / map.width, y * shrunk.height / map.height);
+    let (station_x, station_y) = scale(station.x, station.y);
+    let mut scaled = Station::new(station_x, station_y);
+    for robot in &station.robots {
+        let mut robot = robot.clone();
+        (robot.x, robot.y) = scale(robot.x, robot.y);
+        scaled.robots.push(robot);
+    }
+
+    let lines = ui::map_lines(&shrunk, &scaled, ViewMode::Omniscient, Viewport::default(), shrunk.width, shrunk.height);
+    lines
+        .iter()
+        .enumerate()
+        .map(|(sector_y, line)| {
+            let (y0, y1) = sector_bounds(sector_y, shrunk.height, map.height);
+            let text: String = line.spans.iter().map(|span| span.content.as_ref()).collect();
+            text.chars()
+                .enumerate()
+                .map(|(sector_x, glyph)| {
+                    let (x0, x1) = sector_bounds(sector_x, shrunk.width, map.width);
+                    let any_seen = (y0..y1).any(|y| seen[y * map.width + x0..y * map.width + x1].contains(&true));
+                    match glyph {
+                        ' ' if any_seen => '.',
+                        _ => glyph,
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robot::{Robot, RobotType};
+
+    #[test]
+    fn test_thumbnail_is_reduced_and_shows_only_what_was_seen() {
+        let (mut map, _) = Map::from_ascii(&format!("{}\n", ".".repeat(80)).repeat(24)).unwrap();
+        let mut station = Station::new(0, 0);
+        station.robots.push(Robot::new_with_type(79, 23, RobotType::Explorer));
+        for x in 0..40 {
+            map.explore(x, 0, 0);
+        }
+        let lines = thumbnail(&map, &station, THUMBNAIL_COLUMNS, THUMBNAIL_ROWS);
+        assert_eq!(lines.len(), THUMBNAIL_ROWS);
+        assert!(lines.iter().all(|line| line.chars().count() == THUMBNAIL_COLUMNS));
+        assert_eq!(lines[0], format!("H{}{}", ".".repeat(19), " ".repeat(20)));
+        assert_eq!(lines[THUMBNAIL_ROWS - 1], format!("{}E", " ".repeat(THUMBNAIL_COLUMNS - 1)));
+
+        // Small maps are not stretched
+        let (small, _) = Map::from_ascii("...\n...\n").unwrap();
+        let lines = thumbnail(&small, &Station::new(1, 1), THUMBNAIL_COLUMNS, THUMBNAIL_ROWS);
+        assert_eq!(lines, vec!["   ".to_string(), " H ".to_string()]);
+    }
+
+    #[test]
+    fn test_each_milestone_is_captured_once() {
+        let (map, _) = Map::from_ascii("....\n....\n").unwrap();
+        let station = Station::new(0, 0);
+        let mut recorder = SnapshotRecorder::default();
+        recorder.observe(&map, &station, 1, 100.0, &[1]);
+        assert!(recorder.snapshots.is_empty()); // Not started
+        recorder.start();
+        let progress = [(1, 0.0), (2, 30.0), (3, 30.0), (4, 60.0), (5, 80.0), (6, 80.0), (10, 100.0), (11, 100.0), (20, 100.0)];
+        for (tick, explored) in progress {
+            let scheduled: &[u64] = if tick == 3 || tick == 10 { &[tick] } else { &[] };
+            recorder.observe(&map, &station, tick, explored, scheduled);
+        }
+        let taken: Vec<(&str, u64)> = recorder.snapshots.iter().map(|snapshot| (snapshot.label.as_str(), snapshot.tick)).collect();
+        assert_eq!(
+            taken,
+            vec![("25% explored", 2), ("tick 3", 3), ("50% explored", 4), ("75% explored", 5), ("100% explored", 10), ("tick 10", 10)]
+        );
+        assert!(!recorder.awaits_exploration());
+
+        let report = recorder.report_lines();
+        assert_eq!(report.len(), 6 * 5); // Heading, two frame lines and two rows each
+        assert_eq!(report[0], "25% explored (tick 2):");
+        assert_eq!(report[1], "  +----+");
+    }
+}
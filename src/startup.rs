@@ -1,9 +1,13 @@
 use std::io::{self, Write};
 
+use crate::hash;
+
 pub struct StartupScreen;
 
 impl StartupScreen {
-    pub fn show() -> bool {
+    // Show the title and wait for Enter. With `ask_seed` the player may type a seed first (a
+    // number or any words); returns None if the terminal could not be read, else the seed typed
+    pub fn show(ask_seed: bool) -> Option<Option<u32>> {
         // Clear screen
         print!("\x1B[2J\x1B[1;1H");
         io::stdout().flush().unwrap();
@@ -41,14 +45,18 @@ impl StartupScreen {
         println!();
         println!("\x1B[96m─────────────────────────────────────────────\x1B[0m");
         println!();
-        print!("\x1B[95m⚡ Press ENTER to start exploration... \x1B[0m");
+        if ask_seed {
+            println!("\x1B[94mSeed:\x1B[0m a number or any words to share a world, left empty for a random one");
+            println!();
+            print!("\x1B[95m⚡ Type a seed or press ENTER to start exploration... \x1B[0m");
+        } else {
+            print!("\x1B[95m⚡ Press ENTER to start exploration... \x1B[0m");
+        }
         io::stdout().flush().unwrap();
 
         // Wait for Enter key
         let mut input = String::new();
-        match io::stdin().read_line(&mut input) {
-            Ok(_) => true,
-            Err(_) => false,
-        }
+        io::stdin().read_line(&mut input).ok()?;
+        Some((ask_seed && !input.trim().is_empty()).then(|| hash::seed_from_text(&input)))
     }
 }
@@ -1,54 +1,389 @@
-use std::collections::HashMap;
-use crate::map::{CellType, RobotExplorationUpdate}; // Updated import
-use crate::robot::{Robot, RobotType}; // Import the Robot struct and RobotType
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::behavior::{BehaviorConfig, BehaviorKind};
+use crate::decisions::{BuildVerdict, RefineVerdict, TargetChoice, TargetReason, TypeReason};
+use crate::audit::MINERAL_ENERGY_VALUE;
+use crate::expedition;
+use crate::facilities::{self, Facility, FacilityKind, FacilityUsage, FACILITY_KINDS, FACILITY_PAYBACK_TICKS, FACILITY_WARMUP_TICKS};
+use crate::map::{CellType, ExplorationStats, Map, RobotExplorationUpdate}; // Updated import
+use crate::station_log::{StationEvent, StationLog};
+use crate::robot::{LifetimeStats, Robot, RobotId, RobotState, RobotType, ENERGY_COSTS, REVISIT_AFTER_TICKS}; // Import the Robot struct and RobotType
+use crate::tasks::Task;
+use crate::upgrades::{Upgrade, Upgrades};
 
 const ROBOT_ENERGY_COST: u32 = 100;
-const ROBOT_MINERAL_COST: u32 = 50;
+const ROBOT_MINERAL_COST: u32 = 50; // Paid in refined minerals
+
+// Salvage: parts recovered from wrecks are spent first on new robots, each replacing
+// MINERALS_PER_PART refined minerals of the cost, up to MAX_PARTS_DISCOUNT minerals a robot
+const MINERALS_PER_PART: u32 = 2;
+const MAX_PARTS_DISCOUNT: u32 = ROBOT_MINERAL_COST / 2;
+
+// Decommissioning: a worn robot is scrapped for DECOMMISSION_REFUND_PERCENT of a robot's mineral
+// cost once keeping it costs more than replacing it. Keeping it costs the surcharge on the next
+// WEAR_HORIZON_REFUELS refuels and the refuels its dearer steps add; replacing it costs a new
+// robot less the refund, minerals counted at their audit value in energy
+const DECOMMISSION_REFUND_PERCENT: u32 = 40;
+const WEAR_HORIZON_REFUELS: u32 = 3;
+
+// Mineral refinement: raw minerals delivered by robots become spendable refined minerals
+const REFINEMENT_RATE: u32 = 5; // Raw minerals refined per tick, and as many more per refinery level
+const REFINEMENT_ENERGY_COST: u32 = 1; // Station energy spent per tick of refining
+const REFINEMENT_ENERGY_RESERVE: u32 = 200; // Refining pauses below this much station energy
+
+// Data analysis: every cell the station hears about for the first time adds DATA_PER_NEW_CELL
+// to the unanalyzed pool. Each Scientist docked with nothing to collect analyzes
+// ANALYSIS_PER_SCIENTIST units a tick, MAX_ANALYSIS_PER_TICK in all, and every
+// DATA_PER_SCIENCE_POINT units analyzed yield a science point
+const DATA_PER_NEW_CELL: u32 = 1;
+const ANALYSIS_PER_SCIENTIST: u32 = 1;
+const MAX_ANALYSIS_PER_TICK: u32 = 2;
+const DATA_PER_SCIENCE_POINT: u32 = 20;
+
+// Most the station can hold of each resource; raw and refined minerals share one pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageCapacity {
+    pub energy: u32,
+    pub minerals: u32,
+    pub science: u32,
+}
+
+pub const DEFAULT_STORAGE_CAPACITY: StorageCapacity = StorageCapacity {
+    energy: 20_000,
+    minerals: 10_000,
+    science: 10_000,
+};
+
+impl StorageCapacity {
+    // These caps with `percent` of the default capacity added to each
+    fn raised_by(self, percent: u32) -> Self {
+        let raise = |cap: u32, default: u32| cap + default * percent / 100;
+        Self {
+            energy: raise(self.energy, DEFAULT_STORAGE_CAPACITY.energy),
+            minerals: raise(self.minerals, DEFAULT_STORAGE_CAPACITY.minerals),
+            science: raise(self.science, DEFAULT_STORAGE_CAPACITY.science),
+        }
+    }
+}
+
+// Known deposits walled in by rock before the station builds a miner to open them up
+const MINER_FENCED_DEPOSITS: usize = 3;
+
+// Collectors in the fleet before the station builds a carrier for them
+const CARRIER_MIN_COLLECTORS: usize = 2;
+
+// Below this much energy the station builds energy collectors first
+const LOW_STATION_ENERGY: u32 = 300;
+
+// Queued tasks a docked robot checks for a path before settling for none; each check is an A* search
+const TASK_PATH_CHECKS: usize = 3;
+
+// Known science points within this many cells of each other count towards one cluster, each
+// weighing its value times how much nearer than one cell past the radius it lies
+const SCIENCE_CLUSTER_RADIUS: u32 = 4;
+
+// Once this much of the map is explored, explorers beyond RETROFIT_KEEP_EXPLORERS are surplus:
+// the station refits them as the type it needs when they dock with an empty hold, for refined
+// minerals and a few ticks docked, rather than build new robots
+const RETROFIT_EXPLORED_PERCENT: f64 = 90.0;
+const RETROFIT_KEEP_EXPLORERS: usize = 2;
+pub const RETROFIT_MINERAL_COST: u32 = 20;
+pub const RETROFIT_TICKS: u32 = 5;
+
+// Coarse grid the map is split into when spreading explorers over different areas
+pub const EXPLORER_REGION_GRID: (usize, usize) = (3, 3);
+
+// How far from the station the mapped cells handed to a docking explorer reach, so its copy
+// stays bounded however much of the map is known
+const KNOWN_CELLS_RADIUS: u32 = 60;
+
+// Parts the station salvages from a wreck of each robot type
+pub fn wreck_parts(robot_type: RobotType) -> u32 {
+    match robot_type {
+        RobotType::Explorer => 4,
+        RobotType::EnergyCollector | RobotType::MineralCollector => 6,
+        RobotType::Scientist => 8,
+        RobotType::Miner => 10, // Drilling gear
+        RobotType::Carrier => 6,
+        RobotType::Repairer => 8, // Tools
+    }
+}
+
+// Width of the capacity bars in the stats line
+const CAPACITY_BAR_WIDTH: u32 = 8;
+
+// A station is known by its place among the simulation's bases, the first being the main one
+pub type StationId = usize;
+
+// What the station last heard about a cell, and when. Entries only change when a robot reports
+// the cell again, so a deposit some robot since emptied stays known until that robot docks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownCell {
+    pub cell_type: CellType,
+    pub last_reported_tick: u64,
+}
+
+impl KnownCell {
+    pub fn new(cell_type: CellType, last_reported_tick: u64) -> Self {
+        Self { cell_type, last_reported_tick }
+    }
+}
+
+pub type KnownMap = HashMap<(usize, usize), KnownCell>;
+
+// Only deposits reported within this many ticks count as targets justifying a new robot: older
+// reports are likely of deposits robots have emptied since
+pub const UNTAPPED_REPORT_WINDOW: u64 = 1500;
 
 pub struct Station {
+    pub id: StationId, // Given by Simulation when it places the station
     pub x: usize, // Added x coordinate
     pub y: usize, // Added y coordinate
     pub energy: u32,
-    pub minerals: u32,
+    pub raw_minerals: u32,     // Delivered by robots, waiting to be refined
+    pub refined_minerals: u32, // Spendable on robot construction
     pub science_points: u32,
-    pub known_map: HashMap<(usize, usize), CellType>, // Station's knowledge of the map
+    pub capacity: StorageCapacity,
+    pub storage_full: bool, // A delivery was turned away since the last robot was built
+    pub known_map: KnownMap, // Station's knowledge of the map
+    pub discovered_by: HashMap<(usize, usize), RobotId>, // Robot that first reported each known cell, kept for forgotten deposits
+    pub known_caches: Vec<(usize, usize)>, // Field caches robots reported dropping, not yet recovered or lost
+    pub claimed_targets: HashMap<(usize, usize), RobotId>, // Deposits robots are heading for, and who claimed each
+    pub unanalyzed_data: u32,       // Observations archived but not analyzed yet
+    analysis_progress: u32,         // Data analyzed towards the next science point
+    pub science_from_analysis: u32, // Science points produced by data analysis so far
+    pub parts: u32,                 // Salvaged from wrecks, not spent on construction yet
+    pub parts_savings: u32,         // Refined minerals parts have saved on construction so far
+    pub hold_explorers_on_advisory: bool, // Fleet policy: stop building explorers once exploration is found to cost more than it returns
+    pub explorers_on_hold: bool,          // Set by that policy; explorers are only built to keep the minimum
+    pub upgrades: Upgrades,               // Bought with science points; fitted to robots when built and when they refuel
+    pub facilities: Vec<Facility>,        // Bought with refined minerals, see consider_facility_purchase
+    pub facility_usage: FacilityUsage,    // What facilities are judged by before one is bought
+    pub task_queue: VecDeque<Task>,       // Work waiting for a robot to dock and take it, next one first
+    pub analyzed_cells: HashSet<(usize, usize)>, // Science points scientists analyzed on site
+    pub explorer_behavior: BehaviorKind,  // Fitted to explorers as they join the fleet
+    pub behavior_config: BehaviorConfig,  // Thresholds and weights every robot joining the fleet decides by
+    pub pair_explorers: bool,             // Buddy system: explorers go out in pairs, see pair_up_explorers
+    pub explorer_pairs: Vec<(RobotId, RobotId)>, // (leader, trailer) of each explorer pair
+    pub recall: bool,                     // The whole fleet is called home: robots stay docked and none are built
+    pub retrofits: u32,                   // Robots refitted as another type so far
+    pub events: StationLog,               // What happened here lately, with the tick it happened on
     pub robots: Vec<Robot>, // List of robots managed by the station
 }
 
 impl Station {
     pub fn new(x: usize, y: usize) -> Self { // Added x, y parameters
         Self {
+            id: 0,
             x, // Initialize x
             y, // Initialize y
             energy: 2000, // Increased starting energy for better robot support
-            minerals: 500, // Also increased starting minerals
+            raw_minerals: 0,
+            refined_minerals: 500, // Also increased starting minerals
             science_points: 0,
+            capacity: DEFAULT_STORAGE_CAPACITY,
+            storage_full: false,
             known_map: HashMap::new(), // Initialize with an empty map
+            discovered_by: HashMap::new(),
+            known_caches: Vec::new(),
+            claimed_targets: HashMap::new(),
+            unanalyzed_data: 0,
+            analysis_progress: 0,
+            science_from_analysis: 0,
+            parts: 0,
+            parts_savings: 0,
+            hold_explorers_on_advisory: false,
+            explorers_on_hold: false,
+            upgrades: Upgrades::default(),
+            facilities: Vec::new(),
+            facility_usage: FacilityUsage::default(),
+            task_queue: VecDeque::new(),
+            analyzed_cells: HashSet::new(),
+            explorer_behavior: BehaviorKind::Builtin,
+            behavior_config: BehaviorConfig::default(),
+            pair_explorers: false,
+            explorer_pairs: Vec::new(),
+            recall: false,
+            retrofits: 0,
+            events: StationLog::default(),
             robots: Vec::new(), // Initialize with an empty list of robots
         }
     }
 
-    // Method to collect resources from a robot
-    pub fn collect_resources(&mut self, energy: u32, minerals: u32, science: u32) {
-        self.energy += energy;
-        self.minerals += minerals;
-        self.science_points += science;
-        // Potentially add logging or events here
+    // Method to collect resources from a robot. Deliveries are accepted up to the storage
+    // capacity, minerals with the refinery's bonus on top while there is room for it; returns
+    // the (energy, minerals, science) that did not fit
+    pub fn collect_resources(&mut self, energy: u32, minerals: u32, science: u32) -> (u32, u32, u32) {
+        let energy_accepted = energy.min(self.capacity.energy.saturating_sub(self.energy));
+        let minerals_room = self.capacity.minerals.saturating_sub(self.raw_minerals + self.refined_minerals);
+        let minerals_accepted = minerals.min(minerals_room);
+        let science_accepted = science.min(self.capacity.science.saturating_sub(self.science_points));
+
+        let refinery_bonus = minerals_accepted * facilities::bonus(&self.facilities, FacilityKind::Refinery) / 100;
+        self.energy += energy_accepted;
+        self.raw_minerals += (minerals_accepted + refinery_bonus).min(minerals_room);
+        self.science_points += science_accepted;
+        self.facility_usage.minerals_unloaded += minerals_accepted;
+
+        let overflow = (energy - energy_accepted, minerals - minerals_accepted, science - science_accepted);
+        if overflow != (0, 0, 0) {
+            self.storage_full = true;
+        }
+        self.facility_usage.turned_away += overflow.1 + overflow.0 / MINERAL_ENERGY_VALUE as u32;
+        overflow
     }
 
     // Method to consume resources (e.g., for creating robots)
     pub fn consume_resources(&mut self, energy_cost: u32, mineral_cost: u32) -> bool {
-        if self.energy >= energy_cost && self.minerals >= mineral_cost {
+        if self.energy >= energy_cost && self.refined_minerals >= mineral_cost {
             self.energy -= energy_cost;
-            self.minerals -= mineral_cost;
+            self.refined_minerals -= mineral_cost;
             true
         } else {
             false
         }
     }
 
+    // Per-tick station work: refine part of the raw mineral stock
+    pub fn tick(&mut self) {
+        self.facility_usage.ticks += 1;
+        self.refine_minerals();
+        self.analyze_data();
+    }
+
+    // Scientists docked at the station with no known science point left to collect
+    pub fn is_idle_scientist(&self, robot: &Robot) -> bool {
+        robot.robot_type == RobotType::Scientist && (robot.x, robot.y) == (self.x, self.y) && robot.energy > 0 && !self.knows_of_science()
+    }
+
+    fn knows_of_science(&self) -> bool {
+        self.known_map.values().any(|known| matches!(known.cell_type, CellType::SciencePoint(_)))
+    }
+
+    // How rich the cluster of known science around each known science point is: the values of the
+    // points within SCIENCE_CLUSTER_RADIUS of it, the point itself included, weighted by nearness
+    pub fn science_cluster_density(&self, map: &Map) -> HashMap<(usize, usize), u32> {
+        let points: Vec<((usize, usize), u32)> = self
+            .known_map
+            .iter()
+            .filter_map(|(&position, known)| match known.cell_type {
+                CellType::SciencePoint(value) => Some((position, value)),
+                _ => None,
+            })
+            .collect();
+        points
+            .iter()
+            .map(|&((x, y), _)| {
+                let density = points
+                    .iter()
+                    .map(|&((other_x, other_y), value)| (map.distance(x, y, other_x, other_y), value))
+                    .filter(|&(distance, _)| distance <= SCIENCE_CLUSTER_RADIUS)
+                    .map(|(distance, value)| value * (SCIENCE_CLUSTER_RADIUS + 1 - distance))
+                    .sum();
+                ((x, y), density)
+            })
+            .collect()
+    }
+
+    // Send a docked Scientist that knows of no science to scout the least-explored region no other
+    // scientist is scouting (or the least-explored one when all are), analyzing whatever it comes
+    // across there. Returns the task handed out
+    pub fn send_scientist_scouting(&mut self, index: usize, map: &Map) -> Option<Task> {
+        let robot = self.robots.get(index)?;
+        if robot.robot_type != RobotType::Scientist || robot.current_task.is_some() || self.knows_of_science() {
+            return None;
+        }
+        let (grid_w, grid_h) = EXPLORER_REGION_GRID;
+        let taken: Vec<usize> = self
+            .robots
+            .iter()
+            .filter(|robot| robot.robot_type == RobotType::Scientist)
+            .filter_map(|robot| match robot.current_task {
+                Some(Task::ExploreRegion { x, y }) => Some(map.region_of(x, y, grid_w, grid_h)),
+                _ => None,
+            })
+            .collect();
+        let regions = map.regions(grid_w, grid_h);
+        let least_explored = |free_only: bool| {
+            regions
+                .iter()
+                .filter(|region| region.has_unexplored() && !(free_only && taken.contains(&region.index)))
+                .min_by(|a, b| a.stats.percentage.total_cmp(&b.stats.percentage))
+        };
+        let (x, y) = least_explored(true).or_else(|| least_explored(false))?.center();
+        let task = Task::ExploreRegion { x, y };
+        self.robots[index].current_task = Some(task);
+        Some(task)
+    }
+
+    // Data units analyzed per tick by the Scientists docked right now
+    pub fn analysis_rate(&self) -> u32 {
+        let docked = self
+            .robots
+            .iter()
+            .filter(|robot| robot.state == RobotState::AtStation && self.is_idle_scientist(robot))
+            .count() as u32;
+        (docked * ANALYSIS_PER_SCIENTIST).min(MAX_ANALYSIS_PER_TICK)
+    }
+
+    // Turn archived observations into science at the docked Scientists' rate, while there is
+    // room to store it. Returns the science points produced this tick
+    fn analyze_data(&mut self) -> u32 {
+        if self.science_points >= self.capacity.science {
+            return 0;
+        }
+        let analyzed = self.unanalyzed_data.min(self.analysis_rate());
+        self.unanalyzed_data -= analyzed;
+        self.analysis_progress += analyzed;
+        let produced = (self.analysis_progress / DATA_PER_SCIENCE_POINT).min(self.capacity.science - self.science_points);
+        self.analysis_progress -= produced * DATA_PER_SCIENCE_POINT;
+        self.science_points += produced;
+        self.science_from_analysis += produced;
+        produced
+    }
+
+    // Whether the refinery can run: raw minerals waiting and energy above the reserve
+    pub fn refine_verdict(&self) -> RefineVerdict {
+        if self.raw_minerals == 0 {
+            return RefineVerdict::NothingToRefine;
+        }
+        let need = REFINEMENT_ENERGY_RESERVE + REFINEMENT_ENERGY_COST;
+        if self.energy < need {
+            return RefineVerdict::EnergyReserve { energy: self.energy, need };
+        }
+        RefineVerdict::Refining { raw: self.raw_minerals }
+    }
+
+    // Raw minerals refined a tick at full throughput
+    fn refinement_rate(&self) -> u32 {
+        REFINEMENT_RATE * (1 + facilities::level(&self.facilities, FacilityKind::Refinery))
+    }
+
+    // Convert up to refinement_rate raw minerals, unless energy is below the reserve
+    // Returns the amount refined this tick
+    fn refine_minerals(&mut self) -> u32 {
+        if !matches!(self.refine_verdict(), RefineVerdict::Refining { .. }) {
+            return 0;
+        }
+        let refined = self.raw_minerals.min(self.refinement_rate());
+        self.raw_minerals -= refined;
+        self.refined_minerals += refined;
+        self.energy -= REFINEMENT_ENERGY_COST;
+        refined
+    }
+
+    // Ticks needed to refine the current raw stock at full throughput
+    pub fn refinement_backlog_ticks(&self) -> u32 {
+        self.raw_minerals.div_ceil(self.refinement_rate())
+    }
+
     // Updated robot creation logic
-    pub fn should_create_robot(&self) -> bool {
+    #[cfg(test)]
+    pub fn should_create_robot(&self, now: u64) -> bool {
+        self.build_verdict(now).approves()
+    }
+
+    // The reason to build a robot or not at tick `now`, from the first rule that settles it
+    pub fn build_verdict(&self, now: u64) -> BuildVerdict {
         // Constants for robot creation strategy
         const ROBOT_CREATION_MINERAL_BUFFER: u32 = 100; // Reduced buffer to create robots more aggressively
         const ROBOT_CREATION_ENERGY_BUFFER: u32 = 300; // Reduced buffer
@@ -56,68 +391,302 @@ impl Station {
         // Minimum number of known valuable resource locations to justify building a new robot
         const MIN_KNOWN_UNTAPPED_VALUABLE_CELLS_FOR_NEW_ROBOT: usize = 2; // Reduced threshold
 
+        if self.recall {
+            return BuildVerdict::Recalled;
+        }
+
         // 1. Check if maximum robot capacity has been reached
-        if self.robots.len() >= MAX_ROBOT_COUNT {
-            return false;
+        let fleet = self.fleet_size();
+        if fleet >= MAX_ROBOT_COUNT {
+            return BuildVerdict::FleetFull { robots: fleet, max: MAX_ROBOT_COUNT };
         }
 
         // 2. Check if the station has enough resources (including a buffer)
-        if self.minerals < ROBOT_MINERAL_COST + ROBOT_CREATION_MINERAL_BUFFER ||
-           self.energy < ROBOT_ENERGY_COST + ROBOT_CREATION_ENERGY_BUFFER {
-            return false;
+        let minerals_needed = self.robot_build_cost().1 + ROBOT_CREATION_MINERAL_BUFFER;
+        if self.refined_minerals < minerals_needed {
+            return BuildVerdict::MineralBuffer { have: self.refined_minerals, need: minerals_needed };
+        }
+        let energy_needed = ROBOT_ENERGY_COST + ROBOT_CREATION_ENERGY_BUFFER;
+        if self.energy < energy_needed {
+            return BuildVerdict::EnergyBuffer { have: self.energy, need: energy_needed };
         }
 
-        // 3. Analyze the known map for untapped resources
+        // 3. Full storage: spend instead of hoarding, whatever targets are known
+        if self.storage_full {
+            return BuildVerdict::StorageFull;
+        }
+
+        // 4. Analyze the known map for untapped resources, as recently reported
         let mut known_untapped_valuable_cells = 0;
-        for cell_type in self.known_map.values() {
-            match cell_type {
+        let recent = self.known_map.values().filter(|known| now.saturating_sub(known.last_reported_tick) <= UNTAPPED_REPORT_WINDOW);
+        for known in recent {
+            match &known.cell_type {
                 CellType::Energy(amount) if *amount > 0 => known_untapped_valuable_cells += 1,
                 CellType::Mineral(amount) if *amount > 0 => known_untapped_valuable_cells += 1,
                 // Also consider SciencePoints as valuable targets
-                CellType::SciencePoint => known_untapped_valuable_cells += 1,
+                CellType::SciencePoint(_) => known_untapped_valuable_cells += 1,
                 _ => {}
             }
         }
 
         // Only build if there are enough known targets to make a new robot worthwhile
-        if known_untapped_valuable_cells < MIN_KNOWN_UNTAPPED_VALUABLE_CELLS_FOR_NEW_ROBOT {
+        let (known, need) = (known_untapped_valuable_cells, MIN_KNOWN_UNTAPPED_VALUABLE_CELLS_FOR_NEW_ROBOT);
+        if known < need {
             // Alternative dynamic threshold:
             // if known_untapped_valuable_cells < (self.robots.len() + 1) * TARGETS_PER_ROBOT_THRESHOLD {
-            return false;
+            return BuildVerdict::TooFewTargets { known, need };
         }
 
-        true // All conditions met, station should create a robot
+        BuildVerdict::Targets { known, need } // All conditions met, station should create a robot
     }
 
     // Method to create a new robot with intelligent type selection
-    // Takes starting coordinates for the new robot
-    pub fn create_robot(&mut self, start_x: usize, start_y: usize) -> bool {
-        if self.consume_resources(ROBOT_ENERGY_COST, ROBOT_MINERAL_COST) {
-            let robot_type = self.choose_robot_type();
-            let new_robot = Robot::new_with_type(start_x, start_y, robot_type);
-            self.robots.push(new_robot);
-            // Potentially log robot creation
-            true
+    // Takes starting coordinates for the new robot and the map, to see which deposits are fenced in.
+    // Returns why the new robot got its type, None when the station could not afford it
+    pub fn create_robot(&mut self, start_x: usize, start_y: usize, map: &Map) -> Option<TypeReason> {
+        let (parts, minerals) = self.robot_build_cost();
+        if self.consume_resources(ROBOT_ENERGY_COST, minerals) {
+            self.parts -= parts;
+            self.parts_savings += parts * MINERALS_PER_PART;
+            self.facility_usage.robots_built += 1;
+            let (robot_type, reason) = self.robot_type_choice(map);
+            let robot = self.add_robot(Robot::new_with_type(start_x, start_y, robot_type));
+            self.fit_upgrades(robot);
+            self.robots[robot].energy = self.robots[robot].spec.max_energy; // Built fully charged
+            self.storage_full = false; // Spending made room
+            Some(reason)
         } else {
             // Potentially log failure due to insufficient resources
-            false
+            None
+        }
+    }
+
+    // Parts and refined minerals the next robot costs: as many parts as the discount cap allows,
+    // the rest of the mineral cost, less the construction upgrade and then the workshop's
+    // discount, in minerals
+    pub fn robot_build_cost(&self) -> (u32, u32) {
+        let mineral_cost = ROBOT_MINERAL_COST - self.upgrades.construction_discount();
+        let mineral_cost = mineral_cost.saturating_sub(facilities::bonus(&self.facilities, FacilityKind::Workshop));
+        let parts = self.parts.min(MAX_PARTS_DISCOUNT.min(mineral_cost) / MINERALS_PER_PART);
+        (parts, mineral_cost - parts * MINERALS_PER_PART)
+    }
+
+    // Bring a robot's battery, sensors and harvesting up to the upgrades bought so far, if any
+    // were bought since it was last fitted. The battery grows, but is only filled when the robot
+    // next refuels
+    pub fn fit_upgrades(&mut self, robot: RobotId) {
+        let robot = &mut self.robots[robot];
+        if robot.upgrades == self.upgrades {
+            return;
+        }
+        robot.upgrades = self.upgrades;
+        robot.spec = self.upgrades.apply(robot.robot_type.spec());
+        robot.harvest_boost = self.upgrades.harvest_boost();
+    }
+
+    // Call the whole fleet home, or lift the call. Every robot that can walk drops what it is
+    // doing, its task included, and heads for the station on a ReturnHome task; it stays docked
+    // there, and broken and stranded robots wait for help as before. Once lifted, the calls home
+    // are dropped and docked robots go back out at the next docking pass
+    pub fn set_recall(&mut self, recall: bool, tick: u64) {
+        self.recall = recall;
+        self.events.push(tick, StationEvent::RecallIssued { recall });
+        if !recall {
+            for robot in self.robots.iter_mut().filter(|robot| robot.current_task == Some(Task::ReturnHome)) {
+                robot.current_task = None;
+            }
+            return;
+        }
+        let (x, y) = (self.x, self.y);
+        for robot in self.robots.iter_mut().filter(|robot| robot.energy > 0) {
+            match robot.state {
+                RobotState::AtStation | RobotState::Idle | RobotState::Broken | RobotState::Stranded | RobotState::LowPower { .. } | RobotState::Decommissioned => {}
+                _ => {
+                    robot.state = RobotState::ReturningToStation;
+                    robot.target_x = Some(x);
+                    robot.target_y = Some(y);
+                    robot.current_task = Some(Task::ReturnHome);
+                }
+            }
+        }
+    }
+
+    // Robots in the fleet, the decommissioned ones left out
+    pub fn fleet_size(&self) -> usize {
+        self.robots.iter().filter(|robot| robot.state != RobotState::Decommissioned).count()
+    }
+
+    // Extra station energy a worn robot costs over the horizon, against a new one doing the same
+    // work: its steps cost one more, so the same ground takes (move cost + 1) / move cost batteries
+    pub fn upkeep_of_wear(&self, robot: RobotId) -> u32 {
+        let robot = &self.robots[robot];
+        if !robot.is_worn() {
+            return 0;
+        }
+        let batteries = robot.spec.max_energy * WEAR_HORIZON_REFUELS;
+        let move_cost = robot.spec.move_cost.max(1);
+        robot.refuel_price(batteries * (move_cost + 1) / move_cost) - batteries
+    }
+
+    // Refined minerals scrapping a robot brings back
+    pub fn decommission_refund(&self) -> u32 {
+        ROBOT_MINERAL_COST * DECOMMISSION_REFUND_PERCENT / 100
+    }
+
+    // Energy a new robot costs, less what scrapping the one it replaces brings back
+    pub fn replacement_cost(&self) -> u32 {
+        let minerals = self.robot_build_cost().1.saturating_sub(self.decommission_refund());
+        ROBOT_ENERGY_COST + minerals * MINERAL_ENERGY_VALUE as u32
+    }
+
+    // Whether to scrap a docked robot: worn, with an empty hold, dearer to keep than to replace,
+    // and with a replacement affordable once refunded, as a worn robot still beats none
+    pub fn should_decommission(&self, robot: RobotId) -> bool {
+        let candidate = &self.robots[robot];
+        candidate.is_worn()
+            && candidate.cargo_load() == 0
+            && self.upkeep_of_wear(robot) > self.replacement_cost()
+            && self.energy >= ROBOT_ENERGY_COST
+            && self.refined_minerals + self.decommission_refund() >= self.robot_build_cost().1
+    }
+
+    // Scrap a robot for part of a robot's mineral cost, as far as storage has room. Cargo it
+    // still holds is unloaded first; it is parked at the station for good, its claims and pairing
+    // dropped. Returns the refined minerals refunded
+    pub fn decommission_robot(&mut self, robot: RobotId) -> u32 {
+        let (energy, minerals, science) = self.robots[robot].unload_payload();
+        self.collect_resources(energy, minerals, science);
+        let room = self.capacity.minerals.saturating_sub(self.raw_minerals + self.refined_minerals);
+        let refund = self.decommission_refund().min(room);
+        self.refined_minerals += refund;
+        let (x, y) = (self.x, self.y);
+        let scrapped = &mut self.robots[robot];
+        (scrapped.x, scrapped.y) = (x, y);
+        scrapped.energy = 0;
+        scrapped.state = RobotState::Decommissioned;
+        scrapped.current_task = None;
+        scrapped.expedition.clear();
+        self.claimed_targets.retain(|_, claimant| *claimant != robot);
+        self.explorer_pairs.retain(|&(leader, trailer)| leader != robot && trailer != robot);
+        refund
+    }
+
+    // The type to refit a docked robot as, with the rule that picked it: only a surplus explorer
+    // with an empty hold, once the map is nearly explored, into a type other than an explorer
+    pub fn retrofit_choice(&self, robot: RobotId, map: &Map) -> Option<(RobotType, TypeReason)> {
+        let candidate = &self.robots[robot];
+        let explorers = self
+            .robots
+            .iter()
+            .filter(|robot| robot.robot_type == RobotType::Explorer && robot.state != RobotState::Decommissioned)
+            .count();
+        let surplus = candidate.robot_type == RobotType::Explorer
+            && candidate.retrofit_ticks == 0
+            && candidate.cargo_load() == 0
+            && explorers > RETROFIT_KEEP_EXPLORERS
+            && map.exploration_stats().percentage >= RETROFIT_EXPLORED_PERCENT;
+        if !surplus || self.refined_minerals < RETROFIT_MINERAL_COST {
+            return None;
         }
+        Some(self.robot_type_choice(map)).filter(|&(robot_type, _)| robot_type != RobotType::Explorer)
+    }
+
+    // Refit a docked robot as another type for RETROFIT_MINERAL_COST refined minerals. It stays
+    // docked RETROFIT_TICKS ticks, its claims and pairing dropped. False when the station cannot
+    // pay or the robot already has that type
+    pub fn retrofit_robot(&mut self, robot: RobotId, robot_type: RobotType) -> bool {
+        if self.refined_minerals < RETROFIT_MINERAL_COST || self.robots[robot].robot_type == robot_type {
+            return false;
+        }
+        self.refined_minerals -= RETROFIT_MINERAL_COST;
+        let refitted = &mut self.robots[robot];
+        refitted.retrofit(robot_type, self.upgrades);
+        refitted.retrofit_ticks = RETROFIT_TICKS;
+        if robot_type == RobotType::Explorer {
+            refitted.behavior = self.explorer_behavior.build(RobotType::Explorer);
+        }
+        self.claimed_targets.retain(|_, claimant| *claimant != robot);
+        self.explorer_pairs.retain(|&(leader, trailer)| leader != robot && trailer != robot);
+        self.retrofits += 1;
+        true
+    }
+
+    // Spend science points on the next upgrade the priority policy picks, if one is affordable
+    pub fn maybe_purchase_upgrade(&mut self) -> Option<Upgrade> {
+        let (upgrade, cost) = self.upgrades.next_purchase(self.science_points)?;
+        self.science_points -= cost;
+        self.upgrades.buy(upgrade);
+        Some(upgrade)
+    }
+
+    // Pay for a refuel worth `price` energy, less the charging array's discount, if the station
+    // has that much energy; returns the energy paid, None for a refuel not needed or not paid
+    pub fn pay_for_refuel(&mut self, price: u32) -> Option<u32> {
+        let cost = price - price * facilities::bonus(&self.facilities, FacilityKind::ChargingArray) / 100;
+        if price == 0 || self.energy < cost {
+            return None;
+        }
+        self.energy -= cost;
+        self.facility_usage.refuel_energy += price;
+        Some(cost)
+    }
+
+    // Build or extend the facility that would pay for itself soonest at the rates seen so far,
+    // if that is within FACILITY_PAYBACK_TICKS and leaves the refined minerals for another robot.
+    // Returns the facility at its new level
+    pub fn consider_facility_purchase(&mut self) -> Option<Facility> {
+        if self.facility_usage.ticks < FACILITY_WARMUP_TICKS {
+            return None;
+        }
+        let (kind, cost, _) = FACILITY_KINDS
+            .into_iter()
+            .filter_map(|kind| {
+                let level = facilities::level(&self.facilities, kind);
+                let cost = kind.cost(level);
+                let payback = self.facility_usage.payback_ticks(kind, cost)?;
+                (level < kind.max_level() && payback <= FACILITY_PAYBACK_TICKS).then_some((kind, cost, payback))
+            })
+            .min_by_key(|&(_, _, payback)| payback)?;
+        if self.refined_minerals < cost + self.robot_build_cost().1 {
+            return None;
+        }
+        self.refined_minerals -= cost;
+        match self.facilities.iter_mut().find(|facility| facility.kind == kind) {
+            Some(facility) => facility.level += 1,
+            None => self.facilities.push(Facility { kind, level: 1 }),
+        }
+        if kind == FacilityKind::Warehouse {
+            self.capacity = self.capacity.raised_by(kind.bonus_per_level());
+        }
+        Some(Facility { kind, level: facilities::level(&self.facilities, kind) })
     }
 
     // Intelligent robot type selection based on current needs
-    fn choose_robot_type(&self) -> RobotType {
+    #[cfg(test)]
+    fn choose_robot_type(&self, map: &Map) -> RobotType {
+        self.robot_type_choice(map).0
+    }
+
+    // The type the next robot should have, with the rule that picked it
+    pub fn robot_type_choice(&self, map: &Map) -> (RobotType, TypeReason) {
         // Count existing robots by type
         let mut explorer_count = 0;
         let mut energy_collector_count = 0;
         let mut mineral_collector_count = 0;
         let mut scientist_count = 0;
+        let mut miner_count = 0;
+        let mut carrier_count = 0;
+        let mut repairer_count = 0;
 
-        for robot in &self.robots {
+        for robot in self.robots.iter().filter(|robot| robot.state != RobotState::Decommissioned) {
             match robot.robot_type {
                 RobotType::Explorer => explorer_count += 1,
                 RobotType::EnergyCollector => energy_collector_count += 1,
                 RobotType::MineralCollector => mineral_collector_count += 1,
                 RobotType::Scientist => scientist_count += 1,
+                RobotType::Miner => miner_count += 1,
+                RobotType::Carrier => carrier_count += 1,
+                RobotType::Repairer => repairer_count += 1,
             }
         }
 
@@ -127,11 +696,11 @@ impl Station {
         let mut science_sources = 0;
         let mut unexplored_cells = 0;
 
-        for cell_type in self.known_map.values() {
-            match cell_type {
+        for known in self.known_map.values() {
+            match &known.cell_type {
                 CellType::Energy(amount) if *amount > 0 => energy_sources += 1,
                 CellType::Mineral(amount) if *amount > 0 => mineral_sources += 1,
-                CellType::SciencePoint => science_sources += 1,
+                CellType::SciencePoint(_) => science_sources += 1,
                 CellType::Empty => unexplored_cells += 1,
                 _ => {}
             }
@@ -139,27 +708,63 @@ impl Station {
 
         // Decision logic based on current situation
         // Always ensure at least one explorer if map is not fully explored
-        if explorer_count == 0 || (unexplored_cells > 10 && explorer_count < 2) {
-            return RobotType::Explorer;
+        if explorer_count == 0 {
+            return (RobotType::Explorer, TypeReason::NoExplorer);
+        }
+        if unexplored_cells > 10 && explorer_count < 2 {
+            return (RobotType::Explorer, TypeReason::FewExplorers { explorers: explorer_count, open_cells: unexplored_cells });
         }
 
         // If low on energy and energy sources are available, prioritize energy collectors
-        if self.energy < 300 && energy_sources > 0 && energy_collector_count < 2 {
-            return RobotType::EnergyCollector;
+        if self.energy < LOW_STATION_ENERGY && energy_sources > 0 && energy_collector_count < 2 {
+            let reason = TypeReason::LowEnergy {
+                energy: self.energy,
+                below: LOW_STATION_ENERGY,
+                deposits: energy_sources,
+                collectors: energy_collector_count,
+            };
+            return (RobotType::EnergyCollector, reason);
+        }
+
+        // Robots broken down in the field call for a repairer to put them back to work
+        let broken = self.robots.iter().filter(|robot| robot.state == RobotState::Broken && robot.energy > 0).count();
+        if broken > 0 && repairer_count == 0 {
+            return (RobotType::Repairer, TypeReason::BrokenRobots { robots: broken });
+        }
+
+        // Deposits walled in by rock call for a miner to open them up
+        let fenced_deposits = self.known_map.keys().filter(|&&(x, y)| map.is_fenced_deposit(x, y)).count();
+        if fenced_deposits >= MINER_FENCED_DEPOSITS && miner_count == 0 {
+            return (RobotType::Miner, TypeReason::FencedDeposits { deposits: fenced_deposits });
+        }
+
+        // Collectors in the field are worth a carrier to save them the walks home
+        let collectors = energy_collector_count + mineral_collector_count;
+        if collectors >= CARRIER_MIN_COLLECTORS && carrier_count == 0 {
+            return (RobotType::Carrier, TypeReason::CollectorsInField { collectors });
         }
 
         // If mineral sources are abundant and we need more minerals
         if mineral_sources > energy_sources && mineral_collector_count < 2 {
-            return RobotType::MineralCollector;
+            return (RobotType::MineralCollector, TypeReason::MineralsAbundant { minerals: mineral_sources, energy: energy_sources });
         }
 
         // If science sources are available and we want to maximize science points
         if science_sources > 0 && scientist_count < 1 {
-            return RobotType::Scientist;
+            return (RobotType::Scientist, TypeReason::ScienceAvailable { sites: science_sources });
         }
 
-        // Default to explorer for general exploration
-        RobotType::Explorer
+        // Default to explorer for general exploration, unless exploration has stopped paying
+        // for itself and the policy holds explorers back, or there is little left to explore
+        if self.explorers_on_hold {
+            return (RobotType::EnergyCollector, TypeReason::ExplorersOnHold);
+        }
+        let explored = map.exploration_stats().percentage;
+        if explored >= RETROFIT_EXPLORED_PERCENT {
+            let collector = if mineral_sources > energy_sources { RobotType::MineralCollector } else { RobotType::EnergyCollector };
+            return (collector, TypeReason::MapExplored { explored: explored as u32 });
+        }
+        (RobotType::Explorer, TypeReason::Default)
     }
 
     // Helper method to analyze current map data
@@ -167,8 +772,8 @@ impl Station {
         // Example: Count valuable cells (energy, minerals, science points)
         // This is a placeholder for more sophisticated analysis
         let mut _valuable_cells_count = 0;
-        for ((_x, _y), cell_type) in &self.known_map {
-            match cell_type {
+        for ((_x, _y), known) in &self.known_map {
+            match &known.cell_type {
                 CellType::Energy(amount) if *amount > 0 => _valuable_cells_count += 1,
                 CellType::Mineral(amount) if *amount > 0 => _valuable_cells_count += 1,
                 _ => {}
@@ -181,21 +786,407 @@ impl Station {
         // Example: if _valuable_cells_count > 10 { /* log high resource density */ } // Also prefixed here if used in example
     }
 
-    // Method to integrate exploration data from a robot
-    pub fn share_data(&mut self, data_from_robot: &RobotExplorationUpdate) {
+    // Join the fleet, taking the next robot id
+    pub fn add_robot(&mut self, mut robot: Robot) -> RobotId {
+        robot.id = self.robots.len();
+        robot.home_station = self.id;
+        robot.behavior_config = self.behavior_config;
+        if robot.robot_type == RobotType::Explorer {
+            robot.behavior = self.explorer_behavior.build(RobotType::Explorer);
+        }
+        self.robots.push(robot);
+        self.robots.len() - 1
+    }
+
+    // Pick how explorers move, refitting the ones already in the fleet
+    pub fn set_explorer_behavior(&mut self, kind: BehaviorKind) {
+        self.explorer_behavior = kind;
+        for robot in self.robots.iter_mut().filter(|robot| robot.robot_type == RobotType::Explorer) {
+            robot.behavior = kind.build(RobotType::Explorer);
+        }
+    }
+
+    // Tune the thresholds and weights robots decide by, for the ones already in the fleet too
+    pub fn set_behavior_config(&mut self, config: BehaviorConfig) {
+        self.behavior_config = config;
+        for robot in &mut self.robots {
+            robot.behavior_config = config;
+        }
+    }
+
+    // Cells each robot was first to report, indexed by robot id
+    pub fn discoveries_per_robot(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.robots.len()];
+        for &robot in self.discovered_by.values() {
+            if let Some(count) = counts.get_mut(robot) {
+                *count += 1;
+            }
+        }
+        counts
+    }
+
+    // (robot id, cells first reported) of the robots that reported any, most first
+    pub fn discovery_leaderboard(&self) -> Vec<(RobotId, usize)> {
+        let mut board: Vec<(RobotId, usize)> = self.discoveries_per_robot().into_iter().enumerate().filter(|&(_, cells)| cells > 0).collect();
+        board.sort_by_key(|&(robot, cells)| (std::cmp::Reverse(cells), robot));
+        board
+    }
+
+    // The cells the station has mapped within KNOWN_CELLS_RADIUS of it, for an explorer to leave
+    // alone even where no robot walked (deposits only seen from afar)
+    pub fn known_cells_near(&self, map: &Map) -> HashSet<(usize, usize)> {
+        self.known_map
+            .keys()
+            .filter(|&&(x, y)| map.distance(x, y, self.x, self.y) <= KNOWN_CELLS_RADIUS)
+            .copied()
+            .collect()
+    }
+
+    // Method to integrate exploration data from robot `discoverer`, reported at tick `now`
+    pub fn share_data(&mut self, discoverer: RobotId, data_from_robot: &RobotExplorationUpdate, now: u64) {
         for ((x, y), cell_type) in data_from_robot {
-            // Simple merge: last write wins.
-            // Assumes CellType is Clone.
-            self.known_map.insert((*x, *y), cell_type.clone());
+            // Simple merge: last write wins, so a deposit reported Empty is gone. Only first
+            // sightings are new data to analyze, and credited to the reporting robot; a stale
+            // deposit the station forgot was seen before
+            let seen = self.known_map.insert((*x, *y), KnownCell::new(cell_type.clone(), now)).is_some();
+            if !seen && !self.discovered_by.contains_key(&(*x, *y)) {
+                self.unanalyzed_data += DATA_PER_NEW_CELL;
+                self.discovered_by.insert((*x, *y), discoverer);
+            }
         }
         self.analyze_map_data(); // Trigger analysis based on the new map data.
                                  // Decisions (like robot creation) will use this updated map.
     }
 
+    // Forget the deposits last reported before tick `older_than`, likely emptied by now; cells
+    // without a deposit stay mapped, and who discovered each one stays on record so a re-report
+    // is not new data. Returns the number forgotten
+    pub fn prune_stale(&mut self, older_than: u64) -> usize {
+        let before = self.known_map.len();
+        self.known_map.retain(|_, known| !known.cell_type.is_deposit() || known.last_reported_tick >= older_than);
+        before - self.known_map.len()
+    }
+
+    // Send the nearest robot out working or heading home with enough energy to spare to each stranded or low power
+    // robot nobody is helping yet: energy collectors and carriers bring harvested energy, and any
+    // robot the battery it can do without once it has walked there and back. Returns the
+    // (stranded, rescuer) pairs dispatched
+    pub fn dispatch_rescues(&mut self, map: &Map) -> Vec<(usize, usize)> {
+        let mut dispatched = Vec::new();
+        for target in 0..self.robots.len() {
+            let stranded = &self.robots[target];
+            if !stranded.is_waiting_for_rescue() || stranded.energy == 0 {
+                continue;
+            }
+            let claimed = self.robots.iter().any(|robot| {
+                matches!(robot.state, RobotState::Rescuing { target: claimed, .. } if claimed == target)
+            });
+            if claimed {
+                continue;
+            }
+
+            let (x, y) = (stranded.x, stranded.y);
+            let shortfall = stranded.rescue_shortfall(map, self.x, self.y);
+            let distance = |robot: &Robot| (robot.x.abs_diff(x) + robot.y.abs_diff(y)) as u32;
+            let rescuer = self
+                .robots
+                .iter()
+                .enumerate()
+                .filter(|&(index, robot)| {
+                    let trip = distance(robot) * robot.move_cost();
+                    index != target
+                        && matches!(robot.state, RobotState::Exploring | RobotState::ReturningToStation)
+                        && robot.energy > trip
+                        && robot.energy_cargo + robot.spare_energy(map, self.x, self.y).saturating_sub(2 * trip) >= shortfall
+                })
+                .min_by_key(|(index, robot)| (distance(robot), *index))
+                .map(|(index, _)| index);
+
+            if let Some(rescuer) = rescuer {
+                self.robots[rescuer].state = RobotState::Rescuing { target, x, y };
+                dispatched.push((target, rescuer));
+            }
+        }
+        dispatched
+    }
+
+    // Send the nearest idle collector that can afford to haul it home to each known cache
+    // nobody is fetching yet. Returns the (cache position, robot) pairs dispatched
+    pub fn dispatch_pickups(&mut self, map: &Map) -> Vec<((usize, usize), usize)> {
+        let mut dispatched = Vec::new();
+        for &(x, y) in &self.known_caches {
+            let claimed = self.robots.iter().any(|robot| robot.state == RobotState::Retrieving { x, y });
+            if claimed {
+                continue;
+            }
+            let Some(cache) = map.cache_at(x, y) else {
+                continue;
+            };
+            // Energy for the walk out plus the walk home with the cache on board, on plains
+            let cache_load = (cache.minerals + cache.science) / crate::robot::ENERGY_COSTS.load_per_extra_move;
+            let trip_cost = |robot: &Robot| {
+                map.distance(robot.x, robot.y, x, y) * robot.move_cost()
+                    + map.distance(x, y, self.x, self.y) * (robot.move_cost() + cache_load)
+            };
+            let collector = self
+                .robots
+                .iter()
+                .enumerate()
+                .filter(|(_, robot)| {
+                    robot.robot_type != RobotType::Explorer
+                        && robot.state == RobotState::Exploring
+                        && robot.minerals == 0
+                        && robot.science_points == 0
+                        && robot.energy > trip_cost(robot) + crate::robot::ENERGY_COSTS.return_margin
+                })
+                .min_by_key(|(index, robot)| (map.distance(robot.x, robot.y, x, y), *index))
+                .map(|(index, _)| index);
+
+            if let Some(collector) = collector {
+                self.robots[collector].state = RobotState::Retrieving { x, y };
+                dispatched.push(((x, y), collector));
+            }
+        }
+        dispatched
+    }
+
+    // With the buddy system on, pair up the explorers without a buddy, in the order they joined:
+    // the first of each pair leads, the second trails it. Pairs of robots no longer explorers are
+    // split up first. Returns the pairs made
+    pub fn pair_up_explorers(&mut self) -> Vec<(RobotId, RobotId)> {
+        let is_explorer = |robots: &[Robot], id: RobotId| {
+            robots.get(id).is_some_and(|robot| robot.robot_type == RobotType::Explorer && robot.state != RobotState::Decommissioned)
+        };
+        let robots = &self.robots;
+        self.explorer_pairs.retain(|&(leader, trailer)| is_explorer(robots, leader) && is_explorer(robots, trailer));
+        if !self.pair_explorers {
+            return Vec::new();
+        }
+        let unpaired: Vec<RobotId> = (0..self.robots.len())
+            .filter(|&id| is_explorer(&self.robots, id) && self.buddy_of(id).is_none())
+            .collect();
+        let pairs: Vec<(RobotId, RobotId)> = unpaired.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect();
+        self.explorer_pairs.extend(&pairs);
+        pairs
+    }
+
+    // The other explorer of a robot's pair
+    pub fn buddy_of(&self, robot: RobotId) -> Option<RobotId> {
+        self.explorer_pairs.iter().find_map(|&(leader, trailer)| match robot {
+            _ if robot == leader => Some(trailer),
+            _ if robot == trailer => Some(leader),
+            _ => None,
+        })
+    }
+
+    // The explorer a trailing robot keeps formation with
+    pub fn leader_of(&self, robot: RobotId) -> Option<RobotId> {
+        self.explorer_pairs.iter().find(|&&(_, trailer)| trailer == robot).map(|&(leader, _)| leader)
+    }
+
+    // Point an explorer at the center of the least-explored region that no other exploring
+    // explorer is heading for; once every region with unexplored cells is taken, the
+    // least-explored one is shared. With the whole map explored, the explorer is sent to re-scout
+    // the stalest cell if nobody has visited it for a while. Returns the target assigned and why
+    pub fn assign_explorer_region(&mut self, index: usize, map: &Map, tick: u64) -> Option<TargetChoice> {
+        let (grid_w, grid_h) = EXPLORER_REGION_GRID;
+        if self.robots.get(index)?.robot_type != RobotType::Explorer {
+            return None;
+        }
+        // A trailing explorer goes where its leader is heading
+        let leader = self.leader_of(index).filter(|&leader| self.robots[leader].energy > 0);
+        if let Some(leader) = leader {
+            if let Some((x, y)) = self.robots[leader].target_x.zip(self.robots[leader].target_y) {
+                let robot = &mut self.robots[index];
+                (robot.target_x, robot.target_y) = (Some(x), Some(y));
+                let reason = TargetReason::Buddy { leader };
+                return Some(TargetChoice { region: map.region_of(x, y, grid_w, grid_h), target: (x, y), reason });
+            }
+        }
+        let buddy = self.buddy_of(index);
+        let taken: Vec<usize> = self
+            .robots
+            .iter()
+            .enumerate()
+            .filter(|(other, robot)| {
+                *other != index && Some(*other) != buddy && robot.robot_type == RobotType::Explorer && robot.state == RobotState::Exploring
+            })
+            .filter_map(|(_, robot)| Some(map.region_of(robot.target_x?, robot.target_y?, grid_w, grid_h)))
+            .collect();
+
+        let regions = map.regions(grid_w, grid_h);
+        let least_explored = |free_only: bool| {
+            regions
+                .iter()
+                .filter(|region| region.has_unexplored() && !(free_only && taken.contains(&region.index)))
+                .min_by(|a, b| a.stats.percentage.total_cmp(&b.stats.percentage))
+        };
+        let ((x, y), reason) = match (least_explored(true), least_explored(false)) {
+            (Some(region), _) => (region.center(), TargetReason::FreeRegion { explored: region.stats.percentage }),
+            (None, Some(region)) => (region.center(), TargetReason::SharedRegion { explored: region.stats.percentage }),
+            (None, None) => match map.stalest_explored_cell() {
+                Some((x, y, visited)) if tick.saturating_sub(visited) >= REVISIT_AFTER_TICKS => {
+                    ((x, y), TargetReason::StaleCell { idle_ticks: tick - visited })
+                }
+                _ => return None,
+            },
+        };
+
+        let robot = &mut self.robots[index];
+        robot.target_x = Some(x);
+        robot.target_y = Some(y);
+        Some(TargetChoice { region: map.region_of(x, y, grid_w, grid_h), target: (x, y), reason })
+    }
+
+    // Record the deposit a robot has claimed after its move, dropping the one it held before.
+    // A robot that ran dry holds none
+    pub fn record_claim(&mut self, robot: RobotId, target: Option<(usize, usize)>) {
+        self.claimed_targets.retain(|_, claimant| *claimant != robot);
+        if let Some(target) = target.filter(|_| self.robots.get(robot).is_some_and(|robot| robot.energy > 0)) {
+            self.claimed_targets.insert(target, robot);
+        }
+    }
+
+    // Queue a CollectAt for every known deposit that is not queued, claimed or a robot's task
+    // already, nearest the station first, after dropping queued ones since used up or claimed.
+    // Returns the number of tasks queued
+    pub fn refresh_tasks(&mut self, map: &Map) -> usize {
+        let is_deposit = |cell_type: &CellType| matches!(cell_type, CellType::Energy(_) | CellType::Mineral(_) | CellType::SciencePoint(_));
+        let (known_map, claimed) = (&self.known_map, &self.claimed_targets);
+        self.task_queue.retain(|task| match *task {
+            Task::CollectAt { x, y } => known_map.get(&(x, y)).is_some_and(|known| is_deposit(&known.cell_type)) && !claimed.contains_key(&(x, y)),
+            _ => true,
+        });
+        let taken: HashSet<(usize, usize)> = self
+            .task_queue
+            .iter()
+            .chain(self.robots.iter().filter_map(|robot| robot.current_task.as_ref()))
+            .filter_map(|task| task.destination())
+            .collect();
+        let mut deposits: Vec<(usize, usize)> = known_map
+            .iter()
+            .filter(|&(position, known)| is_deposit(&known.cell_type) && !taken.contains(position) && !claimed.contains_key(position))
+            .map(|(&position, _)| position)
+            .collect();
+        deposits.sort_by_key(|&(x, y)| (map.distance(self.x, self.y, x, y), y, x));
+        let queued = deposits.len();
+        self.task_queue.extend(deposits.into_iter().map(|(x, y)| Task::CollectAt { x, y }));
+        queued
+    }
+
+    // Hand the docked robot `index` the first queued task that suits its type and that it can
+    // walk to and back from on its charge, unless it is still busy with one. A Scientist looks at
+    // the science points in the richest clusters first. A robot called home leaves the other
+    // calls home for the rest. Returns the task handed out
+    pub fn take_task(&mut self, index: usize, map: &Map) -> Option<Task> {
+        let robot = self.robots.get(index)?;
+        let called_home = robot.current_task == Some(Task::ReturnHome);
+        if robot.current_task.is_some() && !called_home {
+            return None;
+        }
+        let mut order: Vec<usize> = (0..self.task_queue.len()).collect();
+        if robot.robot_type == RobotType::Scientist {
+            let density = self.science_cluster_density(map);
+            let queue = &self.task_queue;
+            order.sort_by_key(|&position| {
+                std::cmp::Reverse(queue[position].destination().and_then(|destination| density.get(&destination).copied()).unwrap_or(0))
+            });
+        }
+        let margin = ENERGY_COSTS.return_margin;
+        let mut path_checks = 0;
+        let position = order.into_iter().find(|&position| {
+            let task = self.task_queue[position];
+            if !task.suits(robot.robot_type, &self.known_map) || (called_home && task == Task::ReturnHome) {
+                return false;
+            }
+            let Some((x, y)) = task.destination() else {
+                return true;
+            };
+            // The plains estimate rules most out before a path search is spent on them
+            if 2 * map.distance(self.x, self.y, x, y) * robot.spec.move_cost + margin > robot.energy {
+                return false;
+            }
+            path_checks += 1;
+            path_checks <= TASK_PATH_CHECKS
+                && robot.unloaded_trip(map, (self.x, self.y), (x, y)).is_some_and(|(energy, _)| 2 * energy + margin <= robot.energy)
+        })?;
+        let task = self.task_queue.remove(position)?;
+        let robot = &mut self.robots[index];
+        robot.current_task = Some(task);
+        if let Task::ExploreRegion { x, y } = task {
+            robot.target_x = Some(x);
+            robot.target_y = Some(y);
+        }
+        Some(task)
+    }
+
+    // Give an explorer whose target is beyond a round trip on its current charge an expedition:
+    // the cheapest chain of known energy deposits to refuel at on the way there and back.
+    // Returns the number of refuel stops planned (0 when none are needed or no chain works)
+    pub fn plan_expedition(&mut self, index: usize, map: &Map) -> usize {
+        let station = (self.x, self.y);
+        let Some(robot) = self.robots.get_mut(index) else {
+            return 0;
+        };
+        robot.expedition.clear();
+        let (RobotType::Explorer, Some(target_x), Some(target_y)) = (robot.robot_type, robot.target_x, robot.target_y) else {
+            return 0;
+        };
+        let (start, target) = ((robot.x, robot.y), (target_x, target_y));
+        let margin = ENERGY_COSTS.return_margin;
+        let trip_cost = |from, to| robot.unloaded_trip(map, from, to).map(|(energy, steps)| energy + steps * robot.load_factor());
+        let round_trip = trip_cost(start, target).zip(trip_cost(target, station));
+        if round_trip.is_some_and(|(there, back)| there + back + margin <= robot.energy) {
+            return 0;
+        }
+
+        let mut points = expedition::refuel_points(&self.known_map, map, start, target, station);
+        // Deposits a collector has claimed are left to it
+        points.retain(|point| self.claimed_targets.get(&(point.x, point.y)).is_none_or(|&claimant| claimant == index));
+        let robot = &self.robots[index];
+        let leg_cost = |from: (usize, usize), to: (usize, usize)| {
+            // Legs longer than a full tank on plains are not worth a path search
+            if map.distance(from.0, from.1, to.0, to.1) * robot.spec.move_cost > expedition::MAX_PLANNED_CHARGE {
+                return None;
+            }
+            robot.unloaded_trip(map, from, to)
+        };
+        let plan = expedition::plan(start, robot.energy, target, station, &points, margin, leg_cost).unwrap_or_default();
+        let refuel_stops = plan.iter().filter(|leg| leg.refuel).count();
+        if refuel_stops > 0 {
+            self.robots[index].expedition = plan.into();
+        }
+        refuel_stops
+    }
+
+    // How much of the map's passable area the station has been told about
+    pub fn known_coverage(&self, map: &Map) -> ExplorationStats {
+        let mut passable_cells = 0;
+        let mut known_cells = 0;
+        for (x, y, cell) in map.iter_cells() {
+            if cell.cell_type != CellType::Obstacle {
+                passable_cells += 1;
+                if self.known_map.contains_key(&(x, y)) {
+                    known_cells += 1;
+                }
+            }
+        }
+        ExplorationStats::new(passable_cells, known_cells)
+    }
+
     pub fn display_stats(&self) -> String {
         format!(
-            "Station @ ({}, {}) => Energy: {}, Minerals: {}, Science: {}, Robots: {}",
-            self.x, self.y, self.energy, self.minerals, self.science_points, self.robots.len()
+            "Station @ ({}, {}) => Energy: {} {}/{}, Minerals: {} {} refined + {} raw/{} (backlog {} ticks), Science: {} {}/{}, Data: {} unanalyzed (+{}/tick), Parts: {} (saved {} minerals), Upgrades: {}, Facilities: {}, Robots: {}",
+            self.x, self.y,
+            capacity_bar(self.energy, self.capacity.energy), self.energy, self.capacity.energy,
+            capacity_bar(self.raw_minerals + self.refined_minerals, self.capacity.minerals),
+            self.refined_minerals, self.raw_minerals, self.capacity.minerals, self.refinement_backlog_ticks(),
+            capacity_bar(self.science_points, self.capacity.science), self.science_points, self.capacity.science,
+            self.unanalyzed_data, self.analysis_rate(),
+            self.parts, self.parts_savings,
+            self.upgrades.summary(),
+            facilities::summary(&self.facilities),
+            self.robots.len()
         )
     }
 
@@ -210,52 +1201,104 @@ impl Station {
         let mut energy_collector_count = 0;
         let mut mineral_collector_count = 0;
         let mut scientist_count = 0;
+        let mut miner_count = 0;
+        let mut carrier_count = 0;
+        let mut repairer_count = 0;
         let mut exploring_count = 0;
         let mut returning_count = 0;
         let mut at_station_count = 0;
+        let mut stranded_count = 0;
+        let mut low_power_count = 0;
+        let mut rescuing_count = 0;
+        let mut retrieving_count = 0;
+        let mut working_count = 0;
+        let mut broken_count = 0;
         let mut dead_count = 0;
+        let mut degraded_count = 0;
+        let mut decommissioned_count = 0;
+        let mut total_wear = 0;
 
         let mut total_energy = 0;
         let mut total_minerals = 0;
         let mut total_science = 0;
 
         for robot in &self.robots {
+            if robot.state == RobotState::Decommissioned {
+                decommissioned_count += 1;
+                continue;
+            }
+            total_wear += robot.wear_percent();
+
             // Count by type
             match robot.robot_type {
                 RobotType::Explorer => explorer_count += 1,
                 RobotType::EnergyCollector => energy_collector_count += 1,
                 RobotType::MineralCollector => mineral_collector_count += 1,
                 RobotType::Scientist => scientist_count += 1,
+                RobotType::Miner => miner_count += 1,
+                RobotType::Carrier => carrier_count += 1,
+                RobotType::Repairer => repairer_count += 1,
             }
 
             // Count by state (and check for dead robots)
             if robot.energy == 0 {
                 dead_count += 1;
             } else {
+                if !robot.malfunctions.is_empty() {
+                    degraded_count += 1;
+                }
                 match robot.state {
-                    crate::robot::RobotState::Exploring => exploring_count += 1,
-                    crate::robot::RobotState::ReturningToStation => returning_count += 1,
-                    crate::robot::RobotState::AtStation => at_station_count += 1,
+                    RobotState::Exploring => exploring_count += 1,
+                    RobotState::ReturningToStation => returning_count += 1,
+                    RobotState::AtStation | RobotState::Idle => at_station_count += 1,
+                    RobotState::Stranded => stranded_count += 1,
+                    RobotState::LowPower { .. } => low_power_count += 1,
+                    RobotState::Rescuing { .. } => rescuing_count += 1,
+                    RobotState::Retrieving { .. } => retrieving_count += 1,
+                    RobotState::Working { .. } | RobotState::Analyzing { .. } => working_count += 1,
+                    RobotState::Broken => broken_count += 1,
+                    RobotState::Decommissioned => {}
                 }
             }
 
             // Sum resources
-            total_energy += robot.energy;
+            total_energy += robot.energy_cargo;
             total_minerals += robot.minerals;
             total_science += robot.science_points;
         }
 
+        let lifetime: LifetimeStats = self.robots.iter().map(Robot::lifetime_stats).sum();
+
+        // Robots are numbered from 1 on screen, as in the fleet table
+        let top_discoverer = match self.discovery_leaderboard().first() {
+            Some(&(robot, cells)) => format!("#{} ({:?}) {} cells", robot + 1, self.robots[robot].robot_type, cells),
+            None => "none yet".to_string(),
+        };
+
         format!(
-            "Swarm: {} robots | Types: E:{} En:{} M:{} S:{} | States: Exploring:{} Returning:{} AtStation:{} Dead:{} | Total Cargo: Energy:{} Minerals:{} Science:{}",
+            "Swarm: {} robots | Types: E:{} En:{} M:{} S:{} D:{} C:{} R:{} | States: Exploring:{} Returning:{} AtStation:{} Stranded:{} LowPower:{} Rescuing:{} Retrieving:{} Working:{} Broken:{} Dead:{} Scrapped:{} | Retrofits:{} | Degraded:{} | Avg Wear:{}% | Total Cargo: Energy:{} Minerals:{} Science:{} | Lifetime: {} steps, {} energy used, Delivered E/M/S:{}/{}/{}, {} discovered, {} respawns | Top Discoverer: {}",
             self.robots.len(),
-            explorer_count, energy_collector_count, mineral_collector_count, scientist_count,
-            exploring_count, returning_count, at_station_count, dead_count,
-            total_energy, total_minerals, total_science
+            explorer_count, energy_collector_count, mineral_collector_count, scientist_count, miner_count, carrier_count, repairer_count,
+            exploring_count, returning_count, at_station_count, stranded_count, low_power_count, rescuing_count, retrieving_count, working_count, broken_count,
+            dead_count, decommissioned_count, self.retrofits, degraded_count,
+            total_wear / (self.robots.len() - decommissioned_count).max(1) as u32,
+            total_energy, total_minerals, total_science,
+            lifetime.distance, lifetime.energy_consumed, lifetime.energy_delivered, lifetime.minerals_delivered, lifetime.science_delivered,
+            lifetime.cells_discovered, lifetime.respawns,
+            top_discoverer
         )
     }
 }
 
 // The RobotExplorationUpdate type and known_map field handle shared map information
+// Text gauge of how full a storage pool is, e.g. "[###-----]"
+fn capacity_bar(stored: u32, capacity: u32) -> String {
+    let filled = (stored.min(capacity) as u64 * CAPACITY_BAR_WIDTH as u64)
+        .checked_div(capacity as u64)
+        .unwrap_or(CAPACITY_BAR_WIDTH as u64) as usize;
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(CAPACITY_BAR_WIDTH as usize - filled))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -266,7 +1309,8 @@ mod tests {
         assert_eq!(station.x, 5);
         assert_eq!(station.y, 10);
         assert_eq!(station.energy, 2000);
-        assert_eq!(station.minerals, 500);
+        assert_eq!(station.refined_minerals, 500);
+        assert_eq!(station.raw_minerals, 0);
         assert_eq!(station.science_points, 0);
         assert!(station.robots.is_empty());
         assert!(station.known_map.is_empty());
@@ -276,22 +1320,145 @@ mod tests {
     fn test_collect_resources() {
         let mut station = Station::new(0, 0);
         let initial_energy = station.energy;
-        let initial_minerals = station.minerals;
+        let initial_minerals = station.raw_minerals;
         let initial_science = station.science_points;
 
         station.collect_resources(100, 50, 10);
 
         assert_eq!(station.energy, initial_energy + 100);
-        assert_eq!(station.minerals, initial_minerals + 50);
+        assert_eq!(station.raw_minerals, initial_minerals + 50); // Delivered minerals arrive raw
+        assert_eq!(station.refined_minerals, 500);
         assert_eq!(station.science_points, initial_science + 10);
     }
 
+    #[test]
+    fn test_collect_resources_up_to_capacity() {
+        let mut station = Station::new(0, 0);
+        station.capacity = StorageCapacity { energy: 2050, minerals: 520, science: 5 };
+
+        let overflow = station.collect_resources(100, 30, 10);
+        assert_eq!(overflow, (50, 10, 5)); // Raw and refined minerals share the pool
+        assert_eq!((station.energy, station.raw_minerals, station.science_points), (2050, 20, 5));
+        assert!(station.storage_full);
+
+        // Refining moves minerals within the pool without freeing room
+        station.tick();
+        assert_eq!(station.collect_resources(0, 1, 0), (0, 1, 0));
+    }
+
+    #[test]
+    fn test_full_storage_pushes_station_to_build() {
+        let mut station = Station::new(0, 0);
+        assert!(!station.should_create_robot(0)); // Nothing known worth a new robot
+
+        station.capacity.energy = station.energy;
+        station.collect_resources(10, 0, 0);
+        assert!(station.should_create_robot(0));
+
+        assert!(station.create_robot(1, 1, &Map::new(5, 5, 1)).is_some());
+        assert!(!station.storage_full);
+        assert!(!station.should_create_robot(0));
+    }
+
+    #[test]
+    fn test_fenced_deposits_call_for_a_miner() {
+        let (map, _) = Map::from_ascii("m#.m#.m#.\n##.##.##.\n.........\n").unwrap();
+        let mut station = Station::new(2, 2);
+        station.robots = vec![Robot::new(2, 2), Robot::new(3, 2)];
+        station.known_map.insert((0, 0), KnownCell::new(CellType::Mineral(50), 0));
+        station.known_map.insert((3, 0), KnownCell::new(CellType::Mineral(50), 0));
+        assert_ne!(station.choose_robot_type(&map), RobotType::Miner);
+
+        station.known_map.insert((6, 0), KnownCell::new(CellType::Mineral(50), 0));
+        assert_eq!(station.choose_robot_type(&map), RobotType::Miner);
+        assert!(station.create_robot(2, 1, &map).is_some());
+        assert_ne!(station.choose_robot_type(&map), RobotType::Miner); // One is enough
+    }
+
+    #[test]
+    fn test_broken_robot_calls_for_a_repairer() {
+        let map = Map::new(5, 5, 1);
+        let mut station = Station::new(0, 0);
+        station.robots = vec![Robot::new_with_type(1, 0, RobotType::Explorer), Robot::new_with_type(2, 0, RobotType::Explorer)];
+        station.robots[1].state = RobotState::Broken;
+        assert_eq!(station.robot_type_choice(&map), (RobotType::Repairer, TypeReason::BrokenRobots { robots: 1 }));
+        assert!(station.display_swarm_stats().contains("Broken:1"));
+        assert!(station.create_robot(1, 1, &map).is_some());
+        assert_ne!(station.robot_type_choice(&map).0, RobotType::Repairer); // One is enough
+    }
+
+    #[test]
+    fn test_two_collectors_call_for_a_carrier() {
+        let map = Map::new(5, 5, 1);
+        let mut station = Station::new(0, 0);
+        station.robots = vec![Robot::new_with_type(1, 0, RobotType::Explorer), Robot::new_with_type(2, 0, RobotType::Explorer)];
+        station.robots.push(Robot::new_with_type(3, 0, RobotType::MineralCollector));
+        assert_ne!(station.robot_type_choice(&map).0, RobotType::Carrier);
+        station.robots.push(Robot::new_with_type(4, 0, RobotType::EnergyCollector));
+        assert_eq!(station.robot_type_choice(&map), (RobotType::Carrier, TypeReason::CollectorsInField { collectors: 2 }));
+        assert!(station.create_robot(1, 1, &map).is_some());
+        assert_ne!(station.robot_type_choice(&map).0, RobotType::Carrier); // One is enough
+    }
+
+    #[test]
+    fn test_explorers_on_hold_are_only_built_to_keep_the_minimum() {
+        let (map, _) = Map::from_ascii("....\n").unwrap();
+        let mut station = Station::new(0, 0);
+        station.robots = vec![Robot::new_with_type(1, 0, RobotType::Explorer), Robot::new_with_type(2, 0, RobotType::Explorer)];
+        let kinds = [RobotType::EnergyCollector, RobotType::MineralCollector, RobotType::Carrier];
+        station.robots.extend(kinds.map(|kind| Robot::new_with_type(3, 0, kind)));
+        assert_eq!(station.choose_robot_type(&map), RobotType::Explorer);
+        station.explorers_on_hold = true;
+        assert_eq!(station.choose_robot_type(&map), RobotType::EnergyCollector);
+        station.robots.clear();
+        assert_eq!(station.choose_robot_type(&map), RobotType::Explorer);
+    }
+
+    #[test]
+    fn test_discoveries_survive_the_handoff_to_the_station() {
+        let (mut map, _) = Map::from_ascii("......\n").unwrap();
+        let mut station = Station::new(0, 0);
+        for x in [1, 4] {
+            let mut explorer = Robot::new_with_type(x, 0, RobotType::Explorer);
+            explorer.spec.sensor_radius = 0; // Just the cells walked over
+            station.add_robot(explorer);
+        }
+        // Each robot explores its own cells, then both cross the middle one, robot 1 first
+        for (robot, cells) in [(1, vec![4, 5, 3]), (0, vec![1, 2, 3])] {
+            for x in cells {
+                station.robots[robot].x = x;
+                station.robots[robot].explore(&mut map, 0);
+            }
+        }
+        assert_eq!(map.get_cell(3, 0).unwrap().discovered_by, Some(1));
+        assert_eq!(map.get_cell(1, 0).unwrap().discovered_by, Some(0));
+
+        // Robot 0 reports first, so the station credits it with the shared cell
+        for robot in [0, 1] {
+            let updates = station.robots[robot].get_exploration_updates();
+            station.share_data(station.robots[robot].id, &updates, 0);
+        }
+        assert_eq!(station.discovered_by.get(&(3, 0)), Some(&0));
+        assert_eq!(station.discovered_by.get(&(5, 0)), Some(&1));
+        assert_eq!(station.discoveries_per_robot(), vec![3, 2]);
+        assert_eq!(station.discovery_leaderboard(), vec![(0, 3), (1, 2)]);
+        assert!(station.display_swarm_stats().ends_with("Top Discoverer: #1 (Explorer) 3 cells"));
+    }
+
+    #[test]
+    fn test_capacity_bar() {
+        assert_eq!(capacity_bar(0, 100), "[--------]");
+        assert_eq!(capacity_bar(50, 100), "[####----]");
+        assert_eq!(capacity_bar(500, 100), "[########]");
+        assert_eq!(capacity_bar(0, 0), "[########]"); // No room at all
+    }
+
     #[test]
     fn test_consume_resources_success() {
         let mut station = Station::new(0, 0);
         assert!(station.consume_resources(100, 50));
         assert_eq!(station.energy, 2000 - 100);
-        assert_eq!(station.minerals, 500 - 50);
+        assert_eq!(station.refined_minerals, 500 - 50);
     }
 
     #[test]
@@ -303,14 +1470,14 @@ mod tests {
         
         // Resources should remain unchanged
         assert_eq!(station.energy, 2000);
-        assert_eq!(station.minerals, 500);
+        assert_eq!(station.refined_minerals, 500);
     }
 
     #[test]
     fn test_should_create_robot_with_no_robots() {
         let station = Station::new(0, 0);
         // With no robots and no known resources, should not create robot
-        assert!(!station.should_create_robot());
+        assert!(!station.should_create_robot(0));
     }
 
     #[test]
@@ -318,12 +1485,37 @@ mod tests {
         let mut station = Station::new(0, 0);
         
         // Add some known valuable resources
-        station.known_map.insert((1, 1), CellType::Energy(100));
-        station.known_map.insert((2, 2), CellType::Mineral(50));
-        station.known_map.insert((3, 3), CellType::SciencePoint);
+        station.known_map.insert((1, 1), KnownCell::new(CellType::Energy(100), 0));
+        station.known_map.insert((2, 2), KnownCell::new(CellType::Mineral(50), 0));
+        station.known_map.insert((3, 3), KnownCell::new(CellType::SciencePoint(1), 0));
         
         // Now should be willing to create robot
-        assert!(station.should_create_robot());
+        assert!(station.should_create_robot(0));
+    }
+
+    #[test]
+    fn test_build_verdict_explains_refusals() {
+        let mut station = Station::new(0, 0);
+        station.known_map.insert((1, 1), KnownCell::new(CellType::Energy(100), 0));
+        assert_eq!(station.build_verdict(0).reason(), "too few known targets: 1 < 2");
+        station.known_map.insert((2, 2), KnownCell::new(CellType::Mineral(50), 0));
+        assert_eq!(station.build_verdict(0).reason(), "enough known targets: 2 >= 2");
+
+        station.refined_minerals = 140;
+        assert_eq!(station.build_verdict(0), BuildVerdict::MineralBuffer { have: 140, need: 150 });
+        assert_eq!(station.build_verdict(0).reason(), "insufficient mineral buffer: 140 < 150");
+        station.parts = 5; // Salvaged parts lower the mineral cost
+        assert_eq!(station.build_verdict(0).reason(), "enough known targets: 2 >= 2");
+
+        station.energy = 250;
+        assert_eq!(station.build_verdict(0).reason(), "insufficient energy buffer: 250 < 400");
+        let map = Map::new(5, 5, 1);
+        assert_eq!(station.robot_type_choice(&map), (RobotType::Explorer, TypeReason::NoExplorer));
+        station.robots.push(Robot::new_with_type(1, 1, RobotType::Explorer));
+        assert_eq!(
+            station.robot_type_choice(&map).1.reason(),
+            "station energy low: 250 < 300 with 1 known energy deposit(s) and 0 collector(s)"
+        );
     }
 
     #[test]
@@ -331,12 +1523,84 @@ mod tests {
         let mut station = Station::new(0, 0);
         let initial_robot_count = station.robots.len();
         
-        assert!(station.create_robot(1, 1));
+        assert!(station.create_robot(1, 1, &Map::new(5, 5, 1)).is_some());
         assert_eq!(station.robots.len(), initial_robot_count + 1);
         
         // Check resources were consumed
         assert_eq!(station.energy, 2000 - ROBOT_ENERGY_COST);
-        assert_eq!(station.minerals, 500 - ROBOT_MINERAL_COST);
+        assert_eq!(station.refined_minerals, 500 - ROBOT_MINERAL_COST);
+    }
+
+    #[test]
+    fn test_decommissioning_weighs_upkeep_against_a_refunded_replacement() {
+        let mut station = Station::new(0, 0);
+        let mut worn = Robot::new_with_type(0, 0, RobotType::MineralCollector);
+        worn.lifetime.collections = worn.behavior_config.wear_after_collections;
+        station.add_robot(worn);
+        station.add_robot(Robot::new_with_type(0, 0, RobotType::MineralCollector));
+
+        // Three batteries of 100 at twice the step cost take 600 energy, 750 with the surcharge
+        assert_eq!(station.upkeep_of_wear(0), 750 - 300);
+        assert_eq!(station.upkeep_of_wear(1), 0);
+        // 100 energy and 50 minerals less the 20 refunded, at 4 energy a mineral
+        assert_eq!(station.decommission_refund(), 20);
+        assert_eq!(station.replacement_cost(), 100 + 30 * 4);
+        assert!(station.should_decommission(0));
+        assert!(!station.should_decommission(1));
+        station.robots[0].minerals = 5; // Cargo comes home before the robot is scrapped
+        assert!(!station.should_decommission(0));
+        station.robots[0].minerals = 0;
+
+        station.claimed_targets.insert((3, 3), 0);
+        assert_eq!(station.decommission_robot(0), 20);
+        assert_eq!(station.refined_minerals, 520);
+        assert_eq!(station.robots[0].state, RobotState::Decommissioned);
+        assert!(station.claimed_targets.is_empty());
+        assert_eq!(station.fleet_size(), 1);
+
+        // The refund only fills what room storage has left
+        station.capacity.minerals = station.refined_minerals + 5;
+        assert_eq!(station.decommission_robot(1), 5);
+        assert_eq!(station.fleet_size(), 0);
+    }
+
+    #[test]
+    fn test_recall_turns_every_robot_that_can_walk_home() {
+        let mut station = Station::new(0, 0);
+        let types = [
+            RobotType::Explorer,
+            RobotType::EnergyCollector,
+            RobotType::MineralCollector,
+            RobotType::Scientist,
+            RobotType::Miner,
+            RobotType::Carrier,
+            RobotType::Repairer,
+        ];
+        let busy = [RobotState::Exploring, RobotState::Working { x: 5, y: 6, ticks_left: 3 }, RobotState::Analyzing { x: 5, y: 5, ticks_left: 2 }];
+        for (index, &robot_type) in types.iter().enumerate() {
+            let mut robot = Robot::new_with_type(5, 5, robot_type);
+            robot.state = busy[index % busy.len()];
+            station.add_robot(robot);
+        }
+        for state in [RobotState::Broken, RobotState::Stranded] {
+            let mut robot = Robot::new_with_type(5, 5, RobotType::Explorer);
+            robot.state = state;
+            station.add_robot(robot);
+        }
+
+        station.set_recall(true, 0);
+        for robot in &station.robots[..types.len()] {
+            assert_eq!((robot.state, robot.target_x, robot.target_y), (RobotState::ReturningToStation, Some(0), Some(0)), "{:?}", robot.robot_type);
+            assert_eq!(robot.current_task, Some(Task::ReturnHome));
+        }
+        // Those two cannot walk, and wait for help as before
+        assert_eq!(station.robots[types.len()].state, RobotState::Broken);
+        assert_eq!(station.robots[types.len() + 1].state, RobotState::Stranded);
+        assert_eq!(station.build_verdict(0), BuildVerdict::Recalled);
+
+        station.set_recall(false, 0);
+        assert_ne!(station.build_verdict(0), BuildVerdict::Recalled);
+        assert!(station.robots.iter().all(|robot| robot.current_task.is_none()));
     }
 
     #[test]
@@ -344,13 +1608,150 @@ mod tests {
         let mut station = Station::new(0, 0);
         // Drain resources
         station.energy = 50; // Less than ROBOT_ENERGY_COST (100)
-        station.minerals = 25; // Less than ROBOT_MINERAL_COST (50)
+        station.refined_minerals = 25; // Less than ROBOT_MINERAL_COST (50)
+        station.raw_minerals = 1000; // Raw stock cannot be spent
         
         let initial_robot_count = station.robots.len();
-        assert!(!station.create_robot(1, 1));
+        assert!(station.create_robot(1, 1, &Map::new(5, 5, 1)).is_none());
         assert_eq!(station.robots.len(), initial_robot_count);
     }
 
+    #[test]
+    fn test_parts_discount_the_mineral_cost() {
+        let map = Map::new(5, 5, 1);
+        let mut station = Station::new(0, 0);
+        station.parts = 10;
+        assert!(station.create_robot(1, 1, &map).is_some());
+        // 10 parts stand in for 20 minerals
+        assert_eq!(station.refined_minerals, 500 - (ROBOT_MINERAL_COST - 20));
+        assert_eq!((station.parts, station.parts_savings), (0, 20));
+
+        // Without parts the full cost is paid again
+        assert!(station.create_robot(1, 1, &map).is_some());
+        assert_eq!(station.refined_minerals, 500 - (ROBOT_MINERAL_COST - 20) - ROBOT_MINERAL_COST);
+
+        // Parts make up for minerals the station could not afford on its own
+        station.refined_minerals = ROBOT_MINERAL_COST - 20;
+        station.parts = 10;
+        assert!(station.create_robot(1, 1, &map).is_some());
+        assert_eq!(station.refined_minerals, 0);
+    }
+
+    #[test]
+    fn test_parts_never_cover_more_than_half_the_cost() {
+        let mut station = Station::new(0, 0);
+        station.parts = 100;
+        let (parts, minerals) = station.robot_build_cost();
+        assert!(minerals >= ROBOT_MINERAL_COST / 2);
+        assert_eq!(minerals, ROBOT_MINERAL_COST - parts * MINERALS_PER_PART);
+        assert!(station.create_robot(1, 1, &Map::new(5, 5, 1)).is_some());
+        assert_eq!(station.parts, 100 - parts); // The rest is kept for later robots
+        assert_eq!(station.refined_minerals, 500 - minerals);
+    }
+
+    #[test]
+    fn test_facilities_change_yield_refuel_and_construction_costs() {
+        let mut station = Station::new(0, 0);
+        station.facilities.push(Facility { kind: FacilityKind::Refinery, level: 1 });
+        assert_eq!(station.collect_resources(0, 50, 0), (0, 0, 0));
+        assert_eq!(station.raw_minerals, 55);
+        // The bonus only fills the room left
+        station.raw_minerals = station.capacity.minerals - station.refined_minerals - 50;
+        assert_eq!(station.collect_resources(0, 50, 0), (0, 0, 0));
+        assert_eq!(station.raw_minerals + station.refined_minerals, station.capacity.minerals);
+        assert_eq!(station.facility_usage.minerals_unloaded, 100);
+
+        let mut station = Station::new(0, 0);
+        assert_eq!(station.pay_for_refuel(100), Some(100));
+        station.facilities.push(Facility { kind: FacilityKind::ChargingArray, level: 2 });
+        assert_eq!(station.pay_for_refuel(100), Some(50));
+        assert_eq!(station.energy, 2000 - 150);
+        assert_eq!(station.pay_for_refuel(0), None);
+        station.energy = 49;
+        assert_eq!(station.pay_for_refuel(100), None);
+        assert_eq!((station.energy, station.facility_usage.refuel_energy), (49, 200));
+
+        let mut station = Station::new(0, 0);
+        station.facilities.push(Facility { kind: FacilityKind::Workshop, level: 1 });
+        assert_eq!(station.robot_build_cost(), (0, ROBOT_MINERAL_COST - 10));
+        assert!(station.create_robot(1, 1, &Map::new(5, 5, 1)).is_some());
+        assert_eq!(station.refined_minerals, 500 - (ROBOT_MINERAL_COST - 10));
+        assert_eq!(station.facility_usage.robots_built, 1);
+        // Stacked on the construction upgrade the discount stops at free robots
+        station.facilities[0].level = 3;
+        station.upgrades.construction = 3;
+        assert_eq!(station.robot_build_cost(), (0, 0));
+    }
+
+    #[test]
+    fn test_facilities_are_bought_by_payback_time_keeping_a_robot_in_reserve() {
+        let mut station = Station::new(0, 0);
+        station.facility_usage = FacilityUsage { ticks: 100, minerals_unloaded: 1000, refuel_energy: 4000, ..FacilityUsage::default() };
+        assert_eq!(station.consider_facility_purchase(), None); // Too early to judge
+        station.facility_usage.ticks = 1000;
+        // The refinery pays back in 1000 ticks, the charging array in 320
+        let array = Facility { kind: FacilityKind::ChargingArray, level: 1 };
+        assert_eq!(station.consider_facility_purchase(), Some(array));
+        assert_eq!(station.refined_minerals, 500 - 80);
+        // Its second level costs twice as much and still pays back first
+        station.refined_minerals = 160 + ROBOT_MINERAL_COST - 1;
+        assert_eq!(station.consider_facility_purchase(), None);
+        station.refined_minerals += 1;
+        assert_eq!(station.consider_facility_purchase(), Some(Facility { level: 2, ..array }));
+        assert_eq!(station.refined_minerals, ROBOT_MINERAL_COST);
+        station.refined_minerals = 500;
+        assert_eq!(station.consider_facility_purchase(), Some(Facility { kind: FacilityKind::Refinery, level: 1 }));
+        assert_eq!(facilities::summary(&station.facilities), "charging array 2, refinery 1");
+    }
+
+    #[test]
+    fn test_warehouse_makes_room_for_deliveries_storage_turned_away() {
+        let mut station = Station::new(0, 0);
+        station.raw_minerals = station.capacity.minerals - station.refined_minerals;
+        assert_eq!(station.collect_resources(0, 40, 0), (0, 40, 0));
+        assert_eq!(station.facility_usage.turned_away, 40);
+
+        // Turning deliveries away is all the station has done: the warehouse pays back first
+        station.facility_usage.ticks = 1000;
+        station.refined_minerals += 150 + ROBOT_MINERAL_COST;
+        station.raw_minerals -= 150 + ROBOT_MINERAL_COST;
+        station.facility_usage.turned_away = 2000;
+        assert_eq!(station.consider_facility_purchase(), Some(Facility { kind: FacilityKind::Warehouse, level: 1 }));
+        assert_eq!(station.capacity.minerals, DEFAULT_STORAGE_CAPACITY.minerals * 3 / 2);
+        assert_eq!(station.capacity.energy, DEFAULT_STORAGE_CAPACITY.energy * 3 / 2);
+        assert_eq!(station.collect_resources(0, 40, 0), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_upgrades_cost_science_and_change_new_robots() {
+        let map = Map::new(5, 5, 1);
+        let mut station = Station::new(0, 0);
+        station.science_points = 35;
+        assert_eq!(station.maybe_purchase_upgrade(), Some(Upgrade::Battery));
+        assert_eq!(station.maybe_purchase_upgrade(), Some(Upgrade::Battery)); // Level 2 costs 20
+        assert_eq!(station.science_points, 5);
+        assert_eq!(station.maybe_purchase_upgrade(), None);
+
+        assert!(station.create_robot(1, 1, &map).is_some());
+        let robot = &station.robots[0];
+        let stock = robot.robot_type.spec();
+        assert_eq!(robot.spec.max_energy, stock.max_energy * 140 / 100);
+        assert_eq!(robot.energy, robot.spec.max_energy);
+        assert_eq!(robot.spec.sensor_radius, stock.sensor_radius);
+
+        // Sensors and harvesting reach robots already in the field when they refuel
+        station.upgrades.buy(Upgrade::Sensors);
+        station.upgrades.buy(Upgrade::Harvesting);
+        station.fit_upgrades(0);
+        assert_eq!((station.robots[0].spec.sensor_radius, station.robots[0].harvest_boost), (stock.sensor_radius + 1, 25));
+
+        // Cheaper construction
+        station.upgrades.buy(Upgrade::Construction);
+        let minerals = station.refined_minerals;
+        assert!(station.create_robot(1, 1, &map).is_some());
+        assert_eq!(station.refined_minerals, minerals - (ROBOT_MINERAL_COST - 10));
+    }
+
     #[test]
     fn test_share_data() {
         let mut station = Station::new(0, 0);
@@ -360,10 +1761,351 @@ mod tests {
             ((2, 2), CellType::Mineral(50)),
         ];
         
-        station.share_data(&robot_data);
+        station.share_data(0, &robot_data, 0);
         
         assert_eq!(station.known_map.len(), 2);
-        assert_eq!(station.known_map.get(&(1, 1)), Some(&CellType::Energy(100)));
-        assert_eq!(station.known_map.get(&(2, 2)), Some(&CellType::Mineral(50)));
+        assert_eq!(station.known_map.get(&(1, 1)), Some(&KnownCell::new(CellType::Energy(100), 0)));
+        assert_eq!(station.known_map.get(&(2, 2)), Some(&KnownCell::new(CellType::Mineral(50), 0)));
+    }
+
+    #[test]
+    fn test_surplus_explorers_are_retrofitted_once_the_map_is_nearly_explored() {
+        let (mut map, _) = Map::from_ascii("...e\n....\n").unwrap();
+        let mut station = Station::new(0, 0);
+        station.known_map.insert((3, 0), KnownCell::new(CellType::Energy(50), 0));
+        for x in 0..3 {
+            station.add_robot(Robot::new_with_type(x, 1, RobotType::Explorer));
+        }
+        assert_eq!(station.retrofit_choice(0, &map), None); // Nothing explored yet
+        map.iter_cells_mut().for_each(|(_, _, cell)| cell.explored = true);
+        let choice = station.retrofit_choice(0, &map);
+        assert_eq!(choice, Some((RobotType::EnergyCollector, TypeReason::MapExplored { explored: 100 })));
+        station.robots[0].minerals = 5;
+        assert_eq!(station.retrofit_choice(0, &map), None); // Not with a load to deliver
+        station.robots[0].minerals = 0;
+
+        let minerals = station.refined_minerals;
+        station.robots[0].target_x = Some(3);
+        station.claimed_targets.insert((3, 0), 0);
+        assert!(station.retrofit_robot(0, RobotType::EnergyCollector));
+        assert_eq!(station.refined_minerals, minerals - RETROFIT_MINERAL_COST);
+        let refitted = &station.robots[0];
+        assert_eq!((refitted.robot_type, refitted.spec), (RobotType::EnergyCollector, RobotType::EnergyCollector.spec()));
+        assert_eq!((refitted.state, refitted.retrofit_ticks, refitted.target_x), (RobotState::AtStation, RETROFIT_TICKS, None));
+        assert!(station.claimed_targets.is_empty());
+        assert_eq!(station.retrofits, 1);
+        // Two explorers are kept whatever is left to explore
+        assert_eq!(station.retrofit_choice(1, &map), None);
+        station.refined_minerals = RETROFIT_MINERAL_COST - 1;
+        assert!(!station.retrofit_robot(1, RobotType::Scientist));
+    }
+
+    #[test]
+    fn test_docked_explorer_leaves_the_mapped_zone_alone() {
+        // Unexplored on both sides, but the station had the west mapped from long-range scans
+        let (mut map, _) = Map::from_ascii(&vec![".".repeat(11); 5].join("\n")).unwrap();
+        map.iter_cells_mut().for_each(|(x, _, cell)| cell.explored = (5..8).contains(&x));
+        let mut station = Station::new(5, 2);
+        for (x, y, _) in map.iter_cells().filter(|&(x, _, _)| x < 5) {
+            station.known_map.insert((x, y), KnownCell::new(CellType::Empty, 0));
+        }
+        station.known_map.insert((5 + KNOWN_CELLS_RADIUS as usize + 1, 2), KnownCell::new(CellType::Empty, 0)); // Too far off to hand over
+        station.add_robot(Robot::new_with_type(5, 2, RobotType::Explorer));
+
+        // Knowing only what it saw itself, the wider west draws it
+        assert_eq!(station.robots[0].choose_explorer_direction(&map, &[], 0), Some(crate::robot::Direction::West));
+        station.robots[0].known_by_station = station.known_cells_near(&map);
+        assert_eq!(station.robots[0].known_by_station.len(), 5 * 5);
+        assert_eq!(station.robots[0].choose_explorer_direction(&map, &[], 0), Some(crate::robot::Direction::East));
+    }
+
+    #[test]
+    fn test_only_first_sightings_are_new_data() {
+        let mut station = Station::new(0, 0);
+        station.share_data(0, &vec![((1, 1), CellType::Energy(100)), ((2, 2), CellType::Empty)], 0);
+        assert_eq!(station.unanalyzed_data, 2 * DATA_PER_NEW_CELL);
+
+        // Re-reports, even of a changed cell, add nothing
+        station.share_data(0, &vec![((1, 1), CellType::Empty), ((2, 2), CellType::Empty)], 0);
+        assert_eq!(station.unanalyzed_data, 2 * DATA_PER_NEW_CELL);
+        station.share_data(1, &vec![((1, 1), CellType::Empty), ((3, 3), CellType::Empty)], 0);
+        assert_eq!(station.unanalyzed_data, 3 * DATA_PER_NEW_CELL);
+        // Discoveries are credited to whoever reported the cell first
+        assert_eq!(station.discovered_by.get(&(1, 1)), Some(&0));
+        assert_eq!(station.discovered_by.get(&(3, 3)), Some(&1));
+    }
+
+    #[test]
+    fn test_collected_and_stale_deposits_stop_justifying_robots() {
+        let mut station = Station::new(0, 0);
+        let deposits = vec![((1, 1), CellType::Energy(100)), ((2, 2), CellType::Mineral(50))];
+        station.share_data(0, &deposits, 10);
+        assert_eq!(station.build_verdict(10).reason(), "enough known targets: 2 >= 2");
+
+        // The collector that emptied the mineral deposit reports it back as Empty
+        station.share_data(1, &vec![((2, 2), CellType::Empty)], 40);
+        assert_eq!(station.known_map.get(&(2, 2)), Some(&KnownCell::new(CellType::Empty, 40)));
+        assert_eq!(station.build_verdict(40).reason(), "too few known targets: 1 < 2");
+
+        // Reports outside the window no longer count, and pruning forgets the deposits only
+        station.share_data(0, &deposits, 50);
+        let later = 50 + UNTAPPED_REPORT_WINDOW;
+        assert_eq!(station.build_verdict(later).reason(), "enough known targets: 2 >= 2");
+        assert_eq!(station.build_verdict(later + 1).reason(), "too few known targets: 0 < 2");
+        station.share_data(0, &vec![((3, 3), CellType::Empty)], 30);
+        assert_eq!(station.prune_stale(51), 2);
+        assert_eq!(station.known_map.len(), 1);
+
+        // A forgotten deposit reported again is known again, but neither new data nor a discovery
+        let data = station.unanalyzed_data;
+        station.share_data(1, &vec![((1, 1), CellType::Energy(60))], 2000);
+        assert_eq!(station.known_map.get(&(1, 1)), Some(&KnownCell::new(CellType::Energy(60), 2000)));
+        assert_eq!(station.unanalyzed_data, data);
+        assert_eq!(station.discovered_by.get(&(1, 1)), Some(&0));
+    }
+
+    #[test]
+    fn test_docked_scientists_analyze_at_a_capped_rate() {
+        let mut station = Station::new(0, 0);
+        station.unanalyzed_data = 100;
+        for _ in 0..3 {
+            let mut scientist = Robot::new_with_type(0, 0, RobotType::Scientist);
+            scientist.state = RobotState::AtStation;
+            station.robots.push(scientist);
+        }
+        station.robots.push(Robot::new_with_type(0, 0, RobotType::Explorer)); // Not a scientist
+        assert_eq!(station.analysis_rate(), MAX_ANALYSIS_PER_TICK);
+
+        for _ in 0..DATA_PER_SCIENCE_POINT / MAX_ANALYSIS_PER_TICK {
+            station.tick();
+        }
+        assert_eq!(station.unanalyzed_data, 100 - DATA_PER_SCIENCE_POINT);
+        assert_eq!((station.science_points, station.science_from_analysis), (1, 1));
+
+        // Scientists with a known science point to collect are not idle
+        station.known_map.insert((4, 4), KnownCell::new(CellType::SciencePoint(3), 0));
+        assert_eq!(station.analysis_rate(), 0);
+        station.tick();
+        assert_eq!(station.unanalyzed_data, 100 - DATA_PER_SCIENCE_POINT);
+
+        // Nor is analysis done with no room left for the science
+        station.known_map.clear();
+        station.capacity.science = 1;
+        station.tick();
+        assert_eq!(station.unanalyzed_data, 100 - DATA_PER_SCIENCE_POINT);
+    }
+
+    #[test]
+    fn test_refinement_throughput_limit() {
+        let mut station = Station::new(0, 0);
+        station.collect_resources(0, 12, 0);
+        assert_eq!(station.refinement_backlog_ticks(), 3);
+
+        station.tick();
+        assert_eq!(station.raw_minerals, 12 - REFINEMENT_RATE);
+        assert_eq!(station.refined_minerals, 500 + REFINEMENT_RATE);
+        assert_eq!(station.energy, 2000 - REFINEMENT_ENERGY_COST);
+
+        station.tick();
+        station.tick(); // Only 2 raw minerals left for the last tick
+        assert_eq!(station.raw_minerals, 0);
+        assert_eq!(station.refined_minerals, 512);
+
+        // Nothing to refine: no energy spent
+        let energy = station.energy;
+        station.tick();
+        assert_eq!(station.energy, energy);
+
+        // Each refinery level adds the base rate again
+        station.facilities.push(Facility { kind: FacilityKind::Refinery, level: 2 });
+        station.raw_minerals = 40;
+        assert_eq!(station.refinement_backlog_ticks(), 3);
+        station.tick();
+        assert_eq!(station.raw_minerals, 40 - 3 * REFINEMENT_RATE);
+        assert_eq!(station.energy, energy - REFINEMENT_ENERGY_COST);
+    }
+
+    #[test]
+    fn test_refinement_pauses_below_energy_reserve() {
+        let mut station = Station::new(0, 0);
+        station.collect_resources(0, 20, 0);
+        station.energy = REFINEMENT_ENERGY_RESERVE;
+        assert_eq!(station.refine_verdict().reason(), "energy below refinery reserve: 200 < 201");
+
+        station.tick();
+        assert_eq!(station.raw_minerals, 20);
+        assert_eq!(station.refined_minerals, 500);
+        assert_eq!(station.energy, REFINEMENT_ENERGY_RESERVE);
+
+        station.energy = REFINEMENT_ENERGY_RESERVE + 10;
+        station.tick();
+        assert_eq!(station.raw_minerals, 20 - REFINEMENT_RATE);
+    }
+
+    #[test]
+    fn test_known_coverage_lags_exploration() {
+        let (mut map, _) = Map::from_ascii("...\n.#.\n").unwrap();
+        let mut station = Station::new(0, 0);
+        map.explore(0, 0, 0);
+        map.explore(1, 0, 0);
+
+        // Nothing reported yet
+        assert_eq!(station.known_coverage(&map).explored_cells, 0);
+
+        station.share_data(0, &vec![((0, 0), CellType::Empty)], 0);
+        let coverage = station.known_coverage(&map);
+        assert_eq!(coverage.passable_cells, 5);
+        assert_eq!(coverage.explored_cells, 1);
+        assert_eq!(coverage.percentage, 20.0);
+        assert!(coverage.percentage < map.exploration_stats().percentage);
+    }
+
+    #[test]
+    fn test_explorers_are_paired_and_trailers_share_the_leaders_region() {
+        let map = Map::new(60, 30, 9);
+        let mut station = Station::new(30, 15);
+        for robot_type in [RobotType::Explorer, RobotType::Scientist, RobotType::Explorer, RobotType::Explorer] {
+            station.robots.push(Robot::new_with_type(30, 15, robot_type));
+        }
+        assert!(station.pair_up_explorers().is_empty()); // Buddy system off
+        station.pair_explorers = true;
+        assert_eq!(station.pair_up_explorers(), vec![(0, 2)]);
+        assert_eq!((station.buddy_of(2), station.buddy_of(3), station.leader_of(2)), (Some(0), None, Some(0)));
+
+        // The odd one out is paired once another explorer joins
+        station.robots.push(Robot::new_with_type(30, 15, RobotType::Explorer));
+        assert_eq!(station.pair_up_explorers(), vec![(3, 4)]);
+        assert!(station.pair_up_explorers().is_empty());
+
+        let led = station.assign_explorer_region(0, &map, 0).unwrap();
+        let trailing = station.assign_explorer_region(2, &map, 0).unwrap();
+        assert_eq!((trailing.target, trailing.reason), (led.target, TargetReason::Buddy { leader: 0 }));
+        // Another pair's leader still gets a region of its own
+        assert_ne!(station.assign_explorer_region(3, &map, 0).unwrap().region, led.region);
+
+        // A robot that stops being an explorer leaves its pair
+        station.robots[4].robot_type = RobotType::Carrier;
+        station.pair_up_explorers();
+        assert_eq!(station.explorer_pairs, vec![(0, 2)]);
+    }
+
+    #[test]
+    fn test_explorers_get_distinct_regions() {
+        let mut map = Map::new(60, 30, 9);
+        let mut station = Station::new(30, 15);
+        for _ in 0..7 {
+            station.robots.push(Robot::new_with_type(30, 15, RobotType::Explorer));
+        }
+        station.robots.push(Robot::new_with_type(30, 15, RobotType::Scientist));
+
+        // Finish off the top-left region: it must never be handed out again
+        for y in 0..10 {
+            for x in 0..20 {
+                map.explore(x, y, 0);
+            }
+        }
+        let (grid_w, grid_h) = EXPLORER_REGION_GRID;
+        assert!(!map.regions(grid_w, grid_h)[0].has_unexplored());
+
+        // Seven explorers and eight regions with unexplored cells left: no sharing
+        let assigned: Vec<usize> = (0..7).map(|index| station.assign_explorer_region(index, &map, 0).unwrap().region).collect();
+        let mut distinct = assigned.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 7, "regions assigned: {:?}", assigned);
+        assert!(!assigned.contains(&0));
+        for (index, &region) in assigned.iter().enumerate() {
+            let robot = &station.robots[index];
+            assert_eq!(map.region_of(robot.target_x.unwrap(), robot.target_y.unwrap(), grid_w, grid_h), region);
+        }
+        assert_eq!(station.assign_explorer_region(7, &map, 0), None); // Only explorers get regions
+
+        // Reporting back keeps a robot off regions the others are heading for
+        let reassigned = station.assign_explorer_region(3, &map, 0).unwrap().region;
+        assert!(!assigned.iter().enumerate().any(|(index, &region)| index != 3 && region == reassigned));
+    }
+
+    #[test]
+    fn test_science_cluster_density_weighs_value_by_nearness() {
+        let (map, _) = Map::from_ascii(&vec![".".repeat(40); 30].join("\n")).unwrap();
+        let mut station = Station::new(0, 0);
+        station.known_map.insert((2, 2), KnownCell::new(CellType::SciencePoint(30), 0)); // Rich, but on its own
+        for position in [(20, 20), (21, 20), (19, 20), (20, 22)] {
+            station.known_map.insert(position, KnownCell::new(CellType::SciencePoint(10), 0));
+        }
+        station.known_map.insert((30, 20), KnownCell::new(CellType::SciencePoint(10), 0)); // Too far off to count
+        station.known_map.insert((20, 21), KnownCell::new(CellType::Energy(50), 0));
+        let density = station.science_cluster_density(&map);
+        assert_eq!(density.len(), 6);
+        assert_eq!(density[&(2, 2)], 30 * 5);
+        assert_eq!(density[&(20, 20)], 10 * 5 + 2 * 10 * 4 + 10 * 3);
+        assert_eq!(density[&(20, 22)], 10 * 5 + 10 * 3 + 2 * 10 * 2);
+        assert_eq!(density[&(30, 20)], 10 * 5);
+        let richest = density.iter().max_by_key(|&(_, &density)| density).map(|(&position, _)| position);
+        assert_eq!(richest, Some((20, 20)));
+        assert!(Station::new(0, 0).science_cluster_density(&map).is_empty());
+    }
+
+    #[test]
+    fn test_docked_scientist_is_sent_to_the_richest_science_cluster() {
+        let (map, _) = Map::from_ascii(&vec![".".repeat(40); 30].join("\n")).unwrap();
+        let mut station = Station::new(0, 0);
+        station.known_map.insert((3, 0), KnownCell::new(CellType::SciencePoint(10), 0)); // Nearest, but on its own
+        for position in [(15, 10), (16, 10), (15, 11)] {
+            station.known_map.insert(position, KnownCell::new(CellType::SciencePoint(10), 0));
+        }
+        station.refresh_tasks(&map);
+        assert_eq!(station.task_queue.front(), Some(&Task::CollectAt { x: 3, y: 0 }));
+        station.robots.push(Robot::new_with_type(0, 0, RobotType::Scientist));
+        assert_eq!(station.take_task(0, &map), Some(Task::CollectAt { x: 15, y: 10 }));
+        // The next one goes for what is left of the cluster before the lone point
+        station.robots.push(Robot::new_with_type(0, 0, RobotType::Scientist));
+        assert!(matches!(station.take_task(1, &map), Some(Task::CollectAt { x: 15..=16, y: 10..=11 })));
+        // None of that is a scouting trip
+        station.robots.push(Robot::new_with_type(0, 0, RobotType::Scientist));
+        assert_eq!(station.send_scientist_scouting(2, &map), None);
+    }
+
+    #[test]
+    fn test_scientist_knowing_of_no_science_scouts_the_least_explored_region() {
+        let mut map = Map::new(60, 30, 9);
+        let mut station = Station::new(30, 15);
+        for _ in 0..2 {
+            station.robots.push(Robot::new_with_type(30, 15, RobotType::Scientist));
+        }
+        station.robots.push(Robot::new_with_type(30, 15, RobotType::MineralCollector));
+        // Everything explored but the bottom-right and bottom-middle regions, the former less so
+        map.iter_cells_mut().for_each(|(x, y, cell)| cell.explored = y < 20 || (x < 40 && x % 2 == 0) || x < 20);
+        let (grid_w, grid_h) = EXPLORER_REGION_GRID;
+        let scouted = |task: Option<Task>| match task {
+            Some(Task::ExploreRegion { x, y }) => map.region_of(x, y, grid_w, grid_h),
+            other => panic!("not a scouting trip: {:?}", other),
+        };
+        assert_eq!(scouted(station.send_scientist_scouting(0, &map)), 8);
+        assert_eq!(scouted(station.send_scientist_scouting(1, &map)), 7); // Not where the first one went
+        assert_eq!(station.send_scientist_scouting(2, &map), None); // Only scientists scout
+        assert_eq!(station.send_scientist_scouting(0, &map), None); // Still on its way
+    }
+
+    #[test]
+    fn test_explorers_rescout_stale_cells_once_all_is_explored() {
+        let (mut map, _) = Map::from_ascii("......\n......\n").unwrap();
+        for y in 0..2 {
+            for x in 0..6 {
+                map.explore(x, y, 400);
+            }
+        }
+        map.explore(4, 1, 150); // Everything else was seen more recently
+        let mut station = Station::new(0, 0);
+        station.robots.push(Robot::new_with_type(0, 0, RobotType::Explorer));
+
+        // Nothing unexplored, and the stalest cell is not old enough yet
+        assert_eq!(station.assign_explorer_region(0, &map, 150 + REVISIT_AFTER_TICKS - 1), None);
+
+        let (grid_w, grid_h) = EXPLORER_REGION_GRID;
+        let choice = station.assign_explorer_region(0, &map, 150 + REVISIT_AFTER_TICKS).unwrap();
+        assert_eq!(choice.region, map.region_of(4, 1, grid_w, grid_h));
+        assert_eq!(choice.reason, TargetReason::StaleCell { idle_ticks: REVISIT_AFTER_TICKS });
+        assert_eq!((station.robots[0].target_x, station.robots[0].target_y), (Some(4), Some(1)));
     }
 }
@@ -0,0 +1,169 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::decisions::{Explanation, DECISION_TAG};
+use crate::facilities::FacilityKind;
+use crate::robot::{RobotId, RobotType};
+use crate::upgrades::Upgrade;
+
+// Entries a station keeps; past it the oldest one is dropped for each new one
+pub const STATION_LOG_CAPACITY: usize = 200;
+
+// What happened at a station, as its own log records it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StationEvent {
+    RobotCreated { robot: RobotId, robot_type: RobotType },
+    RobotDied { robot: RobotId }, // Ran out of energy in the field
+    RobotRespawned { robot: RobotId },
+    ResourceDelivered { robot: RobotId, energy: u32, minerals: u32, science: u32 }, // What storage took
+    UpgradePurchased { upgrade: Upgrade, level: u32 },
+    FacilityBuilt { facility: FacilityKind, level: u32 },
+    RecallIssued { recall: bool }, // False when the recall was lifted
+    Decision(Explanation),         // Only logged with --explain-station
+}
+
+// Kinds of event in the order the end report counts them
+pub const STATION_EVENT_KINDS: [&str; 8] = [
+    "robot created",
+    "robot died",
+    "robot respawned",
+    "resource delivered",
+    "upgrade purchased",
+    "facility built",
+    "recall issued",
+    "station decision",
+];
+
+impl StationEvent {
+    // Index of the event's kind in STATION_EVENT_KINDS
+    fn kind(&self) -> usize {
+        match self {
+            StationEvent::RobotCreated { .. } => 0,
+            StationEvent::RobotDied { .. } => 1,
+            StationEvent::RobotRespawned { .. } => 2,
+            StationEvent::ResourceDelivered { .. } => 3,
+            StationEvent::UpgradePurchased { .. } => 4,
+            StationEvent::FacilityBuilt { .. } => 5,
+            StationEvent::RecallIssued { .. } => 6,
+            StationEvent::Decision(_) => 7,
+        }
+    }
+}
+
+impl fmt::Display for StationEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StationEvent::RobotCreated { robot, robot_type } => write!(f, "robot #{} built ({:?})", robot + 1, robot_type),
+            StationEvent::RobotDied { robot } => write!(f, "robot #{} died in the field", robot + 1),
+            StationEvent::RobotRespawned { robot } => write!(f, "robot #{} respawned", robot + 1),
+            StationEvent::ResourceDelivered { robot, energy, minerals, science } => {
+                write!(f, "robot #{} delivered {} energy, {} minerals, {} science", robot + 1, energy, minerals, science)
+            }
+            StationEvent::UpgradePurchased { upgrade, level } => write!(f, "{} upgrade bought (level {})", upgrade.name(), level),
+            StationEvent::FacilityBuilt { facility, level } => write!(f, "{} built (level {})", facility.name(), level),
+            StationEvent::RecallIssued { recall: true } => write!(f, "fleet recalled"),
+            StationEvent::RecallIssued { recall: false } => write!(f, "recall lifted"),
+            StationEvent::Decision(explanation) => {
+                write!(f, "{} {}: {} ({})", DECISION_TAG, explanation.decision, explanation.outcome, explanation.reason)
+            }
+        }
+    }
+}
+
+// An event and the tick it happened on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggedEvent {
+    pub tick: u64,
+    pub event: StationEvent,
+}
+
+// The latest STATION_LOG_CAPACITY events of a station, oldest first, and how many of each kind
+// it has logged in all
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StationLog {
+    pub entries: VecDeque<LoggedEvent>,
+    counts: [u32; STATION_EVENT_KINDS.len()],
+}
+
+impl StationLog {
+    pub fn push(&mut self, tick: u64, event: StationEvent) {
+        if self.entries.len() == STATION_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.counts[event.kind()] += 1;
+        self.entries.push_back(LoggedEvent { tick, event });
+    }
+
+    // (kind, events logged) of every kind logged at least once, in STATION_EVENT_KINDS order
+    pub fn counts(&self) -> Vec<(&'static str, u32)> {
+        STATION_EVENT_KINDS.iter().zip(self.counts).filter(|&(_, count)| count > 0).map(|(&kind, count)| (kind, count)).collect()
+    }
+
+    // The counts, like "robot created 3, robot died 1", or "nothing logged"
+    pub fn summary(&self) -> String {
+        let counts = self.counts();
+        if counts.is_empty() {
+            return "nothing logged".to_string();
+        }
+        counts.iter().map(|(kind, count)| format!("{} {}", kind, count)).collect::<Vec<_>>().join(", ")
+    }
+
+    // The entries kept, or only the station decisions among them
+    fn kept(&self, decisions_only: bool) -> impl Iterator<Item = &LoggedEvent> {
+        self.entries.iter().filter(move |entry| !decisions_only || matches!(entry.event, StationEvent::Decision(_)))
+    }
+
+    // How many entries are kept, or how many of them are station decisions
+    pub fn kept_count(&self, decisions_only: bool) -> usize {
+        self.kept(decisions_only).count()
+    }
+
+    // Up to `rows` lines of the log, or of its decisions only, newest last, ending `back` entries
+    // before the newest
+    pub fn lines(&self, rows: usize, back: usize, decisions_only: bool) -> Vec<String> {
+        let end = self.kept_count(decisions_only).saturating_sub(back);
+        let start = end.saturating_sub(rows);
+        self.kept(decisions_only).skip(start).take(end - start).map(|entry| format!("tick {:>6}: {}", entry.tick, entry.event)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decisions::{BuildVerdict, RefineVerdict};
+
+    #[test]
+    fn test_log_keeps_the_latest_entries_and_counts_them_all() {
+        let mut log = StationLog::default();
+        assert_eq!(log.summary(), "nothing logged");
+        for tick in 0..STATION_LOG_CAPACITY as u64 + 5 {
+            log.push(tick, StationEvent::RobotRespawned { robot: 0 });
+        }
+        log.push(300, StationEvent::RecallIssued { recall: true });
+        assert_eq!(log.entries.len(), STATION_LOG_CAPACITY);
+        assert_eq!(log.entries.front().unwrap().tick, 6);
+        assert_eq!(log.summary(), "robot respawned 205, recall issued 1");
+
+        assert_eq!(log.lines(2, 0, false), vec!["tick    204: robot #1 respawned", "tick    300: fleet recalled"]);
+        assert_eq!(log.lines(1, 1, false), vec!["tick    204: robot #1 respawned"]);
+        assert!(log.lines(3, STATION_LOG_CAPACITY, false).is_empty());
+    }
+
+    #[test]
+    fn test_decision_filter_keeps_only_the_explained_decisions() {
+        let mut log = StationLog::default();
+        log.push(0, StationEvent::RobotRespawned { robot: 0 });
+        log.push(1, StationEvent::Decision(Explanation::refinery(1, RefineVerdict::NothingToRefine, Vec::new())));
+        log.push(2, StationEvent::RecallIssued { recall: true });
+        log.push(3, StationEvent::Decision(Explanation::build(3, BuildVerdict::FleetFull { robots: 9, max: 9 }, Vec::new())));
+
+        assert_eq!(log.kept_count(true), 2);
+        assert_eq!(
+            log.lines(5, 0, true),
+            vec!["tick      1: [decision] refinery: idle (no raw minerals)", "tick      3: [decision] build: declined (fleet full: 9 >= 9 robots)"]
+        );
+        assert_eq!(log.lines(1, 1, true), vec!["tick      1: [decision] refinery: idle (no raw minerals)"]);
+        assert_eq!(log.lines(5, 0, false).len(), 4);
+        assert_eq!(log.summary(), "robot respawned 1, recall issued 1, station decision 2");
+    }
+}
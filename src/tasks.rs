@@ -0,0 +1,54 @@
+use crate::robot::RobotType;
+use crate::station::KnownMap;
+
+// Work the station queues up and hands to robots as they dock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Task {
+    ExploreRegion { x: usize, y: usize }, // Head for (x, y), then explore from there
+    CollectAt { x: usize, y: usize },     // Harvest the deposit at (x, y) until it is used up
+    ReturnHome,                           // Stay docked, idle, until a suitable task is queued
+    BuildOutpostAt { x: usize, y: usize }, // Carry the makings of a new station to (x, y)
+}
+
+impl Task {
+    // Whether a robot of this type can carry the task out; deposits are judged by what the
+    // station knows of them
+    pub fn suits(self, robot_type: RobotType, known_map: &KnownMap) -> bool {
+        match self {
+            Task::ExploreRegion { .. } => robot_type == RobotType::Explorer,
+            Task::CollectAt { x, y } => known_map.get(&(x, y)).is_some_and(|known| robot_type.harvests(&known.cell_type)),
+            // Carriers and repairers keep to their own rounds
+            Task::BuildOutpostAt { .. } => !matches!(robot_type, RobotType::Carrier | RobotType::Repairer),
+            Task::ReturnHome => true,
+        }
+    }
+
+    // Where the task sends the robot, if anywhere
+    pub fn destination(self) -> Option<(usize, usize)> {
+        match self {
+            Task::ExploreRegion { x, y } | Task::CollectAt { x, y } | Task::BuildOutpostAt { x, y } => Some((x, y)),
+            Task::ReturnHome => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::CellType;
+    use crate::station::KnownCell;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_collect_tasks_suit_the_robots_harvesting_the_deposit() {
+        let known_map = HashMap::from([((1, 0), KnownCell::new(CellType::Mineral(50), 0)), ((2, 0), KnownCell::new(CellType::Empty, 0))]);
+        let task = Task::CollectAt { x: 1, y: 0 };
+        assert!(task.suits(RobotType::MineralCollector, &known_map) && task.suits(RobotType::Miner, &known_map));
+        assert!(!task.suits(RobotType::EnergyCollector, &known_map) && !task.suits(RobotType::Explorer, &known_map));
+        assert!(!Task::CollectAt { x: 2, y: 0 }.suits(RobotType::MineralCollector, &known_map));
+        assert!(Task::ExploreRegion { x: 5, y: 5 }.suits(RobotType::Explorer, &known_map));
+        assert!(Task::ReturnHome.suits(RobotType::Carrier, &known_map));
+        assert!(Task::BuildOutpostAt { x: 30, y: 0 }.suits(RobotType::Scientist, &known_map));
+        assert!(!Task::BuildOutpostAt { x: 30, y: 0 }.suits(RobotType::Carrier, &known_map));
+    }
+}
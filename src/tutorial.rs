@@ -0,0 +1,202 @@
+use crate::events::ActivityTally;
+use crate::map::{CellType, Map};
+use crate::simulation::Simulation;
+use crate::viewport::JumpTarget;
+
+// Hand-authored scenario the tutorial runs on: small, with deposits in clusters so the
+// station builds its first robot early
+const TUTORIAL_MAP: &str = include_str!("../maps/tutorial.txt");
+
+// Kinds of deposits a tutorial step can wait for the station to learn about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    Energy,
+    Mineral,
+    Science,
+}
+
+impl Resource {
+    fn matches(self, cell_type: &CellType) -> bool {
+        matches!(
+            (self, cell_type),
+            (Resource::Energy, CellType::Energy(_))
+                | (Resource::Mineral, CellType::Mineral(_))
+                | (Resource::Science, CellType::SciencePoint(_))
+        )
+    }
+}
+
+// When a tutorial step fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    AtTick(u64),                // Once this many ticks have run
+    ResourceReported(Resource), // The station knows of a deposit of this kind
+    FirstDelivery,              // A robot unloaded cargo at the station (needs an ActivityTally subscriber)
+    RobotCount(usize),          // The swarm has grown to this many robots
+    ExploredPercent(u32),       // This share of the passable map has been explored
+}
+
+impl Trigger {
+    fn is_met(self, simulation: &Simulation) -> bool {
+        match self {
+            Trigger::AtTick(tick) => simulation.clock.reached(tick),
+            Trigger::ResourceReported(resource) => {
+                simulation.station().known_map.values().any(|known| resource.matches(&known.cell_type))
+            }
+            Trigger::FirstDelivery => simulation
+                .events
+                .subscriber::<ActivityTally>()
+                .is_some_and(|tally| tally.deliveries > 0),
+            Trigger::RobotCount(count) => simulation.station().robots.len() >= count,
+            Trigger::ExploredPercent(percent) => simulation.map.exploration_stats().percentage >= percent as f64,
+        }
+    }
+}
+
+// One scripted prompt: shown once its trigger is met, optionally moving the view and pausing
+// until the player resumes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TutorialStep {
+    pub trigger: Trigger,
+    pub message: &'static str,
+    pub focus: Option<JumpTarget>,
+    pub pause: bool,
+}
+
+// The scripted steps for the bundled scenario, in order
+pub fn bundled_steps() -> Vec<TutorialStep> {
+    let step = |trigger, message, focus, pause| TutorialStep { trigger, message, focus, pause };
+    vec![
+        step(
+            Trigger::AtTick(0),
+            "This is your station (H). Robots deliver resources here and refuel before heading out again.",
+            Some(JumpTarget::Station),
+            true,
+        ),
+        step(
+            Trigger::AtTick(3),
+            "Every letter is a robot: Explorers (E) map the world, G, M and S collect energy, minerals and science.",
+            None,
+            false,
+        ),
+        step(
+            Trigger::ResourceReported(Resource::Mineral),
+            "A robot just reported minerals (m) to the station. Watch the M robot go after deposits like it.",
+            Some(JumpTarget::LastEvent),
+            true,
+        ),
+        step(
+            Trigger::ResourceReported(Resource::Energy),
+            "Energy (e) found too. G robots bring it in, and the station spends it refueling the swarm.",
+            Some(JumpTarget::LastEvent),
+            false,
+        ),
+        step(
+            Trigger::ResourceReported(Resource::Science),
+            "A science point (s)! S robots collect its data for the station to analyze.",
+            Some(JumpTarget::LastEvent),
+            false,
+        ),
+        step(
+            Trigger::FirstDelivery,
+            "First delivery! Cargo unloaded at the station pays for new robots once the minerals are refined.",
+            Some(JumpTarget::Station),
+            false,
+        ),
+        step(
+            Trigger::RobotCount(10),
+            "The station built a new robot on its own. It picks the type the swarm needs most.",
+            Some(JumpTarget::LastEvent),
+            true,
+        ),
+        step(
+            Trigger::ExploredPercent(98),
+            "The map is almost fully explored. Try [ and ] to change the speed, F for fog of war, and Q to quit.",
+            None,
+            false,
+        ),
+    ]
+}
+
+// Walks through the steps in order, firing each one at most once
+pub struct Tutorial {
+    steps: Vec<TutorialStep>,
+    next: usize,
+}
+
+impl Tutorial {
+    pub fn new(steps: Vec<TutorialStep>) -> Self {
+        Self { steps, next: 0 }
+    }
+
+    // The next step, if its trigger is now met. One step fires per call so every prompt is seen
+    pub fn update(&mut self, simulation: &Simulation) -> Option<&TutorialStep> {
+        let step = self.steps.get(self.next)?;
+        if !step.trigger.is_met(simulation) {
+            return None;
+        }
+        self.next += 1;
+        Some(step)
+    }
+}
+
+// The tutorial scenario with its default roster
+pub fn build_simulation() -> Simulation {
+    let (map, station) = Map::from_ascii(TUTORIAL_MAP).expect("bundled tutorial map is valid");
+    Simulation::setup(map, station)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tick at which each bundled step fires when the scenario runs untouched
+    fn firing_ticks() -> Vec<u64> {
+        let mut simulation = build_simulation();
+        simulation.events.subscribe(Box::new(ActivityTally::default()));
+        let mut tutorial = Tutorial::new(bundled_steps());
+        let mut fired = Vec::new();
+        while fired.len() < bundled_steps().len() && simulation.clock.now() < 2000 {
+            if tutorial.update(&simulation).is_some() {
+                fired.push(simulation.clock.now());
+            } else {
+                simulation.tick();
+            }
+        }
+        fired
+    }
+
+    #[test]
+    fn test_steps_fire_within_their_windows() {
+        let windows = [0..=0, 3..=3, 10..=100, 10..=100, 10..=100, 10..=100, 10..=150, 20..=300];
+        let fired = firing_ticks();
+        assert_eq!(fired.len(), windows.len(), "only fired at {:?}", fired);
+        for (index, (tick, window)) in fired.iter().zip(windows).enumerate() {
+            assert!(window.contains(tick), "step {} fired at tick {}, expected {:?}", index + 1, tick, window);
+        }
+        assert!(fired.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(fired, firing_ticks()); // Fixed scenario, fixed script
+    }
+
+    #[test]
+    fn test_steps_fire_once_and_in_order() {
+        let simulation = build_simulation();
+        let mut tutorial = Tutorial::new(vec![
+            TutorialStep { trigger: Trigger::AtTick(0), message: "first", focus: None, pause: false },
+            TutorialStep { trigger: Trigger::RobotCount(1), message: "second", focus: None, pause: false },
+            TutorialStep { trigger: Trigger::AtTick(5), message: "third", focus: None, pause: false },
+        ]);
+        assert_eq!(tutorial.update(&simulation).unwrap().message, "first");
+        // The second step is already met but only fires on the next update
+        assert_eq!(tutorial.update(&simulation).unwrap().message, "second");
+        assert!(tutorial.update(&simulation).is_none()); // Waits for tick 5
+        assert!(tutorial.update(&simulation).is_none());
+    }
+
+    #[test]
+    fn test_first_delivery_needs_the_tally() {
+        let mut simulation = build_simulation();
+        simulation.run_ticks(300);
+        assert!(!Trigger::FirstDelivery.is_met(&simulation));
+    }
+}
@@ -1,21 +1,250 @@
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent},
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Gauge, Paragraph, Wrap},
 };
 use std::io::{stdout, Result};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::map::Map;
-use crate::station::Station; // Add import for Station
+use crate::config::{self, SimConfig};
+use crate::events::ActivityTally;
+use crate::layout::{self, Panel};
+use crate::map::{self, CellType, Map};
+use crate::motion::RobotMotion;
+use crate::radio;
+use crate::query::{self, FleetEntry, Query};
+use crate::robot::{Robot, RobotState, RobotType};
+use crate::simulation::Simulation;
+use crate::station::Station;
+use crate::tutorial::TutorialStep;
+use crate::viewport::{self, JumpTarget, Viewport, BOOKMARK_SLOTS};
+
+// How long input is waited for each frame
+const INPUT_POLL_TIME: Duration = Duration::from_millis(150);
+
+// Time between the extra frames drawn while robots sweep along their moves
+const SUBFRAME_TIME: Duration = Duration::from_millis(25);
+
+// Cells moved per arrow key press
+const PAN_STEP: i32 = 5;
+
+// Characters produced by Shift+1..Shift+9 on a US layout
+const SHIFTED_DIGITS: [char; BOOKMARK_SLOTS] = ['!', '@', '#', '$', '%', '^', '&', '*', '('];
+
+// Width of the settings overlay
+const SETTINGS_VIEW_WIDTH: u16 = 64;
+
+// Width of the fleet overlay: a table row with a long status line, plus borders
+const FLEET_VIEW_WIDTH: u16 = 150;
+
+// Width and entry rows of the station log overlay
+const LOG_VIEW_WIDTH: u16 = 120;
+const LOG_VIEW_ROWS: usize = 15;
+
+// Shafts between the surface and the underground layer, drawn on both
+const SHAFT_SYMBOL: char = '≡';
+
+// Rows of the tutorial prompt panel, borders included
+const TUTORIAL_PANEL_HEIGHT: u16 = 4;
+
+// Most ticks that can be batched into a single rendered frame
+pub const MAX_TICKS_PER_FRAME: u32 = 100;
+
+// Actions requested by the user through the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputAction {
+    None,
+    Quit,
+    TogglePause,
+    IncreaseTicksPerFrame,
+    DecreaseTicksPerFrame,
+    ToggleRecall, // Call the whole fleet home, or lift the call
+    OrderOutpost, // Send a robot of the main station to found an outpost
+}
+
+// Main loop settings adjustable at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameSettings {
+    pub ticks_per_frame: u32, // Simulation ticks run between two rendered frames
+    pub paused: bool,
+}
+
+impl Default for FrameSettings {
+    fn default() -> Self {
+        Self {
+            ticks_per_frame: config::DEFAULT_TICKS_PER_FRAME,
+            paused: false,
+        }
+    }
+}
+
+impl FrameSettings {
+    // Apply a speed/pause action, returning false if the action was not handled here
+    pub fn apply(&mut self, action: InputAction) -> bool {
+        match action {
+            InputAction::TogglePause => self.paused = !self.paused,
+            InputAction::IncreaseTicksPerFrame => {
+                self.ticks_per_frame = (self.ticks_per_frame + 1).min(MAX_TICKS_PER_FRAME);
+            }
+            InputAction::DecreaseTicksPerFrame => {
+                self.ticks_per_frame = self.ticks_per_frame.saturating_sub(1).max(1);
+            }
+            InputAction::None | InputAction::Quit | InputAction::ToggleRecall | InputAction::OrderOutpost => return false,
+        }
+        true
+    }
+}
+
+// Anything the main loop can read input from and render to (the terminal UI, or a test double)
+pub trait Frontend {
+    fn handle_input(&mut self) -> Result<InputAction>;
+    fn render(&mut self, simulation: &Simulation, settings: &FrameSettings) -> Result<()>;
+
+    // Show a tutorial prompt until the next one replaces it
+    fn show_prompt(&mut self, _step: &TutorialStep) {}
+
+    // Spend the rest of the frame; the terminal UI may draw extra frames in the meantime
+    fn wait(&mut self, _simulation: &Simulation, _settings: &FrameSettings, time_left: Duration) -> Result<()> {
+        std::thread::sleep(time_left);
+        Ok(())
+    }
+}
+
+// What the map view reveals
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewMode {
+    Omniscient, // Ground truth for every cell
+    Fog,        // Only cells the swarm has discovered
+}
+
+impl ViewMode {
+    fn toggled(self) -> Self {
+        match self {
+            ViewMode::Omniscient => ViewMode::Fog,
+            ViewMode::Fog => ViewMode::Omniscient,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ViewMode::Omniscient => "Omniscient",
+            ViewMode::Fog => "Fog",
+        }
+    }
+}
+
+// Robot table drawn over the map ('r'; 'R' recalls the fleet), filtered by a fleet query typed after '/'
+#[derive(Debug, Default)]
+struct FleetView {
+    open: bool,
+    filter: String,
+    editing: bool, // Keys go to the filter line until Enter or Esc
+}
+
+impl FleetView {
+    // Returns false for keys left to the rest of the UI
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        if self.editing {
+            match code {
+                KeyCode::Char(c) => self.filter.push(c),
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Enter | KeyCode::Esc => self.editing = false,
+                _ => {}
+            }
+            return true;
+        }
+        match code {
+            KeyCode::Char('r') => self.open = !self.open,
+            KeyCode::Char('/') if self.open => self.editing = true,
+            _ => return false,
+        }
+        true
+    }
+
+    // The filter line, then the matching robots or why the filter is not understood
+    fn lines(&self, simulation: &Simulation) -> Vec<Line<'static>> {
+        let cursor = if self.editing { "_" } else { "" };
+        let mut lines = vec![Line::from(format!("/{}{}", self.filter, cursor))];
+        let fleet = query::fleet(simulation);
+        match Query::parse(&self.filter) {
+            Ok(query) => {
+                let matching: Vec<&FleetEntry> = fleet.iter().filter(|entry| query.matches(entry)).collect();
+                lines.push(Line::from(format!("{} of {} robots", matching.len(), fleet.len())));
+                lines.push(Line::from(Span::styled(FleetEntry::header(), Style::default().add_modifier(Modifier::BOLD))));
+                lines.extend(matching.iter().map(|entry| Line::from(entry.row())));
+            }
+            Err(err) => lines.push(Line::from(Span::styled(err, Style::default().fg(Color::Red)))),
+        }
+        lines
+    }
+}
+
+// Latest events of the shown station drawn over the map ('e'), scrolled back with PageUp/PageDown
+// and narrowed down to the station's decisions with 'd'
+#[derive(Debug, Default)]
+struct LogView {
+    open: bool,
+    back: usize,     // Entries scrolled back from the newest
+    decisions: bool, // Only the decisions explained with --explain-station
+}
+
+impl LogView {
+    // Returns false for keys left to the rest of the UI
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('e') | KeyCode::Char('E') => {
+                self.open = !self.open;
+                self.back = 0;
+            }
+            KeyCode::Char('d') | KeyCode::Char('D') if self.open => {
+                self.decisions = !self.decisions;
+                self.back = 0;
+            }
+            KeyCode::PageUp if self.open => self.back += LOG_VIEW_ROWS,
+            KeyCode::PageDown if self.open => self.back = self.back.saturating_sub(LOG_VIEW_ROWS),
+            _ => return false,
+        }
+        true
+    }
+
+    // The page of the log scrolled to, kept from scrolling past the oldest entry
+    fn lines(&mut self, station: &Station) -> Vec<Line<'static>> {
+        self.back = self.back.min(station.events.kept_count(self.decisions).saturating_sub(LOG_VIEW_ROWS));
+        let lines = station.events.lines(LOG_VIEW_ROWS, self.back, self.decisions);
+        if lines.is_empty() {
+            let empty = if self.decisions { "No decisions logged: run with --explain-station" } else { "Nothing logged yet" };
+            return vec![Line::from(empty)];
+        }
+        lines.into_iter().map(Line::from).collect()
+    }
+}
 
 // Structure to manage the user interface
 pub struct UI {
     terminal: Terminal<CrosstermBackend<std::io::Stdout>>,
+    view_mode: ViewMode,
+    viewport: Viewport,
+    bookmarks: [Option<Viewport>; BOOKMARK_SLOTS],
+    pending_jump: Option<JumpTarget>, // Resolved against the simulation on the next render
+    followed_robot: Option<usize>,    // Last robot jumped to with 'u'
+    status_message: Option<String>,
+    config: Option<SimConfig>,        // Settings the run started with, shown with 'o'
+    show_settings: bool,
+    fleet: FleetView,
+    log: LogView,
+    tutorial_prompt: Option<&'static str>, // Latest tutorial message, drawn over the bottom of the map
+    layer: usize, // Map layer shown, toggled with 'l' on maps with an underground
+    shown_station: usize, // Station the stats panels show, cycled with 'b' when there are several
+    smooth_motion: bool, // Sweep robots along their path between batches, toggled with 'i'
+    motion: RobotMotion,
+    motion_started: Instant, // When the latest batch of robot moves was captured
+    motion_phase: f64,       // How far along those moves robots are drawn, 0 to 1
 }
 
 impl UI {
@@ -27,7 +256,31 @@ impl UI {
         execute!(stdout(), EnterAlternateScreen)?;
         terminal.hide_cursor()?;
         terminal.clear()?;
-        Ok(Self { terminal })
+        Ok(Self {
+            terminal,
+            view_mode: ViewMode::Omniscient,
+            viewport: Viewport::default(),
+            bookmarks: [None; BOOKMARK_SLOTS],
+            pending_jump: None,
+            followed_robot: None,
+            status_message: None,
+            config: None,
+            show_settings: false,
+            fleet: FleetView::default(),
+            log: LogView::default(),
+            tutorial_prompt: None,
+            layer: map::SURFACE,
+            shown_station: 0,
+            smooth_motion: false,
+            motion: RobotMotion::default(),
+            motion_started: Instant::now(),
+            motion_phase: 1.0,
+        })
+    }
+
+    // Provide the resolved settings for the read-only settings view
+    pub fn set_config(&mut self, config: SimConfig) {
+        self.config = Some(config);
     }
 
     pub fn get_terminal_size(&self) -> Result<Rect> {
@@ -42,98 +295,819 @@ impl UI {
         Ok(())
     }
 
+    // End screen: the run summary over a cleared screen until a key is pressed
+    pub fn show_summary(&mut self, lines: &[String]) -> Result<()> {
+        let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u16 + 4;
+        self.terminal.draw(|frame| {
+            let area = centered_rect(width, lines.len() as u16 + 2, frame.size());
+            frame.render_widget(Clear, frame.size());
+            let lines: Vec<Line> = lines.iter().map(|line| Line::from(line.as_str())).collect();
+            frame.render_widget(
+                Paragraph::new(lines).block(Block::default().title("Run Summary (any key to exit)").borders(Borders::ALL)),
+                area,
+            );
+        })?;
+        loop {
+            if let Event::Key(_) = event::read()? {
+                return Ok(());
+            }
+        }
+    }
+
+    // Apply a pending viewport jump and keep the viewport inside the map
+    fn update_viewport(&mut self, simulation: &Simulation, view_width: usize, view_height: usize) {
+        let map = &simulation.map;
+        let center_on = |x: usize, y: usize| Viewport::centered_on(x, y, view_width, view_height, map.width, map.height);
+        let shown = self.shown_station % simulation.stations.len();
+
+        if let Some(target) = self.pending_jump.take() {
+            let (viewport, message) = match target {
+                JumpTarget::Station => {
+                    let station = &simulation.stations[shown];
+                    let message = if simulation.stations.len() > 1 { format!("Jumped to station {}", shown + 1) } else { "Jumped to station".to_string() };
+                    (Some(center_on(station.x, station.y)), message)
+                }
+                JumpTarget::LastEvent => match simulation.last_event_position {
+                    Some((x, y)) => (Some(center_on(x, y)), format!("Jumped to last activity at ({}, {})", x, y)),
+                    None => (None, "No robot activity yet".to_string()),
+                },
+                JumpTarget::NextExploringRobot => {
+                    match viewport::next_exploring_robot(simulation, self.followed_robot) {
+                        Some(index) => {
+                            self.followed_robot = Some(index);
+                            let robot = &simulation.station().robots[index];
+                            (Some(center_on(robot.x, robot.y)), format!("Jumped to exploring robot #{}", index + 1))
+                        }
+                        None => (None, "No robot is exploring".to_string()),
+                    }
+                }
+                JumpTarget::Bookmark(slot) => match self.bookmarks[slot] {
+                    Some(bookmark) => (Some(bookmark), format!("Jumped to bookmark {}", slot + 1)),
+                    None => (None, format!("Bookmark {} is empty (Shift+{} to set)", slot + 1, slot + 1)),
+                },
+            };
+            if let Some(viewport) = viewport {
+                self.viewport = viewport;
+            }
+            self.status_message = Some(message);
+        }
+
+        self.viewport = self.viewport.clamped(view_width, view_height, map.width, map.height);
+    }
+
     // Display the map and the station's information (autonomous mode)
-    pub fn render(&mut self, map: &Map, station: &Station) -> Result<()> { // Remove robot parameter
+    pub fn render(&mut self, simulation: &Simulation, settings: &FrameSettings) -> Result<()> {
+        let (view_width, view_height) = map_view_size(self.terminal.size()?);
+        self.update_viewport(simulation, view_width, view_height);
+        if self.layer >= simulation.map.layers() {
+            self.layer = map::SURFACE;
+            self.status_message = Some("This map has no underground layer".to_string());
+        }
+
+        if self.motion.update(simulation.robots(), simulation.clock.now()) {
+            self.motion_started = Instant::now();
+            self.motion_phase = 0.0;
+        }
+        let robot_cells: Vec<(usize, usize, usize)> = if self.smooth_motion {
+            self.motion.positions(self.motion_phase)
+        } else {
+            simulation.robots().map(|robot| (robot.x, robot.y, robot.z)).collect()
+        };
+
+        // The breadcrumb trail of the robot last jumped to with 'u'
+        let trail: Vec<(usize, usize)> = self
+            .followed_robot
+            .and_then(|index| simulation.station().robots.get(index))
+            .map_or(Vec::new(), |robot| robot.recent_positions.iter().copied().collect());
+
+        let map = &simulation.map;
+        let layer = self.layer;
+        let stations = &simulation.stations;
+        // The stats panels show one station at a time, cycled through with 'b'
+        let shown = self.shown_station % stations.len();
+        let station = &stations[shown];
+        let station_title = |title: &str| match stations.len() {
+            1 => title.to_string(),
+            count => format!("{} - Station {}/{} (B: next)", title, shown + 1, count),
+        };
+        let view_mode = self.view_mode;
+        let viewport = self.viewport;
+        let status_message = self.status_message.as_deref().unwrap_or("");
+        // The resolved seed of a generated world, so a word given as seed can be shared as a number
+        let seed_status = self.config.as_ref().and_then(|config| config.seed.value).map_or(String::new(), |seed| format!(" | Seed: {}", seed));
+        let settings_view = match (&self.config, self.show_settings) {
+            (Some(config), true) => Some(settings_lines(config, settings)),
+            _ => None,
+        };
+        let fleet_view = self.fleet.open.then(|| self.fleet.lines(simulation));
+        let log_view = self.log.open.then(|| self.log.lines(station));
+        let log_decisions = self.log.decisions;
+        let tutorial_prompt = self.tutorial_prompt;
+        let plan = layout::plan(self.terminal.size()?.height, &layout::ALL_PANELS);
         self.terminal.draw(|frame| {
+            // The map on top, then the panels that fit and the condensed status line
+            let mut constraints = vec![Constraint::Length(plan.map_rows)];
+            constraints.extend(plan.panels.iter().map(|_| Constraint::Length(layout::PANEL_ROWS)));
+            constraints.push(Constraint::Length(plan.status_rows()));
             let main_layout = Layout::default()
                 .direction(ratatui::layout::Direction::Vertical)
-                .constraints([
-                    Constraint::Min(0),    // Map area
-                    Constraint::Length(9), // Bottom panel: 3 sections * 3 lines/section = 9 lines
-                ])
+                .constraints(constraints)
                 .split(frame.size());
 
             // Render map with all robots
-            let mut map_text_lines = Vec::new();
-            for y in 0..map.height {
-                let mut line = String::new();
-                for x in 0..map.width {
-                    // Check if any robot is at this position
-                    let robot_at_position = station.robots.iter().find(|robot| robot.x == x && robot.y == y);
-                    
-                    if let Some(robot) = robot_at_position {
-                        // Display robot with type-specific symbol
-                        let symbol = match robot.robot_type {
-                            crate::robot::RobotType::Explorer => 'E',
-                            crate::robot::RobotType::EnergyCollector => 'G', // G for enerGy
-                            crate::robot::RobotType::MineralCollector => 'M',
-                            crate::robot::RobotType::Scientist => 'S',
-                        };
-                        line.push(symbol);
-                    } else if x == station.x && y == station.y { // Check for station position
-                        line.push('H'); // 'H' for Home/Station
-                    } else if let Some(cell) = map.get_cell(x, y) {
-                        let symbol = match cell.cell_type {
-                            crate::map::CellType::Empty => " ",
-                            crate::map::CellType::Obstacle => "▓",
-                            crate::map::CellType::Energy(_) => "e",
-                            crate::map::CellType::Mineral(_) => "m",
-                            crate::map::CellType::SciencePoint => "s",
-                        };
-                        line.push_str(symbol);
-                    } else {
-                        line.push(' '); // Should not happen if map is correctly sized
+            let (map_text_lines, title) = match layer {
+                map::SURFACE => (
+                    map_lines_at(map, stations, &robot_cells, &trail, view_mode, viewport, view_width, view_height),
+                    "Autonomous Robot Swarm",
+                ),
+                _ => (underground_lines(map, stations, viewport, view_width, view_height), "Autonomous Robot Swarm - Underground"),
+            };
+            let map_paragraph = Paragraph::new(map_text_lines)
+                .block(Block::default().title(title).borders(Borders::ALL));
+            frame.render_widget(map_paragraph, main_layout[0]);
+
+            let explored = map.exploration_stats();
+            let speed_status = if settings.paused {
+                "PAUSED".to_string()
+            } else {
+                format!("Ticks/frame: {}", settings.ticks_per_frame)
+            };
+
+            // Render stats and info, each panel in its own 3-line chunk
+            for (panel, area) in plan.panels.iter().zip(main_layout[1..].iter()) {
+                match panel {
+                    Panel::StationStats => {
+                        let remaining = map.summary();
+                        let station_stats = format!(
+                            "{} | Remaining: {} energy, {} minerals, {} science",
+                            station.display_stats(), remaining.energy_total, remaining.mineral_total, remaining.science_value
+                        );
+                        let station_stats_paragraph = Paragraph::new(station_stats)
+                            .block(Block::default().title(station_title("Station Stats")).borders(Borders::ALL));
+                        frame.render_widget(station_stats_paragraph, *area);
+                    }
+                    Panel::SwarmStats => {
+                        let swarm_stats_paragraph = Paragraph::new(station.display_swarm_stats())
+                            .block(Block::default().title(station_title("Swarm Stats")).borders(Borders::ALL));
+                        frame.render_widget(swarm_stats_paragraph, *area);
+                    }
+                    Panel::Exploration => {
+                        let known = station.known_coverage(map);
+                        let exploration_gauge = Gauge::default()
+                            .block(Block::default().title("Exploration").borders(Borders::ALL))
+                            .gauge_style(Style::default().fg(Color::Green))
+                            .ratio((explored.percentage / 100.0).clamp(0.0, 1.0))
+                            .label(format!(
+                                "Explored {:.1}% ({}/{}) | Reported to station {:.1}%",
+                                explored.percentage, explored.explored_cells, explored.passable_cells, known.percentage
+                            ));
+                        frame.render_widget(exploration_gauge, *area);
+                    }
+                    Panel::Activity => {
+                        let activity_paragraph = Paragraph::new(activity_summary(simulation))
+                            .block(Block::default().title("Activity").borders(Borders::ALL));
+                        frame.render_widget(activity_paragraph, *area);
+                    }
+                    Panel::Info => {
+                        let recall_status = if station.recall { "RECALL ACTIVE | " } else { "" };
+                        let info_paragraph = Paragraph::new(format!(
+                            "{}Tick: {}{} | {} | View: {} @ ({}, {}) | {}",
+                            recall_status, simulation.clock.now(), seed_status, speed_status, view_mode.label(), viewport.x, viewport.y, status_message
+                        ))
+                        .block(Block::default().title(
+                            "Info - Q: Quit | Space: Pause | [/]: Ticks per frame | F: Fog | Arrows: Pan | Home/End/U: Station/Activity/Robot | 1-9: Bookmarks (Shift to set) | O: Settings | r: Robots | E: Log | R: Recall | P: Outpost | L: Layer | I: Smooth",
+                        ).borders(Borders::ALL));
+                        frame.render_widget(info_paragraph, *area);
                     }
                 }
-                map_text_lines.push(Line::from(line));
             }
-            let map_paragraph = Paragraph::new(map_text_lines)
-                .block(Block::default().title("Autonomous Robot Swarm").borders(Borders::ALL));
-            frame.render_widget(map_paragraph, main_layout[0]);
 
-            // Render stats and info
-            let bottom_chunks = Layout::default()
-                .direction(ratatui::layout::Direction::Vertical)
-                .constraints([
-                    Constraint::Length(3), // Station stats (3 lines: title/border, content, border)
-                    Constraint::Length(3), // Swarm stats (3 lines)
-                    Constraint::Length(3), // Info (3 lines)
-                ])
-                .split(main_layout[1]); // Split the 9-line bottom area
-
-            let station_stats_paragraph = Paragraph::new(station.display_stats())
-                .block(Block::default().title("Station Stats").borders(Borders::ALL));
-            frame.render_widget(station_stats_paragraph, bottom_chunks[0]); // Render in the first 3-line chunk
-
-            let swarm_stats_paragraph = Paragraph::new(station.display_swarm_stats())
-                .block(Block::default().title("Swarm Stats").borders(Borders::ALL));
-            frame.render_widget(swarm_stats_paragraph, bottom_chunks[1]); // Render in the second 3-line chunk
-
-            let info_paragraph =
-                Paragraph::new("Autonomous Mode: Robots explore and collect resources automatically | Q: Quit")
-                    .block(Block::default().title("Info").borders(Borders::ALL));
-            frame.render_widget(info_paragraph, bottom_chunks[2]); // Render in the third 3-line chunk
+            // Panels without room are folded into one line along the bottom
+            let condensed: Vec<String> = plan
+                .condensed
+                .iter()
+                .map(|panel| match panel {
+                    Panel::StationStats => format!(
+                        "Energy {} | Minerals {} | Science {}",
+                        station.energy,
+                        station.raw_minerals + station.refined_minerals,
+                        station.science_points
+                    ),
+                    Panel::SwarmStats => format!("Robots {}", station.robots.len()),
+                    Panel::Exploration => format!("Explored {:.1}%", explored.percentage),
+                    Panel::Activity => activity_summary(simulation),
+                    Panel::Info => format!("Tick {} | {} | {}", simulation.clock.now(), speed_status, status_message),
+                })
+                .collect();
+            if let Some(area) = main_layout.last().filter(|_| plan.status_rows() > 0) {
+                frame.render_widget(Paragraph::new(condensed.join(" | ")).style(Style::default().add_modifier(Modifier::REVERSED)), *area);
+            }
+
+            // Current tutorial prompt along the bottom of the map
+            if let Some(message) = tutorial_prompt {
+                let map_area = main_layout[0];
+                let height = TUTORIAL_PANEL_HEIGHT.min(map_area.height);
+                let area = Rect::new(map_area.x, map_area.bottom() - height, map_area.width, height);
+                let title = if settings.paused { "Tutorial (Space to continue)" } else { "Tutorial" };
+                frame.render_widget(Clear, area);
+                frame.render_widget(
+                    Paragraph::new(message)
+                        .wrap(Wrap { trim: true })
+                        .block(Block::default().title(title).borders(Borders::ALL)),
+                    area,
+                );
+            }
 
+            // Fleet table drawn over the map
+            if let Some(lines) = fleet_view {
+                let area = centered_rect(FLEET_VIEW_WIDTH, lines.len() as u16 + 2, main_layout[0]);
+                frame.render_widget(Clear, area);
+                frame.render_widget(
+                    Paragraph::new(lines).block(Block::default().title("Fleet (/ to filter, R to close)").borders(Borders::ALL)),
+                    area,
+                );
+            }
+
+            // Station log drawn over the map
+            if let Some(lines) = log_view {
+                let area = centered_rect(LOG_VIEW_WIDTH, lines.len() as u16 + 2, main_layout[0]);
+                let title = if log_decisions {
+                    "Station Log: decisions (PgUp/PgDn: scroll, D: all events, E to close)"
+                } else {
+                    "Station Log (PgUp/PgDn: scroll, D: decisions, E to close)"
+                };
+                frame.render_widget(Clear, area);
+                frame.render_widget(Paragraph::new(lines).block(Block::default().title(station_title(title)).borders(Borders::ALL)), area);
+            }
+
+            // Read-only settings table drawn over the map
+            if let Some(lines) = settings_view {
+                let area = centered_rect(SETTINGS_VIEW_WIDTH, lines.len() as u16 + 2, main_layout[0]);
+                frame.render_widget(Clear, area);
+                frame.render_widget(
+                    Paragraph::new(lines).block(Block::default().title("Effective Settings (O to close)").borders(Borders::ALL)),
+                    area,
+                );
+            }
         })?;
         Ok(())
     }
 
-    // Wait and process user input (autonomous mode - only quit control)
-    pub fn handle_input(&self) -> Result<bool> { // Remove robot and map parameters
+    // Wait and process user input (autonomous mode - quit, speed and view controls)
+    pub fn handle_input(&mut self) -> Result<InputAction> {
         // Increase the waiting time to reduce polling frequency
-        if event::poll(Duration::from_millis(150))? {
-            if let Event::Key(KeyEvent { code, .. }) = event::read()? {
-                match code {
-                    KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
-                        return Ok(false);
+        // With smooth motion the frame's waiting is spent drawing robot moves instead
+        let poll_time = if self.smooth_motion { Duration::ZERO } else { INPUT_POLL_TIME };
+        if event::poll(poll_time)? {
+            if let Event::Key(KeyEvent { code, modifiers, .. }) = event::read()? {
+                if self.fleet.handle_key(code) || self.log.handle_key(code) {
+                    return Ok(InputAction::None);
+                }
+                if let Some(action) = self.handle_navigation_key(code, modifiers) {
+                    return Ok(action);
+                }
+                return Ok(match code {
+                    KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => InputAction::Quit,
+                    KeyCode::Char(' ') => InputAction::TogglePause,
+                    KeyCode::Char(']') => InputAction::IncreaseTicksPerFrame,
+                    KeyCode::Char('[') => InputAction::DecreaseTicksPerFrame,
+                    KeyCode::Char('R') => InputAction::ToggleRecall,
+                    KeyCode::Char('P') => InputAction::OrderOutpost,
+                    KeyCode::Char('o') | KeyCode::Char('O') => {
+                        self.show_settings = !self.show_settings;
+                        InputAction::None
+                    }
+                    KeyCode::Char('l') | KeyCode::Char('L') => {
+                        // Checked against the map on the next render
+                        self.layer = 1 - self.layer;
+                        self.status_message = Some(if self.layer == map::SURFACE { "Showing the surface" } else { "Showing the underground" }.to_string());
+                        InputAction::None
+                    }
+                    KeyCode::Char('i') | KeyCode::Char('I') => {
+                        self.smooth_motion = !self.smooth_motion;
+                        self.status_message = Some(format!("Smooth robot motion {}", if self.smooth_motion { "on" } else { "off" }));
+                        InputAction::None
+                    }
+                    KeyCode::Char('b') | KeyCode::Char('B') => {
+                        // Wrapped around the stations there are on the next render
+                        self.shown_station = self.shown_station.wrapping_add(1);
+                        InputAction::None
                     }
-                    _ => {
-                        // Ignore all other inputs in autonomous mode
+                    KeyCode::Char('f') | KeyCode::Char('F') => {
+                        // View-only setting, handled by the UI itself
+                        self.view_mode = self.view_mode.toggled();
+                        InputAction::None
                     }
+                    _ => InputAction::None, // Ignore all other inputs in autonomous mode
+                });
+            }
+        }
+
+        Ok(InputAction::None)
+    }
+}
+
+// Rows of the settings view: value and source per setting, with values changed since
+// startup (the speed keys) highlighted
+fn settings_lines(config: &SimConfig, settings: &FrameSettings) -> Vec<Line<'static>> {
+    config
+        .rows()
+        .into_iter()
+        .map(|(name, value, source)| {
+            let label = format!("{:<17} {:<12} {}", name, value, source.label());
+            if name == "ticks_per_frame" && settings.ticks_per_frame != config.ticks_per_frame.value {
+                Line::from(Span::styled(
+                    format!("{:<17} {:<12} changed at runtime (was {})", name, settings.ticks_per_frame, value),
+                    Style::default().fg(Color::Yellow),
+                ))
+            } else {
+                Line::from(label)
+            }
+        })
+        .collect()
+}
+
+// A width x height rectangle centered in `area` (shrunk to fit)
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
+}
+
+// Whether a cell's contents are shown in fog mode: the station knows it, a robot explored it,
+// or it is an obstacle or radiation bordering such a cell (robots see what blocks their way)
+pub fn is_revealed(map: &Map, station: &Station, x: usize, y: usize) -> bool {
+    let discovered = |cx: usize, cy: usize| {
+        station.known_map.contains_key(&(cx, cy)) || map.get_cell(cx, cy).is_some_and(|cell| cell.explored)
+    };
+    if discovered(x, y) {
+        return true;
+    }
+    match map.get_cell(x, y) {
+        Some(cell) if cell.cell_type.blocks_movement() => {
+            (x > 0 && discovered(x - 1, y))
+                || discovered(x + 1, y)
+                || (y > 0 && discovered(x, y - 1))
+                || discovered(x, y + 1)
+        }
+        _ => false,
+    }
+}
+
+// Map symbol of a robot broken down and waiting for repairs
+const BROKEN_ROBOT_SYMBOL: char = 'X';
+
+// Map symbol of a robot: its type's letter, or BROKEN_ROBOT_SYMBOL while it is broken down
+fn robot_symbol(robot: &Robot) -> char {
+    if robot.state == RobotState::Broken {
+        return BROKEN_ROBOT_SYMBOL;
+    }
+    match robot.robot_type {
+        RobotType::Explorer => 'E',
+        RobotType::EnergyCollector => 'G', // G for enerGy
+        RobotType::MineralCollector => 'M',
+        RobotType::Scientist => 'S',
+        RobotType::Miner => 'D', // D for Drill
+        RobotType::Carrier => 'C',
+        RobotType::Repairer => 'R',
+    }
+}
+
+// Rows of the visible part of the underground layer: tunnels, shafts and the robots of every
+// station down there. Fog does not reach underground, whose tunnels are shown as they are
+fn underground_lines(map: &Map, stations: &[Station], viewport: Viewport, view_width: usize, view_height: usize) -> Vec<Line<'static>> {
+    let x_end = (viewport.x + view_width).min(map.width);
+    let y_end = (viewport.y + view_height).min(map.height);
+    (viewport.y..y_end)
+        .map(|y| {
+            let line: String = (viewport.x..x_end)
+                .map(|x| {
+                    let robot = stations.iter().flat_map(|station| &station.robots).find(|robot| (robot.x, robot.y, robot.z) == (x, y, map::UNDERGROUND));
+                    match (robot, map.layer_cell(x, y, map::UNDERGROUND).map(|cell| &cell.cell_type)) {
+                        (Some(robot), _) => robot_symbol(robot),
+                        _ if map.is_shaft(x, y) => SHAFT_SYMBOL,
+                        (None, Some(CellType::Empty) | None) => ' ',
+                        (None, Some(CellType::Obstacle)) => '▓',
+                        (None, Some(CellType::Energy(_))) => 'e',
+                        (None, Some(CellType::Mineral(_))) => 'm',
+                        (None, Some(CellType::SciencePoint(_))) => 's',
+                        (None, Some(CellType::Radiation(_))) => '%',
+                    }
+                })
+                .collect();
+            Line::from(line)
+        })
+        .collect()
+}
+
+// Build the visible map rows, with robots always drawn on top (even in fog mode)
+pub fn map_lines(
+    map: &Map,
+    station: &Station,
+    view_mode: ViewMode,
+    viewport: Viewport,
+    view_width: usize,
+    view_height: usize,
+) -> Vec<Line<'static>> {
+    let robot_cells: Vec<_> = station.robots.iter().map(|robot| (robot.x, robot.y, robot.z)).collect();
+    map_lines_at(map, std::slice::from_ref(station), &robot_cells, &[], view_mode, viewport, view_width, view_height)
+}
+
+// Map symbol of an open cell on the followed robot's breadcrumb trail
+const TRAIL_SYMBOL: char = '·';
+
+// Same for every one of the `stations` and their robots, with each robot (in station order)
+// drawn at the matching cell of `robot_cells` instead of where it is, and the open cells of
+// `trail` marked with TRAIL_SYMBOL
+#[allow(clippy::too_many_arguments)]
+fn map_lines_at(
+    map: &Map,
+    stations: &[Station],
+    robot_cells: &[(usize, usize, usize)],
+    trail: &[(usize, usize)],
+    view_mode: ViewMode,
+    viewport: Viewport,
+    view_width: usize,
+    view_height: usize,
+) -> Vec<Line<'static>> {
+    let fog_style = Style::default().fg(Color::DarkGray);
+    let robots: Vec<&Robot> = stations.iter().flat_map(|station| &station.robots).collect();
+    let is_station = |x: usize, y: usize| stations.iter().any(|station| (station.x, station.y) == (x, y));
+    let x_end = (viewport.x + view_width).min(map.width);
+    let y_end = (viewport.y + view_height).min(map.height);
+    let mut map_text_lines = Vec::with_capacity(view_height);
+    for y in viewport.y..y_end {
+        let mut spans = Vec::new();
+        let mut line = String::new();
+        let mut fogged_run = false;
+        for (x, cell) in (viewport.x..x_end).zip(map.row_slice(y, viewport.x, x_end)) {
+            // Check if any robot is at this position
+            let robot_at_position = robots.iter().zip(robot_cells).find(|(_, cell)| **cell == (x, y, map::SURFACE)).map(|(robot, _)| *robot);
+
+            let fogged = robot_at_position.is_none()
+                && !is_station(x, y)
+                && !stations.iter().any(|station| station.known_caches.contains(&(x, y)))
+                && view_mode == ViewMode::Fog
+                && !stations.iter().any(|station| is_revealed(map, station, x, y));
+
+            // Group consecutive cells with the same style into one span
+            if fogged != fogged_run && !line.is_empty() {
+                let text = std::mem::take(&mut line);
+                spans.push(if fogged_run { Span::styled(text, fog_style) } else { Span::raw(text) });
+            }
+            fogged_run = fogged;
+
+            if let Some(robot) = robot_at_position {
+                // Display robot with type-specific symbol
+                line.push(robot_symbol(robot));
+            } else if is_station(x, y) { // Check for station position
+                line.push('H'); // 'H' for Home/Station
+            } else if fogged {
+                line.push('?');
+            } else if map.cache_at(x, y).is_some() {
+                line.push('c'); // Cargo dropped in the field
+            } else if map.is_shaft(x, y) {
+                line.push(SHAFT_SYMBOL);
+            } else if cell.cell_type == CellType::Empty && !cell.is_rough() && trail.contains(&(x, y)) {
+                line.push(TRAIL_SYMBOL);
+            } else {
+                let symbol = match cell.cell_type {
+                    CellType::Empty if cell.is_rough() => "░", // Rough terrain costs more energy
+                    CellType::Empty => " ",
+                    CellType::Obstacle if map.is_debris(x, y) => "*", // Drifting debris
+                    CellType::Obstacle => "▓",
+                    CellType::Energy(_) => "e",
+                    CellType::Mineral(_) => "m",
+                    CellType::SciencePoint(_) => "s",
+                    CellType::Radiation(_) => "%", // Radiation, off limits until it decays
+                };
+                line.push_str(symbol);
+            }
+        }
+        spans.push(if fogged_run { Span::styled(line, fog_style) } else { Span::raw(line) });
+        map_text_lines.push(Line::from(spans));
+    }
+    map_text_lines
+}
+
+impl UI {
+    // Viewport panning, jumps and bookmarks; returns None for keys it does not handle
+    fn handle_navigation_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> Option<InputAction> {
+        match code {
+            KeyCode::Left => self.viewport = self.viewport.panned(-PAN_STEP, 0),
+            KeyCode::Right => self.viewport = self.viewport.panned(PAN_STEP, 0),
+            KeyCode::Up => self.viewport = self.viewport.panned(0, -PAN_STEP),
+            KeyCode::Down => self.viewport = self.viewport.panned(0, PAN_STEP),
+            KeyCode::Home => self.pending_jump = Some(JumpTarget::Station),
+            KeyCode::End => self.pending_jump = Some(JumpTarget::LastEvent),
+            KeyCode::Char('u') | KeyCode::Char('U') => self.pending_jump = Some(JumpTarget::NextExploringRobot),
+            KeyCode::Char(c) => {
+                if let Some(slot) = SHIFTED_DIGITS.iter().position(|&shifted| shifted == c) {
+                    self.set_bookmark(slot);
+                } else if let Some(digit @ 1..=9) = c.to_digit(10) {
+                    let slot = digit as usize - 1;
+                    if modifiers.contains(KeyModifiers::SHIFT) {
+                        self.set_bookmark(slot);
+                    } else {
+                        self.pending_jump = Some(JumpTarget::Bookmark(slot));
+                    }
+                } else {
+                    return None;
                 }
             }
+            _ => return None,
         }
+        Some(InputAction::None)
+    }
+
+    fn set_bookmark(&mut self, slot: usize) {
+        self.bookmarks[slot] = Some(self.viewport);
+        self.status_message = Some(format!(
+            "Bookmark {} set at ({}, {})",
+            slot + 1, self.viewport.x, self.viewport.y
+        ));
+    }
+}
+
+// Size of a generated map that fills the terminal above the panels (+ 1 map border)
+pub fn generated_map_size(terminal_size: Rect) -> (usize, usize) {
+    let height = layout::plan(terminal_size.height, &layout::ALL_PANELS).map_rows.saturating_sub(1);
+    (terminal_size.width as usize, height as usize)
+}
+
+// Map cells visible inside the bordered map block for a terminal of the given size
+fn map_view_size(terminal_size: Rect) -> (usize, usize) {
+    let width = terminal_size.width.saturating_sub(2);
+    let height = layout::plan(terminal_size.height, &layout::ALL_PANELS).map_rows.saturating_sub(2);
+    (width as usize, height as usize)
+}
+
+// One line of swarm activity counters; empty until the simulation has a tally subscribed
+fn activity_summary(simulation: &Simulation) -> String {
+    // Value explorer discoveries returned per unit of explorer energy, over the audit window
+    let audit = simulation.audit.ratio().map_or("-".to_string(), |ratio| format!("{:.2}", ratio));
+    // Robots the station could reach over radio, directly or through relays, of those still running
+    let station = &simulation.station();
+    let hops = radio::radio_hops(&simulation.map, (station.x, station.y), &station.robots, radio::RELAY_HOP_LIMIT);
+    let in_contact = hops.iter().filter(|hops| hops.is_some()).count();
+    let running = station.robots.iter().filter(|robot| robot.is_active()).count();
+    simulation.events.subscriber::<ActivityTally>().map_or_else(String::new, |tally| {
+        format!(
+            "Collected {} | Deliveries {} | Built {} | Rescues {} | Wrecks {} | Caches {}/{}/{} (dropped/recovered/lost) | Exploration return {} | Radio contact {}/{}",
+            tally.resources_collected,
+            tally.deliveries,
+            tally.robots_created,
+            tally.rescues,
+            tally.wrecks,
+            tally.caches_dropped,
+            tally.caches_recovered,
+            tally.caches_lost,
+            audit,
+            in_contact,
+            running
+        )
+    })
+}
+
+impl Frontend for UI {
+    fn handle_input(&mut self) -> Result<InputAction> {
+        UI::handle_input(self)
+    }
+
+    fn render(&mut self, simulation: &Simulation, settings: &FrameSettings) -> Result<()> {
+        UI::render(self, simulation, settings)
+    }
+
+    fn show_prompt(&mut self, step: &TutorialStep) {
+        self.tutorial_prompt = Some(step.message);
+        if step.focus.is_some() {
+            self.pending_jump = step.focus;
+        }
+    }
+
+    // With smooth motion on, redraw every SUBFRAME_TIME so robots sweep from where they were to
+    // where the batch left them, arriving as the frame ends
+    fn wait(&mut self, simulation: &Simulation, settings: &FrameSettings, time_left: Duration) -> Result<()> {
+        let deadline = Instant::now() + time_left;
+        if !self.smooth_motion || !self.motion.is_moving() {
+            std::thread::sleep(time_left);
+            return Ok(());
+        }
+        let span = deadline.saturating_duration_since(self.motion_started).as_secs_f64();
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            std::thread::sleep(SUBFRAME_TIME.min(deadline - now));
+            self.motion_phase = if span > 0.0 { self.motion_started.elapsed().as_secs_f64() / span } else { 1.0 };
+            self.render(simulation, settings)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ticks_per_frame_bounds() {
+        let mut settings = FrameSettings::default();
+        assert!(settings.apply(InputAction::DecreaseTicksPerFrame));
+        assert_eq!(settings.ticks_per_frame, 1); // Never drops below one tick per frame
+
+        for _ in 0..(MAX_TICKS_PER_FRAME + 5) {
+            settings.apply(InputAction::IncreaseTicksPerFrame);
+        }
+        assert_eq!(settings.ticks_per_frame, MAX_TICKS_PER_FRAME);
+    }
+
+    fn line_text(line: &Line) -> String {
+        line.spans.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn test_settings_view_marks_runtime_changes() {
+        let config = SimConfig::resolve(&crate::config::CliOverrides::default(), None);
+        let mut settings = FrameSettings::default();
+        let speed_row = |settings: &FrameSettings| {
+            settings_lines(&config, settings)
+                .iter()
+                .map(line_text)
+                .find(|text| text.starts_with("ticks_per_frame"))
+                .unwrap()
+        };
+        assert!(speed_row(&settings).ends_with("default"));
+
+        settings.apply(InputAction::IncreaseTicksPerFrame);
+        assert!(speed_row(&settings).contains("changed at runtime (was 1)"));
+    }
+
+    #[test]
+    fn test_fog_hides_undiscovered_cells() {
+        let (mut map, _) = Map::from_ascii("e..#\n.#.m\n").unwrap();
+        let mut station = Station::new(0, 1);
+        station.robots.push(Robot::new(2, 1)); // Robot standing on unexplored ground
+        map.explore(1, 0, 0);
+        station.share_data(0, &vec![((0, 0), CellType::Energy(50))], 0);
+
+        let full_view = |mode| map_lines(&map, &station, mode, Viewport::default(), 4, 2);
+        let omniscient: Vec<String> = full_view(ViewMode::Omniscient).iter().map(line_text).collect();
+        assert_eq!(omniscient, vec!["e  ▓", "H▓Em"]);
+
+        let fog: Vec<String> = full_view(ViewMode::Fog).iter().map(line_text).collect();
+        // Known/explored cells and the obstacle next to them are revealed, robots stay visible
+        assert_eq!(fog, vec!["e ??", "H▓E?"]);
+    }
+
+    #[test]
+    fn test_cache_glyph() {
+        let (mut map, _) = Map::from_ascii("....\n").unwrap();
+        let mut station = Station::new(3, 0);
+        map.drop_cache(0, 0, 10, 0);
+        map.drop_cache(1, 0, 10, 0);
+        station.known_caches.push((1, 0));
+
+        let view = |mode| line_text(&map_lines(&map, &station, mode, Viewport::default(), 4, 1)[0]);
+        assert_eq!(view(ViewMode::Omniscient), "cc H");
+        assert_eq!(view(ViewMode::Fog), "?c?H"); // Only the reported cache shows through the fog
+    }
+
+    #[test]
+    fn test_layers_show_their_own_robots_and_shafts() {
+        let map = Map::new_two_layer(80, 40, 5);
+        let &(shaft_x, shaft_y) = map.underground.as_ref().unwrap().shafts.first().unwrap();
+        let station = {
+            let mut station = Station::new(0, 0);
+            let mut robot = Robot::new_with_type(shaft_x, shaft_y, RobotType::Miner);
+            assert!(robot.climb_shaft(&map));
+            station.robots.push(robot);
+            station
+        };
+        let surface: Vec<String> = map_lines(&map, &station, ViewMode::Omniscient, Viewport::default(), 80, 40).iter().map(line_text).collect();
+        let underground: Vec<String> = underground_lines(&map, std::slice::from_ref(&station), Viewport::default(), 80, 40).iter().map(line_text).collect();
+        // The miner is down the shaft: only the underground view shows it, the surface the shaft
+        assert_eq!(surface[shaft_y].chars().nth(shaft_x), Some(SHAFT_SYMBOL));
+        assert_eq!(underground[shaft_y].chars().nth(shaft_x), Some('D'));
+        assert!(underground.iter().any(|row| row.contains('m')));
+        assert_eq!(underground.iter().map(|row| row.matches(SHAFT_SYMBOL).count()).sum::<usize>(), map.underground.as_ref().unwrap().shafts.len() - 1);
+    }
+
+    #[test]
+    fn test_map_lines_window() {
+        let (map, _) = Map::from_ascii("....\n.#e.\n..m.\n").unwrap();
+        let station = Station::new(0, 0);
+        let lines = map_lines(&map, &station, ViewMode::Omniscient, Viewport { x: 1, y: 1 }, 2, 5);
+        let text: Vec<String> = lines.iter().map(line_text).collect();
+        assert_eq!(text, vec!["▓e", " m"]); // Window is cut at the map's bottom edge
+    }
+
+    #[test]
+    fn test_rough_terrain_glyph() {
+        let (map, _) = Map::from_ascii(".~^\n").unwrap();
+        let station = Station::new(5, 5);
+        let lines = map_lines(&map, &station, ViewMode::Omniscient, Viewport::default(), 3, 1);
+        assert_eq!(line_text(&lines[0]), " ░░");
+    }
+
+    #[test]
+    fn test_smooth_motion_draws_robots_along_their_path() {
+        use ratatui::backend::TestBackend;
+        let (map, _) = Map::from_ascii(&".".repeat(8)).unwrap();
+        let mut station = Station::new(7, 0);
+        let mut motion = RobotMotion::default();
+        motion.update(&[Robot::new_with_type(1, 0, RobotType::Explorer)], 0);
+        // Four ticks batched into one frame carry the explorer four cells east
+        station.robots.push(Robot::new_with_type(5, 0, RobotType::Explorer));
+        motion.update(&station.robots, 4);
+
+        let drawn_at = |phase: f64| {
+            let mut terminal = Terminal::new(TestBackend::new(8, 1)).unwrap();
+            let lines = map_lines_at(&map, std::slice::from_ref(&station), &motion.positions(phase), &[], ViewMode::Omniscient, Viewport::default(), 8, 1);
+            terminal.draw(|frame| frame.render_widget(Paragraph::new(lines), frame.size())).unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            (0..8).find(|&x| buffer.get(x, 0).symbol() == "E")
+        };
+        assert_eq!(drawn_at(0.0), Some(1));
+        assert_eq!(drawn_at(0.25), Some(2));
+        assert_eq!(drawn_at(0.5), Some(3));
+        assert_eq!(drawn_at(1.0), Some(5));
+    }
+
+    #[test]
+    fn test_map_view_size() {
+        assert_eq!(map_view_size(Rect::new(0, 0, 80, 40)), (78, 40 - 12 - 2));
+        // A short terminal condenses panels so the map keeps its rows
+        assert_eq!(map_view_size(Rect::new(0, 0, 80, 18)), (78, 12));
+        assert_eq!(generated_map_size(Rect::new(0, 0, 80, 60)), (80, 44));
+        assert_eq!(map_view_size(Rect::new(0, 0, 1, 5)), (0, 2)); // Everything condensed into the status line
+        assert_eq!(map_view_size(Rect::new(0, 0, 1, 1)), (0, 0));
+    }
+
+    #[test]
+    fn test_view_mode_toggle() {
+        assert_eq!(ViewMode::Omniscient.toggled(), ViewMode::Fog);
+        assert_eq!(ViewMode::Fog.toggled(), ViewMode::Omniscient);
+    }
+
+    #[test]
+    fn test_fleet_filter_editing() {
+        let mut fleet = FleetView::default();
+        assert!(!fleet.handle_key(KeyCode::Char('/'))); // Only once the table is open
+        assert!(fleet.handle_key(KeyCode::Char('r')));
+        assert!(fleet.open);
+        assert!(fleet.handle_key(KeyCode::Char('/')));
+        for c in "type:x q".chars() {
+            assert!(fleet.handle_key(KeyCode::Char(c))); // Even q is typed, not quit
+        }
+        fleet.handle_key(KeyCode::Backspace);
+        fleet.handle_key(KeyCode::Backspace);
+        assert_eq!(fleet.filter, "type:x");
+        fleet.handle_key(KeyCode::Enter);
+        assert!(!fleet.editing);
+        assert!(!fleet.handle_key(KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn test_log_decision_filter() {
+        let mut log = LogView::default();
+        assert!(!log.handle_key(KeyCode::Char('d'))); // Only once the log is open
+        assert!(log.handle_key(KeyCode::Char('e')));
+        log.handle_key(KeyCode::PageUp);
+        assert!(log.handle_key(KeyCode::Char('d')));
+        assert!(log.decisions);
+        assert_eq!(log.back, 0);
+        log.handle_key(KeyCode::Char('D'));
+        assert!(!log.decisions);
+    }
+
+    #[test]
+    fn test_fleet_lines_filter_robots() {
+        let mut station = Station::new(0, 0);
+        station.robots.push(Robot::new_with_type(1, 0, RobotType::Explorer));
+        station.robots.push(Robot::new_with_type(2, 0, RobotType::Scientist));
+        let simulation = Simulation::new(Map::from_ascii("....\n").unwrap().0, station);
+        let mut fleet = FleetView { open: true, filter: "type:sci".to_string(), editing: true };
+        let text: Vec<String> = fleet.lines(&simulation).iter().map(line_text).collect();
+        assert_eq!(text[0], "/type:sci_");
+        assert_eq!(text[1], "1 of 2 robots");
+        assert_eq!(text.len(), 4); // Header and the scientist's row
+        assert!(text[3].contains("Scientist"));
+
+        fleet.filter = "energy<<3".to_string();
+        let text: Vec<String> = fleet.lines(&simulation).iter().map(line_text).collect();
+        assert_eq!(text.len(), 2);
+        assert!(text[1].contains("takes a number"));
+    }
 
-        Ok(true)
+    #[test]
+    fn test_toggle_pause() {
+        let mut settings = FrameSettings::default();
+        settings.apply(InputAction::TogglePause);
+        assert!(settings.paused);
+        settings.apply(InputAction::TogglePause);
+        assert!(!settings.paused);
+        assert!(!settings.apply(InputAction::Quit));
     }
 }
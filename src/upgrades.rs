@@ -0,0 +1,148 @@
+use crate::robot::RobotSpec;
+
+// Persistent improvements the station buys with science points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Upgrade {
+    Battery,      // +20% battery per level
+    Harvesting,   // +25% taken from a deposit per tick per level, so deposits empty in fewer ticks
+    Construction, // 10 fewer refined minerals per robot per level
+    Sensors,      // +1 sensor radius
+}
+
+// Upgrades in the order the station buys them: each tick, the first one not at its top level
+// whose next level is affordable
+pub const UPGRADE_PRIORITY: [Upgrade; 4] = [Upgrade::Battery, Upgrade::Harvesting, Upgrade::Construction, Upgrade::Sensors];
+
+const BATTERY_PERCENT_PER_LEVEL: u32 = 20;
+const HARVEST_PERCENT_PER_LEVEL: u32 = 25;
+const CONSTRUCTION_DISCOUNT_PER_LEVEL: u32 = 10;
+
+impl Upgrade {
+    pub fn name(self) -> &'static str {
+        match self {
+            Upgrade::Battery => "battery",
+            Upgrade::Harvesting => "harvesting",
+            Upgrade::Construction => "construction",
+            Upgrade::Sensors => "sensors",
+        }
+    }
+
+    // Levels on offer
+    pub fn max_level(self) -> u32 {
+        match self {
+            Upgrade::Sensors => 1,
+            _ => 3,
+        }
+    }
+
+    // Science points the level after `level` costs; each level costs more than the one before
+    pub fn cost(self, level: u32) -> u32 {
+        let base = match self {
+            Upgrade::Battery | Upgrade::Harvesting => 10,
+            Upgrade::Construction => 15,
+            Upgrade::Sensors => 30,
+        };
+        base * (level + 1)
+    }
+}
+
+// Upgrade levels bought so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Upgrades {
+    pub battery: u32,
+    pub harvesting: u32,
+    pub construction: u32,
+    pub sensors: u32,
+}
+
+impl Upgrades {
+    pub fn level(&self, upgrade: Upgrade) -> u32 {
+        match upgrade {
+            Upgrade::Battery => self.battery,
+            Upgrade::Harvesting => self.harvesting,
+            Upgrade::Construction => self.construction,
+            Upgrade::Sensors => self.sensors,
+        }
+    }
+
+    // The upgrade to buy with `science` points on hand, by UPGRADE_PRIORITY, and its cost
+    pub fn next_purchase(&self, science: u32) -> Option<(Upgrade, u32)> {
+        UPGRADE_PRIORITY
+            .into_iter()
+            .filter(|&upgrade| self.level(upgrade) < upgrade.max_level())
+            .map(|upgrade| (upgrade, upgrade.cost(self.level(upgrade))))
+            .find(|&(_, cost)| cost <= science)
+    }
+
+    // Raise an upgrade by one level
+    pub fn buy(&mut self, upgrade: Upgrade) {
+        match upgrade {
+            Upgrade::Battery => self.battery += 1,
+            Upgrade::Harvesting => self.harvesting += 1,
+            Upgrade::Construction => self.construction += 1,
+            Upgrade::Sensors => self.sensors += 1,
+        }
+    }
+
+    // Science points spent on the levels bought so far
+    pub fn science_spent(&self) -> u32 {
+        UPGRADE_PRIORITY.into_iter().map(|upgrade| (0..self.level(upgrade)).map(|level| upgrade.cost(level)).sum::<u32>()).sum()
+    }
+
+    // A robot type's spec with the upgrades fitted
+    pub fn apply(&self, spec: RobotSpec) -> RobotSpec {
+        RobotSpec {
+            max_energy: spec.max_energy * (100 + BATTERY_PERCENT_PER_LEVEL * self.battery) / 100,
+            sensor_radius: spec.sensor_radius + self.sensors as usize,
+            ..spec
+        }
+    }
+
+    // Percent added to what robots take from a deposit each tick
+    pub fn harvest_boost(&self) -> u32 {
+        HARVEST_PERCENT_PER_LEVEL * self.harvesting
+    }
+
+    // Refined minerals taken off the cost of a robot
+    pub fn construction_discount(&self) -> u32 {
+        CONSTRUCTION_DISCOUNT_PER_LEVEL * self.construction
+    }
+
+    // The levels bought, like "battery 2, harvesting 1", or "none"
+    pub fn summary(&self) -> String {
+        let bought: Vec<String> = UPGRADE_PRIORITY
+            .into_iter()
+            .filter(|&upgrade| self.level(upgrade) > 0)
+            .map(|upgrade| format!("{} {}", upgrade.name(), self.level(upgrade)))
+            .collect();
+        if bought.is_empty() {
+            "none".to_string()
+        } else {
+            bought.join(", ")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robot::RobotType;
+
+    #[test]
+    fn test_purchases_follow_the_priority_and_stop_at_the_top_level() {
+        let mut upgrades = Upgrades::default();
+        assert_eq!(upgrades.next_purchase(9), None);
+        assert_eq!(upgrades.next_purchase(10), Some((Upgrade::Battery, 10)));
+        upgrades.battery = 3;
+        assert_eq!(upgrades.next_purchase(100), Some((Upgrade::Harvesting, 10)));
+        upgrades.buy(Upgrade::Harvesting);
+        // Harvesting level 2 costs 20: construction's first level is affordable first
+        assert_eq!(upgrades.next_purchase(15), Some((Upgrade::Construction, 15)));
+        assert_eq!(upgrades.science_spent(), 10 + 20 + 30 + 10);
+        assert_eq!(upgrades.summary(), "battery 3, harvesting 1");
+        assert_eq!(Upgrades::default().summary(), "none");
+
+        let spec = upgrades.apply(RobotType::Carrier.spec());
+        assert_eq!((spec.max_energy, spec.sensor_radius), (240, 1));
+    }
+}
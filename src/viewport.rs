@@ -0,0 +1,111 @@
+use crate::robot::RobotState;
+use crate::simulation::Simulation;
+
+// Number of viewport bookmarks (keys 1-9)
+pub const BOOKMARK_SLOTS: usize = 9;
+
+// Top-left corner of the part of the map currently shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Viewport {
+    pub x: usize,
+    pub y: usize,
+}
+
+// Places the viewport can jump to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JumpTarget {
+    Station,
+    LastEvent,          // Most recent robot activity (collection, delivery, spawn)
+    NextExploringRobot, // Cycles through exploring robots in roster order
+    Bookmark(usize),
+}
+
+impl Viewport {
+    // Keep the view inside the map; maps smaller than the view are pinned to the top-left
+    pub fn clamped(self, view_width: usize, view_height: usize, map_width: usize, map_height: usize) -> Self {
+        Self {
+            x: self.x.min(map_width.saturating_sub(view_width)),
+            y: self.y.min(map_height.saturating_sub(view_height)),
+        }
+    }
+
+    // Viewport centered on a map position, clamped so no out-of-bounds area is shown
+    pub fn centered_on(x: usize, y: usize, view_width: usize, view_height: usize, map_width: usize, map_height: usize) -> Self {
+        Self {
+            x: x.saturating_sub(view_width / 2),
+            y: y.saturating_sub(view_height / 2),
+        }
+        .clamped(view_width, view_height, map_width, map_height)
+    }
+
+    // Move the view by a number of cells (the upper bound is applied by `clamped`)
+    pub fn panned(self, dx: i32, dy: i32) -> Self {
+        Self {
+            x: self.x.saturating_add_signed(dx as isize),
+            y: self.y.saturating_add_signed(dy as isize),
+        }
+    }
+}
+
+// Next exploring robot after `previous` in roster order, wrapping around
+pub fn next_exploring_robot(simulation: &Simulation, previous: Option<usize>) -> Option<usize> {
+    let robots = &simulation.station().robots;
+    if robots.is_empty() {
+        return None;
+    }
+    let start = previous.map_or(0, |index| index + 1);
+    (0..robots.len())
+        .map(|offset| (start + offset) % robots.len())
+        .find(|&index| robots[index].state == RobotState::Exploring && robots[index].energy > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Map;
+    use crate::robot::Robot;
+    use crate::station::Station;
+
+    #[test]
+    fn test_centered_on_middle_of_map() {
+        let viewport = Viewport::centered_on(50, 30, 20, 10, 100, 60);
+        assert_eq!(viewport, Viewport { x: 40, y: 25 });
+    }
+
+    #[test]
+    fn test_centered_on_clamps_near_edges() {
+        // Near the top-left corner the view cannot start before 0
+        assert_eq!(Viewport::centered_on(2, 1, 20, 10, 100, 60), Viewport { x: 0, y: 0 });
+        // Near the bottom-right corner the view stops at the map edge
+        assert_eq!(Viewport::centered_on(99, 59, 20, 10, 100, 60), Viewport { x: 80, y: 50 });
+    }
+
+    #[test]
+    fn test_map_smaller_than_view() {
+        assert_eq!(Viewport::centered_on(5, 5, 40, 20, 10, 10), Viewport { x: 0, y: 0 });
+        assert_eq!(Viewport { x: 7, y: 3 }.clamped(40, 20, 10, 10), Viewport { x: 0, y: 0 });
+    }
+
+    #[test]
+    fn test_panning_never_leaves_the_map() {
+        let viewport = Viewport { x: 2, y: 0 }.panned(-5, -5);
+        assert_eq!(viewport, Viewport { x: 0, y: 0 });
+
+        let viewport = Viewport { x: 75, y: 48 }.panned(10, 10).clamped(20, 10, 100, 60);
+        assert_eq!(viewport, Viewport { x: 80, y: 50 });
+    }
+
+    #[test]
+    fn test_robot_cycling_skips_non_exploring() {
+        let mut station = Station::new(0, 0);
+        station.robots.push(Robot::new(1, 1));
+        station.robots.push(Robot::new(2, 2));
+        station.robots.push(Robot::new(3, 3));
+        station.robots[1].state = RobotState::ReturningToStation;
+        let simulation = Simulation::new(Map::new(10, 10, 1), station);
+
+        assert_eq!(next_exploring_robot(&simulation, None), Some(0));
+        assert_eq!(next_exploring_robot(&simulation, Some(0)), Some(2));
+        assert_eq!(next_exploring_robot(&simulation, Some(2)), Some(0)); // Wraps around
+    }
+}